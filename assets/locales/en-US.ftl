@@ -0,0 +1,432 @@
+language-name = English
+language-label = Language
+language-auto = System
+drop-filter-label = Take
+drop-filter-encrypted = Only encrypted PDFs
+drop-filter-encrypted-hint = List only the dropped files that are encrypted; files that cannot be checked are kept
+drop-filter-pdf = All PDFs
+drop-filter-pdf-hint = List every PDF; files that look like PDFs but are named otherwise are asked about
+drop-filter-sniffed = Anything that looks like a PDF
+drop-filter-sniffed-hint = List every file whose content is a PDF, whatever its name, without asking
+
+format-decimal-separator = .
+format-size = { $size } { $unit }
+format-seconds = { $seconds } s
+format-minutes = { $minutes }:{ $seconds }
+format-date = { $month }/{ $day }/{ $year }
+format-date-time = { $month }/{ $day }/{ $year } { $hour }:{ $minute }
+
+hint-empty = Click or drop files here
+hint-imported = { $count ->
+    [one] { $count } file added
+   *[other] { $count } files added
+}
+hint-analyzing = { $count ->
+    [one] Analyzing { $count } file…
+   *[other] Analyzing { $count } files…
+}
+file-list-header = Files ({ $count })
+processing = Working...
+output-fallback-title = The output folder is not writable. Choose another folder
+open-button = Open
+print-button = Print
+print-hint = Send to the printer
+print-done = Sent { $name } to the printer
+print-failed = Printing failed: { $error }
+share-button = Share
+share-hint = Send with AirDrop, Mail or Messages
+share-failed = Sharing failed: { $error }
+extract-pages-menu = Extract pages…
+extract-pages-title = Extract pages: { $name }
+extract-pages-prompt = Pages to keep, separated by commas; leave a range open to run to the last page
+extract-pages-count = { $pages } pages
+extract-pages-ok = Extract
+extract-pages-cancel = Cancel
+letterhead-menu = Apply letterhead…
+letterhead-pick-title = Choose a letterhead PDF
+letterhead-file = Letterhead: { $name }
+letterhead-none = none chosen
+letterhead-choose = Choose…
+letterhead-underlay = Behind the page
+letterhead-overlay = On top of the page
+attachments-menu = Extract attachments
+attachments-hint = { $count ->
+    [one] 1 attachment: { $names }
+   *[other] { $count } attachments: { $names }
+}
+attachments-done = { $count ->
+    [one] Saved 1 attachment to { $folder }
+   *[other] Saved { $count } attachments to { $folder }
+}
+attachments-none = There are no attachments to extract
+attachments-failed = Could not extract attachments: { $error }
+outline-menu = View bookmarks…
+outline-title = Bookmarks - { $name }
+outline-count = { $count ->
+    [one] 1 bookmark
+   *[other] { $count } bookmarks
+}
+outline-entry = { $title } (p. { $page })
+crop-auto = Fit to content
+crop-margins = Fixed margins
+crop-padding = Keep
+crop-top = Top
+crop-right = Right
+crop-bottom = Bottom
+crop-left = Left
+blank-pages-hint = { $count ->
+    [one] 1 blank page: { $pages }
+   *[other] { $count } blank pages: { $pages }
+}
+download-progress = Downloading { $url } ({ $size })
+download-failed = Download failed: { $error }
+compare-size = Size
+compare-pages = Pages
+compare-version = PDF version
+compare-encryption = Encrypted
+compare-bookmarks = Bookmarks
+compare-removed-metadata = Metadata removed
+compare-yes = Yes
+compare-no = No
+compare-unknown = unknown
+compare-none = None
+compare-button = Compare two PDFs…
+compare-menu = Compare with the original
+compare-title = Compare PDFs
+compare-prompt = Drop two PDFs here or choose them:
+compare-first = First: { $name }
+compare-second = Second: { $name }
+compare-empty = none
+compare-choose = Choose…
+compare-start = Compare
+compare-failed = Could not compare: { $error }
+compare-same = Same page count, text and metadata
+compare-pages-differ = Page count differs: { $first } → { $second }
+compare-text-differs = { $count ->
+    [one] Text differs on page { $pages }
+   *[other] Text differs on { $count } pages: { $pages }
+}
+compare-metadata-differs = { $key }: { $first } → { $second }
+protect-button = Encrypt a folder…
+protect-title = Encrypt in bulk
+protect-prompt = Encrypt every PDF in a folder with AES-256 and an owner password, restricting what readers may do; the files still open without a password. Results end in _protected.
+protect-folder = Folder: { $name }
+protect-no-folder = none chosen
+protect-choose = Choose…
+protect-password = Owner password
+protect-confirm = Repeat the password
+protect-mismatch = The passwords do not match
+protect-allow = Allow:
+protect-allow-print = Printing
+protect-allow-copy = Copying text and images
+protect-allow-modify = Changing the document
+protect-allow-annotate = Adding comments
+protect-start = Encrypt
+
+file-encrypted = Restricted
+file-protected = Editing restricted
+security-removable = { $cipher }, restrictions only: removable without a password
+security-password = { $cipher } with an open password: the password is required
+security-password-weak = { $cipher } with an open password: the password is required (weak encryption)
+security-handler = Encrypted with { $cipher } (certificate or plug-in); may not unlock
+file-unrestricted = No unlock needed
+file-unknown = Unknown
+file-cloud-downloading = Downloading cloud file…
+file-cloud-unavailable = Cloud file unavailable
+file-analyzing = Analyzing
+file-already-unlocked = Already unlocked on { $date }
+
+skipped-summary = Skipped { $items }
+skipped-not-pdf = { $count ->
+    [one] { $count } non-PDF file
+   *[other] { $count } non-PDF files
+}
+skipped-duplicate = { $count ->
+    [one] { $count } duplicate
+   *[other] { $count } duplicates
+}
+skipped-empty-folder = { $count ->
+    [one] { $count } folder without PDFs
+   *[other] { $count } folders without PDFs
+}
+skipped-missing = { $count ->
+    [one] { $count } missing file
+   *[other] { $count } missing files
+}
+skipped-archive = { $count ->
+    [one] { $count } unreadable or PDF-free archive
+   *[other] { $count } unreadable or PDF-free archives
+}
+skipped-mislabeled = { $count ->
+    [one] { $count } file without .pdf extension
+   *[other] { $count } files without .pdf extension
+}
+skipped-unencrypted = { $count ->
+    [one] { $count } unencrypted file
+   *[other] { $count } unencrypted files
+}
+list-separator = { ", " }
+
+archive-password-title = Password for { $name }
+archive-password-prompt = This archive is encrypted. Enter its password to list the PDFs inside.
+archive-password-wrong = Wrong password, try again.
+archive-password-ok = Open
+archive-password-skip = Skip
+archive-password-remember = Remember passwords that work
+archive-password-remember-hint = Passwords that open an archive are kept in the system keychain and tried on encrypted archives and PDFs before asking. Turning this off forgets them.
+
+mislabeled-title = Not named .pdf
+mislabeled-one = This looks like a PDF, but its extension is .{ $ext }. Process it anyway?
+mislabeled-no-extension = This looks like a PDF, but it has no extension. Process it anyway?
+mislabeled-many = { $count ->
+    [one] { $count } file looks like a PDF but is not named .pdf. Process it anyway?
+   *[other] { $count } files look like PDFs but are not named .pdf. Process them anyway?
+}
+
+code-queued = WAIT
+code-running = RUN
+code-done = OK
+code-open = OPEN
+code-encrypted = ENC
+code-password = PWD
+code-cloud = CLOUD
+code-failed = FAIL
+code-cancelled = STOP
+high-contrast-button = High contrast
+status-queued = Queued
+status-analyzing = Analyzing
+status-running = Unlocking
+status-stage = { $stage }…
+status-cancelled = Cancelled
+status-unlocked = Unlocked
+status-pages-extracted = Extracted pages { $pages }
+status-split = Unlocked and split into { $count } files by bookmark
+status-failed = Failed
+status-failed-at = { $status } ({ $stage }): { $error }
+status-needs-password = Needs a password
+status-slow-volume = Slow share
+status-slow-volume-hint = The network share holding this file responds slowly; stalled operations time out without holding up other files
+status-page-mismatch = Pages differ
+status-page-mismatch-hint = The original has { $source } pages, the result { $output }; qpdf may have lost pages while repairing a damaged file, check the result
+status-covered-text = Weak redaction
+kind-text = Text
+kind-text-hint = Text-based PDF: the text can be selected, searched and extracted
+kind-scanned = Scan
+kind-scanned-hint = Scanned PDF: the pages are only images; run OCR (the ocr stage) before searching or extracting text
+status-covered-text-hint = { $count ->
+    [one] Page { $pages } has
+   *[other] Pages { $pages } have
+} text under black boxes that can still be copied out; these "redactions" only hide it
+status-signed = Signed
+status-signed-hint = Digitally signed by { $signers }. Decrypting or any other change invalidates the signature; the result will not carry a valid one
+signed-unknown = unknown signer
+signed-by-on = { $signer } ({ $date })
+signed-title = Digitally signed files
+signed-prompt = { $count ->
+    [one] 1 file is
+   *[other] { $count } files are
+} digitally signed; processing invalidates the signatures. Continue anyway?
+large-title = Large files
+large-prompt = { $count ->
+    [one] 1 file is
+   *[other] { $count } files are
+} larger than { $limit } and may take a long time. Continue anyway?
+large-line = { $name }: { $size }
+large-line-estimate = { $name }: { $size }, about { $duration }
+large-allow-menu = Process even though it is large
+status-too-large = Left out, larger than the limit ({ $size })
+status-timeout = Timed out
+status-corrupt = Damaged file
+status-source-missing = Source file removed
+status-source-locked = In use by another program
+status-not-writable = Cannot write output
+status-output-exists = Skipped, result exists
+status-trashed = { $status }, original moved to the trash
+status-moved-original = { $status }, original moved into "originals"
+status-undone = Undone
+
+result-success = Unlocked
+result-partial = Partly done: { $success }/{ $total }
+result-failure = Unlock failed
+result-skipped = { " " }({ $count } needed no unlocking)
+result-nothing-to-unlock = None of the files need unlocking
+
+error-wrong-password = A password is required to open this file
+error-timeout = Stopped after { $seconds } seconds
+error-corrupt = The file is damaged or not a PDF: { $detail }
+error-source-missing = The source file was removed: { $detail }
+error-source-locked = The file is in use by another program: { $detail }
+error-cloud-unavailable = The cloud file could not be downloaded (offline or sync paused?): { $detail }
+error-output-not-writable = Cannot write the output file: { $detail }
+error-output-exists = The result already exists: { $detail }
+error-qpdf-missing = Could not run qpdf (place it next to the app or on PATH): { $detail }
+
+pipeline-header = Pipeline
+pipeline-add = Add step
+pipeline-unknown-step = { $id } (unknown)
+pipeline-preserve-attributes = Keep the original modified time and permissions
+pipeline-copy-unrestricted = Copy files that need no unlocking to the output folder too
+pipeline-split-by-bookmarks = Also split each result into one file per top-level bookmark
+pipeline-originals = Encrypted originals:
+pipeline-originals-hint = What happens to an original once its decrypted copy has been written and checked (readable, no longer encrypted, same page count)
+pipeline-originals-keep = Keep
+pipeline-originals-trash = Move to trash
+pipeline-originals-move = Move into "originals"
+pipeline-large-files = Files larger than
+pipeline-large-files-hint = 0 MB turns the check off. Single files can still be let through from their right-click menu
+pipeline-large-warn = Ask first
+pipeline-large-block = Leave out
+step-decrypt = Decrypt
+step-strip-metadata = Remove metadata
+step-remove-auto-actions = Remove auto-print and open actions
+step-compress = Compress
+step-linearize = Linearize
+step-extract-pages = Extract pages
+step-2-up = 2-up layout
+step-4-up = 4-up layout
+step-booklet = Booklet
+step-page-numbers = Page numbers
+step-watermark = Watermark
+step-letterhead = Letterhead
+step-drop-blank-pages = Drop blank pages
+step-crop-margins = Crop margins
+step-sign = Digital signature
+
+sign-certificate = Certificate: { $name }
+sign-none = none chosen
+sign-choose = Choose…
+sign-pick-title = Choose a signing certificate (.p12 / .pfx)
+sign-password = Certificate password (not saved)
+sign-reason = Reason for signing (optional)
+sign-visible = Show the signature on the first page
+
+undo-button = Undo
+clear-button = Clear list
+retry-locked-button = { $count ->
+    [one] Retry the file that was in use
+   *[other] Retry { $count } files that were in use
+}
+pack-button = Pack results
+pack-title = Pack results
+pack-prompt = { $count ->
+    [one] Put the result in a ZIP archive in Downloads.
+   *[other] Put the { $count } results in one ZIP archive in Downloads.
+}
+pack-password = Password (optional)
+pack-confirm = Pack
+pack-cancel = Cancel
+pack-done = Packed into { $path }
+pack-failed = Packing failed: { $error }
+undo-failed = Undo failed: { $error }
+undo-removed = { $count ->
+    [one] Deleted { $count } file
+   *[other] Deleted { $count } files
+}
+undo-restored = { $count ->
+    [one] , restored { $count } original
+   *[other] , restored { $count } originals
+}
+undo-errors = , { $count } could not be undone
+
+resume-title = Resume previous batch
+resume-prompt = { $count ->
+    [one] { $count } file was not finished last time. Continue?
+   *[other] { $count } files were not finished last time. Continue?
+}
+
+qpdf-missing-title = qpdf is required
+qpdf-missing-macos = qpdf was not found.
+
+    Install it from a terminal:
+    brew install qpdf
+
+    or download it from:
+    https://github.com/qpdf/qpdf/releases
+
+    Restart the app afterwards.
+qpdf-missing-windows = qpdf was not found.
+
+    Download the { $arch } build (e.g. qpdf-<version>-{ $arch }.zip) from:
+    https://github.com/qpdf/qpdf/releases
+
+    and put qpdf.exe next to the app.
+qpdf-missing-windows-arm64 = Windows on ARM runs this x64 build under emulation.
+qpdf-missing-other = qpdf was not found. Install it and restart the app.
+
+update-available = Version { $version } is available
+update-title = Version { $version }
+update-install = Download and install
+update-open-page = Open download page
+update-skip = Skip this version
+update-later = Later
+update-downloading = Downloading update…
+update-installed = Update installed; restart the app to use it
+update-failed = Update failed: { $error }
+
+profile-label = Profile
+output-label = Save to
+output-downloads = Downloads
+output-pin = Pin a folder…
+output-pin-title = Choose a folder to pin
+output-beside-source = Next to the source
+output-beside-source-hint = Each result goes into the folder of its original; files from an archive next to the archive, downloaded ones into Downloads
+output-suffix = _unlocked
+output-suffix-label = Suffix
+output-suffix-hint = Added to the names of the results, e.g. report_unlocked.pdf; leave empty for the default
+output-suffix-invalid = File names cannot contain that character
+output-batch-folders = New folder per batch
+output-batch-folders-hint = Put each batch's results into a subfolder named after when it started, e.g. "CrackLeaf 2024-06-01 14-30"
+output-conflict = If the name is taken:
+output-conflict-hint = What happens when the output folder already holds a file of the result's name
+output-conflict-rename = Add a number
+output-conflict-overwrite = Overwrite
+output-conflict-skip = Skip
+output-unpin = Unpin this folder
+profile-default = Default
+
+preflight-title = Start unlocking?
+preflight-encrypted = { $count } encrypted
+preflight-needs-password = { $count ->
+    [one] { $count } needs a password
+   *[other] { $count } need a password
+}
+preflight-unrestricted = { $count } already unrestricted
+preflight-unknown = { $count } not checked
+preflight-destination = Results go to: { $path }
+preflight-options = Enabled:
+
+history-button = History
+history-title = History
+history-empty = Nothing processed yet
+history-clear = Clear history
+passwords-button = Saved passwords
+passwords-title = Saved passwords
+passwords-empty = No passwords saved
+passwords-hint = Kept in the system keychain, listed by the archive each one first opened
+passwords-delete = Delete
+passwords-delete-all = Delete all
+history-status-ok = Unlocked
+history-status-unchanged = Not restricted
+history-status-failed = Failed
+history-status-failed-kind = Failed ({ $kind })
+
+new-window-button = New window
+new-window-hint = Open another window with its own file list and queue, e.g. for quick one-off files while a long batch runs here
+window-close-title = Close window
+window-close-prompt = This window's batch is still running. Closing the window cancels it; files already finished are kept. Close anyway?
+
+mini-button = Mini mode
+mini-hint = Shrink to a small drop target that stays on top of other windows
+mini-expand = Click to show the full window
+
+queue-button = Queue
+queue-title = Queue
+queue-file = File
+queue-state = State
+queue-started = Started
+queue-duration = Duration
+queue-stages = Stages applied
+queue-done = Done
+queue-unchanged = Nothing to do
+queue-failed-at = Failed at { $stage }
+queue-skipped = Skipped
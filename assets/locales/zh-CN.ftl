@@ -0,0 +1,361 @@
+language-name = 简体中文
+language-label = 语言
+language-auto = 跟随系统
+drop-filter-label = 拖入时
+drop-filter-encrypted = 仅加密的 PDF
+drop-filter-encrypted-hint = 只列出拖入文件中已加密的；无法检查的文件保留
+drop-filter-pdf = 所有 PDF
+drop-filter-pdf-hint = 列出所有 PDF；内容是 PDF 但扩展名不是的文件会先询问
+drop-filter-sniffed = 内容是 PDF 的任何文件
+drop-filter-sniffed-hint = 只要内容是 PDF 就列出，不论文件名，也不询问
+
+format-decimal-separator = .
+format-size = { $size } { $unit }
+format-seconds = { $seconds } 秒
+format-minutes = { $minutes }:{ $seconds }
+format-date = { $year }年{ $month }月{ $day }日
+format-date-time = { $year }年{ $month }月{ $day }日 { $hour }:{ $minute }
+
+hint-empty = 点击或者拖入文件
+hint-imported = 已导入 { $count } 个文件
+hint-analyzing = 正在分析 { $count } 个文件…
+file-list-header = 文件列表（{ $count }）
+processing = 处理中...
+output-fallback-title = 输出文件夹无法写入，请选择其他文件夹
+open-button = 开
+print-button = 打印
+print-hint = 发送到打印机
+print-done = 已发送 { $name } 到打印机
+print-failed = 打印失败：{ $error }
+share-button = 分享
+share-hint = 通过隔空投送、邮件或信息发送
+share-failed = 无法分享：{ $error }
+extract-pages-menu = 提取页面…
+extract-pages-title = 提取页面：{ $name }
+extract-pages-prompt = 输入要保留的页码，用逗号分隔；结尾留空表示到最后一页
+extract-pages-count = 共 { $pages } 页
+extract-pages-ok = 提取
+extract-pages-cancel = 取消
+letterhead-menu = 套用信纸…
+letterhead-pick-title = 选择信纸 PDF
+letterhead-file = 信纸：{ $name }
+letterhead-none = 未选择
+letterhead-choose = 选择…
+letterhead-underlay = 衬于页面下方
+letterhead-overlay = 叠于页面上方
+attachments-menu = 提取附件
+attachments-hint = 含 { $count } 个附件：{ $names }
+attachments-done = 已将 { $count } 个附件保存到 { $folder }
+attachments-none = 没有可提取的附件
+attachments-failed = 提取附件失败：{ $error }
+outline-menu = 查看书签…
+outline-title = 书签 - { $name }
+outline-count = 共 { $count } 个书签
+outline-entry = { $title }（第 { $page } 页）
+crop-auto = 按内容自动裁剪
+crop-margins = 固定页边距
+crop-padding = 保留留白
+crop-top = 上
+crop-right = 右
+crop-bottom = 下
+crop-left = 左
+blank-pages-hint = 含 { $count } 个空白页：第 { $pages } 页
+download-progress = 正在下载 { $url }（{ $size }）
+download-failed = 下载失败：{ $error }
+compare-size = 大小
+compare-pages = 页数
+compare-version = PDF 版本
+compare-encryption = 加密
+compare-bookmarks = 书签数
+compare-removed-metadata = 移除的元数据
+compare-yes = 是
+compare-no = 否
+compare-unknown = 未知
+compare-none = 无
+compare-button = 比较两个 PDF…
+compare-menu = 与原文件比较
+compare-title = 比较 PDF
+compare-prompt = 将两个 PDF 拖到这里，或选择文件：
+compare-first = 第一个：{ $name }
+compare-second = 第二个：{ $name }
+compare-empty = 未选择
+compare-choose = 选择…
+compare-start = 比较
+compare-failed = 无法比较：{ $error }
+compare-same = 页数、文字和元数据均相同
+compare-pages-differ = 页数不同：{ $first } → { $second }
+compare-text-differs = { $count } 页文字不同：第 { $pages } 页
+compare-metadata-differs = { $key }：{ $first } → { $second }
+protect-button = 加密文件夹…
+protect-title = 批量加密
+protect-prompt = 用 AES-256 和所有者密码加密文件夹中的每个 PDF，并限制权限；打开文件无需密码。结果以 _protected 结尾。
+protect-folder = 文件夹：{ $name }
+protect-no-folder = 未选择
+protect-choose = 选择…
+protect-password = 所有者密码
+protect-confirm = 再次输入密码
+protect-mismatch = 两次输入的密码不一致
+protect-allow = 允许：
+protect-allow-print = 打印
+protect-allow-copy = 复制文字和图片
+protect-allow-modify = 修改文档
+protect-allow-annotate = 添加注释
+protect-start = 开始加密
+
+file-encrypted = 加密受限
+file-protected = 编辑受限
+security-removable = { $cipher } 加密，仅有权限限制，无需密码即可直接移除
+security-password = { $cipher } 加密，需要打开密码
+security-password-weak = { $cipher } 加密，需要打开密码（加密强度较弱）
+security-handler = 使用 { $cipher } 加密（证书或插件），可能无法解锁
+file-unrestricted = 无需解锁
+file-unknown = 未知
+file-cloud-downloading = 正在下载云文件…
+file-cloud-unavailable = 云文件无法下载
+file-analyzing = 分析中
+file-already-unlocked = 已于 { $date } 解锁过
+
+skipped-summary = 跳过 { $items }
+skipped-not-pdf = { $count } 个非 PDF 文件
+skipped-duplicate = { $count } 个重复
+skipped-empty-folder = { $count } 个没有 PDF 的文件夹
+skipped-missing = { $count } 个不存在的文件
+skipped-archive = { $count } 个无法读取或不含 PDF 的压缩包
+skipped-mislabeled = { $count } 个没有 .pdf 扩展名的文件
+skipped-unencrypted = { $count } 个未加密的文件
+list-separator = ，
+
+archive-password-title = { $name } 的密码
+archive-password-prompt = 这个压缩包已加密，请输入密码以列出其中的 PDF。
+archive-password-wrong = 密码错误，请重试。
+archive-password-ok = 打开
+archive-password-skip = 跳过
+archive-password-remember = 记住可用的密码
+archive-password-remember-hint = 能打开压缩包的密码会保存在系统钥匙串中，之后遇到加密的压缩包和 PDF 时先自动尝试，再询问。关闭此项会删除已记住的密码。
+
+mislabeled-title = 扩展名不是 .pdf
+mislabeled-one = 这看起来是 PDF，但扩展名是 .{ $ext } — 仍然处理？
+mislabeled-no-extension = 这看起来是 PDF，但没有扩展名 — 仍然处理？
+mislabeled-many = { $count } 个文件看起来是 PDF，但扩展名不是 .pdf — 仍然处理？
+
+code-queued = 等待
+code-running = 处理
+code-done = 完成
+code-open = 无限制
+code-encrypted = 加密
+code-password = 需密码
+code-cloud = 云端
+code-failed = 失败
+code-cancelled = 取消
+high-contrast-button = 高对比度
+status-queued = 排队中
+status-analyzing = 分析中
+status-running = 解锁中
+status-stage = { $stage }中
+status-cancelled = 已取消
+status-unlocked = 解锁成功
+status-pages-extracted = 已提取第 { $pages } 页
+status-split = 已解锁，并按书签拆分为 { $count } 个文件
+status-failed = 解锁失败
+status-failed-at = { $status }（{ $stage }）：{ $error }
+status-needs-password = 需要密码
+status-slow-volume = 网络卷较慢
+status-slow-volume-hint = 这个文件所在的网络卷响应很慢；卡住的操作会超时，不影响其他文件
+status-page-mismatch = 页数不符
+status-page-mismatch-hint = 原文件 { $source } 页，解锁后 { $output } 页；qpdf 修复损坏文件时可能丢失了页面，请核对结果
+status-covered-text = 涂黑可还原
+kind-text = 文字
+kind-text-hint = 文字版 PDF：文字可以选择、搜索和提取
+kind-scanned = 扫描
+kind-scanned-hint = 扫描件：页面只有图片，需先文字识别（ocr 步骤）才能搜索或提取文字
+status-covered-text-hint = 第 { $pages } 页的黑色方块下仍有文字，可被复制或提取；这些“涂黑”并未真正删除内容
+status-signed = 已签名
+status-signed-hint = 数字签名：{ $signers }。解密或任何修改都会使签名失效，结果不再带有效签名
+signed-unknown = 未知签名人
+signed-by-on = { $signer }（{ $date }）
+signed-title = 文件带有数字签名
+signed-prompt = { $count } 个文件带有数字签名，处理后签名将失效。仍要继续吗？
+large-title = 文件较大
+large-prompt = { $count } 个文件超过 { $limit }，处理可能需要很长时间。仍要继续吗？
+large-line = { $name }：{ $size }
+large-line-estimate = { $name }：{ $size }，约需 { $duration }
+large-allow-menu = 超过大小上限也处理
+status-too-large = 已跳过，超过大小上限（{ $size }）
+status-timeout = 处理超时
+status-corrupt = 文件损坏
+status-source-missing = 源文件已移除
+status-source-locked = 文件被其他程序占用
+status-not-writable = 无法写入
+status-output-exists = 已跳过，结果已存在
+status-trashed = { $status }，原文件已移到回收站
+status-moved-original = { $status }，原文件已移入 originals 文件夹
+status-undone = 已撤销
+
+result-success = 解锁成功
+result-partial = 部分成功: { $success }/{ $total }
+result-failure = 解锁失败
+result-skipped = （{ $count } 个无需解锁）
+result-nothing-to-unlock = 所有文件都无需解锁
+
+error-wrong-password = 需要密码才能打开
+error-timeout = 超过 { $seconds } 秒未完成，已终止
+error-corrupt = 文件已损坏或不是 PDF：{ $detail }
+error-source-missing = 源文件已移除：{ $detail }
+error-source-locked = 文件被其他程序占用：{ $detail }
+error-cloud-unavailable = 云文件无法下载（离线或同步已暂停？）：{ $detail }
+error-output-not-writable = 无法写入输出文件：{ $detail }
+error-output-exists = 结果已存在：{ $detail }
+error-qpdf-missing = qpdf 执行失败（请把 qpdf 放在程序同目录或加入 PATH）：{ $detail }
+
+pipeline-header = 处理流程
+pipeline-add = 添加步骤
+pipeline-unknown-step = { $id }（未知）
+pipeline-preserve-attributes = 保留原文件的修改时间和权限
+pipeline-copy-unrestricted = 无需解锁的文件也复制到输出文件夹
+pipeline-split-by-bookmarks = 同时按一级书签将每个结果拆分为多个文件
+pipeline-originals = 加密的原文件：
+pipeline-originals-hint = 解密后的副本写好并检查无误（可以打开、不再加密、页数相同）后如何处理原文件
+pipeline-originals-keep = 保留
+pipeline-originals-trash = 移到回收站
+pipeline-originals-move = 移入 originals 文件夹
+pipeline-large-files = 文件大于
+pipeline-large-files-hint = 设为 0 MB 则不检查。单个文件可在右键菜单中放行
+pipeline-large-warn = 先询问
+pipeline-large-block = 跳过
+step-decrypt = 解密
+step-strip-metadata = 移除元数据
+step-remove-auto-actions = 移除自动打印等自动操作
+step-compress = 压缩
+step-linearize = 线性化
+step-extract-pages = 提取页面
+step-2-up = 2 合 1 排版
+step-4-up = 4 合 1 排版
+step-booklet = 小册子排版
+step-page-numbers = 添加页码
+step-watermark = 添加水印
+step-letterhead = 套用信纸
+step-drop-blank-pages = 删除空白页
+step-crop-margins = 裁剪页边距
+step-sign = 数字签名
+
+sign-certificate = 签名证书：{ $name }
+sign-none = 未选择
+sign-choose = 选择…
+sign-pick-title = 选择签名证书（.p12 / .pfx）
+sign-password = 证书密码（不会保存）
+sign-reason = 签名原因（可选）
+sign-visible = 在第一页显示签名框
+
+undo-button = 撤销
+clear-button = 清空列表
+retry-locked-button = 重试被占用的 { $count } 个文件
+pack-button = 打包结果
+pack-title = 打包结果
+pack-prompt = 把 { $count } 个结果打包成一个 ZIP，保存到下载文件夹。
+pack-password = 密码（可选）
+pack-confirm = 打包
+pack-cancel = 取消
+pack-done = 已打包到 { $path }
+pack-failed = 打包失败：{ $error }
+undo-failed = 撤销失败：{ $error }
+undo-removed = 已删除 { $count } 个文件
+undo-restored = ，恢复 { $count } 个原文件
+undo-errors = ，{ $count } 个未能撤销
+
+resume-title = 继续上次的任务
+resume-prompt = 上次还有 { $count } 个文件未处理完，是否继续？
+
+qpdf-missing-title = 需要安装 qpdf
+qpdf-missing-macos = 未检测到 qpdf。
+
+    请在终端执行：
+    brew install qpdf
+
+    或前往：
+    https://github.com/qpdf/qpdf/releases
+
+    安装完成后重启程序。
+qpdf-missing-windows = 未检测到 qpdf。
+
+    请前往：
+    https://github.com/qpdf/qpdf/releases
+
+    下载 { $arch } 版本（例如 qpdf-<version>-{ $arch }.zip），
+    解压后将 qpdf.exe 放到程序同目录。
+qpdf-missing-windows-arm64 = ARM 版 Windows 会以仿真方式运行这个 x64 版本。
+qpdf-missing-other = 未检测到 qpdf，请安装后重启程序。
+
+update-available = 发现新版本 { $version }
+update-title = 新版本 { $version }
+update-install = 下载并安装
+update-open-page = 打开下载页
+update-skip = 跳过此版本
+update-later = 稍后
+update-downloading = 正在下载更新…
+update-installed = 更新已安装，重启程序后生效
+update-failed = 更新失败：{ $error }
+
+profile-label = 配置方案
+output-label = 保存到
+output-downloads = 下载文件夹
+output-pin = 固定文件夹…
+output-pin-title = 选择要固定的文件夹
+output-beside-source = 源文件所在文件夹
+output-beside-source-hint = 结果保存在原文件旁边；压缩包里的文件放在压缩包旁边，下载的文件仍保存到下载文件夹
+output-suffix = _已解锁
+output-suffix-label = 文件名后缀
+output-suffix-hint = 加在结果文件名后，例如 报告_已解锁.pdf；留空使用默认后缀
+output-suffix-invalid = 文件名不能包含这个字符
+output-batch-folders = 每批新建文件夹
+output-batch-folders-hint = 把每批结果放进以开始时间命名的子文件夹，例如“CrackLeaf 2024-06-01 14-30”
+output-conflict = 同名文件已存在时：
+output-conflict-hint = 输出文件夹里已有与结果同名的文件时如何处理
+output-conflict-rename = 自动编号
+output-conflict-overwrite = 覆盖
+output-conflict-skip = 跳过
+output-unpin = 取消固定此文件夹
+profile-default = 默认
+
+preflight-title = 开始解锁？
+preflight-encrypted = { $count } 个已加密
+preflight-needs-password = { $count } 个需要密码
+preflight-unrestricted = { $count } 个本就没有限制
+preflight-unknown = { $count } 个未能检查
+preflight-destination = 结果保存到：{ $path }
+preflight-options = 已启用：
+
+history-button = 历史记录
+history-title = 历史记录
+history-empty = 暂无记录
+history-clear = 清除记录
+passwords-button = 已存密码
+passwords-title = 已存密码
+passwords-empty = 没有保存的密码
+passwords-hint = 密码保存在系统钥匙串中，按首次打开的压缩包列出
+passwords-delete = 删除
+passwords-delete-all = 全部删除
+history-status-ok = 已解锁
+history-status-unchanged = 无需处理
+history-status-failed = 失败
+history-status-failed-kind = 失败（{ $kind }）
+
+new-window-button = 新窗口
+new-window-hint = 打开另一个窗口，拥有独立的文件列表和队列，例如在这里处理大批文件时在另一个窗口中快速解锁单个文件
+window-close-title = 关闭窗口
+window-close-prompt = 此窗口的批处理仍在进行，关闭窗口将取消处理，已完成的文件会保留。仍要关闭吗？
+
+mini-button = 迷你模式
+mini-hint = 缩小为始终置顶的小窗口，可随时拖入文件
+mini-expand = 点击展开完整窗口
+
+queue-button = 队列
+queue-title = 队列
+queue-file = 文件
+queue-state = 状态
+queue-started = 开始
+queue-duration = 用时
+queue-stages = 已执行步骤
+queue-done = 完成
+queue-unchanged = 无需处理
+queue-failed-at = 失败于{ $stage }
+queue-skipped = 已跳过
@@ -16,11 +16,21 @@ fn main() {
     println!("cargo:rerun-if-env-changed=QPDF_PATH");
 
     let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap_or_default());
-    let tool_path = manifest_dir.join("tools").join("qpdf.exe");
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+    // `tools/<arch>/qpdf.exe`, e.g. `tools/aarch64`, wins over `tools/qpdf.exe`, so one
+    // checkout can hold a qpdf for each target.
+    let tools = manifest_dir.join("tools");
+    let tool_path = [tools.join(&target_arch), tools]
+        .into_iter()
+        .map(|dir| dir.join("qpdf.exe"))
+        .find(|path| path.exists());
 
+    // With libqpdf linked in, there is no qpdf.exe to ship.
+    let vendored = env::var_os("CARGO_FEATURE_VENDORED_QPDF").is_some();
     let qpdf_path = match env::var("QPDF_PATH").ok().filter(|s| !s.is_empty()) {
+        _ if vendored => None,
         Some(path) => Some(PathBuf::from(path)),
-        None if tool_path.exists() => Some(tool_path),
+        None if tool_path.is_some() => tool_path,
         None => {
             println!("cargo:warning=Windows build: qpdf.exe not found in tools/");
             None
@@ -35,6 +45,13 @@ fn main() {
         .unwrap_or_else(|| out_dir.clone());
 
     if let Some(qpdf_path) = qpdf_path.as_ref() {
+        let machine = pe_machine(qpdf_path);
+        if let Some(machine) = machine.filter(|&machine| !runs_on(machine, &target_arch)) {
+            println!(
+                "cargo:warning=qpdf.exe is built for {}, which does not run on {target_arch} Windows",
+                machine_name(machine)
+            );
+        }
         let dest_path = target_dir.join("qpdf.exe");
         if let Err(err) = fs::copy(qpdf_path, &dest_path) {
             println!("cargo:warning=Failed to copy qpdf.exe: {err}");
@@ -47,6 +64,19 @@ fn main() {
                     if let Some(ext) = path.extension() {
                         if ext.eq_ignore_ascii_case("dll") {
                             if let Some(file_name) = path.file_name() {
+                                // qpdf.exe can only load DLLs built for its own machine.
+                                let dll_machine = pe_machine(&path);
+                                if let (Some(dll), Some(exe)) = (dll_machine, machine) {
+                                    if machine_name(dll) != machine_name(exe) {
+                                        println!(
+                                            "cargo:warning=Skipping {}: built for {}, qpdf.exe for {}",
+                                            file_name.to_string_lossy(),
+                                            machine_name(dll),
+                                            machine_name(exe)
+                                        );
+                                        continue;
+                                    }
+                                }
                                 let dest_dll = target_dir.join(file_name);
                                 let _ = fs::copy(&path, &dest_dll);
                             }
@@ -76,8 +106,40 @@ fn main() {
     }
 }
 
+/// Machine type in the PE header of a Windows executable or DLL.
+fn pe_machine(path: &Path) -> Option<u16> {
+    let data = std::fs::read(path).ok()?;
+    let offset = u32::from_le_bytes(data.get(0x3c..0x40)?.try_into().ok()?) as usize;
+    if data.get(offset..offset + 4)? != b"PE\0\0" {
+        return None;
+    }
+    Some(u16::from_le_bytes(data.get(offset + 4..offset + 6)?.try_into().ok()?))
+}
+
+/// Architecture of a PE machine type, named like `CARGO_CFG_TARGET_ARCH`.
+fn machine_name(machine: u16) -> &'static str {
+    match machine {
+        0x014c => "x86",
+        0x8664 => "x86_64",
+        // ARM64, and ARM64EC, which ARM64 processes load too.
+        0xaa64 | 0xa641 => "aarch64",
+        _ => "an unknown machine",
+    }
+}
+
+/// Whether a binary for `machine` runs on `target_arch` Windows: ARM64 Windows runs
+/// x86 and x64 programs under emulation, x64 Windows runs x86 ones.
+fn runs_on(machine: u16, target_arch: &str) -> bool {
+    match machine_name(machine) {
+        "x86" => matches!(target_arch, "x86" | "x86_64" | "aarch64"),
+        "x86_64" => matches!(target_arch, "x86_64" | "aarch64"),
+        "aarch64" => target_arch == "aarch64",
+        _ => true,
+    }
+}
+
 fn build_icon(png_path: &Path, ico_path: &Path) -> std::io::Result<()> {
-    let image = image::open(png_path).map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err))?;
+    let image = image::open(png_path).map_err(std::io::Error::other)?;
     let resized = image.resize_exact(256, 256, FilterType::Lanczos3);
     let rgba = resized.to_rgba8();
     let icon_image = IconImage::from_rgba_data(256, 256, rgba.into_raw());
@@ -0,0 +1,112 @@
+use std::env;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+fn main() {
+    if env::var_os("CARGO_FEATURE_VENDORED_QPDF").is_none() {
+        return;
+    }
+    println!("cargo:rerun-if-env-changed=QPDF_SOURCE_DIR");
+    println!("cargo:rerun-if-env-changed=QPDF_LIB_DIR");
+    println!("cargo:rerun-if-env-changed=QPDF_DEPS_LIB_DIR");
+
+    // A libqpdf built beforehand, e.g. by a packaging script, is linked as it is.
+    let lib_dir = match env::var_os("QPDF_LIB_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => build_qpdf(),
+    };
+    let Some((dir, name)) = find_static_lib(&lib_dir) else {
+        panic!("vendored-qpdf: no static libqpdf found under {}", lib_dir.display());
+    };
+    println!("cargo:rustc-link-search=native={}", dir.display());
+    println!("cargo:rustc-link-lib=static={name}");
+
+    // libqpdf needs zlib and libjpeg; link those statically too when a folder holding
+    // them is given, so the binary runs on machines without them.
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let (zlib, jpeg) = if target_os == "windows" { ("zlib", "jpeg") } else { ("z", "jpeg") };
+    match env::var_os("QPDF_DEPS_LIB_DIR").filter(|dir| !dir.is_empty()) {
+        Some(dir) => {
+            println!("cargo:rustc-link-search=native={}", PathBuf::from(dir).display());
+            println!("cargo:rustc-link-lib=static={zlib}");
+            println!("cargo:rustc-link-lib=static={jpeg}");
+        }
+        None => {
+            println!("cargo:rustc-link-lib={zlib}");
+            println!("cargo:rustc-link-lib={jpeg}");
+        }
+    }
+    match target_os.as_str() {
+        "macos" | "ios" => println!("cargo:rustc-link-lib=c++"),
+        "windows" => {}
+        _ => println!("cargo:rustc-link-lib=stdc++"),
+    }
+}
+
+/// Configures and builds the static libqpdf from the qpdf sources in `QPDF_SOURCE_DIR`,
+/// or `vendor/qpdf` next to this file, with CMake; returns the build folder.
+fn build_qpdf() -> PathBuf {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set"));
+    let source = env::var_os("QPDF_SOURCE_DIR")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .unwrap_or_else(|| manifest_dir.join("vendor").join("qpdf"));
+    if !source.join("CMakeLists.txt").exists() {
+        panic!(
+            "vendored-qpdf: qpdf sources not found in {}; set QPDF_SOURCE_DIR to a qpdf 11 checkout",
+            source.display()
+        );
+    }
+    println!("cargo:rerun-if-changed={}", source.join("CMakeLists.txt").display());
+
+    let build = PathBuf::from(env::var("OUT_DIR").expect("OUT_DIR not set")).join("qpdf");
+    let profile = if env::var("PROFILE").as_deref() == Ok("release") { "Release" } else { "RelWithDebInfo" };
+    run(Command::new("cmake")
+        .arg("-S")
+        .arg(&source)
+        .arg("-B")
+        .arg(&build)
+        .arg(format!("-DCMAKE_BUILD_TYPE={profile}"))
+        .args([
+            "-DBUILD_SHARED_LIBS=OFF",
+            "-DBUILD_STATIC_LIBS=ON",
+            "-DCMAKE_POSITION_INDEPENDENT_CODE=ON",
+            // Native crypto keeps OpenSSL and GnuTLS out of the link.
+            "-DREQUIRE_CRYPTO_NATIVE=ON",
+            "-DUSE_IMPLICIT_CRYPTO=OFF",
+            "-DBUILD_DOC=OFF",
+            "-DINSTALL_EXAMPLES=OFF",
+        ]));
+    run(Command::new("cmake")
+        .arg("--build")
+        .arg(&build)
+        .args(["--config", profile, "--target", "libqpdf_static", "--parallel"]));
+    build
+}
+
+fn run(cmd: &mut Command) {
+    match cmd.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => panic!("vendored-qpdf: {cmd:?} failed with {status}"),
+        Err(err) => panic!("vendored-qpdf: could not run {cmd:?} (is CMake installed?): {err}"),
+    }
+}
+
+/// Folder and link name of the static libqpdf somewhere under `dir`.
+fn find_static_lib(dir: &Path) -> Option<(PathBuf, String)> {
+    const NAMES: [&str; 3] = ["libqpdf.a", "qpdf_static.lib", "qpdf.lib"];
+    for entry in std::fs::read_dir(dir).ok()?.flatten() {
+        let path = entry.path();
+        let file_name = entry.file_name();
+        if path.is_dir() {
+            if let Some(found) = find_static_lib(&path) {
+                return Some(found);
+            }
+        } else if NAMES.iter().any(|name| file_name.eq_ignore_ascii_case(name)) {
+            let stem = path.file_stem()?.to_string_lossy();
+            let name = stem.strip_prefix("lib").unwrap_or(&stem).to_string();
+            return Some((dir.to_path_buf(), name));
+        }
+    }
+    None
+}
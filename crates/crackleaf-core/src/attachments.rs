@@ -0,0 +1,109 @@
+use std::collections::HashSet;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use tracing::{debug, info, instrument};
+
+use crate::error::Error;
+use crate::output::{free_dir, nfc_name, safe_file_name};
+use crate::qpdf::{qpdf_command, run_qpdf};
+use crate::volume::{long_path, with_io_timeout};
+
+/// Names of the files embedded in the PDF at `path`, as `qpdf --list-attachments`
+/// gives them. Returns `None` when qpdf cannot read the file, e.g. without its
+/// password.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn list_attachments(path: &Path, timeout: Option<Duration>) -> Option<Vec<String>> {
+    let mut cmd = qpdf_command();
+    cmd.arg("--list-attachments").arg(&*long_path(path));
+    let output = match run_qpdf(cmd, timeout).await {
+        Ok(output) => output,
+        Err(err) => {
+            debug!("list-attachments failed: {err}");
+            return None;
+        }
+    };
+    if !matches!(output.status.code(), Some(0 | 3)) {
+        debug!(code = ?output.status.code(), "qpdf --list-attachments failed");
+        return None;
+    }
+    // One `<key> -> <object>,<generation>` line per file; more detail is indented.
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    Some(
+        stdout
+            .lines()
+            .filter(|line| !line.starts_with(char::is_whitespace))
+            .filter_map(|line| line.rsplit_once(" -> ").map(|(key, _)| key.to_string()))
+            .collect(),
+    )
+}
+
+/// Saves the files embedded in the PDF at `path` into a new folder next to it, e.g.
+/// `report_unlocked_attachments/data.xlsx`. Returns the folder and the files, or
+/// `None` when there is nothing to extract. A partly written folder is removed.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn extract_attachments(path: &Path, timeout: Option<Duration>) -> Result<Option<(PathBuf, Vec<PathBuf>)>> {
+    let Some(keys) = list_attachments(path, timeout).await else {
+        return Err(anyhow!("无法读取附件列表"));
+    };
+    if keys.is_empty() {
+        return Ok(None);
+    }
+    let mut name = path.file_stem().unwrap_or("attachments".as_ref()).to_os_string();
+    name.push("_attachments");
+    let wanted = path.with_file_name(name);
+    let dir = with_io_timeout(timeout, move || {
+        let dir = free_dir(&wanted);
+        std::fs::create_dir(&dir)?;
+        Ok(dir)
+    })
+    .await?;
+
+    let mut files = Vec::with_capacity(keys.len());
+    let mut taken = HashSet::new();
+    for key in &keys {
+        match save_attachment(path, key, &dir, &mut taken, timeout).await {
+            Ok(file) => files.push(file),
+            Err(err) => {
+                let _ = std::fs::remove_dir_all(&dir);
+                return Err(err);
+            }
+        }
+    }
+    info!(dir = %dir.display(), files = files.len(), "extracted attachments");
+    Ok(Some((dir, files)))
+}
+
+/// Writes the attachment `key` of `path` into `dir` under a name not in `taken`.
+async fn save_attachment(
+    path: &Path,
+    key: &str,
+    dir: &Path,
+    taken: &mut HashSet<String>,
+    timeout: Option<Duration>,
+) -> Result<PathBuf> {
+    let mut cmd = qpdf_command();
+    cmd.arg(format!("--show-attachment={key}")).arg(&*long_path(path));
+    let output = run_qpdf(cmd, timeout).await?;
+    if !matches!(output.status.code(), Some(0 | 3)) {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::from_qpdf_stderr(output.status.code(), &stderr).into());
+    }
+
+    // Keys are often paths or repeat, which a folder cannot hold as they are.
+    let base = safe_file_name(key.rsplit(['/', '\\']).next().unwrap_or(key));
+    let mut name = base.clone();
+    let mut index = 1;
+    while !taken.insert(name.to_lowercase()) {
+        name = match base.rsplit_once('.') {
+            Some((stem, extension)) if !stem.is_empty() => format!("{stem}_{index}.{extension}"),
+            _ => format!("{base}_{index}"),
+        };
+        index += 1;
+    }
+    let file = dir.join(nfc_name(name.as_ref()));
+    let target = long_path(&file).into_owned();
+    with_io_timeout(timeout, move || std::fs::write(target, output.stdout)).await?;
+    Ok(file)
+}
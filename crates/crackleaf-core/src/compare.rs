@@ -0,0 +1,126 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use lopdf::{decode_text_string, Document, Object};
+use serde::Serialize;
+use tracing::{info, instrument};
+
+use crate::volume::{long_path, with_io_timeout};
+
+/// How two PDFs differ, e.g. a restricted original and its unlocked copy.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PdfDiff {
+    /// Page counts of the first and the second file.
+    pub pages: (u32, u32),
+    /// Pages, numbered from 1 and present in both, whose text differs. Spacing is
+    /// ignored.
+    pub text_pages: Vec<u32>,
+    /// Document information entries that differ, by key.
+    pub metadata: Vec<MetadataDiff>,
+}
+
+/// A document information entry with its value in each file; `None` where it is missing.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct MetadataDiff {
+    pub key: String,
+    pub first: Option<String>,
+    pub second: Option<String>,
+}
+
+impl PdfDiff {
+    /// Both files have the same pages, text and metadata.
+    pub fn is_same(&self) -> bool {
+        self.pages.0 == self.pages.1 && self.text_pages.is_empty() && self.metadata.is_empty()
+    }
+}
+
+/// Compares the page count, the text of each page and the document information of
+/// two PDFs. Files that need a password to open cannot be compared.
+#[instrument(skip(timeout), fields(first = %first.display(), second = %second.display()))]
+pub async fn compare_pdfs(first: &Path, second: &Path, timeout: Option<Duration>) -> Result<PdfDiff> {
+    let (first, second) = (long_path(first).into_owned(), long_path(second).into_owned());
+    let (first, second) = with_io_timeout(timeout, move || {
+        let read = |path: &Path| Contents::read(path).map_err(std::io::Error::other);
+        Ok((read(&first)?, read(&second)?))
+    })
+    .await?;
+
+    let text_pages = first
+        .text
+        .iter()
+        .zip(&second.text)
+        .zip(1..)
+        .filter(|((a, b), _)| a != b)
+        .map(|(_, page)| page)
+        .collect();
+    let keys: BTreeSet<&String> = first.info.keys().chain(second.info.keys()).collect();
+    let metadata = keys
+        .into_iter()
+        .filter(|key| first.info.get(*key) != second.info.get(*key))
+        .map(|key| MetadataDiff {
+            key: key.clone(),
+            first: first.info.get(key).cloned(),
+            second: second.info.get(key).cloned(),
+        })
+        .collect();
+    let diff = PdfDiff {
+        pages: (first.text.len() as u32, second.text.len() as u32),
+        text_pages,
+        metadata,
+    };
+    info!(same = diff.is_same(), "compared");
+    Ok(diff)
+}
+
+/// What [`compare_pdfs`] looks at in one file.
+struct Contents {
+    /// Text of each page with spacing removed; `None` where it could not be read.
+    text: Vec<Option<String>>,
+    info: BTreeMap<String, String>,
+}
+
+impl Contents {
+    fn read(path: &Path) -> Result<Self> {
+        let mut doc = Document::load(path).map_err(|err| anyhow!("无法读取 {}：{err}", path.display()))?;
+        if doc.is_encrypted() {
+            doc.decrypt("").map_err(|_| anyhow!("{} 需要密码才能打开", path.display()))?;
+        }
+        let text = doc
+            .get_pages()
+            .into_keys()
+            .map(|page| {
+                let text = doc.extract_text(&[page]).ok()?;
+                Some(text.split_whitespace().collect())
+            })
+            .collect();
+        let info = doc
+            .trailer
+            .get(b"Info")
+            .ok()
+            .and_then(|info| doc.dereference(info).ok()?.1.as_dict().ok())
+            .map(|info| {
+                info.iter()
+                    .map(|(key, value)| (String::from_utf8_lossy(key).into_owned(), describe(&doc, value)))
+                    .collect()
+            })
+            .unwrap_or_default();
+        Ok(Self { text, info })
+    }
+}
+
+/// An information entry's value as text.
+fn describe(doc: &Document, value: &Object) -> String {
+    let value = doc.dereference(value).map_or(value, |(_, value)| value);
+    match value {
+        Object::String(..) => decode_text_string(value)
+            .map(|text| text.trim_start_matches('\u{feff}').to_string())
+            .unwrap_or_else(|_| String::from_utf8_lossy(value.as_str().unwrap_or_default()).into_owned()),
+        Object::Name(name) => String::from_utf8_lossy(name).into_owned(),
+        Object::Integer(number) => number.to_string(),
+        Object::Real(number) => number.to_string(),
+        Object::Boolean(value) => value.to_string(),
+        _ => "…".to_string(),
+    }
+}
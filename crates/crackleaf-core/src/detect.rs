@@ -0,0 +1,375 @@
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use lopdf::{Document, Object};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, instrument, warn};
+
+use crate::office::{is_office_document, is_protected};
+use crate::qpdf::{qpdf_command, run_qpdf};
+use crate::trailer::trailer_encryption;
+use crate::volume::{long_path, with_io_timeout, IO_TIMEOUT};
+
+/// How far into a file the `%PDF-` header may start. Readers accept leading junk
+/// (a BOM, mail headers) up to this point.
+const HEADER_WINDOW: u64 = 1024;
+
+/// Returns `true` when the file starts with a PDF header, whatever its extension.
+/// Unreadable files are not PDFs.
+pub fn is_pdf(path: &Path) -> bool {
+    let mut head = Vec::new();
+    let read = File::open(path).and_then(|file| file.take(HEADER_WINDOW).read_to_end(&mut head));
+    if let Err(err) = read {
+        debug!("cannot read {:?}: {err}", path);
+        return false;
+    }
+    head.windows(PDF_MAGIC.len()).any(|window| window == PDF_MAGIC)
+}
+
+const PDF_MAGIC: &[u8] = b"%PDF-";
+
+/// Tells whether the file is encrypted, from its trailer or, when that is
+/// inconclusive, by asking `qpdf --show-encryption`.
+///
+/// Returns `None` when qpdf fails, times out or its output cannot be interpreted, and
+/// when reading the file takes longer than [`IO_TIMEOUT`].
+/// Answers are cached per path, modification time and size, so asking again about
+/// an unchanged file does not read it again.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn detect_encrypted(path: &Path, timeout: Option<Duration>) -> Option<bool> {
+    let native_path = path.to_path_buf();
+    let read = with_io_timeout(Some(IO_TIMEOUT), move || {
+        let key = CacheKey::of(&native_path);
+        if let Some(detection) = key.as_ref().and_then(|key| cache().get(key)) {
+            return Ok((key, Some(detection.encrypted), true));
+        }
+        Ok((key, trailer_encryption(&native_path), false))
+    });
+    let (key, encrypted) = match read.await {
+        Ok((_, Some(encrypted), true)) => {
+            debug!(encrypted, "detection cached");
+            return Some(encrypted);
+        }
+        Ok((key, Some(encrypted), _)) => {
+            debug!(encrypted, "detected from trailer");
+            (key, Some(encrypted))
+        }
+        Ok((key, None, _)) => (key, probe(path, timeout).await),
+        // qpdf would stall on the same volume.
+        Err(err) => {
+            warn!("Reading the file failed: {err}");
+            return None;
+        }
+    };
+    if let (Some(key), Some(encrypted)) = (key, encrypted) {
+        cache().insert(
+            key,
+            Detection {
+                encrypted,
+                needs_password: None,
+            },
+        );
+    }
+    encrypted
+}
+
+/// Whether `path` is an encrypted PDF or a protected Office document; `None` when
+/// that cannot be told.
+pub async fn is_restricted(path: &Path, timeout: Option<Duration>) -> Option<bool> {
+    if is_office_document(path) {
+        let document = path.to_path_buf();
+        with_io_timeout(timeout, move || Ok(is_protected(&document))).await.ok().flatten()
+    } else {
+        detect_encrypted(path, timeout).await
+    }
+}
+
+/// Asks `qpdf --requires-password` whether an encrypted file can only be opened with
+/// a user password, as opposed to merely carrying restrictions.
+///
+/// Returns `None` when qpdf fails or the file turns out not to be encrypted. Cached
+/// like [`detect_encrypted`].
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn requires_password(path: &Path, timeout: Option<Duration>) -> Option<bool> {
+    let key = CacheKey::of(path);
+    if let Some(needs_password) = key
+        .as_ref()
+        .and_then(|key| cache().get(key))
+        .and_then(|detection| detection.needs_password)
+    {
+        return Some(needs_password);
+    }
+
+    let mut cmd = qpdf_command();
+    cmd.arg("--requires-password").arg(&*long_path(path));
+    let output = match run_qpdf(cmd, timeout).await {
+        Ok(output) => output,
+        Err(err) => {
+            debug!("password check failed: {err}");
+            return None;
+        }
+    };
+    // 0: a password is required, 3: encrypted without one, 2: not encrypted or unreadable.
+    let needs_password = match output.status.code() {
+        Some(0) => true,
+        Some(3) => false,
+        code => {
+            debug!(?code, "qpdf --requires-password inconclusive");
+            return None;
+        }
+    };
+    debug!(needs_password, "password check");
+    if let Some(key) = key {
+        cache().insert(
+            key,
+            Detection {
+                encrypted: true,
+                needs_password: Some(needs_password),
+            },
+        );
+    }
+    Some(needs_password)
+}
+
+/// The cipher of an encrypted PDF and its key length, e.g. `RC4-40`, `RC4-128`,
+/// `AES-128` or `AES-256`, read from its encryption dictionary, which needs no
+/// password. Files that use another security handler than passwords give its name,
+/// e.g. `Adobe.PubSec` for certificates.
+///
+/// Returns `None` when the file cannot be read or is not encrypted.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn encryption_method(path: &Path, timeout: Option<Duration>) -> Option<String> {
+    let file = long_path(path).into_owned();
+    let read = with_io_timeout(timeout, move || {
+        let doc = Document::load(&file).map_err(std::io::Error::other)?;
+        Ok(doc.get_encrypted().ok().and_then(cipher))
+    })
+    .await;
+    match read {
+        Ok(method) => method,
+        Err(err) => {
+            debug!("reading the encryption failed: {err}");
+            None
+        }
+    }
+}
+
+fn cipher(dict: &lopdf::Dictionary) -> Option<String> {
+    let name = |object: &Object| object.as_name().ok().map(|name| String::from_utf8_lossy(name).into_owned());
+    let filter = dict.get(b"Filter").ok().and_then(name);
+    if filter.as_deref().is_some_and(|filter| filter != "Standard") {
+        return filter;
+    }
+    let bits = dict.get(b"Length").and_then(Object::as_i64).unwrap_or(40);
+    let method = match dict.get(b"V").and_then(Object::as_i64).unwrap_or(0) {
+        0 | 1 => "RC4-40".to_string(),
+        2 | 3 => format!("RC4-{bits}"),
+        4 => {
+            // The standard crypt filter names the cipher.
+            let method = dict
+                .get(b"CF")
+                .and_then(Object::as_dict)
+                .and_then(|filters| filters.get(b"StdCF"))
+                .and_then(Object::as_dict)
+                .ok()
+                .and_then(|filter| filter.get(b"CFM").ok())
+                .and_then(name);
+            match method.as_deref() {
+                Some("AESV2") => "AES-128".to_string(),
+                Some("AESV3") => "AES-256".to_string(),
+                _ => "RC4-128".to_string(),
+            }
+        }
+        _ => "AES-256".to_string(),
+    };
+    Some(method)
+}
+
+/// Number of pages, from `qpdf --show-npages`.
+///
+/// Returns `None` when qpdf cannot open the file (e.g. it needs a password), fails or
+/// times out. Not cached: it is asked once per source and once per result.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn page_count(path: &Path, timeout: Option<Duration>) -> Option<u32> {
+    let mut cmd = qpdf_command();
+    cmd.arg("--show-npages").arg(&*long_path(path));
+    let output = match run_qpdf(cmd, timeout).await {
+        Ok(output) => output,
+        Err(err) => {
+            debug!("page count failed: {err}");
+            return None;
+        }
+    };
+    // 3: succeeded with warnings, e.g. after repairing the file.
+    if !matches!(output.status.code(), Some(0 | 3)) {
+        debug!(code = ?output.status.code(), "qpdf --show-npages failed");
+        return None;
+    }
+    String::from_utf8_lossy(&output.stdout).trim().parse().ok()
+}
+
+async fn probe(path: &Path, timeout: Option<Duration>) -> Option<bool> {
+    let mut cmd = qpdf_command();
+    cmd.arg("--show-encryption").arg(&*long_path(path));
+
+    let output = match run_qpdf(cmd, timeout).await {
+        Ok(output) => output,
+        Err(err) => {
+            debug!("detection failed: {err}");
+            return None;
+        }
+    };
+
+    if !output.status.success() {
+        debug!(code = ?output.status.code(), "qpdf --show-encryption failed");
+        return None;
+    }
+
+    let stdout = String::from_utf8_lossy(&output.stdout).to_lowercase();
+    if stdout.contains("file is encrypted")
+        || stdout.contains("encryption: yes")
+        || stdout.contains("user password")
+        || stdout.contains("owner password")
+    {
+        Some(true)
+    } else if stdout.contains("file is not encrypted") || stdout.contains("not encrypted") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Entries kept in the on-disk cache; the in-memory one may hold twice as many.
+const CACHE_LIMIT: usize = 5000;
+
+#[derive(Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct CacheKey {
+    #[serde(with = "crate::path_serde")]
+    path: PathBuf,
+    modified: SystemTime,
+    size: u64,
+}
+
+impl CacheKey {
+    fn of(path: &Path) -> Option<Self> {
+        let metadata = std::fs::metadata(path).ok()?;
+        Some(Self {
+            path: std::path::absolute(path).ok()?,
+            modified: metadata.modified().ok()?,
+            size: metadata.len(),
+        })
+    }
+}
+
+#[derive(Clone, Copy, Serialize, Deserialize)]
+struct Detection {
+    encrypted: bool,
+    /// Only known once [`requires_password`] was asked.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    needs_password: Option<bool>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheEntry {
+    #[serde(flatten)]
+    key: CacheKey,
+    #[serde(flatten)]
+    detection: Detection,
+}
+
+#[derive(Default)]
+struct DetectionCache {
+    /// Result and insertion order of each key.
+    entries: HashMap<CacheKey, (Detection, u64)>,
+    next: u64,
+    file: Option<PathBuf>,
+    dirty: bool,
+}
+
+impl DetectionCache {
+    fn get(&self, key: &CacheKey) -> Option<Detection> {
+        self.entries.get(key).map(|(detection, _)| *detection)
+    }
+
+    fn insert(&mut self, key: CacheKey, detection: Detection) {
+        if self.entries.len() >= CACHE_LIMIT * 2 {
+            self.prune(CACHE_LIMIT);
+        }
+        self.next += 1;
+        self.entries.insert(key, (detection, self.next));
+        self.dirty = true;
+    }
+
+    /// Keeps the `limit` newest entries.
+    fn prune(&mut self, limit: usize) {
+        if self.entries.len() <= limit {
+            return;
+        }
+        let mut order: Vec<u64> = self.entries.values().map(|(_, order)| *order).collect();
+        order.sort_unstable();
+        let oldest_kept = order[order.len() - limit];
+        self.entries.retain(|_, (_, order)| *order >= oldest_kept);
+    }
+}
+
+fn cache() -> MutexGuard<'static, DetectionCache> {
+    static CACHE: OnceLock<Mutex<DetectionCache>> = OnceLock::new();
+    CACHE
+        .get_or_init(Mutex::default)
+        .lock()
+        .unwrap_or_else(|err| err.into_inner())
+}
+
+/// Loads earlier detection results from `path` and keeps it as the file
+/// [`save_detection_cache`] writes to. Without it results are only cached in memory.
+pub fn load_detection_cache(path: &Path) {
+    let mut cache = cache();
+    cache.file = Some(path.to_path_buf());
+    let entries = match std::fs::read(path) {
+        Ok(data) => serde_json::from_slice::<Vec<CacheEntry>>(&data).unwrap_or_else(|err| {
+            warn!("Ignoring unreadable detection cache {:?}: {err}", path);
+            Vec::new()
+        }),
+        Err(_) => return,
+    };
+    for entry in entries {
+        cache.next += 1;
+        let order = cache.next;
+        cache.entries.entry(entry.key).or_insert((entry.detection, order));
+    }
+    debug!(entries = cache.entries.len(), "detection cache loaded");
+}
+
+/// Writes new detection results to the file given to [`load_detection_cache`].
+pub fn save_detection_cache() {
+    let mut cache = cache();
+    let Some(path) = cache.file.clone().filter(|_| cache.dirty) else {
+        return;
+    };
+    cache.prune(CACHE_LIMIT);
+    let mut entries: Vec<_> = cache.entries.iter().collect();
+    entries.sort_unstable_by_key(|(_, (_, order))| *order);
+    let entries: Vec<_> = entries
+        .into_iter()
+        .map(|(key, (detection, _))| CacheEntry {
+            key: key.clone(),
+            detection: *detection,
+        })
+        .collect();
+    let result = serde_json::to_vec(&entries).map_err(std::io::Error::from).and_then(|data| {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, data)?;
+        std::fs::rename(&tmp, &path)
+    });
+    match result {
+        Ok(()) => cache.dirty = false,
+        Err(err) => warn!("Failed to save detection cache {:?}: {err}", path),
+    }
+}
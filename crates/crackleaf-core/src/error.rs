@@ -0,0 +1,88 @@
+use std::time::Duration;
+
+/// Why a file could not be processed.
+///
+/// Engine internals use `anyhow`; errors that carry one of these variants anywhere
+/// in their chain keep their kind when reported through
+/// [`JobEvent`](crate::JobEvent), everything else becomes [`Error::Other`].
+#[derive(Clone, Debug, PartialEq, Eq, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    /// The qpdf binary could not be launched.
+    #[error("qpdf 执行失败（请把 qpdf 放在程序同目录或加入 PATH）：{0}")]
+    QpdfMissing(String),
+    /// The file needs a user password to open.
+    #[error("需要密码才能打开")]
+    WrongPassword,
+    /// The result could not be written.
+    #[error("无法写入输出文件：{0}")]
+    OutputNotWritable(String),
+    /// The result's name was taken and [`ConflictPolicy::Skip`](crate::ConflictPolicy::Skip)
+    /// left the input out.
+    #[error("结果已存在：{0}")]
+    OutputExists(String),
+    /// The input is damaged or not a PDF.
+    #[error("文件已损坏或不是 PDF：{0}")]
+    Corrupt(String),
+    /// The input was deleted, moved or its volume unmounted after it was added.
+    #[error("源文件已移除：{0}")]
+    SourceMissing(String),
+    /// Another program holds the input open exclusively (Windows).
+    #[error("文件被其他程序占用：{0}")]
+    SourceLocked(String),
+    /// The input is a cloud placeholder (iCloud Drive, OneDrive) that could not be downloaded.
+    #[error("云文件无法下载：{0}")]
+    CloudUnavailable(String),
+    /// A single tool call ran longer than the configured limit.
+    #[error("超过 {} 秒未完成，已终止", .0.as_secs())]
+    Timeout(Duration),
+    #[error("{0}")]
+    Other(String),
+}
+
+impl Error {
+    /// Stable, machine-readable name of the variant.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Error::QpdfMissing(_) => "qpdf-missing",
+            Error::WrongPassword => "wrong-password",
+            Error::OutputNotWritable(_) => "output-not-writable",
+            Error::OutputExists(_) => "output-exists",
+            Error::Corrupt(_) => "corrupt",
+            Error::SourceMissing(_) => "source-missing",
+            Error::SourceLocked(_) => "source-locked",
+            Error::CloudUnavailable(_) => "cloud-unavailable",
+            Error::Timeout(_) => "timeout",
+            Error::Other(_) => "other",
+        }
+    }
+
+    /// Finds the typed error inside an `anyhow` chain.
+    pub fn from_anyhow(err: &anyhow::Error) -> Self {
+        err.chain()
+            .find_map(|cause| cause.downcast_ref::<Error>())
+            .cloned()
+            .unwrap_or_else(|| Error::Other(format!("{err:#}")))
+    }
+
+    /// Classifies a failed qpdf run from its stderr.
+    pub(crate) fn from_qpdf_stderr(code: Option<i32>, stderr: &str) -> Self {
+        let lower = stderr.to_lowercase();
+        if lower.contains("invalid password") {
+            Error::WrongPassword
+        } else if lower.contains("permission denied")
+            || lower.contains("read-only file system")
+            || lower.contains("no space left")
+        {
+            Error::OutputNotWritable(stderr.to_string())
+        } else if lower.contains("not a pdf file")
+            || lower.contains("damaged")
+            || lower.contains("unable to find trailer")
+            || lower.contains("can't find pdf header")
+        {
+            Error::Corrupt(stderr.to_string())
+        } else {
+            Error::Other(format!("qpdf 退出码 {code:?}：{stderr}"))
+        }
+    }
+}
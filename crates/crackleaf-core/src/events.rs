@@ -0,0 +1,149 @@
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tracing::Level;
+
+use crate::error::Error;
+use crate::inspect::PdfFacts;
+use crate::job::JobState;
+
+/// Identifies one batch among the events on a shared [`EventBus`].
+pub type BatchId = u64;
+
+/// Something that happened in the engine.
+#[derive(Clone, Debug)]
+pub enum Event {
+    /// A batch of `total` files was started.
+    BatchStarted { batch: BatchId, total: usize },
+    /// Lifecycle change of the file at `index` in the batch's input list.
+    Job {
+        batch: BatchId,
+        index: usize,
+        event: JobEvent,
+    },
+    /// `done` of the batch's `total` files have finished.
+    Progress {
+        batch: BatchId,
+        done: usize,
+        total: usize,
+    },
+    /// Diagnostic line for log views, e.g. the reason a file failed.
+    Log { level: Level, message: String },
+    /// Short note meant for the user, e.g. a warning about the chosen folders.
+    Notification { batch: Option<BatchId>, message: String },
+    /// All files have been processed or the batch was cancelled.
+    BatchFinished { batch: BatchId, cancelled: bool },
+}
+
+/// Lifecycle of one file, see [`Event::Job`].
+#[derive(Clone, Debug)]
+pub enum JobEvent {
+    /// The file moved to [`JobState::Analyzing`] or [`JobState::Running`].
+    StateChanged(JobState),
+    /// The file entered the pipeline stage with the given display name.
+    StageChanged(String),
+    /// The stage with the given display name changed the file.
+    StageApplied(String),
+    /// The result was also split into these files, one per top-level bookmark.
+    Split(Vec<PathBuf>),
+    /// The file is done.
+    ///
+    /// A successful result without `output_path` means every stage skipped the
+    /// file (e.g. it was not encrypted) and nothing was written. On failure,
+    /// `stage` names the stage that failed; [`Error::OutputExists`] means the file
+    /// was skipped.
+    Finished {
+        success: bool,
+        output_path: Option<PathBuf>,
+        error: Option<Error>,
+        stage: Option<String>,
+        elapsed: Duration,
+        /// Whether the result, or the source when nothing was written, is still
+        /// encrypted or protected; `None` on failure or when that cannot be told.
+        restricted: Option<bool>,
+        /// Facts of a PDF result, with [`UnlockOptions::inspect_results`](crate::UnlockOptions::inspect_results).
+        output_facts: Option<Box<PdfFacts>>,
+    },
+}
+
+type Listener = Box<dyn Fn(&Event) + Send + Sync>;
+
+#[derive(Default)]
+struct Inner {
+    subscribers: Mutex<Vec<UnboundedSender<Event>>>,
+    listeners: Mutex<Vec<Listener>>,
+    next_batch: AtomicU64,
+}
+
+/// Fan-out of engine [`Event`]s to any number of consumers.
+///
+/// A bus is meant to live as long as the frontend: every batch started with the
+/// same [`UnlockOptions::events`](crate::UnlockOptions::events) publishes to it, and
+/// events carry their [`BatchId`]. Cloning shares the bus.
+#[derive(Clone, Default)]
+pub struct EventBus {
+    inner: Arc<Inner>,
+}
+
+impl std::fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventBus").finish_non_exhaustive()
+    }
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queues every event published from now on until the subscription is dropped.
+    pub fn subscribe(&self) -> Subscription {
+        let (tx, rx) = mpsc::unbounded_channel();
+        lock(&self.inner.subscribers).push(tx);
+        Subscription { rx }
+    }
+
+    /// Calls `listener` for every event published from now on, on the publishing
+    /// thread. Keep it short, e.g. `move |_| ctx.request_repaint()`.
+    pub fn listen<F>(&self, listener: F)
+    where
+        F: Fn(&Event) + Send + Sync + 'static,
+    {
+        lock(&self.inner.listeners).push(Box::new(listener));
+    }
+
+    pub fn publish(&self, event: Event) {
+        lock(&self.inner.subscribers).retain(|tx| tx.send(event.clone()).is_ok());
+        for listener in lock(&self.inner.listeners).iter() {
+            listener(&event);
+        }
+    }
+
+    pub(crate) fn next_batch_id(&self) -> BatchId {
+        self.inner.next_batch.fetch_add(1, Ordering::Relaxed) + 1
+    }
+}
+
+fn lock<T>(mutex: &Mutex<T>) -> std::sync::MutexGuard<'_, T> {
+    mutex.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+/// Events queued for one consumer, see [`EventBus::subscribe`].
+pub struct Subscription {
+    rx: UnboundedReceiver<Event>,
+}
+
+impl Subscription {
+    /// Returns the next queued event without waiting.
+    pub fn try_next(&mut self) -> Option<Event> {
+        self.rx.try_recv().ok()
+    }
+
+    /// Waits for the next event.
+    pub async fn next(&mut self) -> Option<Event> {
+        self.rx.recv().await
+    }
+}
@@ -0,0 +1,283 @@
+use std::fs::File;
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+use lopdf::Document;
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use crate::attachments::list_attachments;
+use crate::detect::{detect_encrypted, page_count};
+use crate::outline::{outline, Bookmark};
+use crate::qpdf::{qpdf_command, run_qpdf};
+use crate::signatures::{signatures, Signature};
+use crate::steps::{blank_pages, classify, covered_text_pages, PdfKind};
+use crate::volume::{long_path, with_io_timeout};
+
+/// What the before/after comparison of a source and its result looks at.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct PdfFacts {
+    /// File size in bytes.
+    pub size: u64,
+    /// Version in the header, e.g. `1.7`.
+    pub version: Option<String>,
+    pub pages: Option<u32>,
+    pub encrypted: Option<bool>,
+    /// Text-based or scanned; `None` when the file could not be read or is empty.
+    pub kind: Option<PdfKind>,
+    /// Pages that print nothing, numbered from 1; empty when there are none or the
+    /// file could not be checked.
+    pub blank_pages: Vec<u32>,
+    /// Pages where black boxes only cover text that can still be copied out, i.e.
+    /// redactions that can be undone.
+    pub covered_text_pages: Vec<u32>,
+    /// Names of the embedded files.
+    pub attachments: Vec<String>,
+    /// Bookmarks, to check that chapters survived; empty when there are none or
+    /// the file could not be read.
+    pub outline: Vec<Bookmark>,
+    /// Signed signature fields, which unlocking invalidates.
+    pub signatures: Vec<Signature>,
+    /// Keys of the document information dictionary, e.g. `Author` or `Producer`.
+    pub info: Vec<String>,
+    /// The document catalog references an XMP metadata stream.
+    pub xmp: bool,
+}
+
+impl PdfFacts {
+    /// Metadata present in `source` but not in `self`: information dictionary keys,
+    /// and `XMP` when the metadata stream was dropped.
+    pub fn removed_metadata(&self, source: &PdfFacts) -> Vec<String> {
+        let mut removed: Vec<String> = source
+            .info
+            .iter()
+            .filter(|key| !self.info.contains(key))
+            .cloned()
+            .collect();
+        if source.xmp && !self.xmp {
+            removed.push("XMP".to_string());
+        }
+        removed
+    }
+}
+
+/// Collects the [`PdfFacts`] of `path` with a few short qpdf calls. Returns `None`
+/// when the file cannot be read; facts qpdf cannot tell (e.g. the metadata of a file
+/// that needs a password) are left empty.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn inspect_pdf(path: &Path, timeout: Option<Duration>) -> Option<PdfFacts> {
+    let file = path.to_path_buf();
+    let (size, version) = with_io_timeout(timeout, move || {
+        let size = std::fs::metadata(&file)?.len();
+        Ok((size, header_version(&file)))
+    })
+    .await
+    .ok()?;
+    let mut facts = PdfFacts {
+        size,
+        version,
+        pages: page_count(path, timeout).await,
+        encrypted: detect_encrypted(path, timeout).await,
+        attachments: list_attachments(path, timeout).await.unwrap_or_default(),
+        ..PdfFacts::default()
+    };
+    // The checks that walk the whole document share one parse of it.
+    let file = long_path(path).into_owned();
+    let read = with_io_timeout(timeout, move || {
+        let doc = read_document(&file)?;
+        Ok((
+            classify(&doc),
+            blank_pages(&doc),
+            covered_text_pages(&doc),
+            outline(&doc),
+            signatures(&doc),
+        ))
+    })
+    .await;
+    match read {
+        Ok((kind, blank, covered, bookmarks, signed)) => {
+            facts.kind = kind;
+            facts.blank_pages = blank;
+            facts.covered_text_pages = covered;
+            facts.outline = bookmarks;
+            facts.signatures = signed;
+        }
+        Err(err) => debug!("document checks failed: {err}"),
+    }
+    let Some(trailer) = show_object(path, "trailer", timeout).await else {
+        return Some(facts);
+    };
+    let trailer = dict_entries(&trailer);
+    if let Some(info) = resolve(path, &trailer, "Info", timeout).await {
+        facts.info = dict_entries(&info).into_iter().map(|(key, _)| key).collect();
+    }
+    if let Some(root) = resolve(path, &trailer, "Root", timeout).await {
+        facts.xmp = dict_entries(&root).iter().any(|(key, _)| key == "Metadata");
+    }
+    Some(facts)
+}
+
+/// Loads the PDF at `path` for reading, decrypted with the empty password when it
+/// is encrypted.
+pub(crate) fn read_document(path: &Path) -> std::io::Result<Document> {
+    let mut doc = Document::load(path).map_err(std::io::Error::other)?;
+    if doc.is_encrypted() {
+        doc.decrypt("").map_err(std::io::Error::other)?;
+    }
+    Ok(doc)
+}
+
+/// The version after `%PDF-` in the file's header.
+fn header_version(path: &Path) -> Option<String> {
+    let mut head = Vec::new();
+    File::open(path).ok()?.take(1024).read_to_end(&mut head).ok()?;
+    let start = head.windows(5).position(|window| window == b"%PDF-")? + 5;
+    let version: String = head[start..]
+        .iter()
+        .take_while(|byte| byte.is_ascii_digit() || **byte == b'.')
+        .map(|byte| char::from(*byte))
+        .collect();
+    (!version.is_empty()).then_some(version)
+}
+
+/// The dictionary stored under `key` in `entries`, either inline or as an indirect
+/// object that is fetched from qpdf.
+async fn resolve(
+    path: &Path,
+    entries: &[(String, Vec<String>)],
+    key: &str,
+    timeout: Option<Duration>,
+) -> Option<String> {
+    let (_, value) = entries.iter().find(|(name, _)| name == key)?;
+    match value.as_slice() {
+        [dict] if dict.starts_with("<<") => Some(dict.clone()),
+        [object, generation, reference] if reference == "R" => {
+            show_object(path, &format!("{object},{generation}"), timeout).await
+        }
+        _ => None,
+    }
+}
+
+/// `qpdf --show-object=<object>`, or `None` when qpdf fails.
+async fn show_object(path: &Path, object: &str, timeout: Option<Duration>) -> Option<String> {
+    let mut cmd = qpdf_command();
+    cmd.arg(format!("--show-object={object}")).arg(&*long_path(path));
+    let output = match run_qpdf(cmd, timeout).await {
+        Ok(output) => output,
+        Err(err) => {
+            debug!(object, "show-object failed: {err}");
+            return None;
+        }
+    };
+    if !matches!(output.status.code(), Some(0 | 3)) {
+        debug!(object, code = ?output.status.code(), "qpdf --show-object failed");
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Top-level entries of a dictionary as qpdf prints it: each key without its `/`,
+/// and the tokens of its value, nested arrays, dictionaries and strings being one
+/// token each.
+fn dict_entries(text: &str) -> Vec<(String, Vec<String>)> {
+    let text = text.trim();
+    let Some(inner) = text.strip_prefix("<<").and_then(|rest| rest.strip_suffix(">>")) else {
+        return Vec::new();
+    };
+    let mut entries: Vec<(String, Vec<String>)> = Vec::new();
+    for token in tokens(inner) {
+        match (token.strip_prefix('/'), entries.last_mut()) {
+            // A name is the value of a key that has none yet, otherwise a new key.
+            (Some(_), Some((_, value))) if value.is_empty() => value.push(token),
+            (Some(key), _) => entries.push((key.to_string(), Vec::new())),
+            (None, Some((_, value))) => value.push(token),
+            (None, None) => {}
+        }
+    }
+    entries
+}
+
+/// Splits PDF syntax into tokens, keeping arrays, dictionaries and strings whole.
+fn tokens(text: &str) -> Vec<String> {
+    let bytes = text.as_bytes();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        let start = i;
+        match bytes[i] {
+            byte if byte.is_ascii_whitespace() => {
+                i += 1;
+                continue;
+            }
+            b'(' | b'[' | b'<' => i = composite_end(bytes, i),
+            b'/' => i = word_end(bytes, i + 1),
+            _ => i = word_end(bytes, i + 1).max(i + 1),
+        }
+        tokens.push(text[start..i.min(bytes.len())].to_string());
+    }
+    tokens
+}
+
+/// Index just past the name, number or keyword continuing at `i`.
+fn word_end(bytes: &[u8], mut i: usize) -> usize {
+    while i < bytes.len() && !bytes[i].is_ascii_whitespace() && !b"()<>[]{}/%".contains(&bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Index just past the string, hex string, array or dictionary starting at `i`.
+fn composite_end(bytes: &[u8], mut i: usize) -> usize {
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'(' => i = string_end(bytes, i),
+            b'<' if bytes.get(i + 1) == Some(&b'<') => {
+                depth += 1;
+                i += 2;
+            }
+            b'>' if bytes.get(i + 1) == Some(&b'>') => {
+                depth = depth.saturating_sub(1);
+                i += 2;
+            }
+            b'<' => {
+                i = bytes[i..].iter().position(|&byte| byte == b'>').map_or(bytes.len(), |end| i + end + 1);
+            }
+            b'[' => {
+                depth += 1;
+                i += 1;
+            }
+            b']' => {
+                depth = depth.saturating_sub(1);
+                i += 1;
+            }
+            _ => i += 1,
+        }
+        if depth == 0 {
+            return i;
+        }
+    }
+    i
+}
+
+/// Index just past the literal string starting at `i`, which may hold balanced
+/// parentheses and escapes.
+fn string_end(bytes: &[u8], mut i: usize) -> usize {
+    let mut depth = 0usize;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'\\' => i += 1,
+            b'(' => depth += 1,
+            b')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return i + 1;
+                }
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    i
+}
@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use crate::error::Error;
+use crate::events::JobEvent;
+
+/// Lifecycle of a single file in a batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum JobState {
+    Queued,
+    Analyzing,
+    Running,
+    Done,
+    Failed,
+    Cancelled,
+    /// Left out of the batch because there was nothing to do, e.g. not encrypted, or
+    /// because its result already exists ([`Error::OutputExists`]).
+    Skipped,
+}
+
+impl JobState {
+    /// `true` once the job can no longer change state.
+    pub fn is_terminal(self) -> bool {
+        matches!(
+            self,
+            JobState::Done | JobState::Failed | JobState::Cancelled | JobState::Skipped
+        )
+    }
+}
+
+/// One file of a batch together with its progress.
+#[derive(Clone, Debug)]
+pub struct Job {
+    pub path: PathBuf,
+    pub state: JobState,
+    /// Decrypted copy, if one was written. A `Done` job without output was not encrypted.
+    pub output_path: Option<PathBuf>,
+    pub error: Option<Error>,
+    /// Pipeline stage currently running, or the one that failed.
+    pub stage: Option<String>,
+    /// Stages that changed the file so far, by display name.
+    pub applied: Vec<String>,
+    /// When the engine picked the job up.
+    pub started: Option<Instant>,
+    pub elapsed: Option<Duration>,
+    /// Files the result was split into by [`UnlockOptions::split_by_bookmarks`](crate::UnlockOptions::split_by_bookmarks).
+    pub parts: Vec<PathBuf>,
+}
+
+/// Batch of jobs driven by the [`JobEvent`]s of [`run_unlock`](crate::run_unlock).
+///
+/// Job indices match the order of the paths handed to `run_unlock`.
+#[derive(Clone, Debug, Default)]
+pub struct JobQueue {
+    jobs: Vec<Job>,
+}
+
+impl JobQueue {
+    pub fn new(paths: impl IntoIterator<Item = PathBuf>) -> Self {
+        let jobs = paths
+            .into_iter()
+            .map(|path| Job {
+                path,
+                state: JobState::Queued,
+                output_path: None,
+                error: None,
+                stage: None,
+                applied: Vec::new(),
+                started: None,
+                elapsed: None,
+                parts: Vec::new(),
+            })
+            .collect();
+        Self { jobs }
+    }
+
+    pub fn jobs(&self) -> &[Job] {
+        &self.jobs
+    }
+
+    pub fn job(&self, index: usize) -> Option<&Job> {
+        self.jobs.get(index)
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.jobs.iter().map(|job| job.path.clone()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.jobs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.jobs.is_empty()
+    }
+
+    /// Number of jobs currently in `state`.
+    pub fn count(&self, state: JobState) -> usize {
+        self.jobs.iter().filter(|job| job.state == state).count()
+    }
+
+    /// Number of jobs that reached a terminal state.
+    pub fn finished_count(&self) -> usize {
+        self.jobs.iter().filter(|job| job.state.is_terminal()).count()
+    }
+
+    /// `true` when every job reached a terminal state.
+    pub fn is_finished(&self) -> bool {
+        self.jobs.iter().all(|job| job.state.is_terminal())
+    }
+
+    /// Updates the job at `index` from an engine event.
+    pub fn apply(&mut self, index: usize, event: &JobEvent) {
+        let Some(job) = self.jobs.get_mut(index) else {
+            return;
+        };
+        match event {
+            JobEvent::StateChanged(state) => {
+                if !job.state.is_terminal() {
+                    job.state = *state;
+                    job.started.get_or_insert_with(Instant::now);
+                }
+            }
+            JobEvent::StageChanged(stage) => job.stage = Some(stage.clone()),
+            JobEvent::StageApplied(stage) => job.applied.push(stage.clone()),
+            JobEvent::Split(parts) => job.parts = parts.clone(),
+            JobEvent::Finished {
+                success,
+                output_path,
+                error,
+                stage,
+                elapsed,
+                ..
+            } => {
+                job.state = match error {
+                    _ if *success => JobState::Done,
+                    Some(Error::OutputExists(_)) => JobState::Skipped,
+                    _ => JobState::Failed,
+                };
+                job.output_path = output_path.clone();
+                job.error = error.clone();
+                job.stage = stage.clone();
+                job.elapsed = Some(*elapsed);
+            }
+        }
+    }
+
+    /// Adds a queued job for `path` at the end.
+    pub fn push(&mut self, path: PathBuf) {
+        self.jobs.push(Job {
+            path,
+            state: JobState::Queued,
+            output_path: None,
+            error: None,
+            stage: None,
+            applied: Vec::new(),
+            started: None,
+            elapsed: None,
+            parts: Vec::new(),
+        });
+    }
+
+    /// Puts the job at `index` back in the queue, e.g. to retry it in another batch.
+    pub fn requeue(&mut self, index: usize) {
+        if let Some(job) = self.jobs.get_mut(index) {
+            job.state = JobState::Queued;
+            job.output_path = None;
+            job.error = None;
+            job.stage = None;
+            job.applied.clear();
+            job.started = None;
+            job.elapsed = None;
+            job.parts.clear();
+        }
+    }
+
+    /// Marks the job at `index` as [`JobState::Skipped`]; it is not handed to the engine.
+    pub fn skip(&mut self, index: usize) {
+        if let Some(job) = self.jobs.get_mut(index) {
+            job.state = JobState::Skipped;
+        }
+    }
+
+    /// Marks every job that has not finished yet as cancelled.
+    pub fn cancel(&mut self) {
+        for job in &mut self.jobs {
+            if !job.state.is_terminal() {
+                job.state = JobState::Cancelled;
+            }
+        }
+    }
+}
@@ -0,0 +1,258 @@
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use serde::{Deserialize, Serialize};
+use tracing::{debug, warn};
+
+use crate::output::move_file;
+
+/// One change made by a batch.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum JournalEntry {
+    /// A new file or folder was written.
+    Created {
+        #[serde(with = "crate::path_serde")]
+        path: PathBuf,
+    },
+    /// `original` was moved to `backup` before being replaced or trashed.
+    Replaced {
+        #[serde(with = "crate::path_serde")]
+        original: PathBuf,
+        #[serde(with = "crate::path_serde")]
+        backup: PathBuf,
+    },
+    /// A source was moved out of the way, e.g. into an `originals` folder.
+    Moved {
+        #[serde(with = "crate::path_serde")]
+        from: PathBuf,
+        #[serde(with = "crate::path_serde")]
+        to: PathBuf,
+    },
+}
+
+/// What [`Journal::undo`] managed to revert.
+#[derive(Debug, Default)]
+pub struct UndoReport {
+    pub removed: Vec<PathBuf>,
+    pub restored: Vec<PathBuf>,
+    /// Entries that could not be reverted, with the reason.
+    pub errors: Vec<(PathBuf, String)>,
+}
+
+/// Append-only record of the files one batch wrote or replaced.
+///
+/// Each entry is flushed to `<dir>/<id>.jsonl` as soon as it is recorded, so a batch
+/// interrupted by a crash can still be undone. Backups of replaced originals live in
+/// `<dir>/<id>.d`.
+#[derive(Debug)]
+pub struct Journal {
+    path: PathBuf,
+    file: Mutex<Option<File>>,
+}
+
+impl Journal {
+    /// Starts a new journal in `dir`.
+    pub fn create(dir: &Path) -> Result<Self> {
+        std::fs::create_dir_all(dir)?;
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let mut suffix = 0;
+        let mut path = dir.join(format!("{millis:020}-{suffix:03}.jsonl"));
+        while path.exists() {
+            suffix += 1;
+            path = dir.join(format!("{millis:020}-{suffix:03}.jsonl"));
+        }
+        let file = OpenOptions::new().create_new(true).append(true).open(&path)?;
+        Ok(Self {
+            path,
+            file: Mutex::new(Some(file)),
+        })
+    }
+
+    /// Opens the most recent journal in `dir` that recorded anything.
+    pub fn latest(dir: &Path) -> Option<Self> {
+        list_journals(dir)
+            .into_iter()
+            .rev()
+            .map(|path| Self {
+                path,
+                file: Mutex::new(None),
+            })
+            .find(|journal| !journal.is_empty())
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether nothing has been recorded yet.
+    pub fn is_empty(&self) -> bool {
+        std::fs::metadata(&self.path).map_or(true, |meta| meta.len() == 0)
+    }
+
+    fn backup_dir(&self) -> PathBuf {
+        self.path.with_extension("d")
+    }
+
+    pub fn record_created(&self, path: &Path) -> Result<()> {
+        self.append(&JournalEntry::Created {
+            path: path.to_path_buf(),
+        })
+    }
+
+    pub fn record_moved(&self, from: &Path, to: &Path) -> Result<()> {
+        self.append(&JournalEntry::Moved {
+            from: from.to_path_buf(),
+            to: to.to_path_buf(),
+        })
+    }
+
+    /// Moves `original` into the journal's backup folder so it can be restored later.
+    pub fn backup_original(&self, original: &Path) -> Result<PathBuf> {
+        let backup = self.backup_path(original)?;
+        move_file(original, &backup)?;
+        self.record_replaced(original, &backup)?;
+        Ok(backup)
+    }
+
+    /// Copies `original` into the journal's backup folder, for an original about to go
+    /// where undo cannot fetch it back from, such as the trash.
+    pub fn backup_copy(&self, original: &Path) -> Result<PathBuf> {
+        let backup = self.backup_path(original)?;
+        std::fs::copy(original, &backup)?;
+        self.record_replaced(original, &backup)?;
+        Ok(backup)
+    }
+
+    /// A free name for a backup of `original`.
+    fn backup_path(&self, original: &Path) -> Result<PathBuf> {
+        let dir = self.backup_dir();
+        std::fs::create_dir_all(&dir)?;
+        let name = original
+            .file_name()
+            .ok_or_else(|| anyhow!("无效的文件路径：{}", original.display()))?;
+        let mut backup = dir.join(name);
+        let mut index = 0;
+        while backup.exists() {
+            index += 1;
+            let mut numbered = OsString::from(format!("{index}-"));
+            numbered.push(name);
+            backup = dir.join(numbered);
+        }
+        Ok(backup)
+    }
+
+    fn record_replaced(&self, original: &Path, backup: &Path) -> Result<()> {
+        self.append(&JournalEntry::Replaced {
+            original: original.to_path_buf(),
+            backup: backup.to_path_buf(),
+        })
+    }
+
+    fn append(&self, entry: &JournalEntry) -> Result<()> {
+        let mut guard = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        if guard.is_none() {
+            *guard = Some(OpenOptions::new().create(true).append(true).open(&self.path)?);
+        }
+        let file = guard.as_mut().expect("journal file opened above");
+        let mut line = serde_json::to_string(entry)?;
+        line.push('\n');
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Entries in the order they were recorded. Unreadable lines are skipped.
+    pub fn entries(&self) -> Result<Vec<JournalEntry>> {
+        let reader = BufReader::new(File::open(&self.path)?);
+        let mut entries = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(&line) {
+                Ok(entry) => entries.push(entry),
+                Err(err) => warn!("Skipping journal line in {:?}: {err}", self.path),
+            }
+        }
+        Ok(entries)
+    }
+
+    /// Reverts every entry, newest first: created files are deleted and originals
+    /// are moved back. The journal is removed once everything was reverted.
+    pub fn undo(&self) -> Result<UndoReport> {
+        let mut report = UndoReport::default();
+        for entry in self.entries()?.into_iter().rev() {
+            match entry {
+                // Folders are recorded before their files, so they are empty by now.
+                JournalEntry::Created { path } => match remove_created(&path) {
+                    Ok(()) => report.removed.push(path),
+                    Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                        debug!(path = %path.display(), "already gone");
+                    }
+                    Err(err) => report.errors.push((path, err.to_string())),
+                },
+                JournalEntry::Replaced { original: from, backup: to } | JournalEntry::Moved { from, to } => {
+                    match move_file(&to, &from) {
+                        Ok(()) => report.restored.push(from),
+                        Err(err) => report.errors.push((from, err.to_string())),
+                    }
+                }
+            }
+        }
+        if report.errors.is_empty() {
+            self.discard();
+        }
+        Ok(report)
+    }
+
+    /// Deletes the journal and its backups, making the batch permanent.
+    pub fn discard(&self) {
+        let _ = std::fs::remove_dir_all(self.backup_dir());
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Removes a file, or a folder when it is empty.
+fn remove_created(path: &Path) -> std::io::Result<()> {
+    if path.is_dir() {
+        std::fs::remove_dir(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// Deletes all but the newest `keep` journals in `dir`.
+pub fn prune_journals(dir: &Path, keep: usize) {
+    let journals = list_journals(dir);
+    let excess = journals.len().saturating_sub(keep);
+    for path in journals.into_iter().take(excess) {
+        Journal {
+            path,
+            file: Mutex::new(None),
+        }
+        .discard();
+    }
+}
+
+/// Journal files in `dir`, oldest first.
+fn list_journals(dir: &Path) -> Vec<PathBuf> {
+    let Ok(read_dir) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut journals: Vec<PathBuf> = read_dir
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .collect();
+    journals.sort();
+    journals
+}
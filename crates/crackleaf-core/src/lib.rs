@@ -0,0 +1,83 @@
+//! Unlocking engine behind CrackLeaf.
+//!
+//! The heavy lifting is done by an external `qpdf` binary; this crate locates it,
+//! probes whether files are encrypted and writes decrypted copies next to the
+//! user's downloads. Word and Excel documents take a built-in path instead, which
+//! removes their editing protection.
+//!
+//! All process work runs on tokio. [`run_unlock`] can be awaited from an existing
+//! runtime; [`spawn_unlock`] starts a batch on a shared runtime and hands back an
+//! [`UnlockHandle`] for synchronous callers such as a UI thread. Progress is published
+//! as [`Event`]s on the [`EventBus`] in [`UnlockOptions::events`].
+//!
+//! ```no_run
+//! use crackleaf_core::{block_on, check_qpdf_ready, spawn_unlock, Event, UnlockOptions};
+//!
+//! let status = block_on(check_qpdf_ready());
+//! assert!(status.ok, "{:?}", status.error);
+//!
+//! let options = UnlockOptions::default();
+//! let mut events = options.events.subscribe();
+//! let batch = spawn_unlock(vec!["report.pdf".into()], options);
+//! block_on(async {
+//!     while let Some(event) = events.next().await {
+//!         if let Event::BatchFinished { .. } = event {
+//!             break;
+//!         }
+//!     }
+//! });
+//! drop(batch);
+//! ```
+
+mod attachments;
+mod compare;
+mod detect;
+mod error;
+mod events;
+mod inspect;
+mod job;
+mod journal;
+#[cfg(feature = "vendored-qpdf")]
+mod libqpdf;
+mod office;
+mod options;
+mod outline;
+mod output;
+pub mod path_serde;
+mod qpdf;
+mod runtime;
+mod session;
+mod signatures;
+mod split;
+pub mod steps;
+mod trailer;
+mod unlock;
+mod volume;
+
+pub use attachments::{extract_attachments, list_attachments};
+pub use compare::{compare_pdfs, MetadataDiff, PdfDiff};
+pub use detect::{
+    detect_encrypted, encryption_method, is_pdf, is_restricted, load_detection_cache, page_count, requires_password,
+    save_detection_cache,
+};
+pub use error::Error;
+pub use events::{BatchId, Event, EventBus, JobEvent, Subscription};
+pub use inspect::{inspect_pdf, PdfFacts};
+pub use job::{Job, JobQueue, JobState};
+pub use journal::{prune_journals, Journal, JournalEntry, UndoReport};
+pub use office::{is_office_document, is_protected};
+pub use options::{default_concurrency, format_size, scale_size, UnlockOptions, DEFAULT_TIMEOUT, NETWORK_CONCURRENCY_LIMIT};
+pub use outline::{read_outline, Bookmark};
+pub use output::{
+    check_suffix, forbidden_in_name, move_file, nfc_name, resolve_download_dir, unique_output_path, ConflictPolicy,
+    MAX_SUFFIX_LEN, PROTECTED_SUFFIX, UNLOCKED_SUFFIX,
+};
+pub use qpdf::{
+    check_qpdf_ready, qpdf_filename, qpdf_windows_variant, resolve_qpdf_command, set_process_limit, QpdfStatus,
+};
+pub use runtime::{block_on, runtime};
+pub use session::{pending_files, BatchSession};
+pub use signatures::{read_signatures, Signature};
+pub use unlock::{run_unlock, spawn_unlock, unlock_pdf, UnlockHandle};
+pub use volume::{available_space, is_cloud_placeholder, is_network_path, materialize, with_io_timeout, IO_TIMEOUT};
+pub use tokio_util::sync::CancellationToken;
@@ -0,0 +1,131 @@
+use std::ffi::{c_char, c_int, c_void, CStr, OsStr, OsString};
+use std::io;
+use std::process::{ExitStatus, Output};
+
+/// Destination of a `qpdflogger` stream handed to a callback.
+const QPDF_LOG_DEST_CUSTOM: c_int = 4;
+
+type LogFn = unsafe extern "C" fn(data: *const c_char, len: usize, udata: *mut c_void) -> c_int;
+
+extern "C" {
+    fn qpdf_get_qpdf_version() -> *const c_char;
+    fn qpdfjob_init() -> *mut c_void;
+    fn qpdfjob_cleanup(job: *mut *mut c_void);
+    fn qpdfjob_set_logger(job: *mut c_void, logger: *mut c_void);
+    #[cfg_attr(not(windows), link_name = "qpdfjob_initialize_from_argv")]
+    #[cfg_attr(windows, link_name = "qpdfjob_initialize_from_wide_argv")]
+    fn qpdfjob_initialize(job: *mut c_void, argv: *const *const Char) -> c_int;
+    fn qpdfjob_run(job: *mut c_void) -> c_int;
+    fn qpdflogger_create() -> *mut c_void;
+    fn qpdflogger_cleanup(logger: *mut *mut c_void);
+    fn qpdflogger_set_info(logger: *mut c_void, dest: c_int, handler: Option<LogFn>, udata: *mut c_void);
+    fn qpdflogger_set_warn(logger: *mut c_void, dest: c_int, handler: Option<LogFn>, udata: *mut c_void);
+    fn qpdflogger_set_error(logger: *mut c_void, dest: c_int, handler: Option<LogFn>, udata: *mut c_void);
+    fn qpdflogger_set_save(
+        logger: *mut c_void,
+        dest: c_int,
+        handler: Option<LogFn>,
+        udata: *mut c_void,
+        only_if_not_set: c_int,
+    );
+}
+
+/// Version of the linked libqpdf.
+pub(crate) fn version() -> String {
+    // SAFETY: returns a static, NUL-terminated string.
+    unsafe { CStr::from_ptr(qpdf_get_qpdf_version()) }.to_string_lossy().into_owned()
+}
+
+/// Runs a qpdf job with the command line arguments `args`, without the program name,
+/// and returns what the `qpdf` executable would have printed and its exit code.
+pub(crate) fn run(args: &[OsString]) -> io::Result<Output> {
+    let argv = Argv::new(args)?;
+    let mut stdout = Vec::<u8>::new();
+    let mut stderr = Vec::<u8>::new();
+    // SAFETY: the buffers outlive the job and its logger, which are cleaned up before
+    // returning; the argument array is null-terminated and only read during initialisation.
+    let code = unsafe {
+        let mut logger = qpdflogger_create();
+        let out = (&mut stdout as *mut Vec<u8>).cast();
+        let err = (&mut stderr as *mut Vec<u8>).cast();
+        qpdflogger_set_info(logger, QPDF_LOG_DEST_CUSTOM, Some(append), out);
+        qpdflogger_set_save(logger, QPDF_LOG_DEST_CUSTOM, Some(append), out, 0);
+        qpdflogger_set_warn(logger, QPDF_LOG_DEST_CUSTOM, Some(append), err);
+        qpdflogger_set_error(logger, QPDF_LOG_DEST_CUSTOM, Some(append), err);
+        let mut job = qpdfjob_init();
+        qpdfjob_set_logger(job, logger);
+        let code = match qpdfjob_initialize(job, argv.pointers().as_ptr()) {
+            0 => qpdfjob_run(job),
+            code => code,
+        };
+        qpdfjob_cleanup(&mut job);
+        qpdflogger_cleanup(&mut logger);
+        code
+    };
+    Ok(Output {
+        status: exit_status(code),
+        stdout,
+        stderr,
+    })
+}
+
+/// Logger callback appending to the `Vec<u8>` behind `udata`.
+unsafe extern "C" fn append(data: *const c_char, len: usize, udata: *mut c_void) -> c_int {
+    let buffer = &mut *udata.cast::<Vec<u8>>();
+    buffer.extend_from_slice(std::slice::from_raw_parts(data.cast(), len));
+    0
+}
+
+/// Character type of the arguments libqpdf takes: UTF-16 on Windows, bytes elsewhere.
+#[cfg(not(windows))]
+type Char = c_char;
+#[cfg(windows)]
+type Char = u16;
+
+/// NUL-terminated arguments, with `qpdf` as the program name.
+struct Argv(Vec<Vec<Char>>);
+
+impl Argv {
+    fn new(args: &[OsString]) -> io::Result<Self> {
+        std::iter::once(OsStr::new("qpdf"))
+            .chain(args.iter().map(OsString::as_os_str))
+            .map(|arg| {
+                let units = encode(arg);
+                if units.contains(&0) {
+                    return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("argument contains NUL: {arg:?}")));
+                }
+                Ok(units.into_iter().chain([0]).collect())
+            })
+            .collect::<io::Result<_>>()
+            .map(Self)
+    }
+
+    /// Pointers to the arguments, ending with a null pointer.
+    fn pointers(&self) -> Vec<*const Char> {
+        self.0.iter().map(|arg| arg.as_ptr()).chain([std::ptr::null()]).collect()
+    }
+}
+
+#[cfg(not(windows))]
+fn encode(arg: &OsStr) -> Vec<Char> {
+    use std::os::unix::ffi::OsStrExt;
+    arg.as_bytes().iter().map(|&byte| c_char::from_ne_bytes([byte])).collect()
+}
+
+#[cfg(windows)]
+fn encode(arg: &OsStr) -> Vec<Char> {
+    use std::os::windows::ffi::OsStrExt;
+    arg.encode_wide().collect()
+}
+
+#[cfg(unix)]
+fn exit_status(code: c_int) -> ExitStatus {
+    use std::os::unix::process::ExitStatusExt;
+    ExitStatus::from_raw(code << 8)
+}
+
+#[cfg(windows)]
+fn exit_status(code: c_int) -> ExitStatus {
+    use std::os::windows::process::ExitStatusExt;
+    ExitStatus::from_raw(code as u32)
+}
@@ -0,0 +1,158 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use tracing::debug;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// Word and Excel documents, with or without macros.
+const EXTENSIONS: &[&str] = &["docx", "docm", "xlsx", "xlsm"];
+
+/// Elements that restrict editing, by the parts they live in. Removing them lifts
+/// the restriction; the content itself is never encrypted by them.
+const PROTECTION: &[(&str, &[&str])] = &[
+    ("word/settings.xml", &["documentProtection", "writeProtection"]),
+    ("xl/workbook.xml", &["workbookProtection", "fileSharing"]),
+    ("xl/worksheets/", &["sheetProtection"]),
+    ("xl/chartsheets/", &["sheetProtection"]),
+];
+
+/// Start of the compound file that Office uses for documents with an open password.
+const CFB_MAGIC: &[u8] = &[0xD0, 0xCF, 0x11, 0xE0];
+
+/// `true` for Word and Excel documents (`.docx`, `.xlsx` and their macro-enabled
+/// variants), which [`run_unlock`](crate::run_unlock) handles without qpdf.
+pub fn is_office_document(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| EXTENSIONS.iter().any(|known| ext.eq_ignore_ascii_case(known)))
+}
+
+/// Tells whether an Office document restricts editing (document, workbook or sheet
+/// protection). Returns `None` when it cannot be read, e.g. because it needs an open
+/// password, which is file encryption rather than protection.
+pub fn is_protected(path: &Path) -> Option<bool> {
+    let mut zip = ZipArchive::new(File::open(path).ok()?).ok()?;
+    for index in 0..zip.len() {
+        let mut part = zip.by_index(index).ok()?;
+        let Some(names) = protection_elements(part.name()) else {
+            continue;
+        };
+        let mut xml = String::new();
+        part.read_to_string(&mut xml).ok()?;
+        if strip_elements(&xml, names).is_some() {
+            return Some(true);
+        }
+    }
+    Some(false)
+}
+
+/// Copies `input` to `output` without its protection elements. Parts that carry
+/// none are copied as they are. Returns `false`, writing nothing, when there was no
+/// protection to remove.
+pub(crate) fn remove_protection(input: &Path, output: &Path) -> Result<bool> {
+    let mut source = File::open(input)?;
+    let mut magic = [0u8; 4];
+    if source.read_exact(&mut magic).is_ok() && magic == CFB_MAGIC {
+        bail!("文档设有打开密码，无法解除");
+    }
+    let mut zip = ZipArchive::new(source)?;
+    let mut parts = Vec::new();
+    for index in 0..zip.len() {
+        let mut part = zip.by_index(index)?;
+        let Some(names) = protection_elements(part.name()) else {
+            continue;
+        };
+        let mut xml = String::new();
+        part.read_to_string(&mut xml)?;
+        if let Some(stripped) = strip_elements(&xml, names) {
+            debug!(part = part.name(), "protection removed");
+            parts.push((index, stripped));
+        }
+    }
+    if parts.is_empty() {
+        return Ok(false);
+    }
+
+    let mut writer = ZipWriter::new(File::create(output)?);
+    for index in 0..zip.len() {
+        match parts.iter().find(|(changed, _)| *changed == index) {
+            Some((_, xml)) => {
+                let part = zip.by_index_raw(index)?;
+                let name = part.name().to_string();
+                let mut options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+                if let Some(modified) = part.last_modified() {
+                    options = options.last_modified_time(modified);
+                }
+                drop(part);
+                writer.start_file(name, options)?;
+                writer.write_all(xml.as_bytes())?;
+            }
+            None => writer.raw_copy_file(zip.by_index_raw(index)?)?,
+        }
+    }
+    writer.finish()?;
+    Ok(true)
+}
+
+/// Protection elements that may appear in the part called `name`.
+fn protection_elements(name: &str) -> Option<&'static [&'static str]> {
+    PROTECTION.iter().find_map(|(part, elements)| {
+        let matches = if part.ends_with('/') {
+            name.starts_with(part) && name.ends_with(".xml") && !name[part.len()..].contains('/')
+        } else {
+            name == *part
+        };
+        matches.then_some(*elements)
+    })
+}
+
+/// `xml` without any element whose local name is in `names`, whatever its namespace
+/// prefix; `None` when there was none. Protection elements carry only attributes, but
+/// a non-empty one is removed up to its closing tag.
+fn strip_elements(xml: &str, names: &[&str]) -> Option<String> {
+    let mut out = String::with_capacity(xml.len());
+    let mut rest = xml;
+    let mut removed = false;
+    while let Some(start) = rest.find('<') {
+        let tag = &rest[start + 1..];
+        let qname_len = tag
+            .find(|c: char| c.is_whitespace() || c == '/' || c == '>')
+            .unwrap_or(tag.len());
+        let qname = &tag[..qname_len];
+        let local = qname.rsplit(':').next().unwrap_or(qname);
+        let Some(end) = tag_end(tag).filter(|_| names.contains(&local)) else {
+            out.push_str(&rest[..start + 1]);
+            rest = tag;
+            continue;
+        };
+        let mut after = &tag[end + 1..];
+        if !tag[..end].ends_with('/') {
+            let closing = format!("</{qname}>");
+            if let Some(close) = after.find(&closing) {
+                after = &after[close + closing.len()..];
+            }
+        }
+        out.push_str(&rest[..start]);
+        rest = after;
+        removed = true;
+    }
+    out.push_str(rest);
+    removed.then_some(out)
+}
+
+/// Position of the `>` closing the tag that `tag` starts, skipping quoted attribute values.
+fn tag_end(tag: &str) -> Option<usize> {
+    let mut quote = None;
+    for (position, c) in tag.char_indices() {
+        match (quote, c) {
+            (None, '"' | '\'') => quote = Some(c),
+            (Some(open), _) if c == open => quote = None,
+            (None, '>') => return Some(position),
+            _ => {}
+        }
+    }
+    None
+}
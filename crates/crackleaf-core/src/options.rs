@@ -0,0 +1,181 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::events::EventBus;
+use crate::journal::Journal;
+use crate::session::BatchSession;
+use crate::steps::{Decrypt, ProcessingStep};
+use crate::output::{resolve_download_dir, ConflictPolicy, UNLOCKED_SUFFIX};
+use crate::volume::{available_space, is_network_path};
+
+/// Default limit for a single qpdf call.
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(600);
+
+/// Above this many parallel jobs, network shares tend to slow down rather than speed up.
+pub const NETWORK_CONCURRENCY_LIMIT: usize = 4;
+
+/// Tunables for [`run_unlock`](crate::run_unlock).
+#[derive(Clone, Debug)]
+pub struct UnlockOptions {
+    /// Number of files processed at the same time. Values below 1 are treated as 1.
+    pub concurrency: usize,
+    /// Limit for each qpdf call; `None` waits indefinitely.
+    pub timeout: Option<Duration>,
+    /// Stages each file goes through, in order. Defaults to decryption only.
+    pub pipeline: Vec<Arc<dyn ProcessingStep>>,
+    /// Folder for the results; `None` uses the downloads folder.
+    pub output_dir: Option<PathBuf>,
+    /// Write each result into the folder of its source instead of `output_dir`.
+    pub beside_source: bool,
+    /// Folder standing in for the source's own with `beside_source`, for inputs that
+    /// are temporary copies, e.g. the folder of the archive a file was unpacked from.
+    pub source_folders: HashMap<PathBuf, PathBuf>,
+    /// Added to each result's file name, e.g. `_unlocked` for `report_unlocked.pdf`.
+    pub suffix: String,
+    /// What to do when a result's name is taken by an existing file.
+    pub on_conflict: ConflictPolicy,
+    /// Subfolder of the output folder for particular inputs, e.g. the archive a file
+    /// was unpacked from. Other inputs go straight into the output folder.
+    pub output_subdirs: HashMap<PathBuf, PathBuf>,
+    /// Subfolder of every output folder shared by the whole batch, e.g. one named
+    /// after when it started, so the results of different runs stay apart.
+    pub batch_folder: Option<PathBuf>,
+    /// Give each result the modified time and permissions (attributes on Windows)
+    /// of its source.
+    pub preserve_attributes: bool,
+    /// Copy files that every stage skipped (e.g. not encrypted) to the output folder
+    /// as they are, instead of writing nothing for them.
+    pub copy_unchanged: bool,
+    /// Also write one file per top-level bookmark of each PDF result, into a folder
+    /// next to it.
+    pub split_by_bookmarks: bool,
+    /// Collect the [`PdfFacts`](crate::PdfFacts) of each PDF result for
+    /// [`JobEvent::Finished`](crate::JobEvent::Finished), e.g. for a before/after comparison.
+    pub inspect_results: bool,
+    /// Open passwords tried in turn on PDFs the empty password does not open, e.g.
+    /// ones that worked before.
+    pub passwords: Vec<String>,
+    /// Records every written file so the batch can be undone.
+    pub journal: Option<Arc<Journal>>,
+    /// Tracks finished inputs so an interrupted batch can be resumed.
+    pub session: Option<Arc<BatchSession>>,
+    /// Where the batch publishes its progress. Defaults to a bus nobody listens to.
+    pub events: EventBus,
+}
+
+impl Default for UnlockOptions {
+    fn default() -> Self {
+        Self {
+            concurrency: default_concurrency(),
+            timeout: Some(DEFAULT_TIMEOUT),
+            pipeline: vec![Arc::new(Decrypt)],
+            output_dir: None,
+            beside_source: false,
+            source_folders: HashMap::new(),
+            suffix: UNLOCKED_SUFFIX.to_string(),
+            on_conflict: ConflictPolicy::Rename,
+            output_subdirs: HashMap::new(),
+            batch_folder: None,
+            preserve_attributes: false,
+            copy_unchanged: false,
+            split_by_bookmarks: false,
+            inspect_results: false,
+            passwords: Vec::new(),
+            journal: None,
+            session: None,
+            events: EventBus::new(),
+        }
+    }
+}
+
+impl UnlockOptions {
+    /// `true` when running an unencrypted file would do nothing: every stage only
+    /// handles encrypted files and unchanged files are not copied. Callers can then
+    /// leave such files out of the batch.
+    pub fn skips_unencrypted(&self) -> bool {
+        !self.copy_unchanged
+            && !self.pipeline.is_empty()
+            && self.pipeline.iter().all(|step| step.encrypted_only())
+    }
+
+    /// Warns when a high concurrency is combined with inputs on a network share.
+    pub fn network_warning(&self, files: &[PathBuf]) -> Option<String> {
+        if self.concurrency <= NETWORK_CONCURRENCY_LIMIT {
+            return None;
+        }
+        let on_network = files.iter().filter(|path| is_network_path(path)).count();
+        if on_network == 0 {
+            return None;
+        }
+        Some(format!(
+            "{on_network} 个文件位于网络卷，并发数 {} 较高，可能拖慢处理（建议不超过 {NETWORK_CONCURRENCY_LIMIT}）",
+            self.concurrency
+        ))
+    }
+
+    /// Folder the result for `path` goes into, before its
+    /// [`batch_folder`](Self::batch_folder) and [`output_subdirs`](Self::output_subdirs)
+    /// entry.
+    pub fn output_root(&self, path: &Path) -> PathBuf {
+        let source = || self.source_folders.get(path).cloned().or_else(|| path.parent().map(Path::to_path_buf));
+        self.beside_source
+            .then(source)
+            .flatten()
+            .or_else(|| self.output_dir.clone())
+            .or_else(resolve_download_dir)
+            .unwrap_or_else(|| path.parent().map(Path::to_path_buf).unwrap_or_else(|| PathBuf::from(".")))
+    }
+
+    /// Warns when the inputs going to one folder are together larger than the free
+    /// space left there; decrypted copies are about as large as their sources.
+    pub fn disk_space_warning(&self, files: &[PathBuf]) -> Option<String> {
+        let mut folders: BTreeMap<PathBuf, u64> = BTreeMap::new();
+        for path in files {
+            if let Ok(metadata) = std::fs::metadata(path) {
+                *folders.entry(self.output_root(path)).or_default() += metadata.len();
+            }
+        }
+        folders.into_iter().find_map(|(output_dir, needed)| {
+            let available = available_space(&output_dir)?;
+            (needed > available).then(|| {
+                format!(
+                    "{} 剩余空间可能不足：约需 {}，可用 {}，还差 {}",
+                    output_dir.display(),
+                    format_size(needed),
+                    format_size(available),
+                    format_size(needed - available)
+                )
+            })
+        })
+    }
+}
+
+/// Human-readable byte count, e.g. `1.4 GB`.
+pub fn format_size(bytes: u64) -> String {
+    match scale_size(bytes) {
+        (_, "B") => format!("{bytes} B"),
+        (size, unit) => format!("{size:.1} {unit}"),
+    }
+}
+
+/// A byte count in the largest unit it reaches, e.g. `(1.4, "GB")`.
+pub fn scale_size(bytes: u64) -> (f64, &'static str) {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    (size, UNITS[unit])
+}
+
+/// Number of CPU cores, capped at 4 so qpdf does not saturate slow disks.
+pub fn default_concurrency() -> usize {
+    std::thread::available_parallelism()
+        .map(|n| n.get())
+        .unwrap_or(1)
+        .min(4)
+}
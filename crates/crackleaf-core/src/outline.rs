@@ -0,0 +1,152 @@
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::time::Duration;
+
+use lopdf::{decode_text_string, Dictionary, Document, Object, ObjectId};
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use crate::inspect::read_document;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Bookmarks followed at most, in case the outline loops.
+const MAX_BOOKMARKS: usize = 10_000;
+/// Nesting followed at most.
+const MAX_DEPTH: usize = 32;
+
+/// An entry of a PDF's outline (its bookmarks).
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Bookmark {
+    pub title: String,
+    /// Page it leads to, numbered from 1; `None` when it points elsewhere or nowhere.
+    pub page: Option<u32>,
+    pub children: Vec<Bookmark>,
+}
+
+impl Bookmark {
+    /// Number of bookmarks in `outline`, nested ones included.
+    pub fn count(outline: &[Bookmark]) -> usize {
+        outline.iter().map(|bookmark| 1 + Self::count(&bookmark.children)).sum()
+    }
+}
+
+/// The outline of the PDF at `path`. Returns `None` when the file cannot be read or
+/// needs a password.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn read_outline(path: &Path, timeout: Option<Duration>) -> Option<Vec<Bookmark>> {
+    let file = long_path(path).into_owned();
+    let outline = with_io_timeout(timeout, move || {
+        Ok(outline(&read_document(&file)?))
+    })
+    .await;
+    match outline {
+        Ok(outline) => Some(outline),
+        Err(err) => {
+            debug!("outline check failed: {err}");
+            None
+        }
+    }
+}
+
+/// The bookmarks of `doc`, in the order they are listed.
+pub(crate) fn outline(doc: &Document) -> Vec<Bookmark> {
+    let pages: HashMap<ObjectId, u32> = doc.get_pages().into_iter().map(|(number, id)| (id, number)).collect();
+    let first = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| resolve_dict(doc, catalog.get(b"Outlines").ok()?))
+        .and_then(|outlines| outlines.get(b"First").ok()?.as_reference().ok());
+    let mut seen = HashSet::new();
+    children(doc, first, &pages, &mut seen, 0)
+}
+
+/// The bookmark `first` and those following it, with their children.
+fn children(
+    doc: &Document,
+    first: Option<ObjectId>,
+    pages: &HashMap<ObjectId, u32>,
+    seen: &mut HashSet<ObjectId>,
+    depth: usize,
+) -> Vec<Bookmark> {
+    let mut bookmarks = Vec::new();
+    let mut node = first;
+    while let Some(id) = node.filter(|&id| seen.len() < MAX_BOOKMARKS && seen.insert(id)) {
+        let Ok(item) = doc.get_dictionary(id) else {
+            break;
+        };
+        let title = item
+            .get(b"Title")
+            .ok()
+            .and_then(|title| decode_text_string(doc.dereference(title).ok()?.1).ok())
+            .unwrap_or_default();
+        let child = item.get(b"First").ok().and_then(|child| child.as_reference().ok());
+        bookmarks.push(Bookmark {
+            title: title.trim_start_matches('\u{feff}').trim().to_string(),
+            page: destination_page(doc, item).and_then(|page| pages.get(&page).copied()),
+            children: if depth < MAX_DEPTH { children(doc, child, pages, seen, depth + 1) } else { Vec::new() },
+        });
+        node = item.get(b"Next").ok().and_then(|next| next.as_reference().ok());
+    }
+    bookmarks
+}
+
+/// The page a bookmark leads to, through `/Dest` or a GoTo action, named or not.
+fn destination_page(doc: &Document, item: &Dictionary) -> Option<ObjectId> {
+    let destination = match item.get(b"Dest") {
+        Ok(destination) => destination,
+        Err(_) => {
+            let action = resolve_dict(doc, item.get(b"A").ok()?)?;
+            if action.get(b"S").and_then(Object::as_name).ok()? != b"GoTo" {
+                return None;
+            }
+            action.get(b"D").ok()?
+        }
+    };
+    let destination = match doc.dereference(destination).ok()?.1 {
+        Object::Name(name) => named_destination(doc, name, false)?,
+        Object::String(name, _) => named_destination(doc, name, true)?,
+        other => other,
+    };
+    // A destination array, or a dictionary holding one under /D.
+    let array = match destination {
+        Object::Dictionary(dict) => doc.dereference(dict.get(b"D").ok()?).ok()?.1.as_array().ok()?,
+        other => other.as_array().ok()?,
+    };
+    array.first()?.as_reference().ok()
+}
+
+/// Looks `name` up in the catalog's `/Dests` (names) or the `/Dests` name tree
+/// (strings).
+fn named_destination<'a>(doc: &'a Document, name: &[u8], in_tree: bool) -> Option<&'a Object> {
+    let catalog = doc.catalog().ok()?;
+    if !in_tree {
+        let dests = resolve_dict(doc, catalog.get(b"Dests").ok()?)?;
+        return doc.dereference(dests.get(name).ok()?).ok().map(|(_, object)| object);
+    }
+    let names = resolve_dict(doc, catalog.get(b"Names").ok()?)?;
+    let mut pending = vec![resolve_dict(doc, names.get(b"Dests").ok()?)?];
+    let mut visited = 0;
+    while let Some(node) = pending.pop() {
+        visited += 1;
+        if visited > MAX_BOOKMARKS {
+            break;
+        }
+        if let Ok(entries) = node.get(b"Names").and_then(Object::as_array) {
+            for pair in entries.chunks(2) {
+                if let [key, value] = pair {
+                    if doc.dereference(key).ok()?.1.as_str().ok() == Some(name) {
+                        return doc.dereference(value).ok().map(|(_, object)| object);
+                    }
+                }
+            }
+        }
+        if let Ok(kids) = node.get(b"Kids").and_then(Object::as_array) {
+            pending.extend(kids.iter().filter_map(|kid| resolve_dict(doc, kid)));
+        }
+    }
+    None
+}
+
+fn resolve_dict<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    doc.dereference(object).ok()?.1.as_dict().ok()
+}
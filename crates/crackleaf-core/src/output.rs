@@ -0,0 +1,344 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{OsStr, OsString};
+use std::fs::{FileTimes, OpenOptions};
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+
+use crate::office::is_office_document;
+
+/// Added to the file names of unlocked results, as in `report_unlocked.pdf`.
+pub const UNLOCKED_SUFFIX: &str = "_unlocked";
+/// Added to the file names of results of [`Encrypt`](crate::steps::Encrypt), as in
+/// `report_protected.pdf`.
+pub const PROTECTED_SUFFIX: &str = "_protected";
+/// Longest suffix accepted by [`check_suffix`], in characters.
+pub const MAX_SUFFIX_LEN: usize = 40;
+
+/// What to do when a result's name is already taken by a file on disk.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ConflictPolicy {
+    /// Replace the file; with a journal it is kept so undo can put it back.
+    Overwrite,
+    /// Add a number to the new name, as in `report_unlocked_1.pdf`.
+    #[default]
+    Rename,
+    /// Leave the file alone and skip the input with a warning.
+    Skip,
+}
+
+/// Picks `<stem><suffix>.pdf` in `output_dir`, e.g. with [`UNLOCKED_SUFFIX`], adding
+/// a number when taken. The stem is written in composed form (see [`nfc_name`]) and
+/// otherwise kept as is, even when it is not valid Unicode.
+pub fn unique_output_path(output_dir: &Path, file_stem: impl AsRef<OsStr>, suffix: &str) -> PathBuf {
+    first_free(output_dir, &nfc_name(file_stem.as_ref()), suffix, "pdf", |path| path.exists())
+}
+
+/// Checks that `suffix` can be added to file names here: not empty, at most
+/// [`MAX_SUFFIX_LEN`] characters and none that [`forbidden_in_name`].
+pub fn check_suffix(suffix: &str) -> Result<()> {
+    if suffix.is_empty() {
+        bail!("文件名后缀不能为空");
+    }
+    if suffix.chars().count() > MAX_SUFFIX_LEN {
+        bail!("文件名后缀不能超过 {MAX_SUFFIX_LEN} 个字符");
+    }
+    if let Some(ch) = suffix.chars().find(|&ch| forbidden_in_name(ch)) {
+        bail!("文件名后缀不能包含 {ch:?}");
+    }
+    Ok(())
+}
+
+/// Characters file names cannot contain on this platform: `/` and control characters
+/// everywhere, `:` on macOS (Finder shows it as `/`), and `<>:"\|?*` on Windows.
+pub fn forbidden_in_name(ch: char) -> bool {
+    ch == '/'
+        || ch.is_control()
+        || (cfg!(target_os = "macos") && ch == ':')
+        || (cfg!(target_os = "windows") && matches!(ch, '<' | '>' | ':' | '"' | '\\' | '|' | '?' | '*'))
+}
+
+/// Like [`unique_output_path`], for a result of `source` named with `suffix`: Office
+/// documents keep their extension, everything else becomes `.pdf`.
+pub(crate) fn unique_result_path(output_dir: &Path, source: &Path, suffix: &str) -> PathBuf {
+    let stem = nfc_name(source.file_stem().unwrap_or("output".as_ref()));
+    first_free(output_dir, &stem, suffix, result_extension(source), |path| path.exists())
+}
+
+fn result_extension(source: &Path) -> &str {
+    match source.extension().and_then(OsStr::to_str) {
+        Some(ext) if is_office_document(source) => ext,
+        _ => "pdf",
+    }
+}
+
+/// `name` in Unicode normalization form C. Finder hands out decomposed (NFD) names
+/// while file pickers return composed ones, so the same file can arrive spelled two
+/// ways. Names that are not valid Unicode are returned unchanged.
+pub fn nfc_name(name: &OsStr) -> OsString {
+    match name.to_str() {
+        Some(text) => text.nfc().collect::<String>().into(),
+        None => name.to_os_string(),
+    }
+}
+
+fn first_free(
+    output_dir: &Path,
+    file_stem: &OsStr,
+    suffix: &str,
+    extension: &str,
+    taken: impl Fn(&Path) -> bool,
+) -> PathBuf {
+    let name = |number: &str| {
+        let mut name = OsString::from(file_stem);
+        name.push(format!("{suffix}{number}.{extension}"));
+        output_dir.join(name)
+    };
+    let mut candidate = name("");
+    if !taken(&candidate) {
+        return candidate;
+    }
+    for idx in 1..=9999 {
+        candidate = name(&format!("_{idx}"));
+        if !taken(&candidate) {
+            return candidate;
+        }
+    }
+    name("_overflow")
+}
+
+/// Output paths for a whole batch, chosen up front so files processed at the same
+/// time never pick the same name. `output_dir` gives each input's folder.
+///
+/// Inputs whose names would collide get their parent folder appended, e.g.
+/// `2023/report.pdf` → `report_2023_unlocked.pdf`; names taken by an earlier input,
+/// or on disk with [`ConflictPolicy::Rename`], get a number as in [`unique_output_path`].
+/// Names are compared case- and normalization-insensitively, as most desktop file
+/// systems do.
+pub(crate) fn reserve_output_paths(
+    files: &[PathBuf],
+    suffix: &str,
+    on_conflict: ConflictPolicy,
+    output_dir: impl Fn(&Path) -> PathBuf,
+) -> Vec<PathBuf> {
+    let fold = |path: &Path| nfc_name(path.as_os_str()).to_string_lossy().to_lowercase();
+    let stem = |path: &Path| nfc_name(path.file_stem().unwrap_or("output".as_ref()));
+    // Results of `report.pdf` and `report.docx` keep different extensions.
+    let key = |dir: &Path, path: &Path| format!("{}.{}", fold(&dir.join(stem(path))), result_extension(path));
+    let dirs: Vec<PathBuf> = files.iter().map(|path| output_dir(path)).collect();
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (path, dir) in files.iter().zip(&dirs) {
+        *counts.entry(key(dir, path)).or_default() += 1;
+    }
+
+    let mut reserved = HashSet::new();
+    files
+        .iter()
+        .zip(&dirs)
+        .map(|(path, dir)| {
+            let mut name = stem(path);
+            if counts[&key(dir, path)] > 1 {
+                if let Some(parent) = path.parent().and_then(Path::file_name) {
+                    name.push("_");
+                    name.push(nfc_name(parent));
+                }
+            }
+            let output = first_free(dir, &name, suffix, result_extension(path), |candidate| {
+                (on_conflict == ConflictPolicy::Rename && candidate.exists()) || reserved.contains(&fold(candidate))
+            });
+            reserved.insert(fold(&output));
+            output
+        })
+        .collect()
+}
+
+/// Returns the user's downloads folder, creating it if needed.
+pub fn resolve_download_dir() -> Option<PathBuf> {
+    if let Some(dir) = dirs::download_dir() {
+        let _ = std::fs::create_dir_all(&dir);
+        return Some(dir);
+    }
+    if let Some(home) = dirs::home_dir() {
+        let dir = home.join("Downloads");
+        let _ = std::fs::create_dir_all(&dir);
+        return Some(dir);
+    }
+    None
+}
+
+/// Copies the modified and accessed times and the permissions of `source` onto
+/// `target`; on Windows also the file attributes (hidden, archive, ...).
+pub(crate) fn copy_attributes(source: &Path, target: &Path) -> std::io::Result<()> {
+    let metadata = std::fs::metadata(source)?;
+    let mut times = FileTimes::new().set_modified(metadata.modified()?);
+    if let Ok(accessed) = metadata.accessed() {
+        times = times.set_accessed(accessed);
+    }
+    OpenOptions::new().write(true).open(target)?.set_times(times)?;
+    #[cfg(windows)]
+    {
+        use std::os::windows::ffi::OsStrExt;
+        use std::os::windows::fs::MetadataExt;
+
+        use windows_sys::Win32::Storage::FileSystem::SetFileAttributesW;
+
+        let target = crate::volume::long_path(target);
+        let wide: Vec<u16> = target.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+        if unsafe { SetFileAttributesW(wide.as_ptr(), metadata.file_attributes()) } == 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+    }
+    #[cfg(not(windows))]
+    std::fs::set_permissions(target, metadata.permissions())?;
+    Ok(())
+}
+
+/// Renames `from` to `to`, falling back to copying when they sit on different volumes.
+pub fn move_file(from: &Path, to: &Path) -> std::io::Result<()> {
+    if std::fs::rename(from, to).is_ok() {
+        return Ok(());
+    }
+    std::fs::copy(from, to)?;
+    std::fs::remove_file(from)
+}
+
+/// `name` made safe as a file name on every system and cut to 80 characters;
+/// `untitled` when nothing is left.
+pub(crate) fn safe_file_name(name: &str) -> String {
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() || "/\\<>:\"|?*".contains(c) { '_' } else { c })
+        .take(80)
+        .collect();
+    let name = name.trim().trim_matches('.').trim();
+    if name.is_empty() {
+        "untitled".to_string()
+    } else {
+        name.to_string()
+    }
+}
+
+/// `dir`, or while that is taken, `dir` with `_1`, `_2`, … added to its name.
+pub(crate) fn free_dir(dir: &Path) -> PathBuf {
+    let name = dir.file_name().unwrap_or("output".as_ref());
+    let mut candidate = dir.to_path_buf();
+    let mut index = 1;
+    while candidate.exists() {
+        let mut numbered = name.to_os_string();
+        numbered.push(format!("_{index}"));
+        candidate = dir.with_file_name(numbered);
+        index += 1;
+    }
+    candidate
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(unix)]
+    fn raw(bytes: &[u8]) -> OsString {
+        use std::os::unix::ffi::OsStrExt;
+        OsStr::from_bytes(bytes).to_os_string()
+    }
+
+    #[test]
+    fn nfc_name_composes_decomposed_names() {
+        assert_eq!(nfc_name("Cafe\u{301}".as_ref()), OsString::from("Caf\u{e9}"));
+        assert_eq!(nfc_name("报告".as_ref()), OsString::from("报告"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn nfc_name_keeps_non_utf8_names() {
+        assert_eq!(nfc_name(&raw(b"caf\xe9")), raw(b"caf\xe9"));
+    }
+
+    #[test]
+    fn first_free_numbers_taken_names() {
+        let dir = Path::new("out");
+        let taken = [dir.join("report_unlocked.pdf"), dir.join("report_unlocked_1.pdf")];
+        let path = first_free(dir, "report".as_ref(), UNLOCKED_SUFFIX, "pdf", |path| taken.iter().any(|t| t == path));
+        assert_eq!(path, dir.join("report_unlocked_2.pdf"));
+
+        let path = first_free(dir, "report".as_ref(), UNLOCKED_SUFFIX, "pdf", |_| true);
+        assert_eq!(path, dir.join("report_unlocked_overflow.pdf"));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn first_free_keeps_non_utf8_stems() {
+        let path = first_free(Path::new("out"), &raw(b"caf\xe9"), "_x", "docx", |_| false);
+        assert_eq!(path.file_name(), Some(raw(b"caf\xe9_x.docx").as_os_str()));
+    }
+
+    #[test]
+    fn unique_output_path_skips_files_on_disk() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(unique_output_path(dir.path(), "Cafe\u{301}", "_fixed"), dir.path().join("Caf\u{e9}_fixed.pdf"));
+        std::fs::write(dir.path().join("report_unlocked.pdf"), b"").unwrap();
+        assert_eq!(unique_output_path(dir.path(), "report", UNLOCKED_SUFFIX), dir.path().join("report_unlocked_1.pdf"));
+    }
+
+    #[test]
+    fn reserve_output_paths_tells_same_names_apart() {
+        let out = PathBuf::from("out");
+        let files = [
+            PathBuf::from("2023/report.pdf"),
+            PathBuf::from("2024/Report.pdf"),
+            PathBuf::from("2024/report.docx"),
+        ];
+        let paths = reserve_output_paths(&files, UNLOCKED_SUFFIX, ConflictPolicy::Rename, |_| out.clone());
+        assert_eq!(
+            paths,
+            [
+                out.join("report_2023_unlocked.pdf"),
+                out.join("Report_2024_unlocked.pdf"),
+                out.join("report_unlocked.docx"),
+            ]
+        );
+    }
+
+    #[test]
+    fn reserve_output_paths_matches_nfd_and_nfc_names() {
+        let out = PathBuf::from("out");
+        let files = [PathBuf::from("mac/Cafe\u{301}.pdf"), PathBuf::from("win/Caf\u{e9}.pdf")];
+        let paths = reserve_output_paths(&files, UNLOCKED_SUFFIX, ConflictPolicy::Rename, |_| out.clone());
+        assert_eq!(paths, [out.join("Caf\u{e9}_mac_unlocked.pdf"), out.join("Caf\u{e9}_win_unlocked.pdf")]);
+    }
+
+    #[test]
+    fn reserve_output_paths_numbers_names_within_the_batch() {
+        // No parent folder to tell them apart.
+        let out = PathBuf::from("out");
+        let files = [PathBuf::from("report.pdf"), PathBuf::from("report.pdf")];
+        let paths = reserve_output_paths(&files, UNLOCKED_SUFFIX, ConflictPolicy::Overwrite, |_| out.clone());
+        assert_eq!(paths, [out.join("report_unlocked.pdf"), out.join("report_unlocked_1.pdf")]);
+    }
+
+    #[test]
+    fn reserve_output_paths_follows_the_conflict_policy() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("report_unlocked.pdf"), b"").unwrap();
+        let files = [PathBuf::from("in/report.pdf")];
+        let rename = reserve_output_paths(&files, UNLOCKED_SUFFIX, ConflictPolicy::Rename, |_| dir.path().to_path_buf());
+        assert_eq!(rename, [dir.path().join("report_unlocked_1.pdf")]);
+        let overwrite = reserve_output_paths(&files, UNLOCKED_SUFFIX, ConflictPolicy::Overwrite, |_| dir.path().to_path_buf());
+        assert_eq!(overwrite, [dir.path().join("report_unlocked.pdf")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn reserve_output_paths_keeps_non_utf8_names() {
+        let out = PathBuf::from("out");
+        let mut first = PathBuf::from(raw(b"caf\xe9"));
+        first.push(raw(b"r\xe9sum\xe9.pdf"));
+        let files = [first, PathBuf::from("plain.pdf")];
+        let paths = reserve_output_paths(&files, UNLOCKED_SUFFIX, ConflictPolicy::Rename, |_| out.clone());
+        assert_eq!(paths, [out.join(raw(b"r\xe9sum\xe9_unlocked.pdf")), out.join("plain_unlocked.pdf")]);
+    }
+}
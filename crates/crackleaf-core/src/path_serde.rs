@@ -0,0 +1,159 @@
+//! Serde helpers for paths that are not valid Unicode, for use with
+//! `#[serde(with = "crackleaf_core::path_serde")]`.
+//!
+//! A path is written as a string whenever it is one, so existing files stay readable.
+//! On Unix any other path is written as its raw bytes instead of failing the whole
+//! record; elsewhere it is converted lossily.
+//!
+//! ```
+//! # #[cfg(unix)] {
+//! use std::os::unix::ffi::OsStrExt;
+//! use std::path::PathBuf;
+//!
+//! #[derive(serde::Serialize, serde::Deserialize)]
+//! struct Entry {
+//!     #[serde(with = "crackleaf_core::path_serde")]
+//!     path: PathBuf,
+//! }
+//!
+//! let path = PathBuf::from(std::ffi::OsStr::from_bytes(b"/tmp/caf\xe9 \"1\".pdf"));
+//! let json = serde_json::to_string(&Entry { path: path.clone() }).unwrap();
+//! let entry: Entry = serde_json::from_str(&json).unwrap();
+//! assert_eq!(entry.path, path);
+//!
+//! let entry: Entry = serde_json::from_str(r#"{"path": "/tmp/报告 (1).pdf"}"#).unwrap();
+//! assert_eq!(entry.path, PathBuf::from("/tmp/报告 (1).pdf"));
+//! # }
+//! ```
+
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Repr {
+    Text(String),
+    Bytes(Vec<u8>),
+}
+
+pub fn serialize<S: Serializer>(path: &Path, serializer: S) -> Result<S::Ok, S::Error> {
+    if let Some(text) = path.to_str() {
+        return serializer.serialize_str(text);
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        serializer.collect_seq(path.as_os_str().as_bytes())
+    }
+    #[cfg(not(unix))]
+    serializer.serialize_str(&path.to_string_lossy())
+}
+
+pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<PathBuf, D::Error> {
+    Ok(match Repr::deserialize(deserializer)? {
+        Repr::Text(text) => PathBuf::from(text),
+        #[cfg(unix)]
+        Repr::Bytes(bytes) => {
+            use std::os::unix::ffi::OsStringExt;
+            PathBuf::from(std::ffi::OsString::from_vec(bytes))
+        }
+        #[cfg(not(unix))]
+        Repr::Bytes(bytes) => PathBuf::from(String::from_utf8_lossy(&bytes).into_owned()),
+    })
+}
+
+/// The same for `Option<PathBuf>`.
+pub mod option {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(path: &Option<PathBuf>, serializer: S) -> Result<S::Ok, S::Error> {
+        path.as_deref().map(Wrap).serialize(serializer)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<PathBuf>, D::Error> {
+        Ok(Option::<Owned>::deserialize(deserializer)?.map(|path| path.0))
+    }
+}
+
+/// The same for `Vec<PathBuf>`.
+pub mod vec {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(paths: &[PathBuf], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(paths.iter().map(|path| Wrap(path)))
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<PathBuf>, D::Error> {
+        Ok(Vec::<Owned>::deserialize(deserializer)?
+            .into_iter()
+            .map(|path| path.0)
+            .collect())
+    }
+}
+
+struct Wrap<'a>(&'a Path);
+
+impl Serialize for Wrap<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serialize(self.0, serializer)
+    }
+}
+
+struct Owned(PathBuf);
+
+impl<'de> Deserialize<'de> for Owned {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserialize(deserializer).map(Owned)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Record {
+        #[serde(with = "crate::path_serde")]
+        path: PathBuf,
+        #[serde(with = "crate::path_serde::option")]
+        output: Option<PathBuf>,
+        #[serde(with = "crate::path_serde::vec")]
+        files: Vec<PathBuf>,
+    }
+
+    fn round_trip(record: &Record) -> Record {
+        serde_json::from_str(&serde_json::to_string(record).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn unicode_paths_are_written_as_strings() {
+        let record = Record {
+            path: PathBuf::from("/tmp/报告 (1).pdf"),
+            output: None,
+            files: vec![PathBuf::from("Cafe\u{301}.pdf")],
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert_eq!(json["path"], "/tmp/报告 (1).pdf");
+        assert_eq!(json["output"], serde_json::Value::Null);
+        assert_eq!(json["files"][0], "Cafe\u{301}.pdf");
+        assert_eq!(round_trip(&record), record);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_paths_round_trip() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let raw = |bytes: &[u8]| PathBuf::from(std::ffi::OsStr::from_bytes(bytes));
+        let record = Record {
+            path: raw(b"/tmp/caf\xe9.pdf"),
+            output: Some(raw(b"/out/caf\xe9_unlocked.pdf")),
+            files: vec![raw(b"\xff.pdf"), PathBuf::from("plain.pdf")],
+        };
+        let json = serde_json::to_value(&record).unwrap();
+        assert!(json["path"].is_array());
+        assert_eq!(json["files"][1], "plain.pdf");
+        assert_eq!(round_trip(&record), record);
+    }
+}
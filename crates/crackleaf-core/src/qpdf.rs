@@ -0,0 +1,266 @@
+use std::path::PathBuf;
+use std::process::Output;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use anyhow::Result;
+use tokio::process::Command;
+use tokio::sync::{Semaphore, SemaphorePermit};
+#[cfg(not(feature = "vendored-qpdf"))]
+use tracing::warn;
+use tracing::{info, instrument};
+
+use crate::error::Error;
+use crate::options::default_concurrency;
+use crate::runtime::runtime;
+
+/// Result of probing the `qpdf` binary with `--version`.
+pub struct QpdfStatus {
+    /// `qpdf` ran and exited successfully.
+    pub ok: bool,
+    /// User-facing reason why `qpdf` is unusable.
+    pub error: Option<String>,
+    /// Version reported by `qpdf`, if it could be parsed.
+    pub version: Option<String>,
+    /// Non-fatal issue worth showing, e.g. an unrecognised version string.
+    pub warning: Option<String>,
+}
+
+/// Reports the version of the libqpdf linked into the program, which is always there.
+#[cfg(feature = "vendored-qpdf")]
+#[instrument]
+pub async fn check_qpdf_ready() -> QpdfStatus {
+    let version = crate::libqpdf::version();
+    info!(%version, "linked qpdf ready");
+    QpdfStatus {
+        ok: true,
+        error: None,
+        version: Some(version),
+        warning: None,
+    }
+}
+
+/// Checks that `qpdf` can be launched and reports its version.
+#[cfg(not(feature = "vendored-qpdf"))]
+#[instrument]
+pub async fn check_qpdf_ready() -> QpdfStatus {
+    let mut cmd = qpdf_command();
+    cmd.arg("--version");
+
+    match cmd.output().await {
+        Ok(output) => {
+            if output.status.success() {
+                let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+                let version = parse_qpdf_version(&stdout);
+                info!(?version, path = %resolve_qpdf_command().display(), "qpdf ready");
+                let warning = if version.is_none() {
+                    Some("已检测到 qpdf，但版本无法识别".to_string())
+                } else {
+                    None
+                };
+                QpdfStatus {
+                    ok: true,
+                    error: None,
+                    version,
+                    warning,
+                }
+            } else {
+                let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+                let msg = if stderr.is_empty() {
+                    "qpdf 运行失败（依赖缺失或版本不匹配）".to_string()
+                } else {
+                    format!("qpdf 运行失败：{stderr}")
+                };
+                warn!("{msg}");
+                QpdfStatus {
+                    ok: false,
+                    error: Some(msg),
+                    version: None,
+                    warning: None,
+                }
+            }
+        }
+        Err(err) => {
+            warn!("qpdf not found: {err}");
+            QpdfStatus {
+                ok: false,
+                error: Some(qpdf_missing_message(&err.to_string())),
+                version: None,
+                warning: None,
+            }
+        }
+    }
+}
+
+#[cfg(not(feature = "vendored-qpdf"))]
+fn parse_qpdf_version(output: &str) -> Option<String> {
+    for token in output.split_whitespace() {
+        if token.chars().next()?.is_ascii_digit() {
+            return Some(token.trim().to_string());
+        }
+    }
+    None
+}
+
+#[cfg(not(feature = "vendored-qpdf"))]
+fn qpdf_missing_message(detail: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!(
+            "未检测到 qpdf（{detail}）。\n请执行：brew install qpdf\n或访问：https://github.com/qpdf/qpdf/releases"
+        )
+    } else if cfg!(target_os = "windows") {
+        let arch = qpdf_windows_variant();
+        let emulated = if cfg!(target_arch = "aarch64") { "（ARM64 版 Windows 以仿真方式运行它）" } else { "" };
+        format!(
+            "未检测到 qpdf（{detail}）。\n请访问：https://github.com/qpdf/qpdf/releases\n下载 {arch} 版本{emulated}并将 qpdf.exe 放到程序同目录。"
+        )
+    } else {
+        format!("未检测到 qpdf（{detail}）。请安装后重启程序。")
+    }
+}
+
+/// The qpdf release build to download for this program on Windows, e.g. `msvc64` for
+/// `qpdf-<version>-msvc64.zip`. ARM64 Windows gets the x64 build, which it runs under
+/// emulation.
+pub fn qpdf_windows_variant() -> &'static str {
+    if cfg!(any(target_arch = "x86_64", target_arch = "aarch64")) {
+        "msvc64"
+    } else {
+        "msvc32"
+    }
+}
+
+/// Builds a `qpdf` invocation that does not flash a console window on Windows.
+///
+/// The child is killed when its future is dropped, which is how timeouts and
+/// cancellation stop a running qpdf. With `vendored-qpdf` only its arguments are
+/// used, see [`output`].
+pub(crate) fn qpdf_command() -> Command {
+    let mut cmd = Command::new(resolve_qpdf_command());
+    cmd.kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+    cmd
+}
+
+struct ProcessLimit {
+    permits: Semaphore,
+    limit: Mutex<usize>,
+}
+
+fn process_limit() -> &'static ProcessLimit {
+    static LIMIT: OnceLock<ProcessLimit> = OnceLock::new();
+    LIMIT.get_or_init(|| ProcessLimit {
+        permits: Semaphore::new(default_concurrency()),
+        limit: Mutex::new(default_concurrency()),
+    })
+}
+
+/// Caps the child processes (qpdf and hook commands) running at the same time across
+/// detection and every batch. Defaults to [`default_concurrency`].
+pub fn set_process_limit(limit: usize) {
+    let limit = limit.max(1);
+    let state = process_limit();
+    let mut current = state.limit.lock().unwrap_or_else(|err| err.into_inner());
+    if limit > *current {
+        state.permits.add_permits(limit - *current);
+    } else if limit < *current {
+        let excess = *current - limit;
+        let missing = excess - state.permits.forget_permits(excess);
+        if missing > 0 {
+            // The rest are in use; retire them as they come back.
+            runtime().spawn(async move {
+                if let Ok(permits) = process_limit().permits.acquire_many(missing as u32).await {
+                    permits.forget();
+                }
+            });
+        }
+    }
+    *current = limit;
+}
+
+/// Waits until another child process may be started; hold the permit while it runs.
+pub(crate) async fn process_permit() -> SemaphorePermit<'static> {
+    process_limit()
+        .permits
+        .acquire()
+        .await
+        .expect("process semaphore is never closed")
+}
+
+/// Runs `cmd` to completion, giving up after `timeout`. Waiting for a
+/// [`process_permit`] does not count towards the timeout.
+pub(crate) async fn run_qpdf(mut cmd: Command, timeout: Option<Duration>) -> Result<Output> {
+    let _permit = process_permit().await;
+    let output = output(&mut cmd);
+    let output = match timeout {
+        Some(limit) => tokio::time::timeout(limit, output)
+            .await
+            .map_err(|_| Error::Timeout(limit))?,
+        None => output.await,
+    };
+    Ok(output.map_err(|err| Error::QpdfMissing(err.to_string()))?)
+}
+
+/// Runs `cmd` as a child process.
+#[cfg(not(feature = "vendored-qpdf"))]
+async fn output(cmd: &mut Command) -> std::io::Result<Output> {
+    cmd.output().await
+}
+
+/// Runs the arguments of `cmd` through the linked libqpdf on a blocking thread. That
+/// cannot be killed: a job that times out or is cancelled finishes in the background.
+#[cfg(feature = "vendored-qpdf")]
+async fn output(cmd: &mut Command) -> std::io::Result<Output> {
+    let args: Vec<_> = cmd.as_std().get_args().map(ToOwned::to_owned).collect();
+    tokio::task::spawn_blocking(move || crate::libqpdf::run(&args))
+        .await
+        .map_err(std::io::Error::other)?
+}
+
+/// Like [`run_qpdf`], but fails unless qpdf exits with success or warnings (code 3).
+pub(crate) async fn run_qpdf_checked(cmd: Command, timeout: Option<Duration>) -> Result<()> {
+    let output = run_qpdf(cmd, timeout).await?;
+    match output.status.code() {
+        Some(0) | Some(3) => Ok(()),
+        code => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(Error::from_qpdf_stderr(code, &stderr).into())
+        }
+    }
+}
+
+/// Locates `qpdf`, preferring a copy next to the executable or in the `Resources`
+/// folder of the macOS app bundle, then the working directory, and finally falling
+/// back to `PATH`.
+pub fn resolve_qpdf_command() -> PathBuf {
+    let filename = qpdf_filename();
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            let candidate = exe_dir.join(filename);
+            if candidate.exists() {
+                return candidate;
+            }
+            let bundled = exe_dir.join("..").join("Resources").join(filename);
+            if bundled.exists() {
+                return bundled;
+            }
+        }
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        let candidate = cwd.join(filename);
+        if candidate.exists() {
+            return candidate;
+        }
+    }
+    PathBuf::from(filename)
+}
+
+/// Platform file name of the `qpdf` executable.
+pub fn qpdf_filename() -> &'static str {
+    if cfg!(target_os = "windows") {
+        "qpdf.exe"
+    } else {
+        "qpdf"
+    }
+}
@@ -0,0 +1,23 @@
+use std::future::Future;
+use std::sync::OnceLock;
+
+use tokio::runtime::Runtime;
+
+/// Shared multi-threaded runtime used by the blocking entry points.
+pub fn runtime() -> &'static Runtime {
+    static RUNTIME: OnceLock<Runtime> = OnceLock::new();
+    RUNTIME.get_or_init(|| {
+        tokio::runtime::Builder::new_multi_thread()
+            .thread_name("crackleaf-worker")
+            .enable_all()
+            .build()
+            .expect("failed to start tokio runtime")
+    })
+}
+
+/// Runs `future` to completion on the shared runtime.
+///
+/// Must not be called from inside the runtime itself.
+pub fn block_on<F: Future>(future: F) -> F::Output {
+    runtime().block_on(future)
+}
@@ -0,0 +1,98 @@
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize)]
+struct Header {
+    #[serde(with = "crate::path_serde::vec")]
+    files: Vec<PathBuf>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct Finished {
+    finished: usize,
+}
+
+/// On-disk record of a running batch, used to resume it after a crash or quit.
+///
+/// The file starts with the input list, followed by one line per finished input.
+/// It is deleted by [`complete`](Self::complete) once the whole batch has run.
+#[derive(Debug)]
+pub struct BatchSession {
+    path: PathBuf,
+    file: Mutex<File>,
+}
+
+impl BatchSession {
+    /// Writes a new session for `files` to `path`, replacing any previous one.
+    pub fn create(path: &Path, files: &[PathBuf]) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let files = files
+            .iter()
+            .map(|file| std::path::absolute(file).unwrap_or_else(|_| file.clone()))
+            .collect();
+        let mut header = serde_json::to_string(&Header { files })?;
+        header.push('\n');
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, header)?;
+        std::fs::rename(&tmp, path)?;
+        let file = OpenOptions::new().append(true).open(path)?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            file: Mutex::new(file),
+        })
+    }
+
+    /// Records that input `index` no longer needs processing.
+    pub fn mark_finished(&self, index: usize) -> Result<()> {
+        let mut line = serde_json::to_string(&Finished { finished: index })?;
+        line.push('\n');
+        let mut file = self.file.lock().unwrap_or_else(|err| err.into_inner());
+        file.write_all(line.as_bytes())?;
+        file.flush()?;
+        Ok(())
+    }
+
+    /// Removes the session file; the batch will not be offered for resuming.
+    pub fn complete(&self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Inputs of the session at `path` that never finished and still exist.
+///
+/// Returns an empty list when there is no session or it cannot be read.
+pub fn pending_files(path: &Path) -> Vec<PathBuf> {
+    let Ok(file) = File::open(path) else {
+        return Vec::new();
+    };
+    let mut lines = BufReader::new(file).lines().map_while(|line| line.ok());
+    let Some(header) = lines
+        .next()
+        .and_then(|line| serde_json::from_str::<Header>(&line).ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut finished = vec![false; header.files.len()];
+    for line in lines {
+        if let Ok(Finished { finished: index }) = serde_json::from_str(&line) {
+            if let Some(done) = finished.get_mut(index) {
+                *done = true;
+            }
+        }
+    }
+    header
+        .files
+        .into_iter()
+        .zip(finished)
+        .filter(|(path, done)| !done && path.is_file())
+        .map(|(path, _)| path)
+        .collect()
+}
@@ -0,0 +1,150 @@
+use std::path::Path;
+use std::time::Duration;
+
+use lopdf::{decode_text_string, Dictionary, Document, Object};
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use crate::inspect::read_document;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Form fields looked at most, in case the field tree loops.
+const MAX_FIELDS: usize = 10_000;
+/// DER encoding of the commonName attribute type (2.5.4.3).
+const COMMON_NAME: [u8; 5] = [0x06, 0x03, 0x55, 0x04, 0x03];
+
+/// A signed signature field of a PDF. Its validity is not checked; any rewrite of
+/// the file, decryption included, breaks it.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize)]
+pub struct Signature {
+    /// Name of the form field.
+    pub field: String,
+    /// Who signed, from the signature's `/Name` or else its certificate.
+    pub signer: Option<String>,
+    /// When it was signed as the signer's computer says, e.g. `2024-03-01 14:05`.
+    pub signed_at: Option<String>,
+}
+
+/// The signed signature fields of the PDF at `path`. Returns `None` when the file
+/// cannot be read or needs a password.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn read_signatures(path: &Path, timeout: Option<Duration>) -> Option<Vec<Signature>> {
+    let file = long_path(path).into_owned();
+    let read = with_io_timeout(timeout, move || {
+        Ok(signatures(&read_document(&file)?))
+    })
+    .await;
+    match read {
+        Ok(signatures) => Some(signatures),
+        Err(err) => {
+            debug!("signature check failed: {err}");
+            None
+        }
+    }
+}
+
+/// The signed signature fields of `doc`.
+pub(crate) fn signatures(doc: &Document) -> Vec<Signature> {
+    let fields = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| resolve_dict(doc, catalog.get(b"AcroForm").ok()?))
+        .and_then(|form| doc.dereference(form.get(b"Fields").ok()?).ok()?.1.as_array().ok());
+    let mut pending: Vec<(&Object, String)> = fields
+        .map(|fields| fields.iter().map(|field| (field, String::new())).collect())
+        .unwrap_or_default();
+    let mut signatures = Vec::new();
+    let mut visited = 0;
+    while let Some((field, parent)) = pending.pop() {
+        visited += 1;
+        if visited > MAX_FIELDS {
+            break;
+        }
+        let Some(field) = resolve_dict(doc, field) else {
+            continue;
+        };
+        // Fully qualified names join the partial names of the ancestors with dots.
+        let name = text(doc, field.get(b"T").ok()).unwrap_or_default();
+        let name = match (parent.is_empty(), name.is_empty()) {
+            (true, _) => name,
+            (false, true) => parent,
+            (false, false) => format!("{parent}.{name}"),
+        };
+        if let Ok(kids) = field.get(b"Kids").and_then(|kids| doc.dereference(kids)).and_then(|(_, kids)| kids.as_array()) {
+            pending.extend(kids.iter().rev().map(|kid| (kid, name.clone())));
+        }
+        let is_signature = matches!(field.get(b"FT").and_then(Object::as_name), Ok(b"Sig"));
+        let Some(value) = field.get(b"V").ok().and_then(|value| resolve_dict(doc, value)).filter(|_| is_signature) else {
+            continue;
+        };
+        // lopdf leaves strings inside dictionaries encrypted, which shows as control
+        // characters; the certificate in `/Contents` is never encrypted.
+        let signer = text(doc, value.get(b"Name").ok())
+            .filter(|name| !name.is_empty() && !name.chars().any(char::is_control))
+            .or_else(|| value.get(b"Contents").ok().and_then(|contents| certificate_name(contents.as_str().ok()?)));
+        signatures.push(Signature {
+            field: name,
+            signer,
+            signed_at: text(doc, value.get(b"M").ok()).and_then(|date| pdf_date(&date)),
+        });
+    }
+    signatures
+}
+
+/// A text string entry, dereferenced.
+fn text(doc: &Document, object: Option<&Object>) -> Option<String> {
+    let object = doc.dereference(object?).ok()?.1;
+    let text = decode_text_string(object)
+        .ok()
+        .unwrap_or_else(|| String::from_utf8_lossy(object.as_str().unwrap_or_default()).into_owned());
+    Some(text.trim_start_matches('\u{feff}').trim().to_string())
+}
+
+/// Common name of the signer in a PKCS#7 signature. Signing tools put the signer's
+/// certificate first, and in a certificate the issuer's name comes before the
+/// subject's, so that is the second common name, or the only one.
+fn certificate_name(der: &[u8]) -> Option<String> {
+    let starts = der
+        .windows(COMMON_NAME.len())
+        .enumerate()
+        .filter(|(_, window)| *window == COMMON_NAME)
+        .map(|(at, _)| at + COMMON_NAME.len());
+    let mut names = starts.filter_map(|at| {
+        // The value follows as a short string: tag, length, bytes.
+        let rest = der.get(at..)?;
+        let (&tag, rest) = rest.split_first()?;
+        let (&length, rest) = rest.split_first()?;
+        let value = rest.get(..usize::from(length)).filter(|_| length < 0x80)?;
+        match tag {
+            // UTF8String, PrintableString, T61String, IA5String
+            0x0c | 0x13 | 0x14 | 0x16 => Some(String::from_utf8_lossy(value).into_owned()),
+            // BMPString
+            0x1e => {
+                let units: Vec<u16> = value.chunks_exact(2).map(|pair| u16::from_be_bytes([pair[0], pair[1]])).collect();
+                Some(String::from_utf16_lossy(&units))
+            }
+            _ => None,
+        }
+    });
+    let first = names.next()?;
+    Some(names.next().unwrap_or(first))
+}
+
+/// `D:20240301140512+08'00'` as `2024-03-01 14:05`, leaving out what is missing.
+fn pdf_date(date: &str) -> Option<String> {
+    let digits: String = date.trim_start_matches("D:").chars().take_while(char::is_ascii_digit).collect();
+    let part = |range: std::ops::Range<usize>| digits.get(range);
+    let year = part(0..4)?;
+    let mut text = year.to_string();
+    if let (Some(month), Some(day)) = (part(4..6), part(6..8)) {
+        text.push_str(&format!("-{month}-{day}"));
+        if let (Some(hour), Some(minute)) = (part(8..10), part(10..12)) {
+            text.push_str(&format!(" {hour}:{minute}"));
+        }
+    }
+    Some(text)
+}
+
+fn resolve_dict<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    doc.dereference(object).ok()?.1.as_dict().ok()
+}
@@ -0,0 +1,91 @@
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use lopdf::Document;
+use tracing::{debug, info, instrument};
+
+use crate::outline::outline;
+use crate::output::{free_dir, nfc_name, safe_file_name};
+use crate::qpdf::{qpdf_command, run_qpdf_checked};
+use crate::volume::{long_path, with_io_timeout};
+
+/// A top-level bookmark and the pages up to the next one.
+#[derive(Debug)]
+struct Chapter {
+    title: String,
+    first: u32,
+    last: u32,
+}
+
+/// Writes one file per top-level bookmark of `path`, into a new folder next to it
+/// named after it, e.g. `report_unlocked/01 Introduction.pdf`. Pages before the
+/// first bookmark go with it. Returns the folder and the files, or `None` when there
+/// are fewer than two chapters to split into. A partly written folder is removed.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub(crate) async fn split_by_bookmarks(path: &Path, timeout: Option<Duration>) -> Result<Option<(PathBuf, Vec<PathBuf>)>> {
+    let source = long_path(path).into_owned();
+    let chapters = with_io_timeout(timeout, move || {
+        let doc = Document::load(&source).map_err(std::io::Error::other)?;
+        Ok(chapters(&doc))
+    })
+    .await
+    .map_err(|err| anyhow!("无法读取书签：{err}"))?;
+    if chapters.len() < 2 {
+        debug!(chapters = chapters.len(), "nothing to split");
+        return Ok(None);
+    }
+
+    let target = path.to_path_buf();
+    let dir = with_io_timeout(timeout, move || {
+        let dir = free_dir(&target.with_extension(""));
+        std::fs::create_dir(&dir)?;
+        Ok(dir)
+    })
+    .await?;
+    let width = chapters.len().to_string().len().max(2);
+    let mut parts = Vec::with_capacity(chapters.len());
+    for (number, chapter) in (1..).zip(&chapters) {
+        let mut name = OsString::from(format!("{number:0width$} "));
+        name.push(nfc_name(safe_file_name(&chapter.title).as_ref()));
+        name.push(".pdf");
+        let part = dir.join(name);
+        let mut cmd = qpdf_command();
+        cmd.arg("--empty")
+            .arg("--pages")
+            .arg(&*long_path(path))
+            .arg(format!("{}-{}", chapter.first, chapter.last))
+            .arg("--")
+            .arg(&*long_path(&part));
+        if let Err(err) = run_qpdf_checked(cmd, timeout).await {
+            let _ = std::fs::remove_dir_all(&dir);
+            return Err(err);
+        }
+        parts.push(part);
+    }
+    info!(dir = %dir.display(), parts = parts.len(), "split by bookmarks");
+    Ok(Some((dir, parts)))
+}
+
+/// Top-level bookmarks that point at a page of `doc`, in page order. Bookmarks on
+/// the same page as the one before are merged into it.
+fn chapters(doc: &Document) -> Vec<Chapter> {
+    let count = doc.get_pages().len() as u32;
+    let mut starts: Vec<(u32, String)> = outline(doc)
+        .into_iter()
+        .filter_map(|bookmark| Some((bookmark.page?, bookmark.title)))
+        .collect();
+    starts.sort_by_key(|(page, _)| *page);
+    starts.dedup_by_key(|(page, _)| *page);
+    let mut chapters: Vec<Chapter> = Vec::with_capacity(starts.len());
+    for (index, (first, title)) in starts.iter().enumerate() {
+        let last = starts.get(index + 1).map_or(count, |(next, _)| next - 1);
+        chapters.push(Chapter {
+            title: title.clone(),
+            first: if index == 0 { 1 } else { *first },
+            last,
+        });
+    }
+    chapters
+}
@@ -0,0 +1,78 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use lopdf::{Document, Object, ObjectId};
+use tracing::info;
+
+use super::layout::{load, rewrite};
+use super::{ProcessingStep, StepContext};
+
+/// Removes what a viewer runs by itself: the action run when the document opens
+/// (often a print dialog), document-level scripts, and the actions of the document
+/// and its pages (on open, close, print, save). Links and form fields keep theirs,
+/// and an open action that only jumps to a page is kept.
+pub struct RemoveAutoActions;
+
+#[async_trait]
+impl ProcessingStep for RemoveAutoActions {
+    fn id(&self) -> &str {
+        "remove-auto-actions"
+    }
+
+    fn name(&self) -> &str {
+        "移除自动操作"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        rewrite(input, output, ctx, remove_auto_actions).await
+    }
+}
+
+fn remove_auto_actions(input: &Path, output: &Path) -> Result<()> {
+    let mut doc = load(input)?;
+    let removed = strip(&mut doc);
+    if removed == 0 {
+        std::fs::copy(input, output)?;
+        return Ok(());
+    }
+    info!(removed, "removing automatic actions");
+    doc.prune_objects();
+    doc.save(output).map_err(|err| anyhow!("无法保存结果：{err}"))?;
+    Ok(())
+}
+
+/// Removes the automatic actions of `doc` and returns how many there were.
+fn strip(doc: &mut Document) -> usize {
+    let mut removed = 0;
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    for page in pages {
+        if let Ok(page) = doc.get_dictionary_mut(page) {
+            removed += usize::from(page.remove(b"AA").is_some());
+        }
+    }
+    let Ok(catalog) = doc.catalog() else {
+        return removed;
+    };
+    let names = catalog.get(b"Names").ok().and_then(|names| names.as_reference().ok());
+    // An array is only a destination to open at, not an action.
+    let opens_at_page = catalog
+        .get(b"OpenAction")
+        .ok()
+        .and_then(|open| doc.dereference(open).ok())
+        .is_some_and(|(_, open)| matches!(open, Object::Array(_)));
+    let Ok(catalog) = doc.catalog_mut() else {
+        return removed;
+    };
+    removed += usize::from(catalog.remove(b"AA").is_some());
+    if !opens_at_page {
+        removed += usize::from(catalog.remove(b"OpenAction").is_some());
+    }
+    let scripts = match catalog.get_mut(b"Names") {
+        Ok(Object::Dictionary(names)) => names.remove(b"JavaScript"),
+        _ => names
+            .and_then(|names| doc.get_dictionary_mut(names).ok())
+            .and_then(|names| names.remove(b"JavaScript")),
+    };
+    removed + usize::from(scripts.is_some())
+}
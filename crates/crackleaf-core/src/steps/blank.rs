@@ -0,0 +1,291 @@
+use std::path::Path;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use tracing::{debug, info, instrument};
+
+use super::layout::{inherited, load, rewrite, xobject};
+use super::{ProcessingStep, StepContext};
+use crate::inspect::read_document;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Pixels darker than this (of 255) count as ink in decoded images.
+const DARK: u32 = 128;
+/// Share of dark pixels up to which an image is taken for an empty scan: specks and
+/// paper texture stay below it, a single line of text does not.
+const INK_SHARE: f64 = 0.001;
+/// Compressed bytes per uncompressed byte up to which an image we do not decode
+/// (JPEG, JBIG2, CCITT, …) is taken for an empty scan. Blank sheets compress to
+/// almost nothing; text and pictures do not.
+const DENSITY: f64 = 0.02;
+/// Nesting of form XObjects followed before a page counts as printed.
+const MAX_DEPTH: usize = 8;
+
+/// Drops pages that print nothing, e.g. the empty backs of a double-sided scan. A page
+/// is blank when its content draws nothing visible (white shapes and invisible OCR
+/// text do not count) and any scanned image on it is nearly white. A file whose
+/// pages are all blank is kept as it is.
+pub struct DropBlankPages;
+
+#[async_trait]
+impl ProcessingStep for DropBlankPages {
+    fn id(&self) -> &str {
+        "drop-blank-pages"
+    }
+
+    fn name(&self) -> &str {
+        "删除空白页"
+    }
+
+    fn rearranges_pages(&self) -> bool {
+        true
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        rewrite(input, output, ctx, drop_blank_pages).await
+    }
+}
+
+fn drop_blank_pages(input: &Path, output: &Path) -> Result<()> {
+    let mut doc = load(input)?;
+    let blank = blank_pages(&doc);
+    if blank.is_empty() || blank.len() == doc.get_pages().len() {
+        std::fs::copy(input, output)?;
+        return Ok(());
+    }
+    info!(pages = ?blank, "dropping blank pages");
+    doc.delete_pages(&blank);
+    doc.prune_objects();
+    doc.save(output).map_err(|err| anyhow!("无法保存结果：{err}"))?;
+    Ok(())
+}
+
+/// Numbers (from 1) of the pages of `path` that print nothing, by the rules of
+/// [`DropBlankPages`]. Returns `None` when the file cannot be read or needs a password.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn find_blank_pages(path: &Path, timeout: Option<Duration>) -> Option<Vec<u32>> {
+    let file = long_path(path).into_owned();
+    let found = with_io_timeout(timeout, move || {
+        Ok(blank_pages(&read_document(&file)?))
+    })
+    .await;
+    match found {
+        Ok(pages) => Some(pages),
+        Err(err) => {
+            debug!("blank page check failed: {err}");
+            None
+        }
+    }
+}
+
+/// Numbers (from 1) of the pages of `doc` that print nothing.
+pub(crate) fn blank_pages(doc: &Document) -> Vec<u32> {
+    doc.get_pages()
+        .into_iter()
+        .filter(|&(_, page)| is_blank(doc, page))
+        .map(|(number, _)| number)
+        .collect()
+}
+
+fn is_blank(doc: &Document, page: ObjectId) -> bool {
+    // Notes, stamps and form fields are content too; links are not visible.
+    let annotated = doc.get_page_annotations(page).is_ok_and(|annotations| {
+        annotations
+            .iter()
+            .any(|annotation| !matches!(annotation.get(b"Subtype").and_then(Object::as_name), Ok(b"Link" | b"Popup")))
+    });
+    if annotated {
+        return false;
+    }
+    let Ok(content) = doc.get_page_content(page) else {
+        return false;
+    };
+    let resources = inherited(doc, page, b"Resources")
+        .and_then(|resources| doc.dereference(resources).ok())
+        .and_then(|(_, resources)| resources.as_dict().ok());
+    !has_ink(doc, &content, resources, 0)
+}
+
+/// Whether `content` draws anything visible, following form XObjects.
+fn has_ink(doc: &Document, content: &[u8], resources: Option<&Dictionary>, depth: usize) -> bool {
+    let Ok(content) = Content::decode(content) else {
+        return true;
+    };
+    // Fill white, stroke white, text invisible; saved and restored with q/Q.
+    let mut state = (false, false, false);
+    let mut saved = Vec::new();
+    for operation in &content.operations {
+        let operands = &operation.operands;
+        let (fill_white, stroke_white, invisible) = state;
+        let ink = match operation.operator.as_str() {
+            "q" => {
+                saved.push(state);
+                false
+            }
+            "Q" => {
+                state = saved.pop().unwrap_or_default();
+                false
+            }
+            "g" | "rg" | "k" | "sc" | "scn" => {
+                state.0 = is_white(operands, &operation.operator);
+                false
+            }
+            "G" | "RG" | "K" | "SC" | "SCN" => {
+                state.1 = is_white(operands, &operation.operator);
+                false
+            }
+            // A new colour space starts out black.
+            "cs" => {
+                state.0 = false;
+                false
+            }
+            "CS" => {
+                state.1 = false;
+                false
+            }
+            // Mode 3 is what OCR layers use; 7 only clips.
+            "Tr" => {
+                state.2 = matches!(operands.first().and_then(|mode| mode.as_i64().ok()), Some(3 | 7));
+                false
+            }
+            "Tj" | "'" | "\"" | "TJ" => !invisible && !fill_white && shows_text(operands),
+            "S" | "s" => !stroke_white,
+            "f" | "F" | "f*" => !fill_white,
+            "B" | "B*" | "b" | "b*" => !(fill_white && stroke_white),
+            "sh" | "BI" | "EI" => true,
+            "Do" => {
+                let name = operands.first().and_then(|name| name.as_name().ok());
+                match name.and_then(|name| xobject(doc, resources, name)) {
+                    Some(xobject) => xobject_has_ink(doc, xobject, resources, depth),
+                    None => true,
+                }
+            }
+            _ => false,
+        };
+        if ink {
+            return true;
+        }
+    }
+    false
+}
+
+fn xobject_has_ink(doc: &Document, xobject: &Stream, resources: Option<&Dictionary>, depth: usize) -> bool {
+    match xobject.dict.get(b"Subtype").and_then(Object::as_name) {
+        Ok(b"Image") => image_has_ink(doc, xobject),
+        Ok(b"Form") if depth < MAX_DEPTH => {
+            let Some(content) = stream_data(xobject) else {
+                return true;
+            };
+            let own = xobject
+                .dict
+                .get(b"Resources")
+                .ok()
+                .and_then(|resources| doc.dereference(resources).ok()?.1.as_dict().ok());
+            has_ink(doc, &content, own.or(resources), depth + 1)
+        }
+        _ => true,
+    }
+}
+
+/// Whether the colour set by `operator` is white: gray 1, RGB 1 1 1 or CMYK 0 0 0 0.
+/// Patterns and colour spaces we cannot judge count as ink.
+pub(super) fn is_white(operands: &[Object], operator: &str) -> bool {
+    let Ok(values) = operands.iter().map(Object::as_float).collect::<Result<Vec<f32>, _>>() else {
+        return false;
+    };
+    let cmyk = matches!(operator, "k" | "K") || values.len() == 4;
+    match values.len() {
+        1 | 3 if !cmyk => values.iter().all(|&value| value >= 0.99),
+        4 => values.iter().all(|&value| value <= 0.01),
+        _ => false,
+    }
+}
+
+/// Whether the strings of a text operator hold more than spaces.
+pub(super) fn shows_text(operands: &[Object]) -> bool {
+    let visible = |bytes: &[u8]| bytes.iter().any(|byte| !matches!(byte, 0 | b' ' | b'\t' | b'\r' | b'\n'));
+    operands.iter().any(|operand| match operand {
+        Object::String(bytes, _) => visible(bytes),
+        Object::Array(items) => items.iter().any(|item| matches!(item, Object::String(bytes, _) if visible(bytes))),
+        _ => false,
+    })
+}
+
+/// Whether an image is more than an empty scan. 8-bit gray and RGB images and
+/// bilevel ones that are only deflated are measured pixel by pixel; the rest by how
+/// well they compress.
+fn image_has_ink(doc: &Document, image: &Stream) -> bool {
+    let number = |key: &[u8]| image.dict.get(key).and_then(Object::as_i64).ok().filter(|&value| value > 0);
+    let (Some(width), Some(height)) = (number(b"Width"), number(b"Height")) else {
+        return true;
+    };
+    let mask = image.dict.get(b"ImageMask").and_then(Object::as_bool).unwrap_or(false);
+    let bits = if mask { 1 } else { number(b"BitsPerComponent").unwrap_or(8) };
+    let space = image
+        .dict
+        .get(b"ColorSpace")
+        .ok()
+        .and_then(|space| doc.dereference(space).ok())
+        .and_then(|(_, space)| space.as_name().ok());
+    let channels = match space {
+        _ if mask => 1,
+        Some(b"DeviceGray" | b"CalGray") => 1,
+        Some(b"DeviceRGB" | b"CalRGB") => 3,
+        Some(b"DeviceCMYK") => 4,
+        _ => 0,
+    };
+    let filters: Vec<&[u8]> = match image.dict.get(b"Filter") {
+        Ok(Object::Name(name)) => vec![name],
+        Ok(Object::Array(names)) => names.iter().filter_map(|name| name.as_name().ok()).collect(),
+        _ => Vec::new(),
+    };
+    let deflated = filters.iter().all(|filter| matches!(*filter, b"FlateDecode" | b"LZWDecode"));
+    let inverted = matches!(
+        image.dict.get(b"Decode").and_then(Object::as_array).map(Vec::as_slice),
+        Ok([first, ..]) if first.as_float().is_ok_and(|value| value >= 1.0)
+    );
+
+    let (width, height) = (width as usize, height as usize);
+    if deflated && matches!((bits, channels), (8, 1 | 3) | (1, 1)) {
+        let Some(pixels) = stream_data(image) else {
+            return true;
+        };
+        let dark = if bits == 8 {
+            pixels
+                .chunks_exact(channels)
+                .take(width * height)
+                .filter(|pixel| {
+                    let level = pixel.iter().map(|&value| u32::from(value)).sum::<u32>() / channels as u32;
+                    (level < DARK) != inverted
+                })
+                .count()
+        } else {
+            // Rows start on a byte; a 0 bit is black unless the decode array flips it.
+            let black = |row: &[u8], x: usize| row.get(x / 8).is_some_and(|byte| byte & (0x80 >> (x % 8)) == 0);
+            pixels
+                .chunks(width.div_ceil(8))
+                .take(height)
+                .map(|row| (0..width).filter(|&x| black(row, x) != inverted).count())
+                .sum()
+        };
+        return dark as f64 / (width * height) as f64 > INK_SHARE;
+    }
+    // Colour spaces we do not know are taken for RGB.
+    let channels = if channels == 0 { 3 } else { channels };
+    let samples = (width * height * channels) as f64 * bits as f64 / 8.0;
+    image.content.len() as f64 / samples > DENSITY
+}
+
+/// The data of `stream` with its filters undone. lopdf declines to do that for
+/// images, so they are decoded as a plain stream.
+pub(super) fn stream_data(stream: &Stream) -> Option<Vec<u8>> {
+    if stream.dict.get(b"Filter").is_err() {
+        return Some(stream.content.clone());
+    }
+    let mut dict = stream.dict.clone();
+    dict.remove(b"Subtype");
+    Stream::new(dict, stream.content.clone()).decompressed_content().ok()
+}
@@ -0,0 +1,146 @@
+use std::io::Write;
+use std::path::Path;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use tracing::info;
+
+use super::{ProcessingStep, StepContext};
+use crate::detect::detect_encrypted;
+use crate::error::Error;
+use crate::qpdf::{qpdf_command, run_qpdf_checked};
+use crate::volume::long_path;
+
+/// Removes encryption that only needs the (empty) user password, or one of the
+/// [`passwords`](StepContext::passwords) to try. Skips unencrypted files.
+pub struct Decrypt;
+
+#[async_trait]
+impl ProcessingStep for Decrypt {
+    fn id(&self) -> &str {
+        "decrypt"
+    }
+
+    fn name(&self) -> &str {
+        "解密"
+    }
+
+    fn encrypted_only(&self) -> bool {
+        true
+    }
+
+    async fn analyze(&self, input: &Path, ctx: &StepContext<'_>) -> Result<bool> {
+        Ok(detect_encrypted(input, ctx.timeout).await != Some(false))
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let result = decrypt(input, output, "", ctx).await;
+        if !is_wrong_password(&result) {
+            return result;
+        }
+        for (number, password) in ctx.passwords.iter().enumerate() {
+            let retry = decrypt(input, output, password, ctx).await;
+            if !is_wrong_password(&retry) {
+                if retry.is_ok() {
+                    info!(candidate = number + 1, "opened with a remembered password");
+                }
+                return retry;
+            }
+        }
+        result
+    }
+}
+
+/// Runs `qpdf --decrypt`. A non-empty password is handed over in a temp file only
+/// the user can read, so it never shows up in the process list.
+async fn decrypt(input: &Path, output: &Path, password: &str, ctx: &StepContext<'_>) -> Result<()> {
+    let mut cmd = qpdf_command();
+    let password_file = if password.is_empty() {
+        cmd.arg("--password=");
+        None
+    } else {
+        let mut file = tempfile::Builder::new().prefix("crackleaf-password-").tempfile()?;
+        file.write_all(password.as_bytes())?;
+        file.flush()?;
+        let mut arg = std::ffi::OsString::from("--password-file=");
+        arg.push(file.path());
+        cmd.arg(arg);
+        Some(file)
+    };
+    cmd.arg("--decrypt").arg(&*long_path(input)).arg(&*long_path(output));
+    let result = run_qpdf_checked(cmd, ctx.timeout).await;
+    drop(password_file);
+    result
+}
+
+fn is_wrong_password(result: &Result<()>) -> bool {
+    result.as_ref().is_err_and(|err| Error::from_anyhow(err) == Error::WrongPassword)
+}
+
+/// Drops the document information dictionary and XMP metadata.
+pub struct StripMetadata;
+
+#[async_trait]
+impl ProcessingStep for StripMetadata {
+    fn id(&self) -> &str {
+        "strip-metadata"
+    }
+
+    fn name(&self) -> &str {
+        "移除元数据"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let mut cmd = qpdf_command();
+        cmd.arg("--remove-info")
+            .arg("--remove-metadata")
+            .arg(&*long_path(input))
+            .arg(&*long_path(output));
+        run_qpdf_checked(cmd, ctx.timeout).await
+    }
+}
+
+/// Rewrites the file for fast web view.
+pub struct Linearize;
+
+#[async_trait]
+impl ProcessingStep for Linearize {
+    fn id(&self) -> &str {
+        "linearize"
+    }
+
+    fn name(&self) -> &str {
+        "线性化"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let mut cmd = qpdf_command();
+        cmd.arg("--linearize").arg(&*long_path(input)).arg(&*long_path(output));
+        run_qpdf_checked(cmd, ctx.timeout).await
+    }
+}
+
+/// Recompresses streams and packs objects into object streams.
+pub struct Compress;
+
+#[async_trait]
+impl ProcessingStep for Compress {
+    fn id(&self) -> &str {
+        "compress"
+    }
+
+    fn name(&self) -> &str {
+        "压缩"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let mut cmd = qpdf_command();
+        cmd.arg("--object-streams=generate")
+            .arg("--compress-streams=y")
+            .arg("--recompress-flate")
+            .arg("--compression-level=9")
+            .arg(&*long_path(input))
+            .arg(&*long_path(output));
+        run_qpdf_checked(cmd, ctx.timeout).await
+    }
+}
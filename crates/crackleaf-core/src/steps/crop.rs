@@ -0,0 +1,295 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+
+use super::blank::{is_white, stream_data};
+use super::layout::{apply, inherited, load, multiply, rewrite, xobject, Geometry};
+use super::{ProcessingStep, StepContext};
+
+/// Nesting of form XObjects followed when measuring content.
+const MAX_DEPTH: usize = 8;
+/// Extent of a glyph in units of the font size: advance (a generous average), and
+/// how far it reaches below and above the baseline.
+const GLYPH_WIDTH: f32 = 0.6;
+pub(super) const DESCENT: f32 = 0.25;
+pub(super) const ASCENT: f32 = 1.0;
+const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// How [`CropMargins`] finds the new page edges.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Crop {
+    /// Trims `[top, right, bottom, left]` points off each page as displayed.
+    Margins([f32; 4]),
+    /// Cuts each page down to what is drawn on it, keeping `padding` points around it.
+    Auto { padding: f32 },
+}
+
+/// Sets the crop box of every page so viewers show less empty margin, e.g. for
+/// e-books laid out for print. Nothing is removed from the file; pages on which
+/// nothing is drawn keep their size.
+pub struct CropMargins {
+    crop: Crop,
+}
+
+impl CropMargins {
+    pub fn new(crop: Crop) -> Self {
+        Self { crop }
+    }
+}
+
+impl Default for CropMargins {
+    fn default() -> Self {
+        Self::new(Crop::Auto { padding: 10.0 })
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for CropMargins {
+    fn id(&self) -> &str {
+        "crop-margins"
+    }
+
+    fn name(&self) -> &str {
+        "裁剪页边距"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let crop = self.crop;
+        rewrite(input, output, ctx, move |input, output| crop_pages(input, output, crop)).await
+    }
+}
+
+fn crop_pages(input: &Path, output: &Path, crop: Crop) -> Result<()> {
+    let mut doc = load(input)?;
+    for page in doc.get_pages().into_values().collect::<Vec<_>>() {
+        let geometry = Geometry::of(&doc, page)?;
+        let [x0, y0, x1, y1] = geometry.bounds;
+        let area = match crop {
+            Crop::Margins([top, right, bottom, left]) => {
+                let (width, height) = geometry.size();
+                if left + right >= width || top + bottom >= height {
+                    continue;
+                }
+                let matrix = geometry.display_to_page();
+                let (ax, ay) = apply(matrix, (left, bottom));
+                let (bx, by) = apply(matrix, (width - right, height - top));
+                [ax.min(bx), ay.min(by), ax.max(bx), ay.max(by)]
+            }
+            Crop::Auto { padding } => {
+                let Some([left, bottom, right, top]) = content_bounds(&doc, page) else {
+                    continue;
+                };
+                [
+                    (left - padding).max(x0),
+                    (bottom - padding).max(y0),
+                    (right + padding).min(x1),
+                    (top + padding).min(y1),
+                ]
+            }
+        };
+        if area[2] <= area[0] || area[3] <= area[1] {
+            continue;
+        }
+        let crop_box: Vec<Object> = area.iter().map(|&value| value.into()).collect();
+        doc.get_dictionary_mut(page)?.set("CropBox", crop_box);
+    }
+    doc.compress();
+    doc.save(output).map_err(|err| anyhow!("无法保存结果：{err}"))?;
+    Ok(())
+}
+
+/// `[left, bottom, right, top]` of what the page draws, in its own coordinates, or
+/// `None` when it draws nothing. Text is estimated from the number of characters.
+fn content_bounds(doc: &Document, page: ObjectId) -> Option<[f32; 4]> {
+    let content = doc.get_page_content(page).ok()?;
+    let resources = inherited(doc, page, b"Resources")
+        .and_then(|resources| doc.dereference(resources).ok())
+        .and_then(|(_, resources)| resources.as_dict().ok());
+    let mut bounds = Bounds::default();
+    measure(doc, &content, resources, IDENTITY, 0, &mut bounds);
+    bounds.0
+}
+
+/// The smallest rectangle around the points added so far.
+#[derive(Default)]
+struct Bounds(Option<[f32; 4]>);
+
+impl Bounds {
+    fn add(&mut self, (x, y): (f32, f32)) {
+        let [x0, y0, x1, y1] = self.0.get_or_insert([x, y, x, y]);
+        *x0 = x0.min(x);
+        *y0 = y0.min(y);
+        *x1 = x1.max(x);
+        *y1 = y1.max(y);
+    }
+
+    fn merge(&mut self, other: &Bounds) {
+        if let Some([x0, y0, x1, y1]) = other.0 {
+            self.add((x0, y0));
+            self.add((x1, y1));
+        }
+    }
+
+    /// Adds the corners of the unit square under `matrix`, as images are drawn.
+    fn add_unit_square(&mut self, matrix: [f32; 6]) {
+        for corner in [(0.0, 0.0), (1.0, 0.0), (0.0, 1.0), (1.0, 1.0)] {
+            self.add(apply(matrix, corner));
+        }
+    }
+}
+
+/// Graphics state that matters for measuring, saved and restored with q/Q.
+#[derive(Clone, Copy)]
+struct State {
+    ctm: [f32; 6],
+    fill_white: bool,
+    stroke_white: bool,
+    invisible: bool,
+    font_size: f32,
+    leading: f32,
+}
+
+/// Adds what `content` draws under `ctm` to `bounds`, following form XObjects.
+fn measure(
+    doc: &Document,
+    content: &[u8],
+    resources: Option<&Dictionary>,
+    ctm: [f32; 6],
+    depth: usize,
+    bounds: &mut Bounds,
+) {
+    let Ok(content) = Content::decode(content) else {
+        return;
+    };
+    let mut state = State {
+        ctm,
+        fill_white: false,
+        stroke_white: false,
+        invisible: false,
+        font_size: 0.0,
+        leading: 0.0,
+    };
+    let mut saved = Vec::new();
+    let mut path = Bounds::default();
+    let (mut text_matrix, mut line_matrix) = (IDENTITY, IDENTITY);
+    for operation in &content.operations {
+        let operands = &operation.operands;
+        let numbers: Vec<f32> = operands.iter().filter_map(|operand| operand.as_float().ok()).collect();
+        let point = |index: usize| (numbers[index], numbers[index + 1]);
+        let count = numbers.len();
+        match operation.operator.as_str() {
+            "q" => saved.push(state),
+            "Q" => state = saved.pop().unwrap_or(state),
+            "cm" if count == 6 => state.ctm = multiply(numbers[..6].try_into().unwrap_or(IDENTITY), state.ctm),
+            "g" | "rg" | "k" | "sc" | "scn" => state.fill_white = is_white(operands, &operation.operator),
+            "G" | "RG" | "K" | "SC" | "SCN" => state.stroke_white = is_white(operands, &operation.operator),
+            "cs" => state.fill_white = false,
+            "CS" => state.stroke_white = false,
+
+            "m" | "l" if count >= 2 => path.add(apply(state.ctm, point(0))),
+            "c" if count >= 6 => (0..3).for_each(|index| path.add(apply(state.ctm, point(2 * index)))),
+            "v" | "y" if count >= 4 => (0..2).for_each(|index| path.add(apply(state.ctm, point(2 * index)))),
+            "re" if count >= 4 => {
+                let (x, y, width, height) = (numbers[0], numbers[1], numbers[2], numbers[3]);
+                for corner in [(x, y), (x + width, y), (x, y + height), (x + width, y + height)] {
+                    path.add(apply(state.ctm, corner));
+                }
+            }
+            painting @ ("S" | "s" | "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" | "n") => {
+                let visible = match painting {
+                    "S" | "s" => !state.stroke_white,
+                    "f" | "F" | "f*" => !state.fill_white,
+                    "n" => false,
+                    _ => !(state.fill_white && state.stroke_white),
+                };
+                if visible {
+                    bounds.merge(&path);
+                }
+                path = Bounds::default();
+            }
+            "BI" | "EI" => bounds.add_unit_square(state.ctm),
+            "Do" => {
+                let name = operands.first().and_then(|name| name.as_name().ok());
+                let Some(stream) = name.and_then(|name| xobject(doc, resources, name)) else {
+                    continue;
+                };
+                match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+                    Ok(b"Image") => bounds.add_unit_square(state.ctm),
+                    Ok(b"Form") if depth < MAX_DEPTH => {
+                        let matrix = stream
+                            .dict
+                            .get(b"Matrix")
+                            .and_then(Object::as_array)
+                            .ok()
+                            .and_then(|values| values.iter().map(|value| value.as_float().ok()).collect::<Option<Vec<_>>>())
+                            .and_then(|values| <[f32; 6]>::try_from(values).ok())
+                            .unwrap_or(IDENTITY);
+                        let own = stream
+                            .dict
+                            .get(b"Resources")
+                            .ok()
+                            .and_then(|resources| doc.dereference(resources).ok()?.1.as_dict().ok());
+                        if let Some(content) = stream_data(stream) {
+                            measure(doc, &content, own.or(resources), multiply(matrix, state.ctm), depth + 1, bounds);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            "BT" => (text_matrix, line_matrix) = (IDENTITY, IDENTITY),
+            "Tf" if count >= 1 => state.font_size = numbers[count - 1],
+            "TL" if count >= 1 => state.leading = numbers[0],
+            "Tr" => state.invisible = matches!(operands.first().and_then(|mode| mode.as_i64().ok()), Some(3 | 7)),
+            "Tm" if count == 6 => {
+                line_matrix = numbers[..6].try_into().unwrap_or(IDENTITY);
+                text_matrix = line_matrix;
+            }
+            "Td" | "TD" if count >= 2 => {
+                if operation.operator == "TD" {
+                    state.leading = -numbers[1];
+                }
+                line_matrix = multiply([1.0, 0.0, 0.0, 1.0, numbers[0], numbers[1]], line_matrix);
+                text_matrix = line_matrix;
+            }
+            "T*" | "'" | "\"" | "Tj" | "TJ" => {
+                if matches!(operation.operator.as_str(), "T*" | "'" | "\"") {
+                    line_matrix = multiply([1.0, 0.0, 0.0, 1.0, 0.0, -state.leading], line_matrix);
+                    text_matrix = line_matrix;
+                }
+                let advance = text_advance(operands) * state.font_size;
+                if advance != 0.0 && !state.invisible && !state.fill_white {
+                    let matrix = multiply(text_matrix, state.ctm);
+                    let size = state.font_size;
+                    for corner in [(0.0, -DESCENT * size), (advance, -DESCENT * size), (0.0, ASCENT * size), (advance, ASCENT * size)] {
+                        bounds.add(apply(matrix, corner));
+                    }
+                }
+                text_matrix = multiply([1.0, 0.0, 0.0, 1.0, advance, 0.0], text_matrix);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Estimated width of the strings of a text operator, in units of the font size.
+pub(super) fn text_advance(operands: &[Object]) -> f32 {
+    let width = |bytes: &[u8]| bytes.len() as f32 * GLYPH_WIDTH;
+    operands
+        .iter()
+        .map(|operand| match operand {
+            Object::String(bytes, _) => width(bytes),
+            Object::Array(items) => items
+                .iter()
+                .map(|item| match item {
+                    Object::String(bytes, _) => width(bytes),
+                    other => other.as_float().map_or(0.0, |adjust| -adjust / 1000.0),
+                })
+                .sum(),
+            _ => 0.0,
+        })
+        .sum()
+}
@@ -0,0 +1,141 @@
+use std::ffi::{c_char, c_int, CStr, CString};
+use std::path::Path;
+use std::sync::Arc;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use libloading::{Library, Symbol};
+
+use super::{ProcessingStep, StepContext};
+
+/// Version of the C interface plugins must report from `crackleaf_step_abi_version`.
+///
+/// A plugin exports:
+///
+/// ```c
+/// uint32_t    crackleaf_step_abi_version(void);
+/// const char *crackleaf_step_id(void);
+/// const char *crackleaf_step_name(void);
+/// int         crackleaf_step_transform(const char *input, const char *output); // 0 = ok
+/// int         crackleaf_step_analyze(const char *input);   // optional: 1 = apply, 0 = skip, <0 = error
+/// int         crackleaf_step_finalize(const char *output); // optional: 0 = ok
+/// ```
+pub const PLUGIN_ABI_VERSION: u32 = 1;
+
+type AbiVersionFn = unsafe extern "C" fn() -> u32;
+type StrFn = unsafe extern "C" fn() -> *const c_char;
+type PathFn = unsafe extern "C" fn(*const c_char) -> c_int;
+type TransformFn = unsafe extern "C" fn(*const c_char, *const c_char) -> c_int;
+
+/// Step implemented by a shared library loaded at runtime.
+pub struct DynamicStep {
+    id: String,
+    name: String,
+    library: Arc<Library>,
+}
+
+impl DynamicStep {
+    /// Loads the plugin at `path`.
+    ///
+    /// # Safety
+    ///
+    /// Runs the library's initialisers and trusts its exported functions to follow
+    /// the documented interface.
+    pub unsafe fn load(path: &Path) -> Result<Self> {
+        let library = Library::new(path)?;
+        let abi: Symbol<AbiVersionFn> = library.get(b"crackleaf_step_abi_version\0")?;
+        let version = abi();
+        if version != PLUGIN_ABI_VERSION {
+            bail!("插件接口版本 {version} 不受支持（需要 {PLUGIN_ABI_VERSION}）");
+        }
+        let id = read_str(&library, b"crackleaf_step_id\0")?;
+        let name = read_str(&library, b"crackleaf_step_name\0").unwrap_or_else(|_| id.clone());
+        library.get::<TransformFn>(b"crackleaf_step_transform\0")?;
+        Ok(Self {
+            id,
+            name,
+            library: Arc::new(library),
+        })
+    }
+
+    async fn call_path(&self, symbol: &'static [u8], path: &Path) -> Result<Option<c_int>> {
+        let library = self.library.clone();
+        let path = c_path(path)?;
+        tokio::task::spawn_blocking(move || unsafe {
+            match library.get::<PathFn>(symbol) {
+                Ok(func) => Some(func(path.as_ptr())),
+                Err(_) => None,
+            }
+        })
+        .await
+        .map_err(|err| anyhow!("插件崩溃：{err}"))
+    }
+}
+
+unsafe fn read_str(library: &Library, symbol: &[u8]) -> Result<String> {
+    let func: Symbol<StrFn> = library.get(symbol)?;
+    let ptr = func();
+    if ptr.is_null() {
+        bail!("插件返回了空字符串");
+    }
+    Ok(CStr::from_ptr(ptr).to_string_lossy().into_owned())
+}
+
+/// Raw bytes on Unix; elsewhere plugins get UTF-8, so other paths are refused.
+fn c_path(path: &Path) -> Result<CString> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Ok(CString::new(path.as_os_str().as_bytes())?)
+    }
+    #[cfg(not(unix))]
+    {
+        let text = path
+            .to_str()
+            .ok_or_else(|| anyhow!("路径不是有效的 UTF-8：{}", path.display()))?;
+        Ok(CString::new(text)?)
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for DynamicStep {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn analyze(&self, input: &Path, _ctx: &StepContext<'_>) -> Result<bool> {
+        match self.call_path(b"crackleaf_step_analyze\0", input).await? {
+            None => Ok(true),
+            Some(code) if code < 0 => bail!("分析失败（{code}）"),
+            Some(code) => Ok(code > 0),
+        }
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, _ctx: &StepContext<'_>) -> Result<()> {
+        let library = self.library.clone();
+        let input = c_path(input)?;
+        let output = c_path(output)?;
+        let code = tokio::task::spawn_blocking(move || unsafe {
+            library
+                .get::<TransformFn>(b"crackleaf_step_transform\0")
+                .map(|func| func(input.as_ptr(), output.as_ptr()))
+        })
+        .await
+        .map_err(|err| anyhow!("插件崩溃：{err}"))??;
+        if code != 0 {
+            bail!("处理失败（{code}）");
+        }
+        Ok(())
+    }
+
+    async fn finalize(&self, output: &Path, _ctx: &StepContext<'_>) -> Result<()> {
+        match self.call_path(b"crackleaf_step_finalize\0", output).await? {
+            Some(code) if code != 0 => bail!("收尾失败（{code}）"),
+            _ => Ok(()),
+        }
+    }
+}
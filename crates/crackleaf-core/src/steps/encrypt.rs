@@ -0,0 +1,89 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{ProcessingStep, StepContext};
+use crate::qpdf::{qpdf_command, run_qpdf_checked};
+use crate::volume::long_path;
+
+/// What readers of a file written by [`Encrypt`] may still do without the owner
+/// password.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Permissions {
+    /// Print at full quality.
+    pub print: bool,
+    /// Copy or extract text and images.
+    pub copy: bool,
+    /// Change the document, e.g. insert or rotate pages and fill in forms.
+    pub modify: bool,
+    /// Add or change comments.
+    pub annotate: bool,
+}
+
+impl Default for Permissions {
+    fn default() -> Self {
+        Self {
+            print: true,
+            copy: false,
+            modify: false,
+            annotate: false,
+        }
+    }
+}
+
+/// Encrypts with AES-256 under an owner password and restricts what may be done with
+/// the file; it still opens without a password. Inputs that only need the empty user
+/// password are re-encrypted.
+pub struct Encrypt {
+    owner_password: String,
+    permissions: Permissions,
+}
+
+impl Encrypt {
+    pub fn new(owner_password: impl Into<String>, permissions: Permissions) -> Self {
+        Self {
+            owner_password: owner_password.into(),
+            permissions,
+        }
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for Encrypt {
+    fn id(&self) -> &str {
+        "encrypt"
+    }
+
+    fn name(&self) -> &str {
+        "加密"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        if self.owner_password.is_empty() {
+            bail!("未设置所有者密码");
+        }
+        let allow = |allowed: bool, yes: &'static str, no: &'static str| if allowed { yes } else { no };
+        let Permissions {
+            print,
+            copy,
+            modify,
+            annotate,
+        } = self.permissions;
+        let mut cmd = qpdf_command();
+        cmd.arg("--encrypt")
+            .arg("")
+            .arg(&self.owner_password)
+            .arg("256")
+            .arg(format!("--print={}", allow(print, "full", "none")))
+            .arg(format!("--extract={}", allow(copy, "y", "n")))
+            .arg(format!("--modify={}", allow(modify, "all", "none")))
+            .arg(format!("--annotate={}", allow(annotate, "y", "n")))
+            .arg("--")
+            .arg(&*long_path(input))
+            .arg(&*long_path(output));
+        run_qpdf_checked(cmd, ctx.timeout).await
+    }
+}
@@ -0,0 +1,75 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use tokio::process::Command;
+
+use super::{ProcessingStep, StepContext};
+use crate::error::Error;
+use crate::qpdf::process_permit;
+
+/// Runs a user-supplied command as a step.
+///
+/// `{input}`, `{output}` and `{source}` in the arguments are replaced with the
+/// current file, the file the command must write, and the original input.
+pub struct HookStep {
+    id: String,
+    name: String,
+    command: Vec<String>,
+}
+
+impl HookStep {
+    pub fn new(id: impl Into<String>, name: impl Into<String>, command: Vec<String>) -> Self {
+        Self {
+            id: id.into(),
+            name: name.into(),
+            command,
+        }
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for HookStep {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let Some((program, args)) = self.command.split_first() else {
+            bail!("未配置命令");
+        };
+        let mut cmd = Command::new(program);
+        cmd.kill_on_drop(true);
+        #[cfg(target_os = "windows")]
+        cmd.creation_flags(0x08000000);
+        for arg in args {
+            match arg.as_str() {
+                "{input}" => cmd.arg(input),
+                "{output}" => cmd.arg(output),
+                "{source}" => cmd.arg(ctx.source),
+                _ => cmd.arg(arg),
+            };
+        }
+
+        let _permit = process_permit().await;
+        let status = cmd.status();
+        let status = match ctx.timeout {
+            Some(limit) => match tokio::time::timeout(limit, status).await {
+                Ok(status) => status?,
+                Err(_) => return Err(Error::Timeout(limit).into()),
+            },
+            None => status.await?,
+        };
+        if !status.success() {
+            bail!("命令退出码 {:?}", status.code());
+        }
+        if !output.exists() {
+            bail!("命令没有生成输出文件");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use std::time::Duration;
+
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object};
+use serde::Serialize;
+use tracing::{debug, instrument};
+
+use super::blank::{shows_text, stream_data};
+use super::layout::{inherited, xobject};
+use crate::inspect::read_document;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Nesting of form XObjects followed.
+const MAX_DEPTH: usize = 8;
+
+/// Whether a PDF's pages carry text or only pictures of it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PdfKind {
+    /// Some page has text that can be selected and extracted, e.g. a born-digital
+    /// document or a scan that went through OCR.
+    Text,
+    /// Pages are images without text, like a scan: text extraction and search need
+    /// OCR first.
+    Scanned,
+}
+
+/// What the pages looked at so far draw.
+#[derive(Default)]
+struct Found {
+    text: bool,
+    images: bool,
+}
+
+/// Tells text-based PDFs from image-only ones. Returns `None` when the file cannot
+/// be read, needs a password, or draws neither text nor images.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn classify_pdf(path: &Path, timeout: Option<Duration>) -> Option<PdfKind> {
+    let file = long_path(path).into_owned();
+    let found = with_io_timeout(timeout, move || {
+        Ok(classify(&read_document(&file)?))
+    })
+    .await;
+    match found {
+        Ok(kind) => kind,
+        Err(err) => {
+            debug!("classification failed: {err}");
+            None
+        }
+    }
+}
+
+/// What [`classify_pdf`] tells of `doc`.
+pub(crate) fn classify(doc: &Document) -> Option<PdfKind> {
+    let mut found = Found::default();
+    for page in doc.get_pages().into_values() {
+        let Ok(content) = doc.get_page_content(page) else {
+            continue;
+        };
+        let resources = inherited(doc, page, b"Resources")
+            .and_then(|resources| doc.dereference(resources).ok())
+            .and_then(|(_, resources)| resources.as_dict().ok());
+        scan(doc, &content, resources, 0, &mut found);
+        if found.text {
+            break;
+        }
+    }
+    if found.text {
+        Some(PdfKind::Text)
+    } else if found.images {
+        Some(PdfKind::Scanned)
+    } else {
+        None
+    }
+}
+
+/// Notes whether `content` shows text, invisible OCR text included, or images.
+fn scan(doc: &Document, content: &[u8], resources: Option<&Dictionary>, depth: usize, found: &mut Found) {
+    let Ok(content) = Content::decode(content) else {
+        return;
+    };
+    for operation in &content.operations {
+        match operation.operator.as_str() {
+            "Tj" | "'" | "\"" | "TJ" if shows_text(&operation.operands) => {
+                found.text = true;
+                return;
+            }
+            "BI" | "EI" => found.images = true,
+            "Do" => {
+                let name = operation.operands.first().and_then(|name| name.as_name().ok());
+                let Some(stream) = name.and_then(|name| xobject(doc, resources, name)) else {
+                    continue;
+                };
+                match stream.dict.get(b"Subtype").and_then(Object::as_name) {
+                    Ok(b"Image") => found.images = true,
+                    Ok(b"Form") if depth < MAX_DEPTH => {
+                        let own = stream
+                            .dict
+                            .get(b"Resources")
+                            .ok()
+                            .and_then(|resources| doc.dereference(resources).ok()?.1.as_dict().ok());
+                        if let Some(content) = stream_data(stream) {
+                            scan(doc, &content, own.or(resources), depth + 1, found);
+                        }
+                        if found.text {
+                            return;
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+}
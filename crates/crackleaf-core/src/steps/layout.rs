@@ -0,0 +1,196 @@
+//! Shared pieces of the steps that rewrite PDFs in process with lopdf rather than
+//! through qpdf: loading, page geometry and placement on the page.
+
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream};
+use serde::{Deserialize, Serialize};
+
+use super::StepContext;
+use crate::error::Error;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Where a mark goes on the page, as displayed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Position {
+    TopLeft,
+    TopCenter,
+    TopRight,
+    Center,
+    BottomLeft,
+    BottomCenter,
+    BottomRight,
+}
+
+impl Position {
+    /// Lower left corner of an item of `size` placed on an `area`, `margin` away from
+    /// the edges it is aligned to.
+    pub(super) fn place(self, area: (f32, f32), size: (f32, f32), margin: f32) -> (f32, f32) {
+        let x = match self {
+            Position::TopLeft | Position::BottomLeft => margin,
+            Position::TopCenter | Position::Center | Position::BottomCenter => (area.0 - size.0) / 2.0,
+            Position::TopRight | Position::BottomRight => area.0 - margin - size.0,
+        };
+        let y = match self {
+            Position::TopLeft | Position::TopCenter | Position::TopRight => area.1 - margin - size.1,
+            Position::Center => (area.1 - size.1) / 2.0,
+            Position::BottomLeft | Position::BottomCenter | Position::BottomRight => margin,
+        };
+        (x, y)
+    }
+}
+
+/// Runs `work`, which rewrites `input` to `output` in process, on the blocking pool
+/// within the step's time limit.
+pub(super) async fn rewrite<F>(input: &Path, output: &Path, ctx: &StepContext<'_>, work: F) -> Result<()>
+where
+    F: FnOnce(&Path, &Path) -> Result<()> + Send + 'static,
+{
+    let (input, output) = (long_path(input).into_owned(), long_path(output).into_owned());
+    with_io_timeout(ctx.timeout, move || work(&input, &output).map_err(std::io::Error::other))
+        .await
+        .map_err(|err| match ctx.timeout {
+            Some(limit) if err.kind() == std::io::ErrorKind::TimedOut => Error::Timeout(limit).into(),
+            _ => anyhow!("{err}"),
+        })
+}
+
+/// Loads a decrypted PDF for rewriting.
+pub(super) fn load(input: &Path) -> Result<Document> {
+    let doc = Document::load(input).map_err(|err| anyhow!("无法读取文件：{err}"))?;
+    if doc.is_encrypted() {
+        bail!("文件仍然加密，请把这一步放在解密之后");
+    }
+    Ok(doc)
+}
+
+/// Visible area and rotation of a page: the area is shown turned clockwise by a
+/// multiple of 90°.
+pub(super) struct Geometry {
+    /// `[left, bottom, right, top]` of the crop box, or the media box without one.
+    pub bounds: [f32; 4],
+    pub rotate: i64,
+}
+
+impl Geometry {
+    pub fn of(doc: &Document, page: ObjectId) -> Result<Self> {
+        let bounds = inherited(doc, page, b"CropBox")
+            .or_else(|| inherited(doc, page, b"MediaBox"))
+            .and_then(|bounds| rectangle(doc, bounds))
+            .filter(|[x0, y0, x1, y1]| x1 > x0 && y1 > y0)
+            .ok_or_else(|| anyhow!("页面缺少有效尺寸"))?;
+        let rotate = inherited(doc, page, b"Rotate")
+            .and_then(|rotate| doc.dereference(rotate).ok()?.1.as_i64().ok())
+            .unwrap_or(0)
+            .rem_euclid(360);
+        Ok(Self { bounds, rotate })
+    }
+
+    /// Width and height as displayed.
+    pub fn size(&self) -> (f32, f32) {
+        let [x0, y0, x1, y1] = self.bounds;
+        match self.rotate {
+            90 | 270 => (y1 - y0, x1 - x0),
+            _ => (x1 - x0, y1 - y0),
+        }
+    }
+
+    /// Matrix from page space to the displayed area, with the origin at its lower left.
+    pub fn page_to_display(&self) -> [f32; 6] {
+        let [x0, y0, x1, y1] = self.bounds;
+        let (width, height) = (x1 - x0, y1 - y0);
+        match self.rotate {
+            90 => [0.0, -1.0, 1.0, 0.0, -y0, width + x0],
+            180 => [-1.0, 0.0, 0.0, -1.0, width + x0, height + y0],
+            270 => [0.0, 1.0, -1.0, 0.0, height + y0, -x0],
+            _ => [1.0, 0.0, 0.0, 1.0, -x0, -y0],
+        }
+    }
+
+    /// The inverse of [`page_to_display`](Self::page_to_display).
+    pub fn display_to_page(&self) -> [f32; 6] {
+        let [x0, y0, x1, y1] = self.bounds;
+        let (width, height) = (x1 - x0, y1 - y0);
+        match self.rotate {
+            90 => [0.0, 1.0, -1.0, 0.0, x0 + width, y0],
+            180 => [-1.0, 0.0, 0.0, -1.0, x0 + width, y0 + height],
+            270 => [0.0, -1.0, 1.0, 0.0, x0, y0 + height],
+            _ => [1.0, 0.0, 0.0, 1.0, x0, y0],
+        }
+    }
+}
+
+/// `key` of the page, or of the nearest node above it in the page tree.
+pub(super) fn inherited<'a>(doc: &'a Document, page: ObjectId, key: &[u8]) -> Option<&'a Object> {
+    let mut node = doc.get_dictionary(page).ok()?;
+    // Bounded, in case the tree loops.
+    for _ in 0..64 {
+        if let Ok(value) = node.get(key) {
+            return Some(value);
+        }
+        node = doc.get_dictionary(node.get(b"Parent").ok()?.as_reference().ok()?).ok()?;
+    }
+    None
+}
+
+/// The XObject called `name` in `resources`.
+pub(super) fn xobject<'a>(doc: &'a Document, resources: Option<&'a Dictionary>, name: &[u8]) -> Option<&'a Stream> {
+    let xobjects = doc.dereference(resources?.get(b"XObject").ok()?).ok()?.1.as_dict().ok()?;
+    doc.dereference(xobjects.get(name).ok()?).ok()?.1.as_stream().ok()
+}
+
+/// `point` moved by `matrix`.
+pub(super) fn apply([a, b, c, d, e, f]: [f32; 6], (x, y): (f32, f32)) -> (f32, f32) {
+    (a * x + c * y + e, b * x + d * y + f)
+}
+
+/// The matrix doing `first`, then `then`, as `first then cm` does in a content stream.
+pub(super) fn multiply(first: [f32; 6], then: [f32; 6]) -> [f32; 6] {
+    let [a, b, c, d, e, f] = first;
+    let [a2, b2, c2, d2, e2, f2] = then;
+    [
+        a * a2 + b * c2,
+        a * b2 + b * d2,
+        c * a2 + d * c2,
+        c * b2 + d * d2,
+        e * a2 + f * c2 + e2,
+        e * b2 + f * d2 + f2,
+    ]
+}
+
+/// A rectangle as `[left, bottom, right, top]`, whichever corners it was given by.
+fn rectangle(doc: &Document, object: &Object) -> Option<[f32; 4]> {
+    let values = doc.dereference(object).ok()?.1.as_array().ok()?;
+    let mut numbers = values.iter().map(|value| doc.dereference(value).ok()?.1.as_float().ok());
+    let [a, b, c, d] = [numbers.next()??, numbers.next()??, numbers.next()??, numbers.next()??];
+    Some([a.min(c), b.min(d), a.max(c), b.max(d)])
+}
+
+/// Draws `content`, in the page's own coordinates, over `page` and adds `resources`
+/// to it as (category, name, object). The page's content is wrapped in q/Q so
+/// whatever state it leaves behind does not move or recolour the overlay.
+pub(super) fn overlay(doc: &mut Document, page: ObjectId, resources: &[(&str, &str, Object)], content: String) -> Result<()> {
+    let mut merged = match inherited(doc, page, b"Resources") {
+        Some(resources) => doc.dereference(resources)?.1.as_dict()?.clone(),
+        None => Dictionary::new(),
+    };
+    for (category, name, object) in resources {
+        let mut entries = match merged.get(category.as_bytes()) {
+            Ok(entries) => doc.dereference(entries)?.1.as_dict()?.clone(),
+            Err(_) => Dictionary::new(),
+        };
+        entries.set(*name, object.clone());
+        merged.set(*category, entries);
+    }
+
+    let mut contents: Vec<Object> = vec![doc.add_object(Stream::new(Dictionary::new(), b"q\n".to_vec())).into()];
+    contents.extend(doc.get_page_contents(page).into_iter().map(Object::from));
+    let content = format!("\nQ\n{content}");
+    contents.push(doc.add_object(Stream::new(Dictionary::new(), content.into_bytes())).into());
+    let page = doc.get_dictionary_mut(page)?;
+    page.set("Resources", merged);
+    page.set("Contents", contents);
+    Ok(())
+}
@@ -0,0 +1,74 @@
+use std::path::{Path, PathBuf};
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+
+use super::{ProcessingStep, StepContext};
+use crate::qpdf::{qpdf_command, run_qpdf_checked};
+use crate::volume::long_path;
+
+/// Whether [`Letterhead`] goes behind the page's content or on top of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LetterheadMode {
+    /// Behind, like printing on letterhead paper.
+    #[default]
+    Underlay,
+    /// On top, e.g. for a stamp or a form with opaque fields.
+    Overlay,
+}
+
+/// Combines every page with a page of another PDF, such as a company letterhead or a
+/// form background, through qpdf's `--underlay`/`--overlay`. The first page of that
+/// PDF goes with the first page, its last page with all others.
+pub struct Letterhead {
+    file: Option<PathBuf>,
+    mode: LetterheadMode,
+}
+
+impl Letterhead {
+    /// Without a `file` the step fails, telling the user to choose one.
+    pub fn new(file: Option<PathBuf>, mode: LetterheadMode) -> Self {
+        Self { file, mode }
+    }
+}
+
+impl Default for Letterhead {
+    fn default() -> Self {
+        Self::new(None, LetterheadMode::default())
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for Letterhead {
+    fn id(&self) -> &str {
+        "letterhead"
+    }
+
+    fn name(&self) -> &str {
+        "套用信纸"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let Some(file) = &self.file else {
+            bail!("未选择信纸文件，请先在设置中选择");
+        };
+        if !file.is_file() {
+            bail!("找不到信纸文件：{}", file.display());
+        }
+        let option = match self.mode {
+            LetterheadMode::Underlay => "--underlay",
+            LetterheadMode::Overlay => "--overlay",
+        };
+        let mut cmd = qpdf_command();
+        cmd.arg(&*long_path(input))
+            .arg(option)
+            .arg(&*long_path(file))
+            .arg("--from=1")
+            .arg("--repeat=z")
+            .arg("--")
+            .arg(&*long_path(output));
+        run_qpdf_checked(cmd, ctx.timeout).await
+    }
+}
@@ -0,0 +1,207 @@
+//! Processing steps that make up an unlock pipeline, decryption included.
+//!
+//! A step sees the file in three phases: [`analyze`](ProcessingStep::analyze)
+//! decides whether it applies, [`transform`](ProcessingStep::transform) writes a
+//! new file from the current one, and [`finalize`](ProcessingStep::finalize) runs
+//! on the saved output once every step has transformed it.
+
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "dynamic-steps")]
+use std::path::PathBuf;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+mod actions;
+mod blank;
+mod builtin;
+mod crop;
+#[cfg(feature = "dynamic-steps")]
+mod dynamic;
+mod encrypt;
+mod hook;
+mod kind;
+mod layout;
+mod letterhead;
+mod nup;
+mod ocr;
+mod page_numbers;
+mod pages;
+mod redaction;
+mod sign;
+mod watermark;
+
+pub use actions::RemoveAutoActions;
+pub use blank::{find_blank_pages, DropBlankPages};
+pub use builtin::{Compress, Decrypt, Linearize, StripMetadata};
+pub use crop::{Crop, CropMargins};
+#[cfg(feature = "dynamic-steps")]
+pub use dynamic::{DynamicStep, PLUGIN_ABI_VERSION};
+pub use encrypt::{Encrypt, Permissions};
+pub use hook::HookStep;
+pub use kind::{classify_pdf, PdfKind};
+pub use layout::Position;
+pub use letterhead::{Letterhead, LetterheadMode};
+pub use nup::{Booklet, NUp};
+pub use ocr::{check_ocr_ready, Ocr, DEFAULT_OCR_LANGUAGES};
+pub use page_numbers::PageNumbers;
+pub use pages::{parse_page_ranges, ExtractPages};
+pub use redaction::find_covered_text;
+pub use sign::{check_sign_ready, Sign};
+pub use watermark::{Watermark, WatermarkContent};
+
+pub(crate) use blank::blank_pages;
+pub(crate) use kind::classify;
+pub(crate) use redaction::covered_text_pages;
+
+/// Information shared with every step of a file.
+pub struct StepContext<'a> {
+    /// The original, still encrypted input.
+    pub source: &'a Path,
+    /// Limit for each external process a step launches.
+    pub timeout: Option<Duration>,
+    /// Open passwords to try when the empty one does not open the source.
+    pub passwords: &'a [String],
+}
+
+#[async_trait]
+pub trait ProcessingStep: Send + Sync {
+    /// Stable identifier used in configs and on the command line.
+    fn id(&self) -> &str;
+
+    /// Human-readable name for the UI.
+    fn name(&self) -> &str;
+
+    /// `true` when [`analyze`](Self::analyze) skips every unencrypted file.
+    fn encrypted_only(&self) -> bool {
+        false
+    }
+
+    /// `true` when the result may have a different number of pages on purpose, so a
+    /// changed page count is not a sign of damage.
+    fn rearranges_pages(&self) -> bool {
+        false
+    }
+
+    /// Returns `false` to skip this step for `input`.
+    async fn analyze(&self, _input: &Path, _ctx: &StepContext<'_>) -> Result<bool> {
+        Ok(true)
+    }
+
+    /// Writes the processed version of `input` to `output`.
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()>;
+
+    /// Runs on the final output after all transforms.
+    async fn finalize(&self, _output: &Path, _ctx: &StepContext<'_>) -> Result<()> {
+        Ok(())
+    }
+}
+
+impl fmt::Debug for dyn ProcessingStep {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.id())
+    }
+}
+
+/// Error raised by one step, naming the step that failed.
+#[derive(Debug)]
+pub struct StepError {
+    /// Display name of the failing step.
+    pub step: String,
+    pub source: anyhow::Error,
+}
+
+impl StepError {
+    pub fn new(step: impl Into<String>, source: impl Into<anyhow::Error>) -> Self {
+        Self {
+            step: step.into(),
+            source: source.into(),
+        }
+    }
+}
+
+impl fmt::Display for StepError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {:#}", self.step, self.source)
+    }
+}
+
+impl std::error::Error for StepError {}
+
+/// Known steps by id.
+#[derive(Clone, Default)]
+pub struct StepRegistry {
+    steps: BTreeMap<String, Arc<dyn ProcessingStep>>,
+}
+
+impl StepRegistry {
+    /// Registry with the steps compiled into this crate.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::default();
+        registry.register(Arc::new(Decrypt));
+        registry.register(Arc::new(StripMetadata));
+        registry.register(Arc::new(RemoveAutoActions));
+        registry.register(Arc::new(Compress));
+        registry.register(Arc::new(Linearize));
+        registry.register(Arc::new(Ocr::default()));
+        registry.register(Arc::new(NUp::two()));
+        registry.register(Arc::new(NUp::four()));
+        registry.register(Arc::new(Booklet));
+        registry.register(Arc::new(PageNumbers::default()));
+        registry.register(Arc::new(Watermark::default()));
+        registry.register(Arc::new(Letterhead::default()));
+        registry.register(Arc::new(DropBlankPages));
+        registry.register(Arc::new(CropMargins::default()));
+        registry.register(Arc::new(Sign::default()));
+        registry
+    }
+
+    /// Adds `step`, replacing any step with the same id.
+    pub fn register(&mut self, step: Arc<dyn ProcessingStep>) {
+        self.steps.insert(step.id().to_string(), step);
+    }
+
+    pub fn get(&self, id: &str) -> Option<Arc<dyn ProcessingStep>> {
+        self.steps.get(id).cloned()
+    }
+
+    pub fn steps(&self) -> impl Iterator<Item = &Arc<dyn ProcessingStep>> {
+        self.steps.values()
+    }
+
+    /// Looks up `ids` in order, failing on the first unknown id.
+    pub fn resolve<S: AsRef<str>>(&self, ids: &[S]) -> Result<Vec<Arc<dyn ProcessingStep>>> {
+        ids.iter()
+            .map(|id| {
+                let id = id.as_ref();
+                self.get(id).ok_or_else(|| anyhow!("未知的处理步骤：{id}"))
+            })
+            .collect()
+    }
+
+    /// Loads every plugin library in `dir`, returning the ones that failed.
+    #[cfg(feature = "dynamic-steps")]
+    pub fn load_plugins(&mut self, dir: &Path) -> Vec<(PathBuf, anyhow::Error)> {
+        let mut failures = Vec::new();
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return failures;
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let is_library = path
+                .extension()
+                .and_then(|ext| ext.to_str())
+                .is_some_and(|ext| ext == std::env::consts::DLL_EXTENSION);
+            if !is_library {
+                continue;
+            }
+            match unsafe { DynamicStep::load(&path) } {
+                Ok(step) => self.register(Arc::new(step)),
+                Err(err) => failures.push((path, err)),
+            }
+        }
+        failures
+    }
+}
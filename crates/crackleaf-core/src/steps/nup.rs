@@ -0,0 +1,214 @@
+use std::path::Path;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream};
+
+use super::layout::{inherited, load, rewrite, Geometry};
+use super::{ProcessingStep, StepContext};
+
+/// Catalog entries that point at the original pages, which no longer exist as such.
+const PAGE_REFERENCES: &[&[u8]] = &[b"Outlines", b"PageLabels", b"OpenAction", b"AcroForm", b"StructTreeRoot"];
+
+/// Puts several pages on each sheet for printing handouts: two side by side (2-up)
+/// or a 2×2 grid (4-up), scaled to fit and kept upright. Sheets take the size of the
+/// first page, turned sideways for 2-up. Links, annotations, form fields and
+/// bookmarks are not carried over.
+pub struct NUp {
+    per_sheet: usize,
+}
+
+impl NUp {
+    /// 2-up.
+    pub fn two() -> Self {
+        Self { per_sheet: 2 }
+    }
+
+    /// 4-up.
+    pub fn four() -> Self {
+        Self { per_sheet: 4 }
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for NUp {
+    fn id(&self) -> &str {
+        if self.per_sheet == 2 {
+            "2-up"
+        } else {
+            "4-up"
+        }
+    }
+
+    fn name(&self) -> &str {
+        if self.per_sheet == 2 {
+            "2 合 1 排版"
+        } else {
+            "4 合 1 排版"
+        }
+    }
+
+    fn rearranges_pages(&self) -> bool {
+        true
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        run_impose(input, output, self.per_sheet, in_order, ctx).await
+    }
+}
+
+/// Lays pages out 2-up in saddle-stitch order, so the sheets printed on both sides
+/// (flipped on the short edge), stacked and folded in the middle read as a booklet.
+/// Blank pages pad the end to a multiple of four.
+pub struct Booklet;
+
+#[async_trait]
+impl ProcessingStep for Booklet {
+    fn id(&self) -> &str {
+        "booklet"
+    }
+
+    fn name(&self) -> &str {
+        "小册子排版"
+    }
+
+    fn rearranges_pages(&self) -> bool {
+        true
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        run_impose(input, output, 2, booklet_order, ctx).await
+    }
+}
+
+/// Runs [`impose`] within the step's time limit.
+async fn run_impose(
+    input: &Path,
+    output: &Path,
+    per_sheet: usize,
+    order: fn(usize) -> Vec<Option<usize>>,
+    ctx: &StepContext<'_>,
+) -> Result<()> {
+    rewrite(input, output, ctx, move |input, output| impose(input, output, per_sheet, order)).await
+}
+
+/// Every page once, as in the source.
+fn in_order(pages: usize) -> Vec<Option<usize>> {
+    (0..pages).map(Some).collect()
+}
+
+/// Pages by slot for a booklet of `pages`, `None` being a blank. Each sheet carries
+/// the outermost pages left: last and first on the front, second and second to last
+/// on the back.
+fn booklet_order(pages: usize) -> Vec<Option<usize>> {
+    let padded = pages.div_ceil(4) * 4;
+    let mut order = Vec::with_capacity(padded);
+    for sheet in 0..padded / 4 {
+        let (outer, inner) = (2 * sheet, padded - 1 - 2 * sheet);
+        order.extend([inner, outer, outer + 1, inner - 1]);
+    }
+    order.into_iter().map(|page| (page < pages).then_some(page)).collect()
+}
+
+/// A source page turned into a form XObject, with its size as displayed.
+struct Placed {
+    form: ObjectId,
+    width: f32,
+    height: f32,
+}
+
+/// Writes the pages of `input`, placed in the slots `order` gives for their number,
+/// `per_sheet` at a time onto new sheets.
+fn impose(input: &Path, output: &Path, per_sheet: usize, order: fn(usize) -> Vec<Option<usize>>) -> Result<()> {
+    let mut doc = load(input)?;
+    let pages: Vec<ObjectId> = doc.get_pages().into_values().collect();
+    if pages.is_empty() {
+        bail!("文件没有页面");
+    }
+    let root = doc.catalog()?.get(b"Pages")?.as_reference()?;
+    let placed = pages
+        .iter()
+        .map(|&page| place(&mut doc, page))
+        .collect::<Result<Vec<_>>>()?;
+
+    let (width, height) = (placed[0].width, placed[0].height);
+    let (sheet_width, sheet_height, columns) = if per_sheet == 2 {
+        // Portrait pages go side by side on a landscape sheet, landscape ones stack.
+        (height, width, if height >= width { 2 } else { 1 })
+    } else {
+        (width, height, 2)
+    };
+    let rows = per_sheet / columns;
+    let (cell_width, cell_height) = (sheet_width / columns as f32, sheet_height / rows as f32);
+
+    let mut kids = Vec::new();
+    for group in order(placed.len()).chunks(per_sheet) {
+        let mut xobjects = Dictionary::new();
+        let mut content = String::new();
+        for (slot, page) in group.iter().enumerate() {
+            let Some(page) = page.map(|index| &placed[index]) else {
+                continue;
+            };
+            let (column, row) = (slot % columns, slot / columns);
+            let scale = (cell_width / page.width).min(cell_height / page.height);
+            let x = column as f32 * cell_width + (cell_width - page.width * scale) / 2.0;
+            let y = sheet_height - (row + 1) as f32 * cell_height + (cell_height - page.height * scale) / 2.0;
+            let name = format!("P{slot}");
+            content.push_str(&format!("q {scale:.5} 0 0 {scale:.5} {x:.3} {y:.3} cm /{name} Do Q\n"));
+            xobjects.set(name, page.form);
+        }
+        let contents = doc.add_object(Stream::new(Dictionary::new(), content.into_bytes()));
+        let sheet = doc.add_object(dictionary! {
+            "Type" => "Page",
+            "Parent" => root,
+            "MediaBox" => vec![0.into(), 0.into(), sheet_width.into(), sheet_height.into()],
+            "Rotate" => 0,
+            "Resources" => dictionary! { "XObject" => xobjects },
+            "Contents" => contents,
+        });
+        kids.push(Object::Reference(sheet));
+    }
+
+    let count = kids.len() as i64;
+    let tree = doc.get_dictionary_mut(root)?;
+    for inherited in [&b"CropBox"[..], b"BleedBox", b"TrimBox", b"ArtBox", b"Rotate"] {
+        tree.remove(inherited);
+    }
+    tree.set("Kids", kids);
+    tree.set("Count", count);
+    let catalog = doc.catalog_mut()?;
+    for key in PAGE_REFERENCES {
+        catalog.remove(key);
+    }
+    doc.prune_objects();
+    doc.compress();
+    doc.save(output).map_err(|err| anyhow!("无法保存结果：{err}"))?;
+    Ok(())
+}
+
+/// Wraps `page` in a form XObject whose matrix moves its visible area to the origin
+/// and applies the page's rotation.
+fn place(doc: &mut Document, page: ObjectId) -> Result<Placed> {
+    let geometry = Geometry::of(doc, page)?;
+    let resources = inherited(doc, page, b"Resources")
+        .cloned()
+        .unwrap_or_else(|| Dictionary::new().into());
+    let content = doc.get_page_content(page)?;
+    let (bounds, matrix) = (geometry.bounds, geometry.page_to_display());
+    let (width, height) = geometry.size();
+    let form = Stream::new(
+        dictionary! {
+            "Type" => "XObject",
+            "Subtype" => "Form",
+            "BBox" => bounds.iter().map(|&value| value.into()).collect::<Vec<Object>>(),
+            "Matrix" => matrix.iter().map(|&value| value.into()).collect::<Vec<Object>>(),
+            "Resources" => resources,
+        },
+        content,
+    );
+    Ok(Placed {
+        form: doc.add_object(form),
+        width,
+        height,
+    })
+}
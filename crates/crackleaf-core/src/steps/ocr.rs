@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use tokio::process::Command;
+use tracing::info;
+
+use super::{ProcessingStep, StepContext};
+use crate::error::Error;
+use crate::qpdf::process_permit;
+use crate::volume::long_path;
+
+/// Tesseract languages used when none are configured.
+pub const DEFAULT_OCR_LANGUAGES: &str = "chi_sim+eng";
+
+/// Adds a searchable text layer to scanned pages with OCRmyPDF, which drives
+/// Tesseract. Pages that already carry text are left as they are.
+pub struct Ocr {
+    languages: String,
+}
+
+impl Ocr {
+    /// `languages` in Tesseract's form, e.g. `chi_sim+eng`.
+    pub fn new(languages: impl Into<String>) -> Self {
+        Self {
+            languages: languages.into(),
+        }
+    }
+}
+
+impl Default for Ocr {
+    fn default() -> Self {
+        Self::new(DEFAULT_OCR_LANGUAGES)
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for Ocr {
+    fn id(&self) -> &str {
+        "ocr"
+    }
+
+    fn name(&self) -> &str {
+        "文字识别"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let mut cmd = ocrmypdf_command();
+        cmd.arg("--skip-text")
+            .arg("--output-type")
+            .arg("pdf")
+            .arg("--optimize")
+            .arg("0")
+            .arg("--language")
+            .arg(&self.languages)
+            .arg(&*long_path(input))
+            .arg(&*long_path(output));
+
+        let _permit = process_permit().await;
+        let result = cmd.output();
+        let result = match ctx.timeout {
+            Some(limit) => tokio::time::timeout(limit, result)
+                .await
+                .map_err(|_| Error::Timeout(limit))?,
+            None => result.await,
+        };
+        let result = result.map_err(|err| anyhow!("未找到 OCRmyPDF（{err}），文字识别需要安装 OCRmyPDF 和 Tesseract"))?;
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+        // Exit codes documented by OCRmyPDF.
+        match result.status.code() {
+            Some(0) => Ok(()),
+            Some(3) => bail!("缺少 Tesseract、Ghostscript 或语言包 {}：{detail}", self.languages),
+            Some(8) => bail!("文件仍然加密，请把文字识别放在解密之后"),
+            code => bail!("OCRmyPDF 退出码 {code:?}：{detail}"),
+        }
+    }
+}
+
+/// Checks that OCRmyPDF can be launched and returns its version.
+pub async fn check_ocr_ready() -> Result<String> {
+    let mut cmd = ocrmypdf_command();
+    cmd.arg("--version");
+    let output = tokio::time::timeout(Duration::from_secs(30), cmd.output())
+        .await
+        .map_err(|_| anyhow!("OCRmyPDF 没有响应"))?
+        .map_err(|err| anyhow!("未找到 OCRmyPDF（{err}），文字识别需要安装 OCRmyPDF 和 Tesseract"))?;
+    if !output.status.success() {
+        bail!("OCRmyPDF 运行失败：{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    info!(%version, path = %resolve_ocr_command().display(), "OCRmyPDF ready");
+    Ok(version)
+}
+
+/// Builds an `ocrmypdf` invocation, killed when its future is dropped and without
+/// a console window on Windows.
+fn ocrmypdf_command() -> Command {
+    let mut cmd = Command::new(resolve_ocr_command());
+    cmd.kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+    cmd
+}
+
+/// Locates `ocrmypdf`, preferring a copy next to the executable over `PATH`, like
+/// [`resolve_qpdf_command`](crate::resolve_qpdf_command).
+fn resolve_ocr_command() -> PathBuf {
+    let filename = if cfg!(target_os = "windows") { "ocrmypdf.exe" } else { "ocrmypdf" };
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(filename)))
+        .filter(|candidate| candidate.exists())
+        .unwrap_or_else(|| PathBuf::from(filename))
+}
@@ -0,0 +1,82 @@
+use std::path::Path;
+
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use lopdf::dictionary;
+
+use super::layout::{load, overlay, rewrite, Geometry, Position};
+use super::{ProcessingStep, StepContext};
+
+/// Resource name of the font the numbers are set in.
+const FONT: &str = "CrackLeafPageNumber";
+/// Width of a digit in Helvetica, in thousandths of the font size.
+const DIGIT_WIDTH: f32 = 0.556;
+/// Distance of the number from the edges of the page, in points (about 1 cm).
+const MARGIN: f32 = 28.0;
+
+/// Stamps a page number onto every page, upright as the page is displayed.
+pub struct PageNumbers {
+    position: Position,
+    font_size: f32,
+    start: u32,
+}
+
+impl PageNumbers {
+    /// `font_size` in points, kept between 4 and 72; `start` is the first page's number.
+    pub fn new(position: Position, font_size: f32, start: u32) -> Self {
+        Self {
+            position,
+            font_size: font_size.clamp(4.0, 72.0),
+            start,
+        }
+    }
+}
+
+impl Default for PageNumbers {
+    fn default() -> Self {
+        Self::new(Position::BottomCenter, 10.0, 1)
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for PageNumbers {
+    fn id(&self) -> &str {
+        "page-numbers"
+    }
+
+    fn name(&self) -> &str {
+        "添加页码"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let (position, font_size, start) = (self.position, self.font_size, self.start);
+        rewrite(input, output, ctx, move |input, output| {
+            stamp(input, output, position, font_size, start)
+        })
+        .await
+    }
+}
+
+fn stamp(input: &Path, output: &Path, position: Position, font_size: f32, start: u32) -> Result<()> {
+    let mut doc = load(input)?;
+    let font = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type1",
+        "BaseFont" => "Helvetica",
+        "Encoding" => "WinAnsiEncoding",
+    });
+    for (number, page) in (u64::from(start)..).zip(doc.get_pages().into_values()) {
+        let geometry = Geometry::of(&doc, page)?;
+        let (width, height) = geometry.size();
+        let text = number.to_string();
+        let text_width = text.len() as f32 * DIGIT_WIDTH * font_size;
+        let (x, y) = position.place((width, height), (text_width, font_size), MARGIN);
+        let [a, b, c, d, e, f] = geometry.display_to_page();
+        let content =
+            format!("q {a} {b} {c} {d} {e:.3} {f:.3} cm BT /{FONT} {font_size} Tf 0 g {x:.3} {y:.3} Td ({text}) Tj ET Q\n");
+        overlay(&mut doc, page, &[("Font", FONT, font.into())], content)?;
+    }
+    doc.compress();
+    doc.save(output).map_err(|err| anyhow!("无法保存结果：{err}"))?;
+    Ok(())
+}
@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use async_trait::async_trait;
+
+use super::{ProcessingStep, StepContext};
+use crate::qpdf::{qpdf_command, run_qpdf_checked};
+use crate::volume::long_path;
+
+/// Writes a new PDF holding only some pages of the current one, in the order given.
+pub struct ExtractPages {
+    ranges: String,
+}
+
+impl ExtractPages {
+    /// `ranges` in qpdf's page range syntax, as returned by [`parse_page_ranges`].
+    pub fn new(ranges: impl Into<String>) -> Self {
+        Self { ranges: ranges.into() }
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for ExtractPages {
+    fn id(&self) -> &str {
+        "extract-pages"
+    }
+
+    fn name(&self) -> &str {
+        "提取页面"
+    }
+
+    fn rearranges_pages(&self) -> bool {
+        true
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let mut cmd = qpdf_command();
+        cmd.arg("--empty")
+            .arg("--pages")
+            .arg(&*long_path(input))
+            .arg(&self.ranges)
+            .arg("--")
+            .arg(&*long_path(output));
+        run_qpdf_checked(cmd, ctx.timeout).await
+    }
+}
+
+/// Turns ranges as people type them, e.g. `1-3, 5, 8-`, into qpdf's syntax
+/// (`1-3,5,8-z`). A range may run backwards; an open end means the last page. When
+/// `pages` is known, pages past the end are rejected.
+pub fn parse_page_ranges(text: &str, pages: Option<u32>) -> Result<String> {
+    let number = |part: &str, text: &str| -> Result<u32> {
+        let page: u32 = match text.trim().parse() {
+            Ok(page) if page > 0 => page,
+            _ => bail!("无法识别的页码：{part}"),
+        };
+        if let Some(pages) = pages.filter(|&pages| page > pages) {
+            bail!("第 {page} 页超出范围（共 {pages} 页）");
+        }
+        Ok(page)
+    };
+    let mut ranges = Vec::new();
+    for part in text.split([',', '，', ';', '；']).map(str::trim).filter(|part| !part.is_empty()) {
+        let range = match part.split_once(['-', '–', '~']) {
+            None => number(part, part)?.to_string(),
+            Some((start, end)) if end.trim().is_empty() => format!("{}-z", number(part, start)?),
+            Some((start, end)) if start.trim().is_empty() => format!("1-{}", number(part, end)?),
+            Some((start, end)) => format!("{}-{}", number(part, start)?, number(part, end)?),
+        };
+        ranges.push(range);
+    }
+    if ranges.is_empty() {
+        bail!("请输入要提取的页码");
+    }
+    Ok(ranges.join(","))
+}
@@ -0,0 +1,210 @@
+use std::path::Path;
+use std::time::Duration;
+
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId};
+use tracing::{debug, instrument};
+
+use super::blank::stream_data;
+use super::crop::{text_advance, ASCENT, DESCENT};
+use super::layout::{apply, inherited, multiply, xobject};
+use crate::inspect::read_document;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Nesting of form XObjects followed.
+const MAX_DEPTH: usize = 8;
+/// Share of a line of text a box must cover to count as hiding it.
+const COVERED: f32 = 0.5;
+const IDENTITY: [f32; 6] = [1.0, 0.0, 0.0, 1.0, 0.0, 0.0];
+
+/// Numbers (from 1) of the pages of `path` where a dark box is painted over text
+/// that is still in the file, the usual sign of a redaction that only hides what
+/// is beneath it; redaction annotations that were never applied count too. Returns
+/// `None` when the file cannot be read or needs a password.
+#[instrument(skip(timeout), fields(path = %path.display()))]
+pub async fn find_covered_text(path: &Path, timeout: Option<Duration>) -> Option<Vec<u32>> {
+    let file = long_path(path).into_owned();
+    let found = with_io_timeout(timeout, move || {
+        Ok(covered_text_pages(&read_document(&file)?))
+    })
+    .await;
+    match found {
+        Ok(pages) => Some(pages),
+        Err(err) => {
+            debug!("redaction check failed: {err}");
+            None
+        }
+    }
+}
+
+/// Numbers (from 1) of the pages of `doc` with covered text, see [`find_covered_text`].
+pub(crate) fn covered_text_pages(doc: &Document) -> Vec<u32> {
+    doc.get_pages()
+        .into_iter()
+        .filter(|&(_, page)| has_covered_text(doc, page))
+        .map(|(number, _)| number)
+        .collect()
+}
+
+fn has_covered_text(doc: &Document, page: ObjectId) -> bool {
+    let pending = doc.get_page_annotations(page).is_ok_and(|annotations| {
+        annotations
+            .iter()
+            .any(|annotation| matches!(annotation.get(b"Subtype").and_then(Object::as_name), Ok(b"Redact")))
+    });
+    if pending {
+        return true;
+    }
+    let Ok(content) = doc.get_page_content(page) else {
+        return false;
+    };
+    let resources = inherited(doc, page, b"Resources")
+        .and_then(|resources| doc.dereference(resources).ok())
+        .and_then(|(_, resources)| resources.as_dict().ok());
+    let mut page = Painted::default();
+    scan(doc, &content, resources, IDENTITY, 0, &mut page);
+    page.covered
+}
+
+/// Text drawn so far on a page, as boxes in page space, and whether a dark box
+/// has been painted over any of it.
+#[derive(Default)]
+struct Painted {
+    text: Vec<[f32; 4]>,
+    covered: bool,
+}
+
+impl Painted {
+    fn cover(&mut self, [x0, y0, x1, y1]: [f32; 4]) {
+        self.covered |= self.text.iter().any(|&[a0, b0, a1, b1]| {
+            let width = (x1.min(a1) - x0.max(a0)).max(0.0);
+            let height = (y1.min(b1) - y0.max(b0)).max(0.0);
+            let area = (a1 - a0) * (b1 - b0);
+            area > 0.0 && width * height >= area * COVERED
+        });
+    }
+}
+
+/// The smallest rectangle around `points`.
+fn bounding(points: impl IntoIterator<Item = (f32, f32)>) -> Option<[f32; 4]> {
+    points.into_iter().fold(None, |bounds, (x, y)| {
+        let [x0, y0, x1, y1] = bounds.unwrap_or([x, y, x, y]);
+        Some([x0.min(x), y0.min(y), x1.max(x), y1.max(y)])
+    })
+}
+
+/// Whether the colour set by `operator` is close to black: gray and RGB near 0,
+/// CMYK with full black ink or all inks.
+fn is_dark(operands: &[Object], operator: &str) -> bool {
+    let Ok(values) = operands.iter().map(Object::as_float).collect::<Result<Vec<f32>, _>>() else {
+        return false;
+    };
+    let cmyk = matches!(operator, "k" | "K") || values.len() == 4;
+    match values.len() {
+        1 | 3 if !cmyk => values.iter().all(|&value| value <= 0.1),
+        4 => values[3] >= 0.9 || values.iter().all(|&value| value >= 0.9),
+        _ => false,
+    }
+}
+
+/// Follows `content` under `ctm`, recording text and dark rectangles in `page`.
+fn scan(doc: &Document, content: &[u8], resources: Option<&Dictionary>, ctm: [f32; 6], depth: usize, page: &mut Painted) {
+    let Ok(content) = Content::decode(content) else {
+        return;
+    };
+    // Transformation, dark fill (black to start with), font size and leading; saved
+    // and restored with q/Q.
+    let mut state = (ctm, true, 0.0, 0.0);
+    let mut saved = Vec::new();
+    let mut rects: Vec<[f32; 4]> = Vec::new();
+    let (mut text_matrix, mut line_matrix) = (IDENTITY, IDENTITY);
+    for operation in &content.operations {
+        if page.covered {
+            return;
+        }
+        let operands = &operation.operands;
+        let numbers: Vec<f32> = operands.iter().filter_map(|operand| operand.as_float().ok()).collect();
+        let count = numbers.len();
+        let (ctm, dark, font_size, leading) = state;
+        match operation.operator.as_str() {
+            "q" => saved.push(state),
+            "Q" => state = saved.pop().unwrap_or(state),
+            "cm" if count == 6 => state.0 = multiply(numbers[..6].try_into().unwrap_or(IDENTITY), ctm),
+            "g" | "rg" | "k" | "sc" | "scn" => state.1 = is_dark(operands, &operation.operator),
+            "cs" => state.1 = true,
+
+            "re" if count >= 4 => {
+                let (x, y, width, height) = (numbers[0], numbers[1], numbers[2], numbers[3]);
+                let corners = [(x, y), (x + width, y), (x, y + height), (x + width, y + height)];
+                rects.extend(bounding(corners.map(|corner| apply(ctm, corner))));
+            }
+            "f" | "F" | "f*" | "B" | "B*" | "b" | "b*" => {
+                for rect in rects.drain(..) {
+                    if dark {
+                        page.cover(rect);
+                    }
+                }
+            }
+            "S" | "s" | "n" => rects.clear(),
+            "Do" => {
+                let name = operands.first().and_then(|name| name.as_name().ok());
+                let Some(stream) = name.and_then(|name| xobject(doc, resources, name)) else {
+                    continue;
+                };
+                if depth < MAX_DEPTH && matches!(stream.dict.get(b"Subtype").and_then(Object::as_name), Ok(b"Form")) {
+                    let matrix = stream
+                        .dict
+                        .get(b"Matrix")
+                        .and_then(Object::as_array)
+                        .ok()
+                        .and_then(|values| values.iter().map(|value| value.as_float().ok()).collect::<Option<Vec<_>>>())
+                        .and_then(|values| <[f32; 6]>::try_from(values).ok())
+                        .unwrap_or(IDENTITY);
+                    let own = stream
+                        .dict
+                        .get(b"Resources")
+                        .ok()
+                        .and_then(|resources| doc.dereference(resources).ok()?.1.as_dict().ok());
+                    if let Some(content) = stream_data(stream) {
+                        scan(doc, &content, own.or(resources), multiply(matrix, ctm), depth + 1, page);
+                    }
+                }
+            }
+
+            "BT" => (text_matrix, line_matrix) = (IDENTITY, IDENTITY),
+            "Tf" if count >= 1 => state.2 = numbers[count - 1],
+            "TL" if count >= 1 => state.3 = numbers[0],
+            "Tm" if count == 6 => {
+                line_matrix = numbers[..6].try_into().unwrap_or(IDENTITY);
+                text_matrix = line_matrix;
+            }
+            "Td" | "TD" if count >= 2 => {
+                if operation.operator == "TD" {
+                    state.3 = -numbers[1];
+                }
+                line_matrix = multiply([1.0, 0.0, 0.0, 1.0, numbers[0], numbers[1]], line_matrix);
+                text_matrix = line_matrix;
+            }
+            "T*" | "'" | "\"" | "Tj" | "TJ" => {
+                if matches!(operation.operator.as_str(), "T*" | "'" | "\"") {
+                    line_matrix = multiply([1.0, 0.0, 0.0, 1.0, 0.0, -leading], line_matrix);
+                    text_matrix = line_matrix;
+                }
+                // Invisible text counts: hidden under a box or not, it can be copied.
+                let advance = text_advance(operands) * font_size;
+                if advance > 0.0 {
+                    let matrix = multiply(text_matrix, ctm);
+                    let corners = [
+                        (0.0, -DESCENT * font_size),
+                        (advance, -DESCENT * font_size),
+                        (0.0, ASCENT * font_size),
+                        (advance, ASCENT * font_size),
+                    ];
+                    page.text.extend(bounding(corners.map(|corner| apply(matrix, corner))));
+                }
+                text_matrix = multiply([1.0, 0.0, 0.0, 1.0, advance, 0.0], text_matrix);
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,141 @@
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use tokio::process::Command;
+use tracing::info;
+
+use super::{ProcessingStep, StepContext};
+use crate::error::Error;
+use crate::qpdf::process_permit;
+use crate::volume::{long_path, with_io_timeout};
+
+/// Name of the signature field added to each file.
+const FIELD: &str = "CrackLeafSignature";
+/// Where a visible signature goes on the first page, in points from the bottom left.
+const VISIBLE_BOX: &str = "36,36,252,96";
+
+/// Signs each file with a certificate and private key from a PKCS#12 file (`.p12` or
+/// `.pfx`) through pyHanko, for documents that have to be certified again after
+/// unlocking. Put it last: any later change breaks the signature.
+pub struct Sign {
+    certificate: Option<PathBuf>,
+    password: String,
+    visible: bool,
+    reason: Option<String>,
+}
+
+impl Sign {
+    /// `password` unlocks `certificate`; a `visible` signature shows as a box in the
+    /// bottom left corner of the first page, and `reason` is recorded with it.
+    pub fn new(certificate: Option<PathBuf>, password: impl Into<String>, visible: bool, reason: Option<String>) -> Self {
+        Self {
+            certificate,
+            password: password.into(),
+            visible,
+            reason,
+        }
+    }
+}
+
+impl Default for Sign {
+    fn default() -> Self {
+        Self::new(None, "", false, None)
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for Sign {
+    fn id(&self) -> &str {
+        "sign"
+    }
+
+    fn name(&self) -> &str {
+        "数字签名"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let Some(certificate) = &self.certificate else {
+            bail!("未选择签名证书");
+        };
+        // pyHanko otherwise asks for the password on the terminal; the file is removed
+        // with the rest of the temporary folder.
+        let passfile = output.with_extension("pass");
+        let (target, password) = (passfile.clone(), self.password.clone());
+        with_io_timeout(ctx.timeout, move || std::fs::write(target, password)).await?;
+
+        let field = if self.visible { format!("1/{VISIBLE_BOX}/{FIELD}") } else { FIELD.to_string() };
+        let mut cmd = pyhanko_command();
+        cmd.arg("sign").arg("addsig").arg("--field").arg(field);
+        if let Some(reason) = self.reason.as_deref().filter(|reason| !reason.is_empty()) {
+            cmd.arg("--reason").arg(reason);
+        }
+        cmd.arg("pkcs12")
+            .arg("--passfile")
+            .arg(&passfile)
+            .arg(&*long_path(input))
+            .arg(&*long_path(output))
+            .arg(&*long_path(certificate));
+
+        let _permit = process_permit().await;
+        let result = cmd.output();
+        let result = match ctx.timeout {
+            Some(limit) => tokio::time::timeout(limit, result)
+                .await
+                .map_err(|_| Error::Timeout(limit))?,
+            None => result.await,
+        };
+        let _ = std::fs::remove_file(&passfile);
+        let result = result.map_err(|err| anyhow!("未找到 pyHanko（{err}），数字签名需要安装 pyHanko"))?;
+        if !result.status.success() {
+            let stderr = String::from_utf8_lossy(&result.stderr);
+            let detail = stderr.lines().rev().find(|line| !line.trim().is_empty()).unwrap_or_default().trim();
+            let lower = detail.to_lowercase();
+            if lower.contains("password") || lower.contains("mac") {
+                bail!("无法打开签名证书，请检查证书密码：{detail}");
+            }
+            if lower.contains("encrypt") {
+                bail!("文件仍然加密，请把数字签名放在解密之后");
+            }
+            bail!("pyHanko 签名失败：{detail}");
+        }
+        Ok(())
+    }
+}
+
+/// Checks that pyHanko can be launched and returns its version.
+pub async fn check_sign_ready() -> Result<String> {
+    let mut cmd = pyhanko_command();
+    cmd.arg("--version");
+    let output = tokio::time::timeout(Duration::from_secs(30), cmd.output())
+        .await
+        .map_err(|_| anyhow!("pyHanko 没有响应"))?
+        .map_err(|err| anyhow!("未找到 pyHanko（{err}），数字签名需要安装 pyHanko"))?;
+    if !output.status.success() {
+        bail!("pyHanko 运行失败：{}", String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let version = String::from_utf8_lossy(&output.stdout).trim().to_string();
+    info!(%version, path = %resolve_sign_command().display(), "pyHanko ready");
+    Ok(version)
+}
+
+/// Builds a `pyhanko` invocation, killed when its future is dropped and without a
+/// console window on Windows.
+fn pyhanko_command() -> Command {
+    let mut cmd = Command::new(resolve_sign_command());
+    cmd.kill_on_drop(true);
+    #[cfg(target_os = "windows")]
+    cmd.creation_flags(0x08000000);
+    cmd
+}
+
+/// Locates `pyhanko`, preferring a copy next to the executable over `PATH`.
+fn resolve_sign_command() -> PathBuf {
+    let filename = if cfg!(target_os = "windows") { "pyhanko.exe" } else { "pyhanko" };
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| Some(exe.parent()?.join(filename)))
+        .filter(|candidate| candidate.exists())
+        .unwrap_or_else(|| PathBuf::from(filename))
+}
@@ -0,0 +1,256 @@
+use std::fs::File;
+use std::io::BufReader;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, bail, Result};
+use async_trait::async_trait;
+use lopdf::{dictionary, Document, Object, ObjectId, Stream, StringFormat};
+
+use super::layout::{load, overlay, rewrite, Geometry, Position};
+use super::{ProcessingStep, StepContext};
+
+/// Resource names of the watermark's font, image and transparency.
+const FONT: &str = "CrackLeafWatermark";
+const IMAGE: &str = "CrackLeafWatermarkImage";
+const STATE: &str = "CrackLeafWatermarkState";
+/// Distance of the watermark from the edges it is aligned to, in points.
+const MARGIN: f32 = 36.0;
+
+/// What [`Watermark`] puts on the pages.
+#[derive(Clone, Debug, PartialEq)]
+pub enum WatermarkContent {
+    /// A line of text, set in a standard Chinese font the viewer provides.
+    Text(String),
+    /// A PNG file, transparency included.
+    Image(PathBuf),
+}
+
+/// Overlays text or an image on every page, e.g. "内部使用" before passing an unlocked
+/// document on.
+pub struct Watermark {
+    content: WatermarkContent,
+    position: Position,
+    opacity: f32,
+    size: f32,
+    angle: f32,
+}
+
+impl Watermark {
+    /// `opacity` between 0 and 1; `size` is the font size in points for text and the
+    /// width as a fraction of the page's for images; `angle` in degrees counterclockwise.
+    pub fn new(content: WatermarkContent, position: Position, opacity: f32, size: f32, angle: f32) -> Self {
+        let size = match content {
+            WatermarkContent::Text(_) => size.clamp(4.0, 400.0),
+            WatermarkContent::Image(_) => size.clamp(0.01, 1.0),
+        };
+        Self {
+            content,
+            position,
+            opacity: opacity.clamp(0.0, 1.0),
+            size,
+            angle,
+        }
+    }
+}
+
+impl Default for Watermark {
+    fn default() -> Self {
+        Self::new(WatermarkContent::Text("内部使用".to_string()), Position::Center, 0.3, 48.0, 45.0)
+    }
+}
+
+#[async_trait]
+impl ProcessingStep for Watermark {
+    fn id(&self) -> &str {
+        "watermark"
+    }
+
+    fn name(&self) -> &str {
+        "添加水印"
+    }
+
+    async fn transform(&self, input: &Path, output: &Path, ctx: &StepContext<'_>) -> Result<()> {
+        let (content, position, opacity, size, angle) =
+            (self.content.clone(), self.position, self.opacity, self.size, self.angle);
+        rewrite(input, output, ctx, move |input, output| {
+            stamp(input, output, &content, position, opacity, size, angle)
+        })
+        .await
+    }
+}
+
+fn stamp(
+    input: &Path,
+    output: &Path,
+    content: &WatermarkContent,
+    position: Position,
+    opacity: f32,
+    size: f32,
+    angle: f32,
+) -> Result<()> {
+    let mut doc = load(input)?;
+    let state = doc.add_object(dictionary! {
+        "Type" => "ExtGState",
+        "ca" => opacity,
+        "CA" => opacity,
+    });
+    let mark = match content {
+        WatermarkContent::Text(text) => {
+            if text.trim().is_empty() {
+                bail!("水印文字为空");
+            }
+            Mark::Text {
+                font: add_font(&mut doc),
+                text: encode_text(text),
+                width: text_width(text),
+            }
+        }
+        WatermarkContent::Image(path) => {
+            let (image, width, height) = add_image(&mut doc, path)?;
+            Mark::Image {
+                image,
+                aspect: height as f32 / width as f32,
+            }
+        }
+    };
+
+    for page in doc.get_pages().into_values().collect::<Vec<_>>() {
+        let geometry = Geometry::of(&doc, page)?;
+        let (page_width, page_height) = geometry.size();
+        let (width, height) = match &mark {
+            Mark::Text { width, .. } => (width * size, size),
+            Mark::Image { aspect, .. } => (page_width * size, page_width * size * aspect),
+        };
+        let (x, y) = position.place((page_width, page_height), (width, height), MARGIN);
+        // Turned about its centre, so a centred mark stays centred at any angle.
+        let (sin, cos) = angle.to_radians().sin_cos();
+        let (cx, cy) = (x + width / 2.0, y + height / 2.0);
+        let (e, f) = (cx - cos * width / 2.0 + sin * height / 2.0, cy - sin * width / 2.0 - cos * height / 2.0);
+        let [a0, b0, c0, d0, e0, f0] = geometry.display_to_page();
+        let mut content = format!(
+            "q {a0} {b0} {c0} {d0} {e0:.3} {f0:.3} cm {cos:.5} {sin:.5} {:.5} {cos:.5} {e:.3} {f:.3} cm /{STATE} gs ",
+            -sin
+        );
+        let resources = match &mark {
+            Mark::Text { font, text, .. } => {
+                // Baseline a little above the bottom so descenders stay inside the box.
+                let baseline = size * 0.12;
+                content.push_str(&format!("BT /{FONT} {size} Tf 0.5 g 0 {baseline:.3} Td <{text}> Tj ET Q\n"));
+                [("ExtGState", STATE, state.into()), ("Font", FONT, (*font).into())]
+            }
+            Mark::Image { image, .. } => {
+                content.push_str(&format!("{width:.3} 0 0 {height:.3} 0 0 cm /{IMAGE} Do Q\n"));
+                [("ExtGState", STATE, state.into()), ("XObject", IMAGE, (*image).into())]
+            }
+        };
+        overlay(&mut doc, page, &resources, content)?;
+    }
+    doc.compress();
+    doc.save(output).map_err(|err| anyhow!("无法保存结果：{err}"))?;
+    Ok(())
+}
+
+/// The watermark as added to the document, shared by every page.
+enum Mark {
+    /// `text` as hex UTF-16, `width` in units of the font size.
+    Text { font: ObjectId, text: String, width: f32 },
+    /// `aspect` is height over width.
+    Image { image: ObjectId, aspect: f32 },
+}
+
+/// STSong-Light, one of the standard Chinese fonts viewers supply, addressed by
+/// UTF-16 code so any text in the basic plane can be set without embedding a font.
+fn add_font(doc: &mut Document) -> ObjectId {
+    let descendant = dictionary! {
+        "Type" => "Font",
+        "Subtype" => "CIDFontType0",
+        "BaseFont" => "STSong-Light",
+        "CIDSystemInfo" => dictionary! {
+            "Registry" => Object::String(b"Adobe".to_vec(), StringFormat::Literal),
+            "Ordering" => Object::String(b"GB1".to_vec(), StringFormat::Literal),
+            "Supplement" => 2,
+        },
+        "FontDescriptor" => dictionary! {
+            "Type" => "FontDescriptor",
+            "FontName" => "STSong-Light",
+            "Flags" => 6,
+            "FontBBox" => vec![(-25).into(), (-254).into(), 1000.into(), 880.into()],
+            "ItalicAngle" => 0,
+            "Ascent" => 880,
+            "Descent" => -120,
+            "CapHeight" => 880,
+            "StemV" => 93,
+        },
+        "DW" => 1000,
+        "W" => vec![1.into(), 95.into(), 500.into()],
+    };
+    doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type0",
+        "BaseFont" => "STSong-Light-UniGB-UCS2-H",
+        "Encoding" => "UniGB-UCS2-H",
+        "DescendantFonts" => vec![descendant.into()],
+    })
+}
+
+/// `text` as the hex digits of its UTF-16 code units.
+fn encode_text(text: &str) -> String {
+    text.encode_utf16().map(|unit| format!("{unit:04X}")).collect()
+}
+
+/// Width of `text` in units of the font size: half width for ASCII, full otherwise.
+fn text_width(text: &str) -> f32 {
+    text.chars().map(|c| if c.is_ascii() { 0.5 } else { 1.0 }).sum()
+}
+
+/// Decodes the PNG at `path` into an image XObject, its alpha channel as a soft
+/// mask. Returns the object and the image's size in pixels.
+fn add_image(doc: &mut Document, path: &Path) -> Result<(ObjectId, u32, u32)> {
+    let file = File::open(path).map_err(|err| anyhow!("无法读取水印图片 {}：{err}", path.display()))?;
+    let mut decoder = png::Decoder::new(BufReader::new(file));
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder
+        .read_info()
+        .map_err(|err| anyhow!("水印图片不是有效的 PNG：{err}"))?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader
+        .next_frame(&mut pixels)
+        .map_err(|err| anyhow!("水印图片不是有效的 PNG：{err}"))?;
+    pixels.truncate(info.buffer_size());
+
+    let (colour, channels, alpha) = match info.color_type {
+        png::ColorType::Grayscale => ("DeviceGray", 1, false),
+        png::ColorType::GrayscaleAlpha => ("DeviceGray", 1, true),
+        png::ColorType::Rgb => ("DeviceRGB", 3, false),
+        png::ColorType::Rgba => ("DeviceRGB", 3, true),
+        png::ColorType::Indexed => bail!("无法解码水印图片的调色板"),
+    };
+    let (colours, mask) = if alpha {
+        let stride = channels + 1;
+        let colours = pixels.chunks(stride).flat_map(|pixel| &pixel[..channels]).copied().collect();
+        let mask = pixels.chunks(stride).map(|pixel| pixel[channels]).collect();
+        (colours, Some(mask))
+    } else {
+        (pixels, None)
+    };
+
+    let image = |colour: &str, data: Vec<u8>| {
+        Stream::new(
+            dictionary! {
+                "Type" => "XObject",
+                "Subtype" => "Image",
+                "Width" => info.width,
+                "Height" => info.height,
+                "ColorSpace" => colour,
+                "BitsPerComponent" => 8,
+            },
+            data,
+        )
+    };
+    let mut stream = image(colour, colours);
+    if let Some(mask) = mask {
+        let mask = doc.add_object(image("DeviceGray", mask));
+        stream.dict.set("SMask", mask);
+    }
+    Ok((doc.add_object(stream), info.width, info.height))
+}
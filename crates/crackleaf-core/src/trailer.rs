@@ -0,0 +1,81 @@
+//! Reads the encryption flag straight from a PDF's trailer.
+//!
+//! Only the end of the file and, for cross-reference streams, the dictionary the
+//! last `startxref` points to are read, so this stays fast and memory-flat on
+//! multi-gigabyte scans. Anything unusual yields `None` and detection falls back
+//! to qpdf.
+
+use std::fs::File;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::Path;
+
+/// How much of the end of the file is searched for `startxref` and `trailer`.
+const TAIL_WINDOW: usize = 4096;
+/// Longest cross-reference stream dictionary considered.
+const DICT_WINDOW: usize = 4096;
+
+/// `Some(true)` when the newest trailer has an `/Encrypt` entry, `Some(false)` when
+/// it is a plausible trailer without one, `None` when the file could not be read or
+/// understood.
+pub(crate) fn trailer_encryption(path: &Path) -> Option<bool> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    let tail = read_window(&mut file, len.saturating_sub(TAIL_WINDOW as u64), TAIL_WINDOW, len)?;
+
+    let startxref = rfind(&tail, b"startxref")?;
+    let object;
+    let dict = match rfind(&tail[..startxref], b"trailer") {
+        Some(trailer) => &tail[trailer..startxref],
+        None => {
+            // PDF 1.5+ cross-reference stream: its dictionary is the trailer.
+            let offset = parse_offset(&tail[startxref + b"startxref".len()..])?;
+            object = read_window(&mut file, offset, DICT_WINDOW, len)?;
+            &object[..find(&object, b"stream")?]
+        }
+    };
+
+    if has_key(dict, b"/Encrypt") {
+        Some(true)
+    } else if has_key(dict, b"/Root") {
+        Some(false)
+    } else {
+        None
+    }
+}
+
+/// Reads up to `window` bytes from `start`, stopping at the end of the file.
+fn read_window(file: &mut File, start: u64, window: usize, len: u64) -> Option<Vec<u8>> {
+    if start >= len {
+        return None;
+    }
+    let size = (len - start).min(window as u64) as usize;
+    let mut buf = vec![0; size];
+    file.seek(SeekFrom::Start(start)).ok()?;
+    file.read_exact(&mut buf).ok()?;
+    Some(buf)
+}
+
+fn parse_offset(bytes: &[u8]) -> Option<u64> {
+    let digits: Vec<u8> = bytes
+        .iter()
+        .copied()
+        .skip_while(u8::is_ascii_whitespace)
+        .take_while(u8::is_ascii_digit)
+        .collect();
+    std::str::from_utf8(&digits).ok()?.parse().ok()
+}
+
+/// `key` as a whole name, so `/Encrypt` does not match `/EncryptMetadata`.
+fn has_key(dict: &[u8], key: &[u8]) -> bool {
+    dict.windows(key.len() + 1).any(|window| {
+        window.starts_with(key) && !window[key.len()].is_ascii_alphanumeric()
+    }) || dict.ends_with(key)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+fn rfind(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).rposition(|window| window == needle)
+}
@@ -0,0 +1,505 @@
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, Result};
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+use tracing::{debug, info, instrument, warn, Instrument, Level};
+
+use crate::detect::is_restricted;
+use crate::error::Error;
+use crate::events::{BatchId, Event, EventBus, JobEvent};
+use crate::inspect::inspect_pdf;
+use crate::job::JobState;
+use crate::options::UnlockOptions;
+use crate::office::{is_office_document, remove_protection};
+use crate::output::{
+    copy_attributes, move_file, reserve_output_paths, resolve_download_dir, unique_output_path, unique_result_path,
+    ConflictPolicy,
+};
+use crate::qpdf::{qpdf_command, run_qpdf};
+use crate::runtime::runtime;
+use crate::split::split_by_bookmarks;
+use crate::steps::{StepContext, StepError};
+use crate::volume::{is_cloud_placeholder, long_path, materialize, with_io_timeout, IO_TIMEOUT};
+
+/// Runs every file through `options.pipeline`, publishing [`Event`]s tagged with
+/// `batch` on `options.events`.
+///
+/// Up to `options.concurrency` files are processed at once, so results may arrive
+/// out of order. Once `cancel` fires, running qpdf processes are killed, no new
+/// files are started and [`Event::BatchFinished`] is published right away; the
+/// `options.session` file is then left in place so the batch can be resumed.
+#[instrument(skip_all, fields(batch = batch, files = files.len(), concurrency = options.concurrency))]
+pub async fn run_unlock(
+    files: Vec<PathBuf>,
+    options: UnlockOptions,
+    batch: BatchId,
+    cancel: CancellationToken,
+) {
+    let events = options.events.clone();
+    let total = files.len();
+    events.publish(Event::BatchStarted { batch, total });
+    // Both look at the volumes involved; a stalled share must not hold up the batch.
+    let (checked, inputs) = (options.clone(), files.clone());
+    let warnings = with_io_timeout(Some(IO_TIMEOUT), move || {
+        Ok([checked.network_warning(&inputs), checked.disk_space_warning(&inputs)])
+    })
+    .await
+    .unwrap_or_default();
+    for warning in warnings.into_iter().flatten() {
+        events.publish(Event::Notification {
+            batch: Some(batch),
+            message: warning,
+        });
+    }
+
+    if let Some(folder) = &options.batch_folder {
+        create_batch_folders(&files, folder, &options);
+    }
+    let outputs = reserve_output_paths(&files, &options.suffix, options.on_conflict, |path| output_dir(path, &options));
+    let permits = Arc::new(Semaphore::new(options.concurrency.max(1)));
+    let options = Arc::new(options);
+    let done = Arc::new(AtomicUsize::new(0));
+    let mut jobs = JoinSet::new();
+
+    for (index, (path, output)) in files.into_iter().zip(outputs).enumerate() {
+        let permits = permits.clone();
+        let cancel = cancel.clone();
+        let options = options.clone();
+        let done = done.clone();
+        let job = JobReporter {
+            events: events.clone(),
+            batch,
+            index,
+        };
+        let span = tracing::info_span!("job", index, path = %path.display());
+        jobs.spawn(
+            async move {
+                let _permit = tokio::select! {
+                    _ = cancel.cancelled() => return,
+                    permit = permits.acquire_owned() => permit,
+                };
+                tokio::select! {
+                    _ = cancel.cancelled() => info!("cancelled"),
+                    _ = unlock_one(&job, &path, &output, &options) => {
+                        if let Some(session) = &options.session {
+                            if let Err(err) = session.mark_finished(index) {
+                                warn!("Failed to update batch session: {err}");
+                            }
+                        }
+                        let done = done.fetch_add(1, Ordering::Relaxed) + 1;
+                        job.events.publish(Event::Progress { batch, done, total });
+                    }
+                }
+            }
+            .instrument(span),
+        );
+    }
+
+    let cancelled = tokio::select! {
+        _ = cancel.cancelled() => {
+            info!("batch cancelled");
+            jobs.abort_all();
+            true
+        }
+        _ = async { while jobs.join_next().await.is_some() {} } => {
+            if let Some(session) = &options.session {
+                session.complete();
+            }
+            false
+        }
+    };
+
+    events.publish(Event::BatchFinished { batch, cancelled });
+}
+
+/// Publishes the [`JobEvent`]s of one file.
+struct JobReporter {
+    events: EventBus,
+    batch: BatchId,
+    index: usize,
+}
+
+impl JobReporter {
+    fn send(&self, event: JobEvent) {
+        self.events.publish(Event::Job {
+            batch: self.batch,
+            index: self.index,
+            event,
+        });
+    }
+}
+
+async fn unlock_one(job: &JobReporter, path: &Path, output: &Path, options: &UnlockOptions) {
+    let started = Instant::now();
+    job.send(JobEvent::StateChanged(JobState::Analyzing));
+    match run_pipeline(job, path, output, options).await {
+        Ok(output_path) => {
+            // Checked here rather than by the frontend, which may be a UI thread.
+            let result = output_path.as_deref().unwrap_or(path);
+            let restricted = is_restricted(result, options.timeout).await;
+            let output_facts = match &output_path {
+                Some(output) if options.inspect_results && !is_office_document(output) => {
+                    inspect_pdf(output, options.timeout).await.map(Box::new)
+                }
+                _ => None,
+            };
+            job.send(JobEvent::Finished {
+                success: true,
+                output_path,
+                error: None,
+                stage: None,
+                elapsed: started.elapsed(),
+                restricted,
+                output_facts,
+            });
+        }
+        Err(err) => {
+            let error = Error::from_anyhow(&err.source);
+            let message = if let Error::OutputExists(_) = error {
+                info!("skipped: {error}");
+                format!("已跳过 {}：{error}", path.display())
+            } else {
+                warn!(stage = %err.step, kind = error.kind(), "unlock failed: {error}");
+                format!("解锁失败: {}：{}：{error}", path.display(), err.step)
+            };
+            job.send(JobEvent::Finished {
+                success: false,
+                output_path: None,
+                error: Some(error),
+                stage: Some(err.step),
+                elapsed: started.elapsed(),
+                restricted: None,
+                output_facts: None,
+            });
+            job.events.publish(Event::Log {
+                level: Level::WARN,
+                message,
+            });
+        }
+    }
+}
+
+/// Name of the stage that moves the result into place.
+const SAVE_STAGE: &str = "保存";
+
+/// Folder the result for `path` goes to.
+fn output_dir(path: &Path, options: &UnlockOptions) -> PathBuf {
+    let mut dir = options.output_root(path);
+    if let Some(folder) = &options.batch_folder {
+        dir.push(folder);
+    }
+    match options.output_subdirs.get(path) {
+        Some(subdir) => dir.join(subdir),
+        None => dir,
+    }
+}
+
+/// Creates `folder` in every output folder of the batch up front. New ones are
+/// recorded before any result, so undoing the batch removes them once they are empty.
+fn create_batch_folders(files: &[PathBuf], folder: &Path, options: &UnlockOptions) {
+    let dirs: BTreeSet<PathBuf> = files.iter().map(|path| options.output_root(path).join(folder)).collect();
+    for dir in dirs.into_iter().filter(|dir| !dir.exists()) {
+        if let Err(err) = std::fs::create_dir_all(&dir) {
+            // Saving the results will report it.
+            warn!("Failed to create {:?}: {err}", dir);
+            continue;
+        }
+        if let Some(journal) = &options.journal {
+            if let Err(err) = journal.record_created(&dir) {
+                warn!("Failed to record {:?} in the undo journal: {err}", dir);
+            }
+        }
+    }
+}
+
+/// Runs every stage on `path`, each one reading the previous stage's output
+/// from a private temp directory, then moves the result to `output`, the name
+/// reserved for it.
+///
+/// Returns `Ok(None)` when every stage skipped the file, unless
+/// [`UnlockOptions::copy_unchanged`] asks for a copy.
+async fn run_pipeline(
+    job: &JobReporter,
+    path: &Path,
+    output: &Path,
+    options: &UnlockOptions,
+) -> Result<Option<PathBuf>, StepError> {
+    // No point in doing the work for a result that will not be saved.
+    if options.on_conflict == ConflictPolicy::Skip && output.exists() {
+        return Err(StepError::new(SAVE_STAGE, Error::OutputExists(output.display().to_string())));
+    }
+    // Placeholders evicted since they were added are downloaded again up front, so the
+    // wait shows as its own stage rather than a stalled open or qpdf.
+    let probe = path.to_path_buf();
+    let placeholder = with_io_timeout(Some(IO_TIMEOUT), move || Ok(is_cloud_placeholder(&probe))).await;
+    if placeholder.unwrap_or(false) {
+        const STAGE: &str = "下载云文件";
+        job.send(JobEvent::StageChanged(STAGE.to_string()));
+        materialize(path, options.timeout)
+            .await
+            .map_err(|err| StepError::new(STAGE, Error::CloudUnavailable(err.to_string())))?;
+    }
+    // A file deleted, unmounted or locked since it was added would otherwise fail
+    // with an obscure qpdf error.
+    let source = path.to_path_buf();
+    if let Err(err) = with_io_timeout(Some(IO_TIMEOUT), move || std::fs::File::open(source).map(drop)).await {
+        let error = if err.kind() == std::io::ErrorKind::NotFound {
+            Some(Error::SourceMissing(path.display().to_string()))
+        } else if err.kind() == std::io::ErrorKind::TimedOut {
+            Some(Error::Timeout(IO_TIMEOUT))
+        } else if is_sharing_violation(&err) {
+            Some(Error::SourceLocked(path.display().to_string()))
+        } else {
+            None
+        };
+        if let Some(error) = error {
+            return Err(StepError::new("准备", error));
+        }
+    }
+    let workdir = tempfile::Builder::new()
+        .prefix("crackleaf-")
+        .tempdir()
+        .map_err(|err| StepError::new("准备", err))?;
+    let ctx = StepContext {
+        source: path,
+        timeout: options.timeout,
+        passwords: &options.passwords,
+    };
+
+    let mut current = path.to_path_buf();
+    let mut applied = Vec::new();
+    let mut changed = false;
+    // Office documents only have their protection removed; the pipeline is for PDFs.
+    let pipeline = if is_office_document(path) {
+        const STAGE: &str = "解除保护";
+        job.send(JobEvent::StageChanged(STAGE.to_string()));
+        let next = workdir.path().join(path.file_name().unwrap_or_default());
+        let (input, target) = (path.to_path_buf(), next.clone());
+        let removed = with_io_timeout(options.timeout, move || {
+            remove_protection(&input, &target).map_err(std::io::Error::other)
+        })
+        .await
+        .map_err(|err| match options.timeout {
+            Some(limit) if err.kind() == std::io::ErrorKind::TimedOut => StepError::new(STAGE, Error::Timeout(limit)),
+            _ => StepError::new(STAGE, err),
+        })?;
+        if removed {
+            job.send(JobEvent::StateChanged(JobState::Running));
+            job.send(JobEvent::StageApplied(STAGE.to_string()));
+            current = next;
+            changed = true;
+        }
+        &[][..]
+    } else {
+        &options.pipeline[..]
+    };
+    for (position, step) in pipeline.iter().enumerate() {
+        let fail = |err: anyhow::Error| StepError::new(step.name(), err);
+        job.send(JobEvent::StageChanged(step.name().to_string()));
+        if !step.analyze(&current, &ctx).await.map_err(fail)? {
+            debug!(step = step.id(), "skipped");
+            continue;
+        }
+        if applied.is_empty() {
+            job.send(JobEvent::StateChanged(JobState::Running));
+        }
+        let next = workdir.path().join(format!("{position}-{}.pdf", step.id()));
+        step.transform(&current, &next, &ctx).await.map_err(fail)?;
+        if !next.exists() {
+            return Err(fail(anyhow!("未生成输出文件")));
+        }
+        debug!(step = step.id(), "applied");
+        job.send(JobEvent::StageApplied(step.name().to_string()));
+        current = next;
+        applied.push(step);
+        changed = true;
+    }
+    // Nothing applied: the "result" would be the source itself.
+    let unchanged = !changed;
+    if unchanged && !options.copy_unchanged {
+        return Ok(None);
+    }
+
+    let (source, target) = (current.clone(), output.to_path_buf());
+    let (source_path, suffix) = (path.to_path_buf(), options.suffix.clone());
+    let (on_conflict, journal) = (options.on_conflict, options.journal.clone());
+    let output_path = with_io_timeout(options.timeout, move || {
+        let output_dir = target.parent().unwrap_or(Path::new("."));
+        std::fs::create_dir_all(output_dir)?;
+        // Also checked when the name is taken outside the batch since it was reserved.
+        let output_path = match on_conflict {
+            _ if !target.exists() => target,
+            ConflictPolicy::Rename => unique_result_path(output_dir, &source_path, &suffix),
+            ConflictPolicy::Skip => return Err(std::io::ErrorKind::AlreadyExists.into()),
+            ConflictPolicy::Overwrite => {
+                if let Some(journal) = &journal {
+                    journal.backup_original(&target).map_err(std::io::Error::other)?;
+                }
+                target
+            }
+        };
+        if unchanged {
+            std::fs::copy(&source, &output_path)?;
+        } else {
+            move_file(&source, &output_path)?;
+        }
+        Ok(output_path)
+    })
+    .await
+    .map_err(|err| {
+        let error = match options.timeout {
+            Some(limit) if err.kind() == std::io::ErrorKind::TimedOut => Error::Timeout(limit),
+            _ if err.kind() == std::io::ErrorKind::AlreadyExists => Error::OutputExists(output.display().to_string()),
+            _ => Error::OutputNotWritable(format!("{}：{err}", output.display())),
+        };
+        StepError::new(SAVE_STAGE, error)
+    })?;
+    if let Some(journal) = &options.journal {
+        if let Err(err) = journal.record_created(&output_path) {
+            warn!("Failed to record {:?} in the undo journal: {err}", output_path);
+        }
+    }
+
+    for step in applied {
+        if let Err(err) = step.finalize(&output_path, &ctx).await {
+            let _ = std::fs::remove_file(&output_path);
+            return Err(StepError::new(step.name(), err));
+        }
+    }
+    if options.preserve_attributes {
+        let (source, target) = (path.to_path_buf(), output_path.clone());
+        if let Err(err) = with_io_timeout(Some(IO_TIMEOUT), move || copy_attributes(&source, &target)).await {
+            warn!("Failed to copy file attributes to {:?}: {err}", output_path);
+        }
+    }
+    info!(output = %output_path.display(), "saved");
+    if options.split_by_bookmarks && !is_office_document(path) {
+        split(job, &output_path, options).await;
+    }
+    Ok(Some(output_path))
+}
+
+/// Writes the parts of `output` per top-level bookmark and records them. A failure
+/// leaves the whole result in place and is only reported.
+async fn split(job: &JobReporter, output: &Path, options: &UnlockOptions) {
+    job.send(JobEvent::StageChanged("按书签拆分".to_string()));
+    match split_by_bookmarks(output, options.timeout).await {
+        Ok(Some((dir, parts))) => {
+            if let Some(journal) = &options.journal {
+                for path in std::iter::once(&dir).chain(&parts) {
+                    if let Err(err) = journal.record_created(path) {
+                        warn!("Failed to record {:?} in the undo journal: {err}", path);
+                    }
+                }
+            }
+            job.send(JobEvent::Split(parts));
+        }
+        Ok(None) => {}
+        Err(err) => {
+            warn!("Splitting {} by bookmarks failed: {err}", output.display());
+            job.events.publish(Event::Notification {
+                batch: Some(job.batch),
+                message: format!("无法按书签拆分 {}：{err}", output.display()),
+            });
+        }
+    }
+}
+
+/// Another program holds the file open without sharing it, e.g. a PDF reader on
+/// Windows. Other systems do not lock files that way.
+fn is_sharing_violation(err: &std::io::Error) -> bool {
+    #[cfg(windows)]
+    {
+        const ERROR_SHARING_VIOLATION: i32 = 32;
+        const ERROR_LOCK_VIOLATION: i32 = 33;
+        matches!(err.raw_os_error(), Some(ERROR_SHARING_VIOLATION | ERROR_LOCK_VIOLATION))
+    }
+    #[cfg(not(windows))]
+    {
+        let _ = err;
+        false
+    }
+}
+
+/// Handle to a batch started with [`spawn_unlock`]. Dropping it cancels the batch.
+pub struct UnlockHandle {
+    batch: BatchId,
+    cancel: CancellationToken,
+}
+
+impl UnlockHandle {
+    /// Id carried by the batch's events.
+    pub fn id(&self) -> BatchId {
+        self.batch
+    }
+
+    /// Stops the batch, killing any running qpdf process.
+    pub fn cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// Token that cancels this batch, e.g. for wiring up a signal handler.
+    pub fn cancel_token(&self) -> CancellationToken {
+        self.cancel.clone()
+    }
+}
+
+impl Drop for UnlockHandle {
+    fn drop(&mut self) {
+        self.cancel.cancel();
+    }
+}
+
+/// Starts [`run_unlock`] on the shared runtime. Subscribe to `options.events`
+/// beforehand to follow its progress.
+pub fn spawn_unlock(files: Vec<PathBuf>, options: UnlockOptions) -> UnlockHandle {
+    let batch = options.events.next_batch_id();
+    let cancel = CancellationToken::new();
+    runtime().spawn(run_unlock(files, options, batch, cancel.clone()));
+    UnlockHandle { batch, cancel }
+}
+
+/// Writes a decrypted copy of `path` into the downloads folder, named with `suffix`
+/// as configured in [`UnlockOptions::suffix`].
+///
+/// Returns `Ok(None)` when qpdf ran but produced no output, and `Err` when qpdf
+/// could not be launched or exceeded `timeout`.
+#[instrument(skip(suffix, timeout), fields(path = %path.display()))]
+pub async fn unlock_pdf(path: &Path, suffix: &str, timeout: Option<Duration>) -> Result<Option<PathBuf>> {
+    let output_dir = resolve_download_dir().unwrap_or_else(|| {
+        path.parent()
+            .map(|p| p.to_path_buf())
+            .unwrap_or_else(|| PathBuf::from("."))
+    });
+    let file_stem = path.file_stem().unwrap_or("output".as_ref());
+    let output_path = unique_output_path(&output_dir, file_stem, suffix);
+
+    let mut cmd = qpdf_command();
+    cmd.arg("--password=")
+        .arg("--decrypt")
+        .arg(&*long_path(path))
+        .arg(&*long_path(&output_path));
+
+    let output = run_qpdf(cmd, timeout).await?;
+
+    if !output.status.success() {
+        warn!(
+            code = ?output.status.code(),
+            stderr = %String::from_utf8_lossy(&output.stderr).trim(),
+            "qpdf --decrypt failed"
+        );
+        return Ok(None);
+    }
+    info!(output = %output_path.display(), "decrypted");
+    if output_path.exists() {
+        Ok(Some(output_path))
+    } else {
+        Ok(None)
+    }
+}
@@ -0,0 +1,282 @@
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::Duration;
+
+/// Limit for small reads (headers, trailers, folder checks) that should return at once
+/// unless the volume has stalled, e.g. a disconnected network share.
+pub const IO_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// Runs blocking file system work on the blocking pool, giving up after `limit`
+/// (`None` = no limit) with an error of kind [`TimedOut`](std::io::ErrorKind::TimedOut).
+///
+/// A stalled call cannot be interrupted; it keeps its thread until the system gives
+/// up, but the caller moves on.
+pub async fn with_io_timeout<T, F>(limit: Option<Duration>, work: F) -> std::io::Result<T>
+where
+    T: Send + 'static,
+    F: FnOnce() -> std::io::Result<T> + Send + 'static,
+{
+    let task = tokio::task::spawn_blocking(work);
+    let joined = match limit {
+        Some(limit) => tokio::time::timeout(limit, task).await.map_err(|_| {
+            std::io::Error::new(
+                std::io::ErrorKind::TimedOut,
+                format!("超过 {} 秒无响应（网络卷较慢或已断开？）", limit.as_secs()),
+            )
+        })?,
+        None => task.await,
+    };
+    joined.map_err(std::io::Error::other)?
+}
+
+/// Best-effort check whether `path` lives on a network share (SMB, NFS, ...).
+pub fn is_network_path(path: &Path) -> bool {
+    platform::is_network_path(path)
+}
+
+/// The file is a cloud placeholder whose content is not on disk yet: dataless on
+/// macOS (iCloud Drive and other File Provider folders), recalled on access on
+/// Windows (OneDrive Files On-Demand). Always `false` on other systems.
+pub fn is_cloud_placeholder(path: &Path) -> bool {
+    platform::is_cloud_placeholder(path)
+}
+
+/// Has the cloud provider download a placeholder by reading the file through once.
+/// Fails with [`TimedOut`](std::io::ErrorKind::TimedOut) after `limit`.
+pub async fn materialize(path: &Path, limit: Option<Duration>) -> std::io::Result<()> {
+    let path = path.to_path_buf();
+    with_io_timeout(limit, move || {
+        let mut file = std::fs::File::open(&path)?;
+        std::io::copy(&mut file, &mut std::io::sink())?;
+        Ok(())
+    })
+    .await
+}
+
+/// `path` in a form Windows APIs accept beyond `MAX_PATH` (260 characters): long
+/// absolute paths get the `\\?\` extended-length prefix (`\\?\UNC\` for shares).
+/// Needed wherever a path leaves the standard library, which already does this itself,
+/// e.g. qpdf arguments and raw Win32 calls. Unchanged on other systems.
+pub(crate) fn long_path(path: &Path) -> Cow<'_, Path> {
+    #[cfg(windows)]
+    {
+        use std::path::{Component, Prefix};
+
+        /// Directories are limited to `MAX_PATH` minus room for an 8.3 file name.
+        const SHORT_LIMIT: usize = 260 - 12;
+
+        if path.as_os_str().len() < SHORT_LIMIT {
+            return Cow::Borrowed(path);
+        }
+        // `\\?\` turns off normalization, so resolve `.`, `..` and `/` first.
+        let Some(absolute) = std::path::absolute(path).ok().and_then(|p| p.to_str().map(str::to_owned)) else {
+            return Cow::Borrowed(path);
+        };
+        let extended = match Path::new(&absolute).components().next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::Disk(_) => format!(r"\\?\{absolute}"),
+                Prefix::UNC(..) => format!(r"\\?\UNC\{}", &absolute[2..]),
+                _ => return Cow::Borrowed(path),
+            },
+            _ => return Cow::Borrowed(path),
+        };
+        Cow::Owned(extended.into())
+    }
+    #[cfg(not(windows))]
+    Cow::Borrowed(path)
+}
+
+/// Bytes the current user can still write on the volume holding `path`, or its
+/// nearest existing ancestor. `None` when it cannot be determined.
+pub fn available_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|dir| dir.exists())?;
+    free_space(existing)
+}
+
+#[cfg(unix)]
+fn free_space(path: &Path) -> Option<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)]
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn free_space(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+    let mut available = 0u64;
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(wide.as_ptr(), &mut available, std::ptr::null_mut(), std::ptr::null_mut())
+    };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn free_space(_path: &Path) -> Option<u64> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+mod platform {
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::{Component, Path, Prefix};
+
+    use windows_sys::Win32::Storage::FileSystem::GetDriveTypeW;
+
+    const DRIVE_REMOTE: u32 = 4;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x0004_0000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x0040_0000;
+
+    pub fn is_cloud_placeholder(path: &Path) -> bool {
+        use std::os::windows::fs::MetadataExt;
+
+        let placeholder = FILE_ATTRIBUTE_OFFLINE | FILE_ATTRIBUTE_RECALL_ON_OPEN | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS;
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.file_attributes() & placeholder != 0)
+    }
+
+    pub fn is_network_path(path: &Path) -> bool {
+        match path.components().next() {
+            Some(Component::Prefix(prefix)) => match prefix.kind() {
+                Prefix::UNC(..) | Prefix::VerbatimUNC(..) => true,
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                    let root: Vec<u16> = format!("{}:\\", letter as char)
+                        .as_str()
+                        .encode_utf16()
+                        .chain(std::iter::once(0))
+                        .collect();
+                    unsafe { GetDriveTypeW(root.as_ptr()) == DRIVE_REMOTE }
+                }
+                _ => false,
+            },
+            _ => path.as_os_str().encode_wide().take(2).eq([b'\\' as u16; 2]),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+mod platform {
+    use std::path::Path;
+
+    const NETWORK_FS: &[&str] = &[
+        "nfs", "nfs4", "cifs", "smb3", "smbfs", "ncpfs", "afs", "9p", "fuse.sshfs",
+    ];
+
+    pub fn is_cloud_placeholder(_path: &Path) -> bool {
+        false
+    }
+
+    pub fn is_network_path(path: &Path) -> bool {
+        let path = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        let Ok(mounts) = std::fs::read_to_string("/proc/mounts") else {
+            return false;
+        };
+        mounts
+            .lines()
+            .filter_map(|line| {
+                let mut fields = line.split_whitespace();
+                let _device = fields.next()?;
+                let mount_point = fields.next()?.replace("\\040", " ");
+                let fs_type = fields.next()?;
+                Some((mount_point, fs_type.to_string()))
+            })
+            .filter(|(mount_point, _)| path.starts_with(mount_point))
+            .max_by_key(|(mount_point, _)| mount_point.len())
+            .is_some_and(|(_, fs_type)| NETWORK_FS.contains(&fs_type.as_str()))
+    }
+}
+
+#[cfg(target_os = "macos")]
+mod platform {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    const NETWORK_FS: &[&str] = &["smbfs", "nfs", "afpfs", "webdav", "ftp"];
+    /// `st_flags` bit of files whose content lives with a File Provider.
+    const SF_DATALESS: u32 = 0x4000_0000;
+
+    pub fn is_cloud_placeholder(path: &Path) -> bool {
+        use std::os::macos::fs::MetadataExt;
+
+        std::fs::metadata(path).is_ok_and(|metadata| metadata.st_flags() & SF_DATALESS != 0)
+    }
+
+    pub fn is_network_path(path: &Path) -> bool {
+        let Ok(c_path) = CString::new(path.as_os_str().as_bytes()) else {
+            return false;
+        };
+        let mut stat: libc::statfs = unsafe { std::mem::zeroed() };
+        if unsafe { libc::statfs(c_path.as_ptr(), &mut stat) } != 0 {
+            return false;
+        }
+        let fs_type = unsafe { CStr::from_ptr(stat.f_fstypename.as_ptr()) };
+        NETWORK_FS.contains(&fs_type.to_string_lossy().as_ref())
+    }
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux", target_os = "macos")))]
+mod platform {
+    use std::path::Path;
+
+    pub fn is_network_path(_path: &Path) -> bool {
+        false
+    }
+
+    pub fn is_cloud_placeholder(_path: &Path) -> bool {
+        false
+    }
+}
+
+#[cfg(all(test, windows))]
+mod tests {
+    use super::*;
+
+    /// A folder name that pushes any path past the short limit.
+    fn long_dir() -> String {
+        "d".repeat(250)
+    }
+
+    #[test]
+    fn short_paths_pass_through() {
+        for path in [r"C:\Users\me\report.pdf", r"\\server\share\report.pdf", r"relative\report.pdf"] {
+            assert!(matches!(long_path(Path::new(path)), Cow::Borrowed(_)), "{path}");
+        }
+    }
+
+    #[test]
+    fn long_disk_paths_get_the_extended_prefix() {
+        let path = format!(r"C:\{}\report.pdf", long_dir());
+        assert_eq!(long_path(Path::new(&path)), Path::new(&format!(r"\\?\{path}")));
+    }
+
+    #[test]
+    fn long_unc_paths_get_the_unc_prefix() {
+        let path = format!(r"\\server\share\{}\report.pdf", long_dir());
+        let expected = format!(r"\\?\UNC\server\share\{}\report.pdf", long_dir());
+        assert_eq!(long_path(Path::new(&path)), Path::new(&expected));
+    }
+
+    #[test]
+    fn long_paths_are_normalized_before_the_prefix() {
+        let path = format!(r"C:\{}\skipped\..\.\sub/report.pdf", long_dir());
+        let expected = format!(r"\\?\C:\{}\sub\report.pdf", long_dir());
+        assert_eq!(long_path(Path::new(&path)), Path::new(&expected));
+    }
+
+    #[test]
+    fn extended_paths_are_left_alone() {
+        let path = format!(r"\\?\C:\{}\report.pdf", long_dir());
+        assert_eq!(long_path(Path::new(&path)), Path::new(&path));
+    }
+}
@@ -0,0 +1,1615 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::path::{Path, PathBuf};
+use std::sync::{mpsc, Arc};
+use std::time::{Duration, Instant};
+
+use crackleaf_core::steps::{Encrypt, ProcessingStep};
+#[cfg(feature = "gui")]
+use crackleaf_core::steps::{Decrypt, ExtractPages, Letterhead, PdfKind, StepRegistry};
+use crackleaf_core::{
+    block_on, default_concurrency, encryption_method, is_cloud_placeholder, is_network_path, is_office_document, is_pdf, is_restricted,
+    inspect_pdf, load_detection_cache, materialize, move_file, nfc_name, requires_password, resolve_download_dir, runtime, save_detection_cache, set_process_limit, spawn_unlock, with_io_timeout,
+    Error, Event, EventBus, JobEvent, JobQueue, JobState, Journal, PdfFacts, QpdfStatus, Signature, Subscription, UnlockHandle,
+    UnlockOptions, IO_TIMEOUT, PROTECTED_SUFFIX,
+};
+#[cfg(feature = "gui")]
+use crackleaf_core::Bookmark;
+use tokio::sync::Semaphore;
+use tokio::task::JoinSet;
+use tracing::{debug, info, warn};
+
+use crate::archive::{self, ArchiveError, Extracted};
+use crate::config::{Config, DropFilter, LargeFiles, Originals};
+#[cfg(feature = "network")]
+use crate::download::Downloaded;
+#[cfg(feature = "history")]
+use crate::history::{self, History};
+use crate::i18n::{self, t};
+use crate::icons::Icon;
+#[cfg(feature = "passwords")]
+use crate::passwords;
+use crate::{links, resume, undo};
+
+/// A file in the list, with the icon and status text shown for it.
+#[derive(Clone)]
+pub struct FileEntry {
+    pub path: PathBuf,
+    pub icon: Icon,
+    pub status: String,
+    pub output_path: Option<PathBuf>,
+    /// Opening the file takes a user password, so it cannot be unlocked as is.
+    pub needs_password: bool,
+    /// Content hash for the processing history.
+    #[cfg(feature = "history")]
+    pub hash: Option<String>,
+    /// Earlier successful unlock of the same content, if its output still exists.
+    #[cfg(feature = "history")]
+    pub previous: Option<history::Record>,
+    /// Outcome of the encryption check; `None` until it is known or when it was inconclusive.
+    pub encrypted: Option<bool>,
+    /// Cipher of an encrypted PDF, see [`encryption_method`].
+    pub encryption: Option<String>,
+    /// The file is on a network share that answered slowly or not at all.
+    pub slow_volume: bool,
+    /// Size, version, page count and metadata of the source, when qpdf could read it.
+    pub facts: Option<PdfFacts>,
+    /// The same of the result, for the before/after comparison.
+    pub output_facts: Option<PdfFacts>,
+    /// ZIP archive the file was unpacked from; `path` is then a temporary copy.
+    pub archive: Option<PathBuf>,
+    /// Link the file was downloaded from; `path` is then a temporary copy.
+    pub url: Option<String>,
+    /// Pages the result was limited to, as typed, when it came from
+    /// [`AppCore::extract_pages`].
+    pub extracted_pages: Option<String>,
+    /// The result came from a pipeline that changes the page count on purpose.
+    pub rearranged: bool,
+    /// Processed even when larger than the profile's `max_file_size_mb`.
+    pub allow_large: bool,
+}
+
+impl FileEntry {
+    /// Size of the source in bytes, from its facts or else the file system.
+    pub fn size(&self) -> Option<u64> {
+        self.facts
+            .as_ref()
+            .map(|facts| facts.size)
+            .or_else(|| std::fs::metadata(&self.path).ok().map(|metadata| metadata.len()))
+    }
+
+    /// Source and result page counts when the result has a different number of pages,
+    /// e.g. because qpdf dropped some while recovering a damaged file.
+    pub fn page_mismatch(&self) -> Option<(u32, u32)> {
+        if self.rearranged {
+            return None;
+        }
+        let source = self.facts.as_ref()?.pages?;
+        let output = self.output_facts.as_ref()?.pages?;
+        (source != output).then_some((source, output))
+    }
+
+    /// Names of the files embedded in the result, or in the source before it is unlocked.
+    #[cfg(feature = "gui")]
+    pub fn attachments(&self) -> &[String] {
+        self.output_facts
+            .as_ref()
+            .or(self.facts.as_ref())
+            .map_or(&[], |facts| facts.attachments.as_slice())
+    }
+
+    /// How hard the source is to unlock, e.g. "RC4-40, removable without a password",
+    /// when it is an encrypted PDF.
+    #[cfg(feature = "gui")]
+    pub fn security(&self) -> Option<String> {
+        let cipher = self.encryption.as_deref()?;
+        let key = if self.needs_password {
+            if cipher.starts_with("RC4") {
+                "security-password-weak"
+            } else {
+                "security-password"
+            }
+        } else if cipher.starts_with("RC4") || cipher.starts_with("AES") {
+            "security-removable"
+        } else {
+            "security-handler"
+        };
+        Some(t!(key, cipher = cipher))
+    }
+
+    /// Whether the result, or else the source, is text-based or scanned.
+    #[cfg(feature = "gui")]
+    pub fn kind(&self) -> Option<PdfKind> {
+        self.output_facts.as_ref().or(self.facts.as_ref())?.kind
+    }
+
+    /// Pages whose redactions only cover text that is still there, in the result or
+    /// else the source.
+    pub fn covered_text_pages(&self) -> &[u32] {
+        self.output_facts
+            .as_ref()
+            .or(self.facts.as_ref())
+            .map_or(&[], |facts| facts.covered_text_pages.as_slice())
+    }
+
+    /// Bookmarks of the result, or of the source before it is unlocked.
+    #[cfg(feature = "gui")]
+    pub fn outline(&self) -> &[Bookmark] {
+        self.output_facts
+            .as_ref()
+            .or(self.facts.as_ref())
+            .map_or(&[], |facts| facts.outline.as_slice())
+    }
+
+    /// Digital signatures of the source, which processing it invalidates.
+    pub fn signatures(&self) -> &[Signature] {
+        self.facts.as_ref().map_or(&[], |facts| facts.signatures.as_slice())
+    }
+
+    /// Metadata the result no longer carries, see [`PdfFacts::removed_metadata`].
+    pub fn removed_metadata(&self) -> Vec<String> {
+        match (&self.facts, &self.output_facts) {
+            (Some(source), Some(output)) => output.removed_metadata(source),
+            _ => Vec::new(),
+        }
+    }
+}
+
+/// Folder next to the results that [`Originals::Move`] puts the sources into.
+const ORIGINALS_DIR: &str = "originals";
+/// Name of a batch's own subfolder, in SQLite's `strftime` format; no colons, which
+/// Windows and macOS do not allow in names.
+#[cfg(feature = "history")]
+const BATCH_FOLDER_FORMAT: &str = "CrackLeaf %Y-%m-%d %H-%M";
+
+/// Analysed files are handed to the UI in chunks of this size...
+const ANALYSIS_CHUNK: usize = 64;
+/// ...or at least this often, so a large drop does not redraw the list per file.
+const ANALYSIS_FLUSH_INTERVAL: Duration = Duration::from_millis(200);
+/// Analysing a file on a network share for longer than this marks the share as slow.
+const SLOW_VOLUME_THRESHOLD: Duration = Duration::from_secs(5);
+
+/// Encryption probe and content hash of one listed file.
+struct Analysis {
+    path: PathBuf,
+    encrypted: Option<bool>,
+    needs_password: bool,
+    encryption: Option<String>,
+    #[cfg(feature = "history")]
+    hash: Option<String>,
+    slow_volume: bool,
+    facts: Option<PdfFacts>,
+    /// The file was a cloud placeholder that could not be downloaded.
+    cloud_unavailable: bool,
+}
+
+/// Sent from the analysis to the UI, tagged with the list generation.
+enum AnalysisUpdate {
+    /// A cloud placeholder is being downloaded before it can be checked.
+    Downloading(PathBuf),
+    Done(Vec<Analysis>),
+}
+
+/// Called from worker threads whenever there is something new to show.
+type Waker = Arc<dyn Fn() + Send + Sync>;
+
+/// Outcome of a finished batch.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BatchSummary {
+    pub succeeded: usize,
+    /// Files that were processed; skipped ones are not counted.
+    pub total: usize,
+    /// Files left out because there was nothing to do for them.
+    pub skipped: usize,
+}
+
+impl BatchSummary {
+    /// Nothing in the batch succeeded.
+    #[cfg(feature = "gui")]
+    pub fn is_failure(&self) -> bool {
+        self.total > 0 && self.succeeded == 0
+    }
+
+    pub fn message(&self) -> String {
+        if self.total == 0 && self.skipped > 0 {
+            return t!("result-nothing-to-unlock");
+        }
+        let mut text = if self.succeeded == self.total && self.total > 0 {
+            t!("result-success")
+        } else if self.succeeded > 0 {
+            t!("result-partial", success = self.succeeded, total = self.total)
+        } else {
+            t!("result-failure")
+        };
+        if self.skipped > 0 {
+            text.push_str(&t!("result-skipped", count = self.skipped));
+        }
+        text
+    }
+}
+
+/// The listed files a batch started now would take, by what the encryption check
+/// found; see [`AppCore::preflight`].
+#[cfg(feature = "gui")]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Preflight {
+    /// Encrypted files that open without a password.
+    pub encrypted: usize,
+    /// Files that take a user password to open.
+    pub needs_password: usize,
+    /// Files with no restrictions to remove.
+    pub unrestricted: usize,
+    /// Files whose check was inconclusive.
+    pub unknown: usize,
+}
+
+/// What [`AppCore::add_files`] did with the paths it was given.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct AddSummary {
+    pub added: usize,
+    /// Files that do not start with a PDF header.
+    pub not_pdf: usize,
+    /// Files already listed, or given more than once.
+    pub duplicates: usize,
+    /// Folders without any PDF inside.
+    pub empty_folders: usize,
+    /// Paths that do not exist (any more).
+    pub missing: usize,
+    /// ZIP archives that could not be read or hold no PDF.
+    pub archives: usize,
+    /// PDFs without a `.pdf` extension that were declined.
+    pub mislabeled: usize,
+    /// Files left out by [`DropFilter::Encrypted`] as not encrypted.
+    pub unencrypted: usize,
+}
+
+impl AddSummary {
+    pub fn skipped(&self) -> usize {
+        self.not_pdf + self.duplicates + self.empty_folders + self.missing + self.archives + self.mislabeled + self.unencrypted
+    }
+
+    /// What was skipped and why, e.g. "跳过 3 个非 PDF 文件，1 个重复"; `None` when nothing was.
+    #[cfg(feature = "gui")]
+    pub fn skipped_message(&self) -> Option<String> {
+        let parts: Vec<String> = [
+            ("skipped-not-pdf", self.not_pdf),
+            ("skipped-duplicate", self.duplicates),
+            ("skipped-empty-folder", self.empty_folders),
+            ("skipped-missing", self.missing),
+            ("skipped-archive", self.archives),
+            ("skipped-mislabeled", self.mislabeled),
+            ("skipped-unencrypted", self.unencrypted),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(id, count)| t!(id, count = count))
+        .collect();
+        if parts.is_empty() {
+            return None;
+        }
+        Some(t!("skipped-summary", items = parts.join(&t!("list-separator"))))
+    }
+}
+
+/// Everything the window does apart from drawing: the file list, the running batch,
+/// undo and history. Frontends feed it paths, let it consume engine events and render its state.
+pub struct AppCore {
+    pub config: Config,
+    #[cfg(feature = "gui")]
+    pub step_registry: StepRegistry,
+    /// Shared by every batch; other consumers can subscribe to it too.
+    events: EventBus,
+    subscription: Subscription,
+    entries: Vec<FileEntry>,
+    queue: Option<JobQueue>,
+    batch: Option<UnlockHandle>,
+    /// Entry index of each file in the running batch.
+    batch_entries: Vec<usize>,
+    /// Undo journal of the current or last batch.
+    journal: Option<Arc<Journal>>,
+    #[cfg(feature = "history")]
+    history: Option<History>,
+    analysis_tx: mpsc::Sender<(u64, AnalysisUpdate)>,
+    analysis_rx: mpsc::Receiver<(u64, AnalysisUpdate)>,
+    /// Bumped by [`clear`](Self::clear) so results for a discarded list are ignored.
+    analysis_generation: u64,
+    /// Listed files whose analysis has not arrived yet.
+    analysis_pending: usize,
+    /// Files dropped by [`DropFilter::Encrypted`] once their analysis arrived, not yet reported.
+    #[cfg(feature = "gui")]
+    unencrypted: usize,
+    waker: Option<Waker>,
+    /// Unpacked archives, kept until the list is cleared.
+    archives: Vec<Extracted>,
+    /// Archives waiting for a password, oldest first.
+    pending_archives: Vec<PathBuf>,
+    /// Files downloaded from links, kept until the list is cleared.
+    #[cfg(feature = "network")]
+    downloads: Vec<Downloaded>,
+    /// Output subfolder of each file unpacked from an archive or found in a dropped
+    /// folder: the archive's or folder's name followed by the folders inside it.
+    output_subdirs: HashMap<PathBuf, PathBuf>,
+    /// Settings of the running batch, stored with each history entry.
+    #[cfg(feature = "history")]
+    history_options: String,
+    /// A step of the running batch changes the page count on purpose.
+    rearranges_pages: bool,
+    /// The encryption of the last batch, when it was started by [`protect`](Self::protect).
+    protection: Option<Arc<dyn ProcessingStep>>,
+    /// Batches are recorded so they can be resumed after a crash. There is a single
+    /// session file, so only one window may do so.
+    pub records_session: bool,
+    /// Status line under the file list.
+    pub result_text: String,
+    pub qpdf_ok: bool,
+    pub qpdf_error: Option<String>,
+    #[cfg(feature = "gui")]
+    pub qpdf_warning: Option<String>,
+}
+
+impl AppCore {
+    pub fn new(config: Config, qpdf_status: QpdfStatus) -> Self {
+        #[cfg(feature = "gui")]
+        let step_registry = config.step_registry();
+        let events = EventBus::new();
+        let subscription = events.subscribe();
+        let (analysis_tx, analysis_rx) = mpsc::channel();
+        set_process_limit(config.unlock_options().concurrency);
+        if let Some(path) = detection_cache() {
+            load_detection_cache(&path);
+        }
+        Self {
+            config,
+            #[cfg(feature = "gui")]
+            step_registry,
+            events,
+            subscription,
+            entries: Vec::new(),
+            queue: None,
+            batch: None,
+            batch_entries: Vec::new(),
+            journal: None,
+            #[cfg(feature = "history")]
+            history: History::open(),
+            analysis_tx,
+            analysis_rx,
+            analysis_generation: 0,
+            analysis_pending: 0,
+            #[cfg(feature = "gui")]
+            unencrypted: 0,
+            waker: None,
+            archives: Vec::new(),
+            pending_archives: Vec::new(),
+            #[cfg(feature = "network")]
+            downloads: Vec::new(),
+            output_subdirs: HashMap::new(),
+            #[cfg(feature = "history")]
+            history_options: String::new(),
+            rearranges_pages: false,
+            protection: None,
+            records_session: true,
+            result_text: String::new(),
+            qpdf_ok: qpdf_status.ok,
+            qpdf_error: qpdf_status.error,
+            #[cfg(feature = "gui")]
+            qpdf_warning: qpdf_status.warning,
+        }
+    }
+
+    pub fn entries(&self) -> &[FileEntry] {
+        &self.entries
+    }
+
+    /// Jobs of the current or last batch, indexed like [`entries`](Self::entries).
+    /// Entries appended after that batch have no job until the next one starts.
+    pub fn queue(&self) -> Option<&JobQueue> {
+        self.queue.as_ref()
+    }
+
+    /// Registers a callback run from background threads whenever [`poll`](Self::poll)
+    /// has something new, e.g. `move || ctx.request_repaint()`.
+    #[cfg(feature = "gui")]
+    pub fn set_waker<F>(&mut self, waker: F)
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let waker: Waker = Arc::new(waker);
+        let on_event = waker.clone();
+        self.events.listen(move |_| on_event());
+        self.waker = Some(waker);
+    }
+
+    pub fn batch(&self) -> Option<&UnlockHandle> {
+        self.batch.as_ref()
+    }
+
+    /// Entry (and job) index of the file at `index` in the running batch, which may
+    /// hold only some of the listed files.
+    pub fn entry_index(&self, index: usize) -> Option<usize> {
+        self.batch_entries.get(index).copied()
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn history(&self) -> Option<&History> {
+        self.history.as_ref()
+    }
+
+    /// A batch has been started and has not reported [`Event::BatchFinished`] yet.
+    pub fn is_running(&self) -> bool {
+        self.batch.is_some()
+    }
+
+    #[cfg(feature = "gui")]
+    pub fn can_undo(&self) -> bool {
+        self.journal.is_some()
+    }
+
+    /// Adds the PDFs among `paths`, and those found in folders among them, that are not
+    /// listed yet. Adding to a finished batch starts a new list unless
+    /// `ui.append_after_unlock` is set. Returns what was added and what was skipped.
+    ///
+    /// PDFs are recognised by content; those without a `.pdf` extension are only added
+    /// if `confirm_mislabeled` agrees to take them, or without asking with
+    /// [`DropFilter::Sniffed`]. The PDFs inside ZIP archives are unpacked and listed
+    /// too; encrypted archives wait in [`pending_archive`](Self::pending_archive) for a
+    /// password.
+    ///
+    /// The files are listed right away; encryption and history checks run in the
+    /// background and arrive through [`poll`](Self::poll). With [`DropFilter::Encrypted`]
+    /// the files found not to be encrypted are taken off the list then, see
+    /// [`take_unencrypted`](Self::take_unencrypted).
+    pub fn add_files(
+        &mut self,
+        paths: Vec<PathBuf>,
+        confirm_mislabeled: impl FnOnce(&[PathBuf]) -> bool,
+    ) -> AddSummary {
+        self.replace_finished_list();
+        let mut summary = AddSummary::default();
+        let mut listed: HashSet<OsString> = self.entries.iter().map(|entry| dedupe_key(&entry.path)).collect();
+        let mut added = Vec::new();
+        let mut archives = Vec::new();
+        let mut subdirs = HashMap::new();
+        for path in expand_folders(paths, &mut summary, &mut archives, &mut subdirs) {
+            if !listed.insert(dedupe_key(&path)) {
+                summary.duplicates += 1;
+                continue;
+            }
+            if let Some(subdir) = subdirs.remove(&path) {
+                self.output_subdirs.insert(path.clone(), subdir);
+            }
+            added.push(path);
+        }
+        let mislabeled: Vec<PathBuf> = added.iter().filter(|path| is_mislabeled(path)).cloned().collect();
+        let sniffed = self.config.ui.drop_filter == DropFilter::Sniffed;
+        if !mislabeled.is_empty() && !sniffed && !confirm_mislabeled(&mislabeled) {
+            info!(files = mislabeled.len(), "files without a .pdf extension declined");
+            summary.mislabeled += mislabeled.len();
+            added.retain(|path| !is_mislabeled(path));
+        }
+        self.list(added, None, &mut summary);
+        for archive in archives {
+            let listed = self.pending_archives.contains(&archive)
+                || self.entries.iter().any(|entry| entry.archive.as_ref() == Some(&archive));
+            if listed {
+                summary.duplicates += 1;
+            } else {
+                self.add_archive_or_wait(archive, &mut summary);
+            }
+        }
+        if summary.skipped() > 0 {
+            info!(?summary, "some paths skipped");
+        }
+        summary
+    }
+
+    /// Lists a file downloaded from a link, or the PDFs in it when it is a ZIP archive,
+    /// like [`add_files`](Self::add_files) does for local files.
+    #[cfg(feature = "network")]
+    pub fn add_download(&mut self, downloaded: Downloaded) -> AddSummary {
+        self.replace_finished_list();
+        let mut summary = AddSummary::default();
+        let path = downloaded.path.clone();
+        if self.entries.iter().any(|entry| entry.url.as_ref() == Some(&downloaded.url)) {
+            summary.duplicates += 1;
+            return summary;
+        }
+        let listed = self.entries.len();
+        if archive::is_zip(&path) {
+            self.add_archive_or_wait(path, &mut summary);
+        } else if is_pdf(&path) || is_office_document(&path) {
+            self.list(vec![path], None, &mut summary);
+        } else {
+            summary.not_pdf += 1;
+            return summary;
+        }
+        for entry in &mut self.entries[listed..] {
+            entry.url = Some(downloaded.url.clone());
+        }
+        self.downloads.push(downloaded);
+        summary
+    }
+
+    /// Starts a new list when files are added to a finished batch, unless
+    /// `ui.append_after_unlock` is set.
+    fn replace_finished_list(&mut self) {
+        let finished = self.queue.as_ref().is_some_and(|queue| queue.finished_count() > 0);
+        if finished && !self.config.ui.append_after_unlock {
+            self.clear();
+        }
+    }
+
+    /// Encrypted archive waiting for its password, see [`unlock_archive`](Self::unlock_archive).
+    pub fn pending_archive(&self) -> Option<&Path> {
+        self.pending_archives.first().map(PathBuf::as_path)
+    }
+
+    /// Lists the PDFs in the [`pending_archive`](Self::pending_archive), decrypted with
+    /// `password`. After a wrong password the archive stays pending to try again.
+    pub fn unlock_archive(&mut self, password: &str) -> Result<AddSummary, ArchiveError> {
+        let mut summary = AddSummary::default();
+        let Some(archive) = self.pending_archives.first().cloned() else {
+            return Ok(summary);
+        };
+        self.add_archive(&archive, Some(password), &mut summary)?;
+        self.pending_archives.remove(0);
+        #[cfg(feature = "passwords")]
+        if self.config.ui.remember_passwords {
+            passwords::remember(password, &archive.file_name().unwrap_or_default().to_string_lossy());
+        }
+        Ok(summary)
+    }
+
+    /// Passwords to try before asking, when `ui.remember_passwords` is on.
+    pub fn remembered_passwords(&self) -> Vec<String> {
+        #[cfg(feature = "passwords")]
+        if self.config.ui.remember_passwords {
+            return passwords::load();
+        }
+        Vec::new()
+    }
+
+    /// Gives up on the [`pending_archive`](Self::pending_archive).
+    pub fn skip_archive(&mut self) {
+        if !self.pending_archives.is_empty() {
+            let archive = self.pending_archives.remove(0);
+            info!(archive = %archive.display(), "archive skipped");
+        }
+    }
+
+    /// Lists the PDFs in `archive`, trying the remembered passwords when it is
+    /// encrypted; if none fits it waits in [`pending_archive`](Self::pending_archive).
+    fn add_archive_or_wait(&mut self, archive: PathBuf, summary: &mut AddSummary) {
+        if self.add_archive(&archive, None, summary).is_ok() {
+            return;
+        }
+        for password in self.remembered_passwords() {
+            if self.add_archive(&archive, Some(&password), summary).is_ok() {
+                info!(archive = %archive.display(), "archive opened with a remembered password");
+                return;
+            }
+        }
+        info!(archive = %archive.display(), "archive needs a password");
+        self.pending_archives.push(archive);
+    }
+
+    /// Unpacks the PDFs in `archive` and lists them. Fails only when the archive needs
+    /// a password and `password` is missing or wrong; unreadable archives are counted
+    /// in `summary`.
+    fn add_archive(
+        &mut self,
+        archive: &Path,
+        password: Option<&str>,
+        summary: &mut AddSummary,
+    ) -> Result<(), ArchiveError> {
+        let extracted = match archive::extract(archive, password) {
+            Ok(extracted) => extracted,
+            Err(ArchiveError::Other(err)) => {
+                warn!("Failed to unpack {:?}: {err}", archive);
+                summary.archives += 1;
+                return Ok(());
+            }
+            Err(err) => return Err(err),
+        };
+        if extracted.files.is_empty() {
+            summary.archives += 1;
+            return Ok(());
+        }
+        self.output_subdirs.extend(extracted.files.iter().cloned());
+        let files = extracted.files.iter().map(|(path, _)| path.clone()).collect();
+        self.archives.push(extracted);
+        self.list(files, Some(archive), summary);
+        Ok(())
+    }
+
+    /// Appends `paths` to the list and starts checking them.
+    fn list(&mut self, added: Vec<PathBuf>, archive: Option<&Path>, summary: &mut AddSummary) {
+        if added.is_empty() {
+            return;
+        }
+        summary.added += added.len();
+        self.entries.extend(added.iter().map(|path| FileEntry {
+            path: path.clone(),
+            icon: Icon::Waiting,
+            status: t!("file-analyzing"),
+            output_path: None,
+            needs_password: false,
+            #[cfg(feature = "history")]
+            hash: None,
+            #[cfg(feature = "history")]
+            previous: None,
+            encrypted: None,
+            encryption: None,
+            slow_volume: false,
+            facts: None,
+            output_facts: None,
+            archive: archive.map(Path::to_path_buf),
+            url: None,
+            extracted_pages: None,
+            rearranged: false,
+            allow_large: false,
+        }));
+        self.result_text.clear();
+        info!(added = added.len(), total = self.entries.len(), "files added");
+        self.analysis_pending += added.len();
+        self.spawn_analysis(added);
+    }
+
+    /// Probes and hashes `paths` on the shared runtime, several at a time.
+    fn spawn_analysis(&self, paths: Vec<PathBuf>) {
+        let tx = self.analysis_tx.clone();
+        let generation = self.analysis_generation;
+        let timeout = self.config.unlock_options().timeout;
+        let waker = self.waker.clone();
+        let started = Instant::now();
+        let total = paths.len();
+        runtime().spawn(async move {
+            let permits = Arc::new(Semaphore::new(default_concurrency() * 2));
+            let mut jobs = JoinSet::new();
+            for path in paths {
+                let permits = permits.clone();
+                let (tx, waker) = (tx.clone(), waker.clone());
+                jobs.spawn(async move {
+                    let _permit = permits.acquire_owned().await;
+                    let started = Instant::now();
+                    let probe = path.clone();
+                    let placeholder = with_io_timeout(Some(IO_TIMEOUT), move || Ok(is_cloud_placeholder(&probe)))
+                        .await
+                        .unwrap_or(false);
+                    let mut cloud_unavailable = false;
+                    if placeholder {
+                        info!(path = %path.display(), "downloading cloud placeholder");
+                        let _ = tx.send((generation, AnalysisUpdate::Downloading(path.clone())));
+                        if let Some(waker) = &waker {
+                            waker();
+                        }
+                        if let Err(err) = materialize(&path, timeout).await {
+                            warn!("Failed to download cloud file {:?}: {err}", path);
+                            cloud_unavailable = true;
+                        }
+                    }
+                    let office = is_office_document(&path);
+                    let encrypted = is_restricted(&path, timeout).await;
+                    let needs_password = !office
+                        && encrypted == Some(true)
+                        && requires_password(&path, timeout).await == Some(true);
+                    let encryption = match encrypted {
+                        Some(true) if !office => encryption_method(&path, timeout).await,
+                        _ => None,
+                    };
+                    let facts = if needs_password || office {
+                        None
+                    } else {
+                        inspect_pdf(&path, timeout).await
+                    };
+                    #[cfg(feature = "history")]
+                    let hash_path = path.clone();
+                    #[cfg(feature = "history")]
+                    let hash = match with_io_timeout(timeout, move || history::hash_file(&hash_path)).await {
+                        Ok(hash) => Some(hash),
+                        Err(err) => {
+                            warn!("Failed to hash {:?}: {err}", path);
+                            None
+                        }
+                    };
+                    let slow_volume = started.elapsed() >= SLOW_VOLUME_THRESHOLD && {
+                        let network_path = path.clone();
+                        with_io_timeout(Some(IO_TIMEOUT), move || Ok(is_network_path(&network_path)))
+                            .await
+                            .unwrap_or(true)
+                    };
+                    if slow_volume {
+                        warn!(path = %path.display(), elapsed = ?started.elapsed(), "slow network volume");
+                    }
+                    Analysis {
+                        path,
+                        encrypted,
+                        needs_password,
+                        encryption,
+                        #[cfg(feature = "history")]
+                        hash,
+                        slow_volume,
+                        facts,
+                        cloud_unavailable,
+                    }
+                });
+            }
+
+            let mut ready = Vec::new();
+            let mut last_flush = Instant::now();
+            while let Some(result) = jobs.join_next().await {
+                match result {
+                    Ok(analysis) => ready.push(analysis),
+                    Err(err) => warn!("File analysis failed: {err}"),
+                }
+                let done = jobs.is_empty();
+                if done || ready.len() >= ANALYSIS_CHUNK || last_flush.elapsed() >= ANALYSIS_FLUSH_INTERVAL {
+                    if tx.send((generation, AnalysisUpdate::Done(std::mem::take(&mut ready)))).is_err() {
+                        return;
+                    }
+                    if let Some(waker) = &waker {
+                        waker();
+                    }
+                    last_flush = Instant::now();
+                }
+            }
+            debug!(files = total, elapsed = ?started.elapsed(), "analysis finished");
+            save_detection_cache();
+        });
+    }
+
+    /// Some listed files have not been analysed yet.
+    pub fn is_analyzing(&self) -> bool {
+        self.analysis_pending > 0
+    }
+
+    /// Number of listed files whose analysis is still running.
+    #[cfg(feature = "gui")]
+    pub fn analysis_pending(&self) -> usize {
+        self.analysis_pending
+    }
+
+    /// Blocks until every listed file has been analysed.
+    pub fn wait_for_analysis(&mut self) {
+        while self.is_analyzing() {
+            match self.analysis_rx.recv() {
+                Ok((generation, update)) => self.apply_analysis(generation, update),
+                Err(_) => break,
+            }
+        }
+    }
+
+    fn apply_analysis(&mut self, generation: u64, update: AnalysisUpdate) {
+        if generation != self.analysis_generation {
+            return;
+        }
+        let chunk = match update {
+            AnalysisUpdate::Downloading(path) => {
+                if let Some(entry) = self.entries.iter_mut().find(|entry| entry.path == path) {
+                    entry.icon = Icon::Cloud;
+                    entry.status = t!("file-cloud-downloading");
+                }
+                return;
+            }
+            AnalysisUpdate::Done(chunk) => chunk,
+        };
+        self.analysis_pending = self.analysis_pending.saturating_sub(chunk.len());
+        // Only files known to be unencrypted go; those the check is unsure about stay.
+        let only_encrypted = self.config.ui.drop_filter == DropFilter::Encrypted && self.queue.is_none();
+        for analysis in chunk {
+            let Some(position) = self.entries.iter().position(|entry| entry.path == analysis.path) else {
+                continue;
+            };
+            if only_encrypted && analysis.encrypted == Some(false) && !analysis.cloud_unavailable {
+                info!(path = %analysis.path.display(), "unencrypted file filtered out");
+                self.entries.remove(position);
+                #[cfg(feature = "gui")]
+                {
+                    self.unencrypted += 1;
+                }
+                continue;
+            }
+            let entry = &mut self.entries[position];
+            let (icon, status) = match analysis.encrypted {
+                _ if analysis.cloud_unavailable => (Icon::Cloud, t!("file-cloud-unavailable")),
+                Some(true) if analysis.needs_password => (Icon::Password, t!("status-needs-password")),
+                Some(true) if is_office_document(&analysis.path) => (Icon::Locked, t!("file-protected")),
+                Some(true) => (Icon::Locked, t!("file-encrypted")),
+                Some(false) => (Icon::Unlocked, t!("file-unrestricted")),
+                None => (Icon::Locked, t!("file-unknown")),
+            };
+            entry.icon = icon;
+            entry.status = status;
+            entry.needs_password = analysis.needs_password;
+            entry.encrypted = analysis.encrypted;
+            entry.encryption = analysis.encryption;
+            entry.slow_volume = analysis.slow_volume;
+            entry.facts = analysis.facts;
+            #[cfg(feature = "history")]
+            {
+                entry.previous = self
+                    .history
+                    .as_ref()
+                    .zip(analysis.hash.as_deref())
+                    .and_then(|(history, hash)| history.find_unlocked(hash));
+                if let Some(record) = &entry.previous {
+                    info!(path = %entry.path.display(), output = ?record.output, "already unlocked before");
+                    entry.status = t!("file-already-unlocked", date = i18n::format_date(&record.finished));
+                    entry.output_path = record.output.clone();
+                }
+                entry.hash = analysis.hash;
+            }
+        }
+    }
+
+    /// Once every listed file has been analysed, what [`DropFilter::Encrypted`] took off
+    /// the list since the last call; `None` while analysing or when nothing was.
+    #[cfg(feature = "gui")]
+    pub fn take_unencrypted(&mut self) -> Option<AddSummary> {
+        if self.is_analyzing() || self.unencrypted == 0 {
+            return None;
+        }
+        let unencrypted = std::mem::take(&mut self.unencrypted);
+        Some(AddSummary { unencrypted, ..AddSummary::default() })
+    }
+
+    /// Keeps only the entries for which `keep` returns `true`. Ignored while a batch exists.
+    pub fn retain(&mut self, keep: impl FnMut(&FileEntry) -> bool) {
+        if self.queue.is_none() {
+            self.entries.retain(keep);
+        }
+    }
+
+    /// Forgets the file list and the last batch.
+    pub fn clear(&mut self) {
+        self.entries.clear();
+        self.analysis_generation += 1;
+        self.analysis_pending = 0;
+        #[cfg(feature = "gui")]
+        {
+            self.unencrypted = 0;
+        }
+        self.result_text.clear();
+        self.queue = None;
+        self.batch = None;
+        self.journal = None;
+        self.archives.clear();
+        self.pending_archives.clear();
+        #[cfg(feature = "network")]
+        self.downloads.clear();
+        self.output_subdirs.clear();
+    }
+
+    /// Unlocks every listed file that has not been unlocked yet with `options`, publishing
+    /// on [`events`](Self::events). Unencrypted files are marked skipped instead when
+    /// running them would do nothing, see [`UnlockOptions::skips_unencrypted`].
+    /// Returns `false` if a batch is running, files are still being analysed or there is
+    /// nothing to do.
+    pub fn start(&mut self, options: UnlockOptions) -> bool {
+        if self.is_running() {
+            return false;
+        }
+        self.protection = None;
+        let skips_unencrypted = options.skips_unencrypted();
+        let blocked = self.block_oversized();
+        self.start_leaving_out(options, |entry| {
+            (skips_unencrypted && entry.encrypted == Some(false)) || blocked.contains(&entry.path)
+        })
+    }
+
+    /// Encrypts every listed PDF that has not been processed yet with `step`, the
+    /// reverse of [`start`](Self::start). Results are named with `_protected` instead
+    /// of `_unlocked`; Office documents are marked skipped. Retries of the batch
+    /// encrypt too.
+    pub fn protect(&mut self, step: Encrypt, options: UnlockOptions) -> bool {
+        if self.is_running() {
+            return false;
+        }
+        let step: Arc<dyn ProcessingStep> = Arc::new(step);
+        self.protection = Some(step.clone());
+        let blocked = self.block_oversized();
+        self.start_leaving_out(protect_options(step, options), |entry| {
+            is_office_document(&entry.path) || blocked.contains(&entry.path)
+        })
+    }
+
+    /// Counts the listed files not processed yet by what the encryption check found,
+    /// for the summary shown before a batch starts.
+    #[cfg(feature = "gui")]
+    pub fn preflight(&self) -> Preflight {
+        let mut preflight = Preflight::default();
+        for (index, entry) in self.entries.iter().enumerate() {
+            let job = self.queue.as_ref().and_then(|queue| queue.job(index));
+            if job.is_some_and(|job| job.state == JobState::Done) {
+                continue;
+            }
+            match entry.encrypted {
+                _ if entry.needs_password => preflight.needs_password += 1,
+                Some(true) => preflight.encrypted += 1,
+                Some(false) => preflight.unrestricted += 1,
+                None => preflight.unknown += 1,
+            }
+        }
+        preflight
+    }
+
+    /// Listed files not processed yet that are larger than the active profile's
+    /// `max_file_size_mb` and not allowed one by one, with their sizes.
+    pub fn oversized(&self) -> Vec<(usize, u64)> {
+        let Some(limit) = self.config.active().size_limit() else {
+            return Vec::new();
+        };
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.allow_large)
+            .filter(|&(index, _)| {
+                let job = self.queue.as_ref().and_then(|queue| queue.job(index));
+                !job.is_some_and(|job| job.state == JobState::Done)
+            })
+            .filter_map(|(index, entry)| Some((index, entry.size().filter(|&size| size > limit)?)))
+            .collect()
+    }
+
+    /// Lets the entry at `index` through the size limit, or not.
+    #[cfg(feature = "gui")]
+    pub fn set_allow_large(&mut self, index: usize, allow: bool) {
+        if let Some(entry) = self.entries.get_mut(index) {
+            entry.allow_large = allow;
+        }
+    }
+
+    /// Rough time processing `bytes` takes, at the speed of earlier files; `None`
+    /// while the history has none to go by.
+    pub fn estimate(&self, bytes: u64) -> Option<Duration> {
+        #[cfg(feature = "history")]
+        if let Some(throughput) = self.history.as_ref().and_then(History::throughput) {
+            return Some(Duration::from_secs_f64(bytes as f64 / throughput));
+        }
+        let _ = bytes;
+        None
+    }
+
+    /// With [`LargeFiles::Block`], marks the [`oversized`](Self::oversized) entries and
+    /// returns their paths for the batch to leave out.
+    fn block_oversized(&mut self) -> HashSet<PathBuf> {
+        if self.config.active().large_files != LargeFiles::Block {
+            return HashSet::new();
+        }
+        let mut blocked = HashSet::new();
+        for (index, size) in self.oversized() {
+            let entry = &mut self.entries[index];
+            entry.status = t!("status-too-large", size = i18n::format_size(size));
+            blocked.insert(entry.path.clone());
+        }
+        blocked
+    }
+
+    fn start_leaving_out(&mut self, options: UnlockOptions, leave_out: impl Fn(&FileEntry) -> bool) -> bool {
+        if self.is_running() || self.is_analyzing() {
+            return false;
+        }
+        let pending: Vec<usize> = match self.queue.as_mut() {
+            Some(queue) if queue.len() <= self.entries.len() => {
+                // Files appended since the last batch.
+                for entry in &self.entries[queue.len()..] {
+                    queue.push(entry.path.clone());
+                }
+                let pending: Vec<usize> = (0..queue.len())
+                    .filter(|&index| queue.job(index).is_some_and(|job| job.state != JobState::Done))
+                    .collect();
+                for &index in &pending {
+                    queue.requeue(index);
+                }
+                pending
+            }
+            _ => {
+                self.queue = Some(JobQueue::new(self.entries.iter().map(|entry| entry.path.clone())));
+                (0..self.entries.len()).collect()
+            }
+        };
+        let (pending, skipped): (Vec<usize>, Vec<usize>) = pending
+            .into_iter()
+            .partition(|&index| !leave_out(&self.entries[index]));
+        if !skipped.is_empty() {
+            info!(files = skipped.len(), "files left out");
+            if let Some(queue) = self.queue.as_mut() {
+                for &index in &skipped {
+                    queue.skip(index);
+                }
+            }
+        }
+        if pending.is_empty() {
+            if let Some(summary) = self.summary().filter(|_| !skipped.is_empty()) {
+                self.result_text = summary.message();
+            }
+            return false;
+        }
+        self.launch(pending, options);
+        true
+    }
+
+    /// Entries with digital signatures that the next batch would rewrite, and so
+    /// invalidate. Those already done keep theirs, and so do unencrypted ones when the
+    /// batch leaves them out (see [`UnlockOptions::skips_unencrypted`]).
+    pub fn signatures_at_risk(&self, skips_unencrypted: bool) -> Vec<usize> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !entry.signatures().is_empty())
+            .filter(|(_, entry)| !(skips_unencrypted && entry.encrypted == Some(false)))
+            .filter(|&(index, _)| {
+                let job = self.queue.as_ref().and_then(|queue| queue.job(index));
+                !job.is_some_and(|job| job.state == JobState::Done)
+            })
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The last batch, once finished, wrote at least one file.
+    #[cfg(feature = "gui")]
+    pub fn has_results(&self) -> bool {
+        !self.is_running()
+            && self
+                .queue
+                .as_ref()
+                .is_some_and(|queue| queue.jobs().iter().any(|job| job.output_path.is_some()))
+    }
+
+    /// Files the last batch wrote that still exist, in list order.
+    pub fn results(&self) -> Vec<PathBuf> {
+        if self.is_running() {
+            return Vec::new();
+        }
+        self.queue
+            .iter()
+            .flat_map(JobQueue::jobs)
+            .filter_map(|job| job.output_path.clone())
+            .filter(|path| path.exists())
+            .collect()
+    }
+
+    /// Files of the last batch that failed because the output folder could not be written.
+    pub fn unwritable(&self) -> Vec<usize> {
+        self.failed_with(|error| matches!(error, Error::OutputNotWritable(_)))
+    }
+
+    /// Runs the [`unwritable`](Self::unwritable) files again with `options`, typically
+    /// pointing `output_dir` somewhere else. The other results are kept.
+    #[cfg(feature = "gui")]
+    pub fn retry_unwritable(&mut self, options: UnlockOptions) -> bool {
+        info!(output_dir = ?options.output_dir, "retrying in another folder");
+        let options = self.same_work(options);
+        self.retry(self.unwritable(), options)
+    }
+
+    /// Files of the last batch that another program held open.
+    pub fn locked(&self) -> Vec<usize> {
+        self.failed_with(|error| matches!(error, Error::SourceLocked(_)))
+    }
+
+    /// Runs the [`locked`](Self::locked) files again, e.g. once the other program has
+    /// closed them. The other results are kept.
+    #[cfg(feature = "gui")]
+    pub fn retry_locked(&mut self, options: UnlockOptions) -> bool {
+        let options = self.same_work(options);
+        self.retry(self.locked(), options)
+    }
+
+    /// `options` encrypting again when the last batch was a [`protect`](Self::protect) one.
+    #[cfg(feature = "gui")]
+    fn same_work(&self, options: UnlockOptions) -> UnlockOptions {
+        match &self.protection {
+            Some(step) => protect_options(step.clone(), options),
+            None => options,
+        }
+    }
+
+    fn failed_with(&self, matches: impl Fn(&Error) -> bool) -> Vec<usize> {
+        let Some(queue) = self.queue.as_ref().filter(|_| !self.is_running()) else {
+            return Vec::new();
+        };
+        queue
+            .jobs()
+            .iter()
+            .enumerate()
+            .filter(|(_, job)| job.error.as_ref().is_some_and(&matches))
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    #[cfg(feature = "gui")]
+    fn retry(&mut self, indices: Vec<usize>, options: UnlockOptions) -> bool {
+        let Some(queue) = self.queue.as_mut().filter(|_| !indices.is_empty()) else {
+            return false;
+        };
+        for &index in &indices {
+            queue.requeue(index);
+        }
+        info!(files = indices.len(), "retrying");
+        self.launch(indices, options);
+        true
+    }
+
+    /// The PDF at `index` went through the last batch, which has finished, so
+    /// [`extract_pages`](Self::extract_pages) or [`apply_letterhead`](Self::apply_letterhead)
+    /// can run on it.
+    #[cfg(feature = "gui")]
+    pub fn can_rework(&self, index: usize) -> bool {
+        !self.is_running()
+            && self.entries.get(index).is_some_and(|entry| !is_office_document(&entry.path))
+            && self
+                .queue
+                .as_ref()
+                .and_then(|queue| queue.job(index))
+                .is_some_and(|job| job.state.is_terminal())
+    }
+
+    /// Writes a new PDF with only `ranges` of the file at `index`, in qpdf's syntax (see
+    /// [`parse_page_ranges`](crackleaf_core::steps::parse_page_ranges)); `typed` is what
+    /// the user entered. Runs as a one-file batch, so the result is named, recorded and
+    /// reported like any other and replaces the entry's output.
+    #[cfg(feature = "gui")]
+    pub fn extract_pages(&mut self, index: usize, ranges: String, typed: &str, mut options: UnlockOptions) -> bool {
+        if !self.can_rework(index) {
+            return false;
+        }
+        options.pipeline = vec![Arc::new(Decrypt), Arc::new(ExtractPages::new(ranges))];
+        self.protection = None;
+        info!(index, pages = typed, "extracting pages");
+        if !self.retry(vec![index], options) {
+            return false;
+        }
+        self.entries[index].extracted_pages = Some(typed.to_string());
+        true
+    }
+
+    /// Puts `file` behind or over the pages of the file at `index`, as the configured
+    /// letterhead mode says. Runs as a one-file batch like
+    /// [`extract_pages`](Self::extract_pages).
+    #[cfg(feature = "gui")]
+    pub fn apply_letterhead(&mut self, index: usize, file: PathBuf, mut options: UnlockOptions) -> bool {
+        if !self.can_rework(index) {
+            return false;
+        }
+        info!(index, letterhead = %file.display(), "applying letterhead");
+        let step = Letterhead::new(Some(file), self.config.letterhead.mode);
+        options.pipeline = vec![Arc::new(Decrypt), Arc::new(step)];
+        self.protection = None;
+        self.retry(vec![index], options)
+    }
+
+    /// The entry at `index` has a result on disk with embedded files and no batch is
+    /// running, so its embedded files can be saved, see [`attachments_source`](Self::attachments_source).
+    #[cfg(feature = "gui")]
+    pub fn can_extract_attachments(&self, index: usize) -> bool {
+        !self.is_running()
+            && self.entries.get(index).is_some_and(|entry| {
+                !entry.attachments().is_empty() && entry.output_path.as_ref().is_some_and(|path| path.exists())
+            })
+    }
+
+    /// The result of the entry at `index`, to save its embedded files from with
+    /// [`crackleaf_core::extract_attachments`]; `None` when that cannot be done now.
+    #[cfg(feature = "gui")]
+    pub fn attachments_source(&self, index: usize) -> Option<PathBuf> {
+        if !self.can_extract_attachments(index) {
+            return None;
+        }
+        self.entries[index].output_path.clone()
+    }
+
+    /// Starts a batch over the listed entries at `indices`, whose jobs are queued.
+    fn launch(&mut self, indices: Vec<usize>, mut options: UnlockOptions) {
+        for &index in &indices {
+            self.entries[index].extracted_pages = None;
+        }
+        let files: Vec<PathBuf> = indices.iter().map(|&index| self.entries[index].path.clone()).collect();
+        self.batch_entries = indices;
+        #[cfg(feature = "history")]
+        {
+            self.history_options = history::describe_options(&options, self.config.active_profile.as_deref());
+        }
+        self.rearranges_pages = options.pipeline.iter().any(|step| step.rearranges_pages());
+        options.output_subdirs = self
+            .batch_entries
+            .iter()
+            .map(|&index| &self.entries[index])
+            // Next to their sources, files from folders are already where they belong.
+            .filter(|entry| !options.beside_source || entry.archive.is_some())
+            .filter_map(|entry| Some((entry.path.clone(), self.output_subdirs.get(&entry.path)?.clone())))
+            .collect();
+        let source_folders = self
+            .batch_entries
+            .iter()
+            .filter_map(|&index| {
+                let entry = &self.entries[index];
+                let folder = match (&entry.archive, &entry.url) {
+                    (Some(archive), _) => archive.parent()?.to_path_buf(),
+                    (None, Some(_)) => options.output_dir.clone().or_else(resolve_download_dir)?,
+                    (None, None) => return None,
+                };
+                Some((entry.path.clone(), folder))
+            })
+            .collect();
+        options.source_folders = source_folders;
+        if self.config.active().batch_folders {
+            // Named in local time, which SQLite works out.
+            #[cfg(feature = "history")]
+            {
+                options.batch_folder = history::local_now(BATCH_FOLDER_FORMAT).map(PathBuf::from);
+            }
+            #[cfg(not(feature = "history"))]
+            warn!("This build has no batch folders; they need the history feature");
+        }
+        options.session = if self.records_session { resume::start_batch(&files) } else { None };
+        self.journal = undo::start_batch();
+        options.journal = self.journal.clone();
+        options.passwords = self.remembered_passwords();
+        options.events = self.events.clone();
+        options.inspect_results = true;
+        set_process_limit(options.concurrency);
+        self.result_text = t!("processing");
+
+        info!(files = files.len(), concurrency = options.concurrency, "unlock started");
+        self.batch = Some(spawn_unlock(files, options));
+    }
+
+    /// Applies every waiting event without blocking. Returns `true` once the batch is done.
+    #[cfg(feature = "gui")]
+    pub fn poll(&mut self) -> bool {
+        while let Ok((generation, update)) = self.analysis_rx.try_recv() {
+            self.apply_analysis(generation, update);
+        }
+        while let Some(event) = self.subscription.try_next() {
+            if self.apply(&event) {
+                return true;
+            }
+        }
+        false
+    }
+
+    /// Blocks for the next event and applies it; `None` when no batch is running.
+    /// Must not be called from inside the async runtime.
+    pub fn wait(&mut self) -> Option<Event> {
+        self.batch.as_ref()?;
+        let event = block_on(self.subscription.next())?;
+        self.apply(&event);
+        Some(event)
+    }
+
+    /// Returns `true` when `event` finished the current batch.
+    fn apply(&mut self, event: &Event) -> bool {
+        let current = self.batch.as_ref().map(UnlockHandle::id);
+        match event {
+            Event::Job { batch, index, event } if Some(*batch) == current => {
+                let Some(&index) = self.batch_entries.get(*index) else {
+                    return false;
+                };
+                if let Some(queue) = self.queue.as_mut() {
+                    queue.apply(index, event);
+                }
+                if let JobEvent::Finished {
+                    success,
+                    output_path,
+                    error,
+                    restricted,
+                    output_facts,
+                    ..
+                } = event
+                {
+                    let output_facts = output_facts.as_deref().cloned();
+                    self.file_finished(index, *success, output_path.as_ref(), error.as_ref(), *restricted, output_facts);
+                }
+                false
+            }
+            Event::Notification { batch, message } if batch.is_none() || *batch == current => {
+                self.result_text = message.clone();
+                false
+            }
+            Event::BatchFinished { batch, cancelled } if Some(*batch) == current => {
+                info!(cancelled, "unlock finished");
+                if let Some(queue) = self.queue.as_mut() {
+                    queue.cancel();
+                }
+                self.journal = undo::finish_batch(self.journal.take());
+                self.batch = None;
+                save_detection_cache();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Updates the entry from the engine's result. `restricted` and `output_facts`
+    /// come checked with the event, so nothing here reads the files.
+    fn file_finished(
+        &mut self,
+        index: usize,
+        success: bool,
+        output_path: Option<&PathBuf>,
+        error: Option<&Error>,
+        restricted: Option<bool>,
+        output_facts: Option<PdfFacts>,
+    ) {
+        debug!(index, success, ?output_path, "file result");
+        #[cfg(feature = "history")]
+        self.record_history(index);
+        if let Some(Error::QpdfMissing(_)) = error {
+            self.qpdf_ok = false;
+            self.qpdf_error = error.map(i18n::error_message);
+        }
+        let originals = self.config.active().originals;
+        let Some(entry) = self.entries.get_mut(index) else {
+            return;
+        };
+        entry.output_facts = None;
+        entry.rearranged = self.rearranges_pages;
+        if success {
+            if let Some(output_path) = output_path {
+                entry.output_path = Some(output_path.clone());
+            } else if restricted == Some(false) {
+                entry.output_path = Some(entry.path.clone());
+                entry.status = t!("file-unrestricted");
+                entry.icon = Icon::Unlocked;
+                return;
+            }
+            let parts = self.queue.as_ref().and_then(|queue| queue.job(index)).map_or(0, |job| job.parts.len());
+            entry.status = match &entry.extracted_pages {
+                Some(pages) => t!("status-pages-extracted", pages = pages.as_str()),
+                None if parts > 0 => t!("status-split", count = parts),
+                None => t!("status-unlocked"),
+            };
+            entry.icon = if restricted == Some(true) { Icon::Locked } else { Icon::Unlocked };
+            entry.output_facts = output_facts.filter(|_| entry.facts.is_some());
+            if let Some((source, output)) = entry.page_mismatch() {
+                warn!(path = %entry.path.display(), source, output, "page count changed");
+            }
+            // Only an original that was encrypted, of which a whole, readable and
+            // decrypted copy now exists elsewhere.
+            let verified = entry.encrypted == Some(true)
+                && restricted == Some(false)
+                && entry.archive.is_none()
+                && entry.url.is_none()
+                && entry.extracted_pages.is_none()
+                && entry.output_path.as_ref().is_some_and(|output| *output != entry.path)
+                && (entry.output_facts.is_some() || is_office_document(&entry.path))
+                && entry.page_mismatch().is_none();
+            match (originals, &entry.output_path) {
+                (Originals::Trash, _) if verified => match trash_original(&entry.path, self.journal.as_deref()) {
+                    Ok(()) => {
+                        info!(path = %entry.path.display(), "original moved to trash");
+                        entry.status = t!("status-trashed", status = entry.status.as_str());
+                    }
+                    Err(err) => warn!(path = %entry.path.display(), "Failed to move original to trash: {err:#}"),
+                },
+                (Originals::Move, Some(output)) if verified => {
+                    match move_to_originals(&entry.path, output, self.journal.as_deref()) {
+                        Ok(moved) => {
+                            info!(path = %entry.path.display(), to = %moved.display(), "original moved aside");
+                            entry.status = t!("status-moved-original", status = entry.status.as_str());
+                        }
+                        Err(err) => warn!(path = %entry.path.display(), "Failed to move original: {err:#}"),
+                    }
+                }
+                _ => {}
+            }
+        } else {
+            let (icon, status) = match error {
+                Some(Error::WrongPassword) => (Icon::Password, "status-needs-password"),
+                Some(Error::Timeout(_)) => (Icon::Timeout, "status-timeout"),
+                Some(Error::Corrupt(_)) => (Icon::Warning, "status-corrupt"),
+                Some(Error::SourceMissing(_)) => (Icon::Missing, "status-source-missing"),
+                Some(Error::SourceLocked(_)) => (Icon::InUse, "status-source-locked"),
+                Some(Error::CloudUnavailable(_)) => (Icon::Cloud, "file-cloud-unavailable"),
+                Some(Error::OutputNotWritable(_)) => (Icon::Warning, "status-not-writable"),
+                Some(Error::OutputExists(_)) => (Icon::Cancelled, "status-output-exists"),
+                _ => (entry.icon, "status-failed"),
+            };
+            entry.icon = icon;
+            entry.status = t!(status);
+        }
+    }
+
+    #[cfg(feature = "history")]
+    fn record_history(&self, index: usize) {
+        let (Some(history), Some(queue)) = (&self.history, &self.queue) else {
+            return;
+        };
+        let hash = self.entries.get(index).and_then(|entry| entry.hash.as_deref());
+        if let (Some(job), Some(hash)) = (queue.job(index), hash) {
+            history.record(hash, job, &self.history_options);
+        }
+    }
+
+    /// Counts of the last batch once it has finished.
+    pub fn summary(&self) -> Option<BatchSummary> {
+        let queue = self.queue.as_ref().filter(|queue| queue.is_finished())?;
+        if self.is_running() {
+            return None;
+        }
+        let skipped = queue.count(JobState::Skipped);
+        Some(BatchSummary {
+            succeeded: queue.count(JobState::Done),
+            total: queue.len() - skipped,
+            skipped,
+        })
+    }
+
+    /// Reverts the last batch and updates the affected entries.
+    #[cfg(feature = "gui")]
+    pub fn undo_last_batch(&mut self) {
+        let Some(journal) = self.journal.take() else {
+            return;
+        };
+        match undo::undo(&journal) {
+            Ok(report) => {
+                for entry in &mut self.entries {
+                    if entry
+                        .output_path
+                        .as_ref()
+                        .is_some_and(|path| report.removed.contains(path))
+                    {
+                        entry.output_path = None;
+                        entry.output_facts = None;
+                        entry.status = t!("status-undone");
+                        entry.icon = Icon::Locked;
+                    }
+                }
+                self.result_text = undo::summary(&report);
+                if !report.errors.is_empty() {
+                    self.journal = Some(journal);
+                }
+            }
+            Err(err) => {
+                warn!("Undo failed: {err}");
+                self.result_text = t!("undo-failed", error = err.to_string());
+                self.journal = Some(journal);
+            }
+        }
+    }
+}
+
+/// Replaces folders with the PDFs inside them, recursively and sorted by path;
+/// other paths are kept if they name a PDF. Links are resolved to their targets.
+/// ZIP archives (not those inside folders) go to `archives`; skipped paths are
+/// counted in `summary`.
+fn expand_folders(
+    paths: Vec<PathBuf>,
+    summary: &mut AddSummary,
+    archives: &mut Vec<PathBuf>,
+    subdirs: &mut HashMap<PathBuf, PathBuf>,
+) -> Vec<PathBuf> {
+    let mut files = Vec::new();
+    for path in paths {
+        let path = links::resolve(&path);
+        if path.is_dir() {
+            let mut found = Vec::new();
+            collect_pdfs(&path, path.file_name().map(PathBuf::from).unwrap_or_default(), &mut found);
+            if found.is_empty() {
+                summary.empty_folders += 1;
+            }
+            found.sort();
+            for (file, subdir) in found {
+                subdirs.insert(file.clone(), subdir);
+                files.push(file);
+            }
+        } else if !path.exists() {
+            summary.missing += 1;
+        } else if archive::is_zip(&path) {
+            // Checked first: an uncompressed archive may show a PDF header early on.
+            archives.push(path);
+        } else if is_pdf(&path) || is_office_document(&path) {
+            files.push(path);
+        } else {
+            summary.not_pdf += 1;
+        }
+    }
+    files
+}
+
+/// What two paths naming the same file have in common. macOS file systems ignore
+/// Unicode normalization, so a name dragged from Finder (decomposed) matches the
+/// same name from a file picker (composed); elsewhere those are different files.
+fn dedupe_key(path: &Path) -> OsString {
+    if cfg!(target_os = "macos") {
+        nfc_name(path.as_os_str())
+    } else {
+        path.as_os_str().to_os_string()
+    }
+}
+
+/// Where encryption checks are remembered between runs: the cache folder
+/// (`$XDG_CACHE_HOME/crackleaf` on Linux), since they can always be redone. Moves the
+/// file there from the data folder earlier versions kept it in.
+fn detection_cache() -> Option<PathBuf> {
+    let path = dirs::cache_dir()?.join("crackleaf").join("detection.json");
+    if let Some(old) = dirs::data_local_dir().map(|dir| dir.join("crackleaf").join("detection.json")) {
+        if old != path && old.exists() && !path.exists() {
+            let moved = path.parent().map_or(Ok(()), std::fs::create_dir_all).and_then(|()| std::fs::rename(&old, &path));
+            if let Err(err) = moved {
+                warn!("Failed to move detection cache to {}: {err}", path.display());
+            }
+        }
+    }
+    Some(path)
+}
+
+/// A PDF, recognised by content, whose name does not end in `.pdf` in any case.
+fn is_mislabeled(path: &Path) -> bool {
+    !path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf")) && !is_office_document(path)
+}
+
+/// Adds the PDFs and Office documents under `dir` to `files`, each with `subdir`
+/// followed by the folders leading to it from `dir`.
+fn collect_pdfs(dir: &Path, subdir: PathBuf, files: &mut Vec<(PathBuf, PathBuf)>) {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(err) => {
+            warn!("Failed to read {:?}: {err}", dir);
+            return;
+        }
+    };
+    for entry in entries.flatten() {
+        let Ok(file_type) = entry.file_type() else {
+            continue;
+        };
+        let path = entry.path();
+        if file_type.is_dir() {
+            collect_pdfs(&path, subdir.join(entry.file_name()), files);
+        } else if file_type.is_file() || file_type.is_symlink() {
+            let path = links::resolve(&path);
+            if path.is_file() && (is_pdf(&path) || is_office_document(&path)) {
+                files.push((path, subdir.clone()));
+            }
+        }
+    }
+}
+
+/// Moves `source` to the trash, keeping a copy in `journal` so undo can put it back.
+fn trash_original(source: &Path, journal: Option<&Journal>) -> anyhow::Result<()> {
+    if let Some(journal) = journal {
+        journal.backup_copy(source)?;
+    }
+    trash::delete(source)?;
+    Ok(())
+}
+
+/// Moves `source` into an [`ORIGINALS_DIR`] folder next to `output`, numbering its name
+/// when taken, and records the move in `journal` so undoing the batch brings it back.
+fn move_to_originals(source: &Path, output: &Path, journal: Option<&Journal>) -> anyhow::Result<PathBuf> {
+    let dir = output.parent().unwrap_or(Path::new(".")).join(ORIGINALS_DIR);
+    if !dir.is_dir() {
+        std::fs::create_dir_all(&dir)?;
+        if let Some(journal) = journal {
+            journal.record_created(&dir)?;
+        }
+    }
+    let name = |number: usize| {
+        let mut name = source.file_stem().unwrap_or_default().to_os_string();
+        if number > 0 {
+            name.push(format!("_{number}"));
+        }
+        if let Some(extension) = source.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        dir.join(name)
+    };
+    let target = (0..=9999)
+        .map(name)
+        .find(|candidate| !candidate.exists())
+        .ok_or_else(|| anyhow::anyhow!("{} 中同名文件过多", dir.display()))?;
+    move_file(source, &target)?;
+    if let Some(journal) = journal {
+        journal.record_moved(source, &target)?;
+    }
+    Ok(target)
+}
+
+/// `options` for a batch that only encrypts with `step`.
+fn protect_options(step: Arc<dyn ProcessingStep>, mut options: UnlockOptions) -> UnlockOptions {
+    options.pipeline = vec![step];
+    options.suffix = PROTECTED_SUFFIX.to_string();
+    options.copy_unchanged = false;
+    options
+}
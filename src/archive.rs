@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+use std::fmt;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use crackleaf_core::is_office_document;
+use tempfile::TempDir;
+use tracing::info;
+use zip::result::ZipError;
+use zip::write::SimpleFileOptions;
+use zip::{AesMode, CompressionMethod, ZipArchive, ZipWriter};
+
+/// Name of the archive [`pack`] writes, before a number is added when it is taken.
+const PACK_NAME: &str = "crackleaf-results";
+
+/// PDFs unpacked from a ZIP archive into a private folder, which is deleted when
+/// this is dropped.
+pub struct Extracted {
+    /// Each unpacked PDF and the folder its result goes to, relative to the output
+    /// folder: the archive's name followed by the folders inside the archive.
+    pub files: Vec<(PathBuf, PathBuf)>,
+    _dir: TempDir,
+}
+
+#[derive(Debug)]
+pub enum ArchiveError {
+    /// The archive is encrypted and no password was given.
+    PasswordRequired,
+    WrongPassword,
+    Other(anyhow::Error),
+}
+
+impl fmt::Display for ArchiveError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::PasswordRequired => f.write_str("password required"),
+            Self::WrongPassword => f.write_str("wrong password"),
+            Self::Other(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl From<ZipError> for ArchiveError {
+    fn from(err: ZipError) -> Self {
+        match err {
+            ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => Self::PasswordRequired,
+            ZipError::InvalidPassword => Self::WrongPassword,
+            err => Self::Other(err.into()),
+        }
+    }
+}
+
+impl From<std::io::Error> for ArchiveError {
+    fn from(err: std::io::Error) -> Self {
+        Self::Other(err.into())
+    }
+}
+
+/// The file name ends in `.zip`, in any case.
+pub fn is_zip(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("zip"))
+}
+
+/// Unpacks the PDFs and Office documents in `archive` to a temporary folder; other
+/// files are left in it.
+/// Encrypted archives (ZipCrypto or AES) need `password`.
+pub fn extract(archive: &Path, password: Option<&str>) -> Result<Extracted, ArchiveError> {
+    let mut zip = ZipArchive::new(File::open(archive)?)?;
+    let dir = tempfile::Builder::new().prefix("crackleaf-zip-").tempdir()?;
+    let folder = PathBuf::from(archive.file_stem().unwrap_or("archive".as_ref()));
+    let mut files = Vec::new();
+    for index in 0..zip.len() {
+        let Some(name) = zip.by_index_raw(index)?.enclosed_name().filter(|name| is_supported_name(name)) else {
+            continue;
+        };
+        let mut entry = match password {
+            Some(password) => zip.by_index_decrypt(index, password.as_bytes())?,
+            None => zip.by_index(index)?,
+        };
+        let target = dir.path().join(&name);
+        if let Some(parent) = target.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let copied = File::create(&target).and_then(|mut out| std::io::copy(&mut entry, &mut out));
+        if let Err(err) = copied {
+            // ZipCrypto only notices most wrong passwords once the checksum fails.
+            return Err(if entry.encrypted() { ArchiveError::WrongPassword } else { err.into() });
+        }
+        let subfolder = name.parent().map_or_else(|| folder.clone(), |inner| folder.join(inner));
+        files.push((target, subfolder));
+    }
+    info!(archive = %archive.display(), files = files.len(), "archive extracted");
+    Ok(Extracted { files, _dir: dir })
+}
+
+/// Zips `files` into a new archive in `dir`, encrypted with AES-256 when `password`
+/// is given, and returns its path. Entries are named after the files, numbered when
+/// two share a name.
+pub fn pack(files: &[PathBuf], dir: &Path, password: Option<&str>) -> Result<PathBuf> {
+    let mut target = dir.join(format!("{PACK_NAME}.zip"));
+    let mut n = 1;
+    while target.exists() {
+        n += 1;
+        target = dir.join(format!("{PACK_NAME}-{n}.zip"));
+    }
+    if let Err(err) = write_pack(files, &target, password) {
+        let _ = std::fs::remove_file(&target);
+        return Err(err);
+    }
+    info!(archive = %target.display(), files = files.len(), "results packed");
+    Ok(target)
+}
+
+fn write_pack(files: &[PathBuf], target: &Path, password: Option<&str>) -> Result<()> {
+    let mut zip = ZipWriter::new(File::create(target)?);
+    let mut options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+    if let Some(password) = password {
+        options = options.with_aes_encryption(AesMode::Aes256, password);
+    }
+    let mut names = HashSet::new();
+    for path in files {
+        let mut source = File::open(path)?;
+        let large = source.metadata()?.len() >= u64::from(u32::MAX);
+        zip.start_file(entry_name(path, &mut names), options.large_file(large))?;
+        std::io::copy(&mut source, &mut zip)?;
+    }
+    zip.finish()?;
+    Ok(())
+}
+
+/// `path`'s file name, or `<stem> (2).<ext>` and so on when an earlier entry took it.
+fn entry_name(path: &Path, taken: &mut HashSet<String>) -> String {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+    let mut candidate = name.to_string();
+    let mut n = 1;
+    while !taken.insert(candidate.to_lowercase()) {
+        n += 1;
+        candidate = format!("{stem} ({n}){extension}");
+    }
+    candidate
+}
+
+/// PDFs and Office documents by name, leaving out the `._` resource forks macOS
+/// adds under `__MACOSX`.
+fn is_supported_name(name: &Path) -> bool {
+    let pdf = name.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"));
+    (pdf || is_office_document(name)) && !name.starts_with("__MACOSX")
+}
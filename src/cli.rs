@@ -0,0 +1,875 @@
+#[cfg(feature = "network")]
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+use serde::Serialize;
+
+use crate::config::{Config, DropFilter, LargeFiles, Originals};
+use crate::app::{AppCore, FileEntry};
+#[cfg(feature = "history")]
+use crate::history::{self, History};
+use crate::{archive, resume, undo};
+#[cfg(feature = "network")]
+use crate::{download, update};
+
+use crackleaf_core::{
+    block_on, check_qpdf_ready, check_suffix, compare_pdfs, format_size, is_office_document, is_pdf, resolve_download_dir, runtime, ConflictPolicy, Error, Event, Job,
+    JobEvent, JobQueue, JobState, PdfFacts,
+};
+use crackleaf_core::steps::{check_ocr_ready, check_sign_ready, Encrypt, Permissions};
+use tracing::Level;
+
+#[derive(Parser)]
+#[command(name = env!("CARGO_BIN_NAME"), version, about = "CrackLeaf PDF unlocker")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<CliCommand>,
+
+    /// Mirror the log file to stderr, including debug output.
+    #[arg(short, long, global = true)]
+    pub verbose: bool,
+
+    /// `crackleaf://` links handed over by the system URL handler.
+    #[arg(hide = true)]
+    pub urls: Vec<String>,
+}
+
+#[derive(Subcommand)]
+pub enum CliCommand {
+    /// Unlock PDFs without opening the window.
+    Unlock(Box<UnlockArgs>),
+    /// List the pipeline stages that can be passed to `unlock --step`.
+    Steps,
+    /// Delete the files written by the last batch and restore any originals it replaced.
+    Undo,
+    /// Compare the page count, text and metadata of two PDFs, e.g. an original and its unlocked copy.
+    /// Exits with 0 when they match, 1 when they differ.
+    Compare {
+        first: PathBuf,
+        second: PathBuf,
+
+        /// Print the differences as JSON.
+        #[arg(long)]
+        json: bool,
+    },
+    /// List recently processed files.
+    #[cfg(feature = "history")]
+    History {
+        /// Number of entries to show.
+        #[arg(short = 'n', long, default_value_t = history::DEFAULT_LIMIT)]
+        limit: usize,
+
+        /// Print the entries as JSON.
+        #[arg(long)]
+        json: bool,
+
+        /// Delete the history.
+        #[arg(long, conflicts_with_all = ["limit", "json"])]
+        clear: bool,
+    },
+    /// Check for a newer release and install it where supported.
+    #[cfg(feature = "network")]
+    Update {
+        /// Only report whether an update is available.
+        #[arg(long)]
+        check: bool,
+    },
+    /// Print a shell completion script to stdout.
+    Completions {
+        /// Shell to generate the script for.
+        shell: Shell,
+    },
+}
+
+#[derive(Args)]
+pub struct UnlockArgs {
+    /// PDF files, Word/Excel documents, folders to search for them, ZIP archives of them, or http(s) links to download, to unlock.
+    #[arg(required_unless_present = "resume")]
+    files: Vec<PathBuf>,
+
+    /// Continue the batch that was interrupted last time.
+    #[arg(long)]
+    resume: bool,
+
+    /// Print a JSON summary of the results on stdout.
+    #[arg(long)]
+    json: bool,
+
+    /// Skip files whose unlocked copy from an earlier run still exists.
+    #[cfg(feature = "history")]
+    #[arg(long)]
+    skip_unlocked: bool,
+
+    /// Process files above the profile's size limit (`max_file_size_mb`) too.
+    #[arg(long)]
+    allow_large: bool,
+
+    /// Settings profile from the config to use [default: the active one].
+    #[arg(long)]
+    profile: Option<String>,
+
+    /// Folder for the unlocked files [default: from profile, else Downloads].
+    #[arg(short, long, value_name = "DIR")]
+    output_dir: Option<PathBuf>,
+
+    /// Write each result next to its source [default: from profile].
+    #[arg(long, conflicts_with = "output_dir")]
+    beside_source: bool,
+
+    /// Added to the names of the results [default: from profile, else _unlocked].
+    #[arg(long, value_name = "TEXT")]
+    suffix: Option<String>,
+
+    /// Put the results into a subfolder named after when the batch started [default: from profile].
+    #[arg(long)]
+    batch_folder: bool,
+
+    /// What to do when a result's name is taken by an existing file [default: from profile, else rename].
+    #[arg(long, value_name = "POLICY")]
+    on_conflict: Option<OnConflict>,
+
+    /// Give outputs the modified time and permissions of their source [default: from config].
+    #[arg(long)]
+    preserve_attributes: bool,
+
+    /// Copy files that need no unlocking to the output folder as they are [default: from config].
+    #[arg(long)]
+    copy_unrestricted: bool,
+
+    /// Also write one file per top-level bookmark of each result, into a folder next to it [default: from config].
+    #[arg(long)]
+    split_by_bookmarks: bool,
+
+    /// Move encrypted originals to the trash once their result checks out [default: from config].
+    #[arg(long)]
+    trash_originals: bool,
+
+    /// Move encrypted originals into an `originals` folder next to their result once it
+    /// checks out [default: from config].
+    #[arg(long, conflicts_with = "trash_originals")]
+    move_originals: bool,
+
+    /// Number of files to process at the same time [default: from config].
+    #[arg(short = 'j', long, value_parser = clap::value_parser!(u16).range(1..))]
+    jobs: Option<u16>,
+
+    /// Give up on a file after this many seconds; 0 disables the limit [default: from config].
+    #[arg(long)]
+    timeout: Option<u64>,
+
+    /// Pipeline stage, e.g. `decrypt`; repeat to chain stages in order [default: from config].
+    #[arg(long = "step", value_name = "ID")]
+    steps: Vec<String>,
+
+    /// Password for encrypted ZIP archives among the inputs.
+    #[arg(long, value_name = "PASSWORD")]
+    archive_password: Option<String>,
+
+    /// Also zip all results into one archive in Downloads.
+    #[arg(long)]
+    pack: bool,
+
+    /// Encrypt the `--pack` archive with this password (AES-256).
+    #[arg(long, value_name = "PASSWORD", requires = "pack")]
+    pack_password: Option<String>,
+
+    /// Password of the certificate the `sign` stage uses.
+    #[arg(long, value_name = "PASSWORD")]
+    sign_password: Option<String>,
+
+    /// Encrypt the PDFs with AES-256 and this owner password instead of unlocking them; they still open without a password.
+    #[arg(long, value_name = "PASSWORD", conflicts_with_all = ["steps", "copy_unrestricted"])]
+    encrypt: Option<String>,
+
+    /// What readers of `--encrypt`ed files may still do, comma-separated; `none` allows nothing [default: from config].
+    #[arg(long, value_name = "PERMISSION", value_delimiter = ',', requires = "encrypt")]
+    allow: Option<Vec<Permission>>,
+}
+
+/// A policy for `unlock --on-conflict`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum OnConflict {
+    /// Replace the existing file; `undo` puts it back.
+    Overwrite,
+    /// Add a number to the new name.
+    Rename,
+    /// Leave the existing file and skip the input.
+    Skip,
+}
+
+/// A permission for `unlock --allow`.
+#[derive(Clone, Copy, PartialEq, Eq, ValueEnum)]
+enum Permission {
+    None,
+    Print,
+    Copy,
+    Modify,
+    Annotate,
+}
+
+#[derive(Serialize)]
+struct FileReport {
+    #[serde(with = "crackleaf_core::path_serde")]
+    path: PathBuf,
+    /// ZIP archive the file was unpacked from; `path` is then a temporary copy.
+    #[serde(with = "crackleaf_core::path_serde::option")]
+    archive: Option<PathBuf>,
+    /// Link the file was downloaded from; `path` is then a temporary copy.
+    url: Option<String>,
+    status: &'static str,
+    /// Cipher of an encrypted source, e.g. `RC4-40` or `AES-256`.
+    encryption: Option<String>,
+    #[serde(with = "crackleaf_core::path_serde::option")]
+    output: Option<PathBuf>,
+    /// Files the result was split into, one per top-level bookmark.
+    #[serde(with = "crackleaf_core::path_serde::vec")]
+    parts: Vec<PathBuf>,
+    error: Option<String>,
+    /// Machine-readable error kind, e.g. `wrong-password` or `timeout`.
+    kind: Option<&'static str>,
+    /// Pipeline stage that failed.
+    stage: Option<String>,
+    seconds: f64,
+    /// Page counts of the source and the result; they differ when qpdf lost pages.
+    pages: Option<u32>,
+    output_pages: Option<u32>,
+    /// Size, PDF version, page count, encryption and metadata of the source and the
+    /// result, for comparing them.
+    source_facts: Option<PdfFacts>,
+    output_facts: Option<PdfFacts>,
+    /// Metadata the result no longer carries, e.g. `Author` or `XMP`.
+    removed_metadata: Vec<String>,
+}
+
+pub fn run(command: CliCommand) -> i32 {
+    attach_parent_console();
+    match command {
+        CliCommand::Unlock(args) => run_unlock_command(*args),
+        CliCommand::Steps => {
+            for step in Config::load().step_registry().steps() {
+                println!("{}\t{}", step.id(), step.name());
+            }
+            0
+        }
+        CliCommand::Undo => match undo::undo_latest() {
+            Ok(report) => {
+                for path in &report.removed {
+                    eprintln!("removed {}", path.display());
+                }
+                for path in &report.restored {
+                    eprintln!("restored {}", path.display());
+                }
+                for (path, err) in &report.errors {
+                    eprintln!("failed {}: {err}", path.display());
+                }
+                i32::from(!report.errors.is_empty())
+            }
+            Err(err) => {
+                eprintln!("{err}");
+                1
+            }
+        },
+        CliCommand::Compare { first, second, json } => run_compare_command(&first, &second, json),
+        #[cfg(feature = "history")]
+        CliCommand::History { limit, json, clear } => run_history_command(limit, json, clear),
+        #[cfg(feature = "network")]
+        CliCommand::Update { check } => run_update_command(check),
+        CliCommand::Completions { shell } => {
+            let mut command = Cli::command();
+            let name = command.get_name().to_string();
+            clap_complete::generate(shell, &mut command, name, &mut std::io::stdout());
+            0
+        }
+    }
+}
+
+fn run_unlock_command(args: UnlockArgs) -> i32 {
+    let qpdf_status = block_on(check_qpdf_ready());
+    if !qpdf_status.ok {
+        eprintln!("{}", qpdf_status.error.unwrap_or_default());
+        return 1;
+    }
+
+    let mut files = Vec::new();
+    if args.resume {
+        files = resume::pending();
+        if files.is_empty() && args.files.is_empty() {
+            eprintln!("nothing to resume");
+            return 0;
+        }
+        eprintln!("resuming {} file(s)", files.len());
+    }
+    files.extend(args.files);
+
+    let mut config = Config::load();
+    if let Some(profile) = &args.profile {
+        if let Err(err) = config.select_profile(Some(profile)) {
+            eprintln!("{err}");
+            return 2;
+        }
+    }
+    if let Some(password) = args.sign_password {
+        config.sign.password = password;
+    }
+    if args.trash_originals {
+        config.active_mut().originals = Originals::Trash;
+    }
+    if args.move_originals {
+        config.active_mut().originals = Originals::Move;
+    }
+    if args.allow_large {
+        config.active_mut().max_file_size_mb = 0;
+    }
+    // The drop filter is for the window; files named here are all taken.
+    config.ui.drop_filter = DropFilter::Pdf;
+    config.active_mut().batch_folders |= args.batch_folder;
+    let mut options = config.unlock_options();
+    if let Some(dir) = args.output_dir {
+        options.output_dir = Some(dir);
+        options.beside_source = false;
+    }
+    options.beside_source |= args.beside_source;
+    if let Some(suffix) = args.suffix {
+        if let Err(err) = check_suffix(&suffix) {
+            eprintln!("{err}");
+            return 2;
+        }
+        options.suffix = suffix;
+    }
+    if let Some(policy) = args.on_conflict {
+        options.on_conflict = match policy {
+            OnConflict::Overwrite => ConflictPolicy::Overwrite,
+            OnConflict::Rename => ConflictPolicy::Rename,
+            OnConflict::Skip => ConflictPolicy::Skip,
+        };
+    }
+    if !args.steps.is_empty() {
+        match config.step_registry().resolve(&args.steps) {
+            Ok(steps) => options.pipeline = steps,
+            Err(err) => {
+                eprintln!("{err}");
+                return 2;
+            }
+        }
+    }
+    if args.preserve_attributes {
+        options.preserve_attributes = true;
+    }
+    if args.copy_unrestricted {
+        options.copy_unchanged = true;
+    }
+    if args.split_by_bookmarks {
+        options.split_by_bookmarks = true;
+    }
+    if let Some(jobs) = args.jobs {
+        options.concurrency = usize::from(jobs);
+    }
+    if let Some(seconds) = args.timeout {
+        options.timeout = (seconds > 0).then(|| Duration::from_secs(seconds));
+    }
+    if options.pipeline.iter().any(|step| step.id() == "ocr") {
+        if let Err(err) = block_on(check_ocr_ready()) {
+            eprintln!("{err}");
+            return 1;
+        }
+    }
+    if options.pipeline.iter().any(|step| step.id() == "sign") {
+        if let Err(err) = block_on(check_sign_ready()) {
+            eprintln!("{err}");
+            return 1;
+        }
+    }
+    let protection = args.encrypt.map(|password| {
+        let permissions = match &args.allow {
+            Some(allowed) => Permissions {
+                print: allowed.contains(&Permission::Print),
+                copy: allowed.contains(&Permission::Copy),
+                modify: allowed.contains(&Permission::Modify),
+                annotate: allowed.contains(&Permission::Annotate),
+            },
+            None => config.encrypt,
+        };
+        Encrypt::new(password, permissions)
+    });
+    let protecting = protection.is_some();
+
+    let mut paths = Vec::new();
+    #[cfg(feature = "network")]
+    let mut urls = Vec::new();
+    for path in files {
+        #[cfg(feature = "network")]
+        if let Some(url) = path.to_str().filter(|text| download::is_url(text)) {
+            urls.push(url.to_string());
+            continue;
+        }
+        #[cfg(feature = "network")]
+        if let Some(url) = download::shortcut_url(&path) {
+            urls.push(url);
+            continue;
+        }
+        #[cfg(not(feature = "network"))]
+        if path.to_str().is_some_and(|text| text.starts_with("http://") || text.starts_with("https://")) {
+            eprintln!("skip {}: this build cannot download links", path.display());
+            continue;
+        }
+        if !path.exists() {
+            eprintln!("skip {}: not found", path.display());
+            continue;
+        }
+        if !path.is_dir() && !is_pdf(&path) && !is_office_document(&path) && !archive::is_zip(&path) {
+            eprintln!("skip {}: not a PDF or Office document", path.display());
+            continue;
+        }
+        paths.push(path);
+    }
+
+    let mut core = AppCore::new(config, qpdf_status);
+    let summary = core.add_files(paths, |files| {
+        for path in files {
+            eprintln!("note: {} has no .pdf extension, processing it by content", path.display());
+        }
+        true
+    });
+    if summary.duplicates > 0 {
+        eprintln!("skip {} duplicate(s)", summary.duplicates);
+    }
+    if summary.empty_folders > 0 {
+        eprintln!("skip {} folder(s) without PDFs", summary.empty_folders);
+    }
+    let mut archives = summary.archives;
+    #[cfg(feature = "network")]
+    for url in urls {
+        match download_with_progress(&url) {
+            Ok(downloaded) => {
+                let summary = core.add_download(downloaded);
+                archives += summary.archives;
+                if summary.not_pdf > 0 {
+                    eprintln!("skip {url}: not a PDF or Office document");
+                } else if summary.duplicates > 0 {
+                    eprintln!("skip {url}: duplicate");
+                }
+            }
+            Err(err) => eprintln!("skip {url}: {err}"),
+        }
+    }
+    while let Some(archive) = core.pending_archive().map(Path::to_path_buf) {
+        let Some(password) = &args.archive_password else {
+            eprintln!("skip {}: encrypted archive, pass --archive-password", archive.display());
+            core.skip_archive();
+            continue;
+        };
+        match core.unlock_archive(password) {
+            Ok(summary) => archives += summary.archives,
+            Err(err) => {
+                eprintln!("skip {}: {err}", archive.display());
+                core.skip_archive();
+            }
+        }
+    }
+    if archives > 0 {
+        eprintln!("skip {archives} archive(s) that could not be read or hold no PDFs");
+    }
+    core.wait_for_analysis();
+    for entry in core.entries() {
+        if entry.needs_password {
+            eprintln!("note: {} requires a password to open", source_name(entry));
+        }
+        if entry.slow_volume {
+            eprintln!("note: {} is on a slow network share", source_name(entry));
+        }
+        #[cfg(feature = "history")]
+        if let Some(record) = &entry.previous {
+            let output = record.output.clone().unwrap_or_default();
+            if args.skip_unlocked {
+                eprintln!("skip {}: already unlocked as {}", source_name(entry), output.display());
+            } else {
+                eprintln!(
+                    "note: {} was already unlocked on {} as {}",
+                    source_name(entry),
+                    record.finished,
+                    output.display()
+                );
+            }
+        }
+    }
+    let limit = core.config.active().size_limit().map(format_size).unwrap_or_default();
+    let oversized: Vec<PathBuf> = core
+        .oversized()
+        .into_iter()
+        .map(|(index, size)| {
+            let entry = &core.entries()[index];
+            let estimate = core
+                .estimate(size)
+                .map(|estimate| format!(", about {:.0}s", estimate.as_secs_f64()))
+                .unwrap_or_default();
+            if core.config.active().large_files == LargeFiles::Block {
+                eprintln!(
+                    "skip {}: {} is above the {limit} limit (--allow-large to process it)",
+                    source_name(entry),
+                    format_size(size)
+                );
+            } else {
+                eprintln!("warning: {} is {}, above the {limit} limit{estimate}", source_name(entry), format_size(size));
+            }
+            entry.path.clone()
+        })
+        .collect();
+    if core.config.active().large_files == LargeFiles::Block && !oversized.is_empty() {
+        core.retain(|entry| !oversized.contains(&entry.path));
+        if core.entries().is_empty() {
+            return 1;
+        }
+    }
+    #[cfg(feature = "history")]
+    if args.skip_unlocked {
+        let before = core.entries().len();
+        core.retain(|entry| entry.previous.is_none());
+        if before > 0 && core.entries().is_empty() {
+            return 0;
+        }
+    }
+    if core.entries().is_empty() {
+        eprintln!("no PDF files to unlock");
+        return 1;
+    }
+
+    for index in core.signatures_at_risk(!protecting && options.skips_unencrypted()) {
+        let entry = &core.entries()[index];
+        let signers: Vec<String> = entry
+            .signatures()
+            .iter()
+            .map(|signature| signature.signer.clone().unwrap_or_else(|| "an unknown signer".to_string()))
+            .collect();
+        eprintln!(
+            "warning: {} is digitally signed by {}; processing it invalidates the signature",
+            source_name(entry),
+            signers.join(", ")
+        );
+    }
+    match protection {
+        Some(step) => core.protect(step, options),
+        None => core.start(options),
+    };
+    let mut total = core.entries().len();
+    for (job, entry) in core.queue().map(JobQueue::jobs).unwrap_or_default().iter().zip(core.entries()) {
+        if job.state == JobState::Skipped {
+            let reason = if protecting { "not a PDF" } else { "not encrypted" };
+            eprintln!("skip {}: {reason}", source_name(entry));
+            total -= 1;
+        }
+    }
+    let mut done = 0;
+
+    if let Some(cancel) = core.batch().map(|batch| batch.cancel_token()) {
+        runtime().spawn(async move {
+            if tokio::signal::ctrl_c().await.is_ok() {
+                eprintln!("cancelling…");
+                cancel.cancel();
+            }
+        });
+    }
+    while let Some(event) = core.wait() {
+        match event {
+            Event::Job {
+                index,
+                event: JobEvent::Finished { .. },
+                ..
+            } => {
+                let index = core.entry_index(index);
+                if let Some(job) = index.and_then(|index| core.queue()?.job(index)) {
+                    done += 1;
+                    print_progress(done, total, job);
+                }
+                if let Some(entry) = index.and_then(|index| core.entries().get(index)) {
+                    if let Some((source, output)) = entry.page_mismatch() {
+                        eprintln!(
+                            "warning: {} has {output} page(s) after unlocking, the original has {source}",
+                            source_name(entry)
+                        );
+                    }
+                    let covered = entry.covered_text_pages();
+                    if !covered.is_empty() {
+                        let pages: Vec<String> = covered.iter().map(u32::to_string).collect();
+                        eprintln!(
+                            "warning: {} has text under black boxes on page(s) {}; these redactions can be undone",
+                            source_name(entry),
+                            pages.join(", ")
+                        );
+                    }
+                }
+            }
+            Event::Log { level, message } if level <= Level::WARN => eprintln!("{message}"),
+            Event::Notification { message, .. } => eprintln!("warning: {message}"),
+            _ => {}
+        }
+        if !core.is_running() {
+            break;
+        }
+    }
+    let Some(queue) = core.queue() else {
+        return 1;
+    };
+
+    let reports: Vec<FileReport> = queue
+        .jobs()
+        .iter()
+        .zip(core.entries())
+        .map(|(job, entry)| FileReport {
+            path: job.path.clone(),
+            archive: entry.archive.clone(),
+            url: entry.url.clone(),
+            status: job_status(job),
+            encryption: entry.encryption.clone(),
+            output: job.output_path.clone(),
+            parts: job.parts.clone(),
+            error: job.error.as_ref().map(ToString::to_string),
+            kind: job.error.as_ref().map(Error::kind),
+            stage: job.stage.clone().filter(|_| job.state == JobState::Failed),
+            seconds: job.elapsed.unwrap_or_default().as_secs_f64(),
+            pages: entry.facts.as_ref().and_then(|facts| facts.pages),
+            output_pages: entry.output_facts.as_ref().and_then(|facts| facts.pages),
+            source_facts: entry.facts.clone(),
+            output_facts: entry.output_facts.clone(),
+            removed_metadata: entry.removed_metadata(),
+        })
+        .collect();
+    let failed = queue.count(JobState::Failed) + queue.count(JobState::Cancelled);
+    let locked = core.locked().len();
+    if locked > 0 {
+        eprintln!("{locked} file(s) are open in another program; close it and run again");
+    }
+    let unwritable = core.unwritable().len();
+    if unwritable > 0 {
+        eprintln!("{unwritable} file(s) could not be written to the output folder; retry them with --output-dir <DIR>");
+    }
+
+    let mut pack_failed = false;
+    if args.pack {
+        let results = core.results();
+        let packed = resolve_download_dir()
+            .ok_or_else(|| anyhow::anyhow!("no Downloads folder"))
+            .and_then(|dir| archive::pack(&results, &dir, args.pack_password.as_deref()));
+        match packed {
+            Ok(path) => eprintln!("packed {} result(s) into {}", results.len(), path.display()),
+            Err(err) => {
+                eprintln!("packing failed: {err}");
+                pack_failed = true;
+            }
+        }
+    }
+
+    if args.json {
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        if serde_json::to_writer_pretty(&mut out, &reports).is_ok() {
+            let _ = writeln!(out);
+        }
+    } else {
+        for report in &reports {
+            if let Some(output) = &report.output {
+                println!("{}", output.display());
+            }
+            for part in &report.parts {
+                println!("{}", part.display());
+            }
+        }
+    }
+
+    if failed > 0 || pack_failed {
+        1
+    } else {
+        0
+    }
+}
+
+fn run_compare_command(first: &Path, second: &Path, json: bool) -> i32 {
+    let diff = match block_on(compare_pdfs(first, second, Config::load().unlock_options().timeout)) {
+        Ok(diff) => diff,
+        Err(err) => {
+            eprintln!("{err}");
+            return 2;
+        }
+    };
+    if json {
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        if serde_json::to_writer_pretty(&mut out, &diff).is_ok() {
+            let _ = writeln!(out);
+        }
+    } else if diff.is_same() {
+        println!("same pages, text and metadata");
+    } else {
+        if diff.pages.0 != diff.pages.1 {
+            println!("pages: {} -> {}", diff.pages.0, diff.pages.1);
+        }
+        if !diff.text_pages.is_empty() {
+            let pages: Vec<String> = diff.text_pages.iter().map(u32::to_string).collect();
+            println!("text differs on pages: {}", pages.join(", "));
+        }
+        for entry in &diff.metadata {
+            let value = |value: &Option<String>| value.as_ref().map_or("(none)".to_string(), |value| format!("{value:?}"));
+            println!("metadata {}: {} -> {}", entry.key, value(&entry.first), value(&entry.second));
+        }
+    }
+    i32::from(!diff.is_same())
+}
+
+#[cfg(feature = "history")]
+fn run_history_command(limit: usize, json: bool, clear: bool) -> i32 {
+    let Some(history) = History::open() else {
+        eprintln!("history is not available");
+        return 1;
+    };
+    if clear {
+        return match history.clear() {
+            Ok(()) => 0,
+            Err(err) => {
+                eprintln!("{err}");
+                1
+            }
+        };
+    }
+    let records = match history.recent(limit) {
+        Ok(records) => records,
+        Err(err) => {
+            eprintln!("{err}");
+            return 1;
+        }
+    };
+    if json {
+        let stdout = std::io::stdout();
+        let mut out = stdout.lock();
+        if serde_json::to_writer_pretty(&mut out, &records).is_ok() {
+            let _ = writeln!(out);
+        }
+        return 0;
+    }
+    for record in &records {
+        let status = record.error_kind.as_deref().unwrap_or(&record.status);
+        let output = record.output.as_ref().map(|path| path.display().to_string()).unwrap_or_default();
+        println!("{}\t{}\t{}\t{}", record.finished, status, record.source.display(), output);
+    }
+    0
+}
+
+#[cfg(feature = "network")]
+fn run_update_command(check_only: bool) -> i32 {
+    let release = match update::check() {
+        Ok(Some(release)) => release,
+        Ok(None) => {
+            eprintln!("crackleaf {} is up to date", env!("CARGO_PKG_VERSION"));
+            return 0;
+        }
+        Err(err) => {
+            eprintln!("update check failed: {err}");
+            return 1;
+        }
+    };
+    println!("crackleaf {} is available ({})", release.version, release.page_url);
+    if !release.changelog.trim().is_empty() {
+        println!("\n{}", release.changelog.trim());
+    }
+    if check_only {
+        return 0;
+    }
+    if !update::can_install(&release) {
+        match &release.asset {
+            Some(asset) => eprintln!("automatic install is not supported here; download {}", asset.url),
+            None => eprintln!("automatic install is not supported here; download it from the page above"),
+        }
+        return 0;
+    }
+    match update::install(&release) {
+        Ok(()) => {
+            eprintln!("installed {}; restart to use it", release.version);
+            0
+        }
+        Err(err) => {
+            eprintln!("install failed: {err}");
+            1
+        }
+    }
+}
+
+fn job_status(job: &Job) -> &'static str {
+    match job.state {
+        JobState::Done if job.output_path.is_some() => "ok",
+        JobState::Done => "unchanged",
+        JobState::Failed => match job.error {
+            Some(Error::WrongPassword) => "needs password",
+            Some(Error::Timeout(_)) => "timed out",
+            Some(Error::SourceMissing(_)) => "source missing",
+            Some(Error::SourceLocked(_)) => "in use",
+            Some(Error::CloudUnavailable(_)) => "cloud file unavailable",
+            _ => "failed",
+        },
+        JobState::Cancelled => "cancelled",
+        JobState::Skipped if matches!(job.error, Some(Error::OutputExists(_))) => "exists",
+        JobState::Skipped => "skipped",
+        JobState::Queued | JobState::Analyzing | JobState::Running => "pending",
+    }
+}
+
+/// Downloads `url`, showing the bytes received on stderr when it is a terminal.
+#[cfg(feature = "network")]
+fn download_with_progress(url: &str) -> anyhow::Result<download::Downloaded> {
+    let terminal = std::io::stderr().is_terminal();
+    eprintln!("downloading {url}");
+    let mut shown = 0;
+    let downloaded = download::download(url, |received, total| {
+        // Redraw every 256 KiB rather than per chunk.
+        if !terminal || (received > 0 && received - shown < 256 * 1024 && Some(received) != total) {
+            return;
+        }
+        shown = received;
+        match total {
+            Some(total) if total > 0 => {
+                eprint!("\r  {} / {} ({}%)", format_size(received), format_size(total), received * 100 / total)
+            }
+            _ => eprint!("\r  {}", format_size(received)),
+        }
+    });
+    if terminal {
+        eprintln!();
+    }
+    downloaded
+}
+
+/// The file's path, or the link it was downloaded from; for files unpacked from an
+/// archive `<archive> › <name>`.
+fn source_name(entry: &FileEntry) -> String {
+    let source = match &entry.url {
+        Some(url) => url.clone(),
+        None => entry.archive.as_ref().unwrap_or(&entry.path).display().to_string(),
+    };
+    match &entry.archive {
+        Some(_) => format!("{source} › {}", entry.path.file_name().unwrap_or_default().to_string_lossy()),
+        None => source,
+    }
+}
+
+fn print_progress(done: usize, total: usize, job: &Job) {
+    let name = job
+        .path
+        .file_name()
+        .unwrap_or(job.path.as_os_str())
+        .to_string_lossy();
+    let seconds = job.elapsed.unwrap_or_default().as_secs_f64();
+    eprintln!("[{done}/{total}] {name} … {}, {seconds:.1}s", job_status(job));
+}
+
+/// Release builds use the GUI subsystem on Windows, so borrow the parent console for output.
+#[cfg(target_os = "windows")]
+fn attach_parent_console() {
+    use windows_sys::Win32::System::Console::{AttachConsole, ATTACH_PARENT_PROCESS};
+    unsafe {
+        AttachConsole(ATTACH_PARENT_PROCESS);
+    }
+}
+
+#[cfg(not(target_os = "windows"))]
+fn attach_parent_console() {}
@@ -0,0 +1,571 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use crackleaf_core::steps::{
+    Crop, CropMargins, HookStep, Letterhead, LetterheadMode, Ocr, PageNumbers, Permissions, Position, ProcessingStep,
+    Sign, StepRegistry, Watermark, WatermarkContent,
+};
+use crackleaf_core::{check_suffix, default_concurrency, ConflictPolicy, UnlockOptions, DEFAULT_TIMEOUT};
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+/// Bump when the file layout changes and add a step to [`migrate`].
+pub const CONFIG_VERSION: u32 = 2;
+
+const CONFIG_FILE: &str = "config.toml";
+
+/// User preferences stored in `<config dir>/crackleaf/config.toml`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub version: u32,
+    /// Name of the profile in use; unset selects `unlock`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active_profile: Option<String>,
+    /// Settings of the default profile.
+    pub unlock: UnlockConfig,
+    /// Additional named settings, selectable instead of `unlock`.
+    pub profiles: Vec<Profile>,
+    pub ui: UiConfig,
+    pub update: UpdateConfig,
+    pub ocr: OcrConfig,
+    pub page_numbers: PageNumbersConfig,
+    pub watermark: WatermarkConfig,
+    pub letterhead: LetterheadConfig,
+    pub crop: CropConfig,
+    pub sign: SignConfig,
+    /// What files encrypted in a protect batch may still be used for; the owner
+    /// password is asked for each batch and never stored.
+    pub encrypt: Permissions,
+    /// External commands usable as processing steps.
+    pub hooks: Vec<HookConfig>,
+    /// Set when the file was written by a newer release; it is then never overwritten.
+    #[serde(skip)]
+    read_only: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UnlockConfig {
+    /// Files processed at the same time.
+    pub concurrency: usize,
+    /// Limit for each qpdf call in seconds; 0 disables it.
+    pub timeout_secs: u64,
+    /// Ids of the stages every file goes through, in order.
+    pub pipeline: Vec<String>,
+    /// Where results are written; unset uses the downloads folder.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub output_dir: Option<PathBuf>,
+    /// Write each result next to its source instead of into `output_dir`: files from an
+    /// archive next to the archive, downloaded ones still into `output_dir`.
+    pub beside_source: bool,
+    /// Give results the modified time and permissions of their source.
+    pub preserve_attributes: bool,
+    /// Copy files that need no unlocking to the output folder instead of leaving them out.
+    pub copy_unrestricted: bool,
+    /// Also write one file per top-level bookmark of each result, into a folder next to it.
+    pub split_by_bookmarks: bool,
+    /// Put each batch's results into its own subfolder of the output folder, named
+    /// after when the batch started, e.g. `CrackLeaf 2024-06-01 14-30`.
+    pub batch_folders: bool,
+    /// What becomes of encrypted originals once their decrypted result checks out.
+    pub originals: Originals,
+    /// Added to the names of results, e.g. `_done`; unset uses the language's default
+    /// in the window (`_已解锁`, `_unlocked`) and `_unlocked` on the command line.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub suffix: Option<String>,
+    /// What to do when a result's name is taken: overwrite, rename or skip.
+    pub on_conflict: ConflictPolicy,
+    /// Inputs larger than this many megabytes are handled as `large_files` says;
+    /// 0 turns the check off.
+    pub max_file_size_mb: u64,
+    /// Whether such inputs are asked about or left out.
+    pub large_files: LargeFiles,
+}
+
+impl UnlockConfig {
+    /// `max_file_size_mb` in bytes, or `None` when there is no limit.
+    pub fn size_limit(&self) -> Option<u64> {
+        (self.max_file_size_mb > 0).then(|| self.max_file_size_mb.saturating_mul(1024 * 1024))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile {
+    pub name: String,
+    #[serde(flatten)]
+    pub unlock: UnlockConfig,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    /// Language tag such as `en-US`; unset follows the system language.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Files added after a batch join its list instead of replacing it; the list is
+    /// then only emptied with the clear button.
+    pub append_after_unlock: bool,
+    /// The window is shrunk to a small drop target that stays on top.
+    pub mini: bool,
+    /// Output folders offered for picking under the mascot, e.g. project folders or NAS shares.
+    pub pinned_folders: Vec<PathBuf>,
+    /// Keep archive passwords that worked in the system keychain, and try them on
+    /// encrypted archives and PDFs before asking.
+    pub remember_passwords: bool,
+    /// Black on white with stronger outlines, for projectors and poor eyesight.
+    pub high_contrast: bool,
+    /// Which of the files dropped on the window, or found in dropped folders and
+    /// archives, are listed.
+    pub drop_filter: DropFilter,
+}
+
+/// Files taken from what is dropped on the window.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum DropFilter {
+    /// Only encrypted PDFs and protected Office documents; files whose check is
+    /// inconclusive are kept.
+    Encrypted,
+    /// PDFs and Office documents; PDFs without a `.pdf` extension are asked about.
+    #[default]
+    Pdf,
+    /// Anything that starts like a PDF, whatever its name, without asking.
+    Sniffed,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateConfig {
+    /// Look for a new release on GitHub when the window opens.
+    pub check_on_startup: bool,
+    /// Release the user chose not to be reminded about.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub skipped_version: Option<String>,
+}
+
+impl Default for UpdateConfig {
+    fn default() -> Self {
+        Self {
+            check_on_startup: true,
+            skipped_version: None,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct OcrConfig {
+    /// Tesseract languages for the `ocr` step, e.g. `chi_sim+eng`; unset uses
+    /// [`DEFAULT_OCR_LANGUAGES`](crackleaf_core::steps::DEFAULT_OCR_LANGUAGES).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub languages: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PageNumbersConfig {
+    /// Where the `page-numbers` step puts the number, e.g. `bottom-center` or `top-right`.
+    pub position: Position,
+    /// Font size in points.
+    pub font_size: f32,
+    /// Number of the first page.
+    pub start: u32,
+}
+
+impl Default for PageNumbersConfig {
+    fn default() -> Self {
+        Self {
+            position: Position::BottomCenter,
+            font_size: 10.0,
+            start: 1,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WatermarkConfig {
+    /// Text the `watermark` step puts on every page.
+    pub text: String,
+    /// PNG to use instead of `text`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub image: Option<PathBuf>,
+    /// From 0 (invisible) to 1 (opaque).
+    pub opacity: f32,
+    pub position: Position,
+    /// Font size of the text in points.
+    pub font_size: f32,
+    /// Width of the image as a fraction of the page width.
+    pub image_width: f32,
+    /// Rotation in degrees, counterclockwise.
+    pub angle: f32,
+}
+
+impl Default for WatermarkConfig {
+    fn default() -> Self {
+        Self {
+            text: "内部使用".to_string(),
+            image: None,
+            opacity: 0.3,
+            position: Position::Center,
+            font_size: 48.0,
+            image_width: 0.3,
+            angle: 45.0,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LetterheadConfig {
+    /// PDF the `letterhead` step puts behind or over every page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub file: Option<PathBuf>,
+    pub mode: LetterheadMode,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CropConfig {
+    pub mode: CropMode,
+    /// Millimetres trimmed off the top, right, bottom and left in `margins` mode.
+    pub margins: [f32; 4],
+    /// Millimetres kept around the content in `auto` mode.
+    pub padding: f32,
+}
+
+/// What becomes of an encrypted original once its decrypted result checks out.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Originals {
+    /// Left where it is.
+    #[default]
+    Keep,
+    /// Moved to the trash.
+    Trash,
+    /// Moved into an `originals` folder next to the result.
+    Move,
+}
+
+/// What happens to inputs above the size limit, unless allowed one by one.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum LargeFiles {
+    /// Asked about before the batch starts.
+    #[default]
+    Warn,
+    /// Left out of the batch.
+    Block,
+}
+
+/// How the `crop-margins` step finds the new page edges.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum CropMode {
+    /// Cut each page down to what is drawn on it.
+    Auto,
+    /// Trim the same margins off every page.
+    Margins,
+}
+
+impl Default for CropConfig {
+    fn default() -> Self {
+        Self {
+            mode: CropMode::Auto,
+            margins: [15.0; 4],
+            padding: 3.5,
+        }
+    }
+}
+
+impl CropConfig {
+    /// The configured crop, in points.
+    pub fn crop(&self) -> Crop {
+        let points = |mm: f32| mm.max(0.0) * 72.0 / 25.4;
+        match self.mode {
+            CropMode::Auto => Crop::Auto {
+                padding: points(self.padding),
+            },
+            CropMode::Margins => Crop::Margins(self.margins.map(points)),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SignConfig {
+    /// PKCS#12 file (`.p12` or `.pfx`) with the certificate and key the `sign` step uses.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub certificate: Option<PathBuf>,
+    /// Password of `certificate`, typed in for the session and never saved.
+    #[serde(skip)]
+    pub password: String,
+    /// Show the signature as a box on the first page rather than only in the
+    /// viewer's signature panel.
+    pub visible: bool,
+    /// Recorded with the signature, e.g. "归档前重新认证".
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub reason: Option<String>,
+}
+
+/// A custom command registered as a processing step.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HookConfig {
+    pub id: String,
+    pub name: Option<String>,
+    /// Program and arguments; `{input}`, `{output}` and `{source}` are substituted.
+    pub command: Vec<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            version: CONFIG_VERSION,
+            unlock: UnlockConfig::default(),
+            profiles: Vec::new(),
+            active_profile: None,
+            ui: UiConfig::default(),
+            update: UpdateConfig::default(),
+            ocr: OcrConfig::default(),
+            page_numbers: PageNumbersConfig::default(),
+            watermark: WatermarkConfig::default(),
+            letterhead: LetterheadConfig::default(),
+            crop: CropConfig::default(),
+            sign: SignConfig::default(),
+            encrypt: Permissions::default(),
+            hooks: Vec::new(),
+            read_only: false,
+        }
+    }
+}
+
+impl Default for UnlockConfig {
+    fn default() -> Self {
+        Self {
+            concurrency: default_concurrency(),
+            timeout_secs: DEFAULT_TIMEOUT.as_secs(),
+            pipeline: vec!["decrypt".to_string()],
+            output_dir: None,
+            beside_source: false,
+            preserve_attributes: false,
+            copy_unrestricted: false,
+            split_by_bookmarks: false,
+            batch_folders: false,
+            originals: Originals::Keep,
+            suffix: None,
+            on_conflict: ConflictPolicy::Rename,
+            max_file_size_mb: 0,
+            large_files: LargeFiles::Warn,
+        }
+    }
+}
+
+impl Config {
+    pub fn dir() -> Option<PathBuf> {
+        dirs::config_dir().map(|dir| dir.join("crackleaf"))
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        Self::dir().map(|dir| dir.join(CONFIG_FILE))
+    }
+
+    /// Reads the config, migrating older layouts. Falls back to defaults (and writes
+    /// them) when the file is missing; a corrupt file is kept aside as `config.toml.bak`.
+    pub fn load() -> Self {
+        let Some(path) = Self::path() else {
+            return Self::default();
+        };
+        let text = match std::fs::read_to_string(&path) {
+            Ok(text) => text,
+            Err(_) => {
+                let config = Self::default();
+                if let Err(err) = config.save() {
+                    warn!("Failed to write default config: {err}");
+                }
+                return config;
+            }
+        };
+
+        match Self::parse(&text) {
+            Ok((config, migrated)) => {
+                if migrated {
+                    info!(version = CONFIG_VERSION, "config migrated");
+                    if let Err(err) = config.save() {
+                        warn!("Failed to save migrated config: {err}");
+                    }
+                }
+                config
+            }
+            Err(err) => {
+                warn!("Invalid config {:?}: {err}", path);
+                let _ = std::fs::rename(&path, path.with_extension("toml.bak"));
+                Self::default()
+            }
+        }
+    }
+
+    fn parse(text: &str) -> Result<(Self, bool)> {
+        let mut table: toml::Table = text.parse()?;
+        let version = table
+            .get("version")
+            .and_then(|v| v.as_integer())
+            .unwrap_or(0)
+            .max(0) as u32;
+
+        let migrated = version < CONFIG_VERSION;
+        if migrated {
+            migrate(&mut table, version);
+        }
+        let mut config: Self = table.try_into()?;
+        if version > CONFIG_VERSION {
+            warn!(version, "config written by a newer version, not saving changes");
+            config.read_only = true;
+        }
+        Ok((config, migrated))
+    }
+
+    /// Writes the config atomically through a temporary file.
+    pub fn save(&self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let path = Self::path().ok_or_else(|| anyhow!("无法定位配置目录"))?;
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let text = toml::to_string_pretty(self)?;
+        let tmp = path.with_extension("toml.tmp");
+        std::fs::write(&tmp, text)?;
+        std::fs::rename(&tmp, &path)?;
+        Ok(())
+    }
+
+    /// Built-in steps plus configured hooks and, with `dynamic-steps`, plugins from
+    /// `<config dir>/crackleaf/plugins`.
+    pub fn step_registry(&self) -> StepRegistry {
+        let mut registry = StepRegistry::with_builtin();
+        if let Some(languages) = &self.ocr.languages {
+            registry.register(Arc::new(Ocr::new(languages)));
+        }
+        let numbers = &self.page_numbers;
+        registry.register(Arc::new(PageNumbers::new(numbers.position, numbers.font_size, numbers.start)));
+        let mark = &self.watermark;
+        let (content, size) = match &mark.image {
+            Some(image) => (WatermarkContent::Image(image.clone()), mark.image_width),
+            None => (WatermarkContent::Text(mark.text.clone()), mark.font_size),
+        };
+        registry.register(Arc::new(Watermark::new(content, mark.position, mark.opacity, size, mark.angle)));
+        let letterhead = &self.letterhead;
+        registry.register(Arc::new(Letterhead::new(letterhead.file.clone(), letterhead.mode)));
+        registry.register(Arc::new(CropMargins::new(self.crop.crop())));
+        let sign = &self.sign;
+        registry.register(Arc::new(Sign::new(
+            sign.certificate.clone(),
+            sign.password.clone(),
+            sign.visible,
+            sign.reason.clone(),
+        )));
+        for hook in &self.hooks {
+            let name = hook.name.clone().unwrap_or_else(|| hook.id.clone());
+            registry.register(Arc::new(HookStep::new(&hook.id, name, hook.command.clone())));
+        }
+        #[cfg(feature = "dynamic-steps")]
+        if let Some(dir) = Self::dir() {
+            for (path, err) in registry.load_plugins(&dir.join("plugins")) {
+                warn!("Failed to load plugin {:?}: {err}", path);
+            }
+        }
+        registry
+    }
+
+    /// Settings of the active profile, falling back to `unlock` when it no longer exists.
+    pub fn active(&self) -> &UnlockConfig {
+        self.active_profile
+            .as_deref()
+            .and_then(|name| self.profiles.iter().find(|profile| profile.name == name))
+            .map_or(&self.unlock, |profile| &profile.unlock)
+    }
+
+    pub fn active_mut(&mut self) -> &mut UnlockConfig {
+        let index = self.active_profile.as_deref().and_then(|name| {
+            self.profiles.iter().position(|profile| profile.name == name)
+        });
+        match index {
+            Some(index) => &mut self.profiles[index].unlock,
+            None => &mut self.unlock,
+        }
+    }
+
+    /// Selects a profile by name, or the default settings for `None`.
+    pub fn select_profile(&mut self, name: Option<&str>) -> Result<()> {
+        if let Some(name) = name {
+            if !self.profiles.iter().any(|profile| profile.name == name) {
+                return Err(anyhow!("未知的配置方案：{name}"));
+            }
+        }
+        self.active_profile = name.map(str::to_string);
+        Ok(())
+    }
+
+    pub fn unlock_options(&self) -> UnlockOptions {
+        let unlock = self.active();
+        let mut options = UnlockOptions {
+            concurrency: unlock.concurrency.max(1),
+            timeout: (unlock.timeout_secs > 0).then(|| Duration::from_secs(unlock.timeout_secs)),
+            pipeline: self.resolve_steps(&unlock.pipeline),
+            output_dir: unlock.output_dir.clone(),
+            beside_source: unlock.beside_source,
+            preserve_attributes: unlock.preserve_attributes,
+            copy_unchanged: unlock.copy_unrestricted,
+            split_by_bookmarks: unlock.split_by_bookmarks,
+            on_conflict: unlock.on_conflict,
+            ..UnlockOptions::default()
+        };
+        if let Some(suffix) = &unlock.suffix {
+            match check_suffix(suffix) {
+                Ok(()) => options.suffix = suffix.clone(),
+                Err(err) => warn!("Ignoring suffix {suffix:?}: {err}"),
+            }
+        }
+        options
+    }
+
+    /// Looks up step ids, skipping unknown ones with a warning.
+    pub fn resolve_steps(&self, ids: &[String]) -> Vec<Arc<dyn ProcessingStep>> {
+        let registry = self.step_registry();
+        ids.iter()
+            .filter_map(|id| {
+                let step = registry.get(id);
+                if step.is_none() {
+                    warn!("Unknown processing step {id:?}");
+                }
+                step
+            })
+            .collect()
+    }
+}
+
+/// Upgrades a table written by config version `from` to [`CONFIG_VERSION`], one step at a time.
+fn migrate(table: &mut toml::Table, from: u32) {
+    for version in from..CONFIG_VERSION {
+        match version {
+            // Files from before versioning share the v1 layout.
+            0 => {}
+            // `unlock.steps` ran after an implicit decrypt; it became the whole pipeline.
+            1 => {
+                if let Some(toml::Value::Table(unlock)) = table.get_mut("unlock") {
+                    let mut pipeline = vec![toml::Value::String("decrypt".to_string())];
+                    if let Some(toml::Value::Array(steps)) = unlock.remove("steps") {
+                        pipeline.extend(steps);
+                    }
+                    unlock.insert("pipeline".to_string(), toml::Value::Array(pipeline));
+                }
+            }
+            _ => unreachable!("missing config migration from v{version}"),
+        }
+    }
+    table.insert("version".to_string(), toml::Value::Integer(CONFIG_VERSION.into()));
+}
@@ -0,0 +1,165 @@
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use anyhow::{bail, Result};
+use crackleaf_core::{format_size, is_office_document, is_pdf};
+use percent_encoding::percent_decode_str;
+use tempfile::TempDir;
+use tracing::info;
+
+use crate::archive;
+
+/// Name used when neither the server nor the link gives one.
+const FALLBACK_NAME: &str = "download";
+/// Largest file accepted from a link.
+const MAX_DOWNLOAD_SIZE: u64 = 2 * 1024 * 1024 * 1024;
+
+/// A file downloaded from a link into a private folder, which is deleted when this
+/// is dropped.
+pub struct Downloaded {
+    pub url: String,
+    pub path: PathBuf,
+    _dir: TempDir,
+}
+
+/// `text` is an `http://` or `https://` link.
+pub fn is_url(text: &str) -> bool {
+    let text = text.trim();
+    ["http://", "https://"].iter().any(|scheme| {
+        text.get(..scheme.len()).is_some_and(|start| start.eq_ignore_ascii_case(scheme)) && text.len() > scheme.len()
+    })
+}
+
+/// The links in pasted text, which may hold several separated by whitespace.
+#[cfg(feature = "gui")]
+pub fn links(text: &str) -> Vec<String> {
+    text.split_whitespace().filter(|word| is_url(word)).map(str::to_string).collect()
+}
+
+/// The link in an Internet shortcut, i.e. a link dragged out of a browser onto the
+/// desktop: `.url` on Windows, `.webloc` on macOS.
+pub fn shortcut_url(path: &Path) -> Option<String> {
+    let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+    if extension != "url" && extension != "webloc" {
+        return None;
+    }
+    let text = std::fs::read_to_string(path).ok()?;
+    let url = if extension == "url" {
+        text.lines().find_map(|line| line.trim().strip_prefix("URL="))?
+    } else {
+        let start = text.find("<string>")? + "<string>".len();
+        let end = start + text[start..].find("</string>")?;
+        &text[start..end]
+    };
+    let url = url.trim().replace("&amp;", "&");
+    is_url(&url).then_some(url)
+}
+
+/// Downloads `url` to a temporary folder, calling `progress` with the bytes received
+/// so far and the total when the server tells it. The file is named as the server or
+/// the link suggests, with `.pdf` added when a PDF arrives under another name.
+///
+/// Fails when the file is larger than [`MAX_DOWNLOAD_SIZE`] or the connection ends
+/// before the announced length arrived.
+pub fn download(url: &str, mut progress: impl FnMut(u64, Option<u64>)) -> Result<Downloaded> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout_connect(Duration::from_secs(15))
+        .timeout_read(Duration::from_secs(60))
+        .build();
+    let response = agent
+        .get(url.trim())
+        .set("User-Agent", concat!("crackleaf/", env!("CARGO_PKG_VERSION")))
+        .call()?;
+    if response.content_type().eq_ignore_ascii_case("text/html") {
+        bail!("链接打开的是网页而不是文件，可能需要先登录");
+    }
+    let total: Option<u64> = response.header("Content-Length").and_then(|length| length.parse().ok());
+    if total.is_some_and(|total| total > MAX_DOWNLOAD_SIZE) {
+        bail!("文件超过 {} 的下载上限", format_size(MAX_DOWNLOAD_SIZE));
+    }
+    let name = response
+        .header("Content-Disposition")
+        .and_then(disposition_name)
+        .or_else(|| url_name(response.get_url()))
+        .unwrap_or_else(|| FALLBACK_NAME.to_string());
+
+    let dir = tempfile::Builder::new().prefix("crackleaf-download-").tempdir()?;
+    let mut path = dir.path().join(&name);
+    let mut file = File::create(&path)?;
+    let mut reader = response.into_reader();
+    let mut buffer = vec![0; 64 * 1024];
+    let mut received = 0;
+    progress(received, total);
+    loop {
+        let read = reader.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        file.write_all(&buffer[..read])?;
+        received += read as u64;
+        if received > MAX_DOWNLOAD_SIZE {
+            bail!("文件超过 {} 的下载上限", format_size(MAX_DOWNLOAD_SIZE));
+        }
+        progress(received, total);
+    }
+    drop(file);
+    if let Some(total) = total.filter(|&total| total != received) {
+        bail!("下载不完整：只收到 {received} / {total} 字节");
+    }
+
+    let known = path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("pdf"))
+        || is_office_document(&path)
+        || archive::is_zip(&path);
+    if !known && is_pdf(&path) {
+        let renamed = dir.path().join(format!("{name}.pdf"));
+        std::fs::rename(&path, &renamed)?;
+        path = renamed;
+    }
+    info!(url, path = %path.display(), bytes = received, "link downloaded");
+    Ok(Downloaded {
+        url: url.trim().to_string(),
+        path,
+        _dir: dir,
+    })
+}
+
+/// The file name in a `Content-Disposition` header, preferring the UTF-8
+/// `filename*=` form.
+fn disposition_name(header: &str) -> Option<String> {
+    let mut plain = None;
+    for param in header.split(';').map(str::trim) {
+        let Some((key, value)) = param.split_once('=') else {
+            continue;
+        };
+        match key.trim().to_ascii_lowercase().as_str() {
+            "filename*" => {
+                let encoded = value.trim().split("''").nth(1)?;
+                return sanitize(&percent_decode_str(encoded).decode_utf8_lossy());
+            }
+            "filename" => plain = sanitize(value.trim().trim_matches('"')),
+            _ => {}
+        }
+    }
+    plain
+}
+
+/// The last segment of the link's path, decoded.
+fn url_name(url: &str) -> Option<String> {
+    let path = url.split(['?', '#']).next()?;
+    let path = path.split_once("://").map_or(path, |(_, rest)| rest);
+    let (_, segment) = path.rsplit_once('/')?;
+    sanitize(&percent_decode_str(segment).decode_utf8_lossy())
+}
+
+/// `name` without folders and characters Windows does not allow in file names.
+fn sanitize(name: &str) -> Option<String> {
+    let name = name.rsplit(['/', '\\']).next()?;
+    let name: String = name
+        .chars()
+        .map(|c| if c.is_control() || "<>:\"|?*".contains(c) { '_' } else { c })
+        .collect();
+    let name = name.trim().trim_matches('.');
+    (!name.is_empty()).then(|| name.to_string())
+}
@@ -0,0 +1,112 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use anyhow::Result;
+use eframe::egui::{self, ColorImage, TextureHandle};
+use tracing::{debug, warn};
+
+/// Animation frame sets by key; each name is a PNG in the assets folder. Sets may
+/// share images, which are then decoded and uploaded once.
+const SETS: &[(&str, &[&str])] = &[
+    ("logo", &["crackleaf"]),
+    ("happy_loop", &["高兴1", "高兴2", "高兴3", "高兴4", "高兴3", "高兴2", "高兴1"]),
+    ("peck", &["啄1", "啄2"]),
+    ("success", &["成功1", "成功2", "成功3", "成功4", "成功5"]),
+    ("success_reverse", &["成功5", "成功4", "成功3", "成功2", "成功1"]),
+];
+
+/// Image shown when a set is unknown; never evicted.
+const LOGO: &str = "crackleaf";
+
+/// Frames not drawn for this long are released.
+const IDLE_EVICT: Duration = Duration::from_secs(30);
+const TRIM_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Cached {
+    texture: TextureHandle,
+    last_used: Instant,
+}
+
+/// Loads animation frames on first use and releases the ones that are no longer shown.
+pub struct FrameCache {
+    assets_dir: PathBuf,
+    textures: HashMap<&'static str, Cached>,
+    last_trim: Instant,
+}
+
+impl FrameCache {
+    pub fn new(assets_dir: &Path) -> Self {
+        Self {
+            assets_dir: assets_dir.to_path_buf(),
+            textures: HashMap::new(),
+            last_trim: Instant::now(),
+        }
+    }
+
+    fn names(key: &str) -> &'static [&'static str] {
+        SETS.iter()
+            .find(|(set, _)| *set == key)
+            .map_or(&[LOGO], |(_, names)| *names)
+    }
+
+    /// Number of frames in set `key`.
+    pub fn frame_count(key: &str) -> usize {
+        Self::names(key).len()
+    }
+
+    /// Frame `index` of set `key`, loading it if needed.
+    pub fn get(&mut self, ctx: &egui::Context, key: &str, index: usize) -> TextureHandle {
+        let names = Self::names(key);
+        let name = names[index.min(names.len() - 1)];
+        let now = Instant::now();
+        if let Some(cached) = self.textures.get_mut(name) {
+            cached.last_used = now;
+            return cached.texture.clone();
+        }
+
+        let path = self.assets_dir.join(format!("{name}.png"));
+        let texture = load_texture(ctx, &path, name).unwrap_or_else(|err| {
+            warn!("Failed to load {:?}: {err}", path);
+            load_placeholder(ctx, &format!("{name}_placeholder"))
+        });
+        debug!(name, "frame loaded");
+        self.textures.insert(
+            name,
+            Cached {
+                texture: texture.clone(),
+                last_used: now,
+            },
+        );
+        texture
+    }
+
+    /// Releases frames that have not been drawn for a while, or every frame but the
+    /// logo when `aggressive` (e.g. while the window is minimised).
+    pub fn trim(&mut self, aggressive: bool) {
+        if !aggressive && self.last_trim.elapsed() < TRIM_INTERVAL {
+            return;
+        }
+        self.last_trim = Instant::now();
+        let before = self.textures.len();
+        self.textures.retain(|name, cached| {
+            *name == LOGO || (!aggressive && cached.last_used.elapsed() < IDLE_EVICT)
+        });
+        if self.textures.len() < before {
+            debug!(released = before - self.textures.len(), "frames released");
+        }
+    }
+}
+
+fn load_texture(ctx: &egui::Context, path: &Path, name: &str) -> Result<TextureHandle> {
+    let image = image::open(path)?;
+    let size = [image.width() as usize, image.height() as usize];
+    let rgba = image.to_rgba8();
+    let color_image = ColorImage::from_rgba_unmultiplied(size, &rgba);
+    Ok(ctx.load_texture(name.to_string(), color_image, egui::TextureOptions::LINEAR))
+}
+
+fn load_placeholder(ctx: &egui::Context, name: &str) -> TextureHandle {
+    let image = ColorImage::new([64, 64], egui::Color32::from_rgb(200, 50, 50));
+    ctx.load_texture(name.to_string(), image, egui::TextureOptions::LINEAR)
+}
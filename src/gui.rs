@@ -0,0 +1,3100 @@
+use std::future::Future;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+use crackleaf_core::steps::{check_ocr_ready, check_sign_ready, parse_page_ranges, Encrypt, LetterheadMode, PdfKind, ProcessingStep};
+use crackleaf_core::{
+    block_on, check_qpdf_ready, check_suffix, compare_pdfs, extract_attachments, qpdf_windows_variant, Bookmark, ConflictPolicy, Job, JobState,
+    PdfDiff, PdfFacts, QpdfStatus, UnlockOptions, MAX_SUFFIX_LEN,
+};
+use eframe::egui::{self, Color32, Frame, IconData, TextureHandle, Vec2};
+use image::GenericImageView;
+use rfd::FileDialog;
+use tracing::{debug, info, warn};
+
+use crate::app::{AddSummary, AppCore, FileEntry};
+use crate::config::{Config, CropMode, DropFilter, LargeFiles, Originals};
+use crate::frames::FrameCache;
+use crate::i18n::{format_date, format_duration, format_size, t};
+use crate::icons::{icon_button, Icon};
+use crate::{archive, download, history, i18n, icons, passwords, resume, update, url_scheme};
+
+const WINDOW_WIDTH: f32 = 390.0;
+const WINDOW_HEIGHT: f32 = 560.0;
+/// Room for the mascot and the controls; the file list takes whatever is left.
+const WINDOW_MIN_HEIGHT: f32 = 390.0;
+/// Side of the mascot image, whatever the window or batch size.
+const LOGO_SIZE: f32 = WINDOW_WIDTH * 0.5;
+/// Size of the window in mini mode: the mascot and a counter under it.
+const MINI_WINDOW: Vec2 = Vec2::new(120.0, 140.0);
+const MINI_LOGO_SIZE: f32 = 88.0;
+const BACKGROUND: Color32 = Color32::from_rgb(0xFC, 0xF5, 0xEA);
+/// Fixed height of a file row, so the list can lay out only the visible rows.
+const ROW_HEIGHT: f32 = 24.0;
+/// How long a toast stays on screen.
+const TOAST_DURATION: Duration = Duration::from_secs(4);
+
+/// Where the window is in the add → unlock → result cycle. The animation follows the
+/// state: every transition goes through [`CrackLeafApp::transition`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum AppState {
+    /// No files; shows the logo.
+    Idle,
+    /// Files are listed and can be unlocked.
+    FilesLoaded,
+    /// A batch is running.
+    Processing,
+    /// The result animation is playing; `failed` plays it backwards.
+    ShowingResult { failed: bool },
+}
+
+/// Peck loops played before the result is shown, even for instant batches.
+const MIN_PECK_LOOPS: u32 = 2;
+
+struct AnimationState {
+    frame_index: usize,
+    /// Complete loops of the current frame set.
+    loops: u32,
+    /// The pointer is over the logo, which pauses the happy loop.
+    hovered: bool,
+}
+
+/// Whether an external tool a stage needs can run, probed in the background so that
+/// starting a batch does not wait for it.
+#[derive(Default)]
+struct ToolCheck {
+    /// `Err` says why the tool cannot be used; `None` until a probe has finished.
+    ready: Option<Result<(), String>>,
+    rx: Option<mpsc::Receiver<Result<(), String>>>,
+}
+
+impl ToolCheck {
+    /// Picks up the outcome of a running probe, or starts `probe` on a worker thread
+    /// when there is neither an outcome nor a probe running.
+    fn update<F>(&mut self, ctx: &egui::Context, probe: fn() -> F)
+    where
+        F: Future<Output = anyhow::Result<String>> + Send + 'static,
+    {
+        if let Some(ready) = self.rx.as_ref().and_then(|rx| rx.try_recv().ok()) {
+            self.ready = Some(ready);
+            self.rx = None;
+        }
+        if self.ready.is_some() || self.rx.is_some() {
+            return;
+        }
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(block_on(probe()).map(drop).map_err(|err| err.to_string()));
+            ctx.request_repaint();
+        });
+        self.rx = Some(rx);
+    }
+
+    /// Outcome of the last probe, `None` while there is none yet. A failed outcome is
+    /// forgotten, so the tool is checked again in case it has been installed since.
+    fn outcome(&mut self) -> Option<Result<(), String>> {
+        let outcome = self.ready.take()?;
+        if outcome.is_ok() {
+            self.ready = Some(Ok(()));
+        }
+        Some(outcome)
+    }
+}
+
+struct CrackLeafApp {
+    frames: FrameCache,
+    /// The window was minimised last frame.
+    minimized: bool,
+    core: AppCore,
+    /// 0 for the main window, counting up for the extra batch windows.
+    number: usize,
+    /// Extra batch windows, each with its own list and queue; only the main window has any.
+    windows: Vec<CrackLeafApp>,
+    /// The "新窗口" button was clicked.
+    new_window_requested: bool,
+    state: AppState,
+    animation: AnimationState,
+    last_frame_time: Instant,
+    frame_interval: Duration,
+    /// Unlock was requested while files were still being analysed, or the tools its
+    /// stages need were still being checked.
+    start_when_analyzed: bool,
+    /// OCRmyPDF, checked while the active pipeline has the OCR stage.
+    ocr_check: ToolCheck,
+    /// pyHanko, checked while the active pipeline has the signing stage.
+    sign_check: ToolCheck,
+    /// Another output folder was already asked for during this batch.
+    output_fallback_offered: bool,
+    qpdf_prompted: bool,
+    update_rx: Option<mpsc::Receiver<UpdateEvent>>,
+    update: Option<update::Release>,
+    update_window_open: bool,
+    update_status: Option<String>,
+    history_window_open: bool,
+    history_records: Vec<history::Record>,
+    queue_window_open: bool,
+    passwords_window_open: bool,
+    saved_passwords: Vec<passwords::Saved>,
+    /// Short message floating over the window, and when it appeared.
+    toast: Option<(String, Instant)>,
+    /// Typed into the password prompt of the pending archive.
+    archive_password: String,
+    /// The last password tried for the pending archive was wrong.
+    archive_password_wrong: bool,
+    pack_window_open: bool,
+    /// Optional password for the results archive.
+    pack_password: String,
+    /// Outcome of the results archive being written in the background.
+    pack_rx: Option<mpsc::Receiver<anyhow::Result<PathBuf>>>,
+    /// Toast saying where the embedded files being saved in the background went.
+    attachments_rx: Option<mpsc::Receiver<String>>,
+    /// Toasts from work done off the UI thread.
+    toast_tx: mpsc::Sender<String>,
+    toast_rx: mpsc::Receiver<String>,
+    download_tx: mpsc::Sender<DownloadEvent>,
+    download_rx: mpsc::Receiver<DownloadEvent>,
+    /// Links queued or being downloaded.
+    downloads_running: usize,
+    /// Link being downloaded, bytes received and the total when known.
+    download_progress: Option<(String, u64, Option<u64>)>,
+    /// Entry whose "提取页面" dialog is open.
+    extract_pages: Option<usize>,
+    /// Page ranges typed into that dialog.
+    extract_ranges: String,
+    /// Why the typed ranges were rejected.
+    extract_error: Option<String>,
+    /// Entry whose bookmarks are shown.
+    outline_view: Option<usize>,
+    compare_window_open: bool,
+    /// The two files to compare; files dropped while the window is open go here.
+    compare_files: [Option<PathBuf>; 2],
+    /// Outcome of the comparison running in the background.
+    compare_rx: Option<mpsc::Receiver<anyhow::Result<PdfDiff>>>,
+    /// Outcome of the last comparison, or why it failed.
+    compare_result: Option<Result<PdfDiff, String>>,
+    protect_window_open: bool,
+    /// Folder whose PDFs the protect window lists for encryption.
+    protect_folder: Option<PathBuf>,
+    /// Owner password typed into the protect window, and its repetition.
+    protect_password: String,
+    protect_confirm: String,
+}
+
+/// Progress of links being downloaded in the background.
+enum DownloadEvent {
+    Progress(String, u64, Option<u64>),
+    Finished(String, anyhow::Result<download::Downloaded>),
+}
+
+/// How a status badge is drawn; each also has its own shape, so it reads without color.
+#[derive(Clone, Copy)]
+enum Tone {
+    Good,
+    Warning,
+    Bad,
+    Neutral,
+}
+
+/// Buttons and menu items on a file row.
+enum RowAction {
+    Print(PathBuf),
+    Share(PathBuf),
+    /// Open the "提取页面" dialog for the entry at this index.
+    ExtractPages(usize),
+    Letterhead(usize),
+    ExtractAttachments(usize),
+    Outline(usize),
+    /// Compare the source of the entry at this index with its result.
+    Compare(usize),
+    /// Let the entry at this index through the size limit, or not.
+    AllowLarge(usize, bool),
+}
+
+/// Results of background update work, delivered to the UI thread.
+enum UpdateEvent {
+    Available(update::Release),
+    Installed,
+    Failed(String),
+}
+
+impl CrackLeafApp {
+    fn new(cc: &eframe::CreationContext<'_>, launch: LaunchRequest) -> Self {
+        let assets_dir = resolve_assets_dir();
+        apply_custom_font(&cc.egui_ctx, &assets_dir);
+        let config = Config::load();
+        i18n::init(&assets_dir, config.ui.language.as_deref());
+        if config.ui.mini {
+            apply_window_mode(&cc.egui_ctx, true);
+        }
+        apply_theme(&cc.egui_ctx, config.ui.high_contrast);
+        let qpdf_status = block_on(check_qpdf_ready());
+        let mut app = Self::with_core(AppCore::new(config, qpdf_status), FrameCache::new(&assets_dir), 0);
+        let ctx = cc.egui_ctx.clone();
+        app.core.set_waker(move || ctx.request_repaint());
+        #[cfg(target_os = "macos")]
+        {
+            let ctx = cc.egui_ctx.clone();
+            url_scheme::set_waker(move || ctx.request_repaint());
+        }
+        if app.core.config.update.check_on_startup {
+            app.check_for_update(&cc.egui_ctx);
+        }
+
+        let resuming = launch.paths.is_empty() && app.offer_resume();
+        app.add_files(launch.paths);
+        if resuming {
+            app.start_unlock();
+        }
+        if let Some(err) = launch.error {
+            app.core.result_text = err;
+        }
+        app
+    }
+
+    /// Window number `number` (0 for the main window) around `core`, with nothing
+    /// listed or open yet.
+    fn with_core(core: AppCore, frames: FrameCache, number: usize) -> Self {
+        let (toast_tx, toast_rx) = mpsc::channel();
+        let (download_tx, download_rx) = mpsc::channel();
+        Self {
+            frames,
+            minimized: false,
+            core,
+            number,
+            windows: Vec::new(),
+            new_window_requested: false,
+            state: AppState::Idle,
+            animation: AnimationState {
+                frame_index: 0,
+                loops: 0,
+                hovered: false,
+            },
+            last_frame_time: Instant::now(),
+            frame_interval: Duration::from_millis(150),
+            start_when_analyzed: false,
+            ocr_check: ToolCheck::default(),
+            sign_check: ToolCheck::default(),
+            output_fallback_offered: false,
+            qpdf_prompted: false,
+            update_rx: None,
+            update: None,
+            update_window_open: false,
+            update_status: None,
+            history_window_open: false,
+            history_records: Vec::new(),
+            queue_window_open: false,
+            passwords_window_open: false,
+            saved_passwords: Vec::new(),
+            toast: None,
+            archive_password: String::new(),
+            archive_password_wrong: false,
+            pack_window_open: false,
+            pack_password: String::new(),
+            pack_rx: None,
+            attachments_rx: None,
+            toast_tx,
+            toast_rx,
+            download_tx,
+            download_rx,
+            downloads_running: 0,
+            download_progress: None,
+            extract_pages: None,
+            extract_ranges: String::new(),
+            extract_error: None,
+            outline_view: None,
+            compare_window_open: false,
+            compare_files: [None, None],
+            compare_rx: None,
+            compare_result: None,
+            protect_window_open: false,
+            protect_folder: None,
+            protect_password: String::new(),
+            protect_confirm: String::new(),
+        }
+    }
+
+    /// Opens another batch window with its own list and queue. Its batches are not
+    /// offered for resuming, which is the main window's.
+    fn open_window(&mut self, ctx: &egui::Context) {
+        let number = self.windows.iter().map(|window| window.number).max().unwrap_or(0) + 1;
+        let mut config = Config::load();
+        config.ui.mini = false;
+        let qpdf_status = QpdfStatus {
+            ok: self.core.qpdf_ok,
+            error: self.core.qpdf_error.clone(),
+            version: None,
+            warning: self.core.qpdf_warning.clone(),
+        };
+        let mut core = AppCore::new(config, qpdf_status);
+        core.records_session = false;
+        let mut window = Self::with_core(core, FrameCache::new(&resolve_assets_dir()), number);
+        // Extra windows are drawn as part of the main one.
+        let ctx = ctx.clone();
+        window.core.set_waker(move || ctx.request_repaint_of(egui::ViewportId::ROOT));
+        window.qpdf_prompted = true;
+        info!(number, "opened batch window");
+        self.windows.push(window);
+    }
+
+    /// Draws the extra batch windows, opening one when asked and dropping those that
+    /// were closed.
+    fn draw_windows(&mut self, ctx: &egui::Context) {
+        let mut requested = std::mem::take(&mut self.new_window_requested);
+        for window in &mut self.windows {
+            requested |= std::mem::take(&mut window.new_window_requested);
+        }
+        if requested {
+            self.open_window(ctx);
+        }
+        self.windows.retain_mut(|window| {
+            let builder = egui::ViewportBuilder::default()
+                .with_title(format!("CrackLeaf ({})", window.number + 1))
+                .with_inner_size(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT))
+                .with_min_inner_size(Vec2::new(WINDOW_WIDTH, WINDOW_MIN_HEIGHT));
+            let id = egui::ViewportId::from_hash_of(("batch-window", window.number));
+            ctx.show_viewport_immediate(id, builder, |ctx, _| {
+                window.ui(ctx);
+                !ctx.input(|i| i.viewport().close_requested()) || window.may_close(ctx)
+            })
+        });
+    }
+
+    /// Closing a window cancels its batch, so that is confirmed first.
+    fn may_close(&self, ctx: &egui::Context) -> bool {
+        if self.state != AppState::Processing {
+            return true;
+        }
+        let answer = rfd::MessageDialog::new()
+            .set_title(t!("window-close-title"))
+            .set_description(t!("window-close-prompt"))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .set_level(rfd::MessageLevel::Warning)
+            .show();
+        if answer == rfd::MessageDialogResult::Yes {
+            return true;
+        }
+        ctx.send_viewport_cmd(egui::ViewportCommand::CancelClose);
+        false
+    }
+
+    /// Asks whether to continue a batch interrupted by a crash or quit, loading its
+    /// remaining files if so.
+    fn offer_resume(&mut self) -> bool {
+        let pending = resume::pending();
+        if pending.is_empty() {
+            return false;
+        }
+        let answer = rfd::MessageDialog::new()
+            .set_title(t!("resume-title"))
+            .set_description(t!("resume-prompt", count = pending.len()))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .set_level(rfd::MessageLevel::Info)
+            .show();
+        if answer != rfd::MessageDialogResult::Yes {
+            resume::discard();
+            return false;
+        }
+        info!(files = pending.len(), "resuming interrupted batch");
+        self.add_files(pending);
+        self.core.qpdf_ok && !self.core.entries().is_empty()
+    }
+
+    /// Frame set for the current state.
+    fn animation_key(&self) -> &'static str {
+        match self.state {
+            AppState::Idle => "logo",
+            AppState::FilesLoaded if self.animation.hovered => "logo",
+            AppState::FilesLoaded => "happy_loop",
+            AppState::Processing => "peck",
+            AppState::ShowingResult { failed: false } => "success",
+            AppState::ShowingResult { failed: true } => "success_reverse",
+        }
+    }
+
+    fn current_texture(&mut self, ctx: &egui::Context) -> TextureHandle {
+        self.frames.get(ctx, self.animation_key(), self.animation.frame_index)
+    }
+
+    fn transition(&mut self, next: AppState) {
+        if self.state == next {
+            return;
+        }
+        debug!(from = ?self.state, to = ?next, "state");
+        self.state = next;
+        self.animation.frame_index = 0;
+        self.animation.loops = 0;
+    }
+
+    fn set_logo_hovered(&mut self, hovered: bool) {
+        if self.animation.hovered != hovered {
+            self.animation.hovered = hovered;
+            self.animation.frame_index = 0;
+        }
+    }
+
+    /// An unlock batch is running or its result animation is still playing.
+    fn is_busy(&self) -> bool {
+        matches!(self.state, AppState::Processing | AppState::ShowingResult { .. })
+    }
+
+    /// One row of the file list; returns the action of the button clicked on it, if any.
+    fn draw_file_row(&self, ui: &mut egui::Ui, index: usize, job: Option<&Job>, row_width: f32) -> Option<RowAction> {
+        let entry = &self.core.entries()[index];
+        let stage = job.and_then(|job| job.stage.as_deref());
+        let (icon, status) = match (job.map(|job| job.state), stage) {
+            (Some(JobState::Queued), _) => (Icon::Waiting, t!("status-queued")),
+            (Some(JobState::Analyzing), _) => (Icon::Searching, t!("status-analyzing")),
+            (Some(JobState::Running), Some(stage)) => (Icon::Working, t!("status-stage", stage = stage)),
+            (Some(JobState::Running), None) => (Icon::Working, t!("status-running")),
+            (Some(JobState::Cancelled), _) => (Icon::Cancelled, t!("status-cancelled")),
+            (Some(JobState::Failed), Some(stage)) => {
+                let error = job
+                    .and_then(|job| job.error.as_ref())
+                    .map(i18n::error_message)
+                    .unwrap_or_default();
+                let status = t!(
+                    "status-failed-at",
+                    status = entry.status.as_str(),
+                    stage = stage,
+                    error = error
+                );
+                (entry.icon, status)
+            }
+            _ => (entry.icon, entry.status.clone()),
+        };
+        let mut filename = entry
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy();
+        if let Some(archive) = entry.archive.as_ref().and_then(|archive| archive.file_name()) {
+            filename = format!("{} › {filename}", archive.to_string_lossy()).into();
+        }
+
+        let icon_width = 24.0;
+        let code_width = 56.0;
+        let button_width = 40.0;
+        let spacing = 8.0;
+        let page_mismatch = entry.page_mismatch();
+        let badge_width = 72.0;
+        let kind = entry.kind();
+        let kind_width = if kind.is_some() { 40.0 + spacing } else { 0.0 };
+        let covered = entry.covered_text_pages();
+        let signed = !entry.signatures().is_empty();
+        let badges = usize::from(entry.slow_volume)
+            + usize::from(page_mismatch.is_some())
+            + usize::from(!covered.is_empty())
+            + usize::from(signed);
+        // "分享" opens the native share sheet, which only macOS has.
+        let buttons = if cfg!(target_os = "macos") { 3.0 } else { 2.0 };
+        let text_width = (row_width - icon_width - code_width - button_width * buttons - badge_width * badges as f32 - kind_width
+            - (spacing * (buttons + 2.0)))
+            .max(120.0);
+        let mut action = None;
+
+        ui.allocate_ui_with_layout(
+            Vec2::new(row_width, ROW_HEIGHT),
+            egui::Layout::left_to_right(egui::Align::Center),
+            |ui| {
+                ui.spacing_mut().item_spacing = Vec2::new(spacing, 4.0);
+                icons::icon(ui, icon, Vec2::new(icon_width, ROW_HEIGHT)).on_hover_text(&status);
+                let (code, tone) = status_code(entry, job.map(|job| job.state));
+                ui.allocate_ui_with_layout(
+                    Vec2::new(code_width, ROW_HEIGHT),
+                    egui::Layout::centered_and_justified(egui::Direction::LeftToRight),
+                    |ui| draw_code(ui, &t!(code), tone, self.core.config.ui.high_contrast),
+                )
+                .response
+                .on_hover_text(&status);
+                ui.add_space(spacing);
+                let label = egui::Label::new(filename.as_ref()).truncate().sense(egui::Sense::click());
+                ui.add_sized(Vec2::new(text_width, ROW_HEIGHT), label)
+                    .on_hover_ui(|ui| {
+                        ui.label(format!("{filename}\n{status}"));
+                        if let Some(security) = entry.security() {
+                            ui.label(security);
+                        }
+                        if let Some(blank) = entry.facts.as_ref().map(|facts| &facts.blank_pages).filter(|blank| !blank.is_empty()) {
+                            let pages: Vec<String> = blank.iter().map(u32::to_string).collect();
+                            ui.label(t!("blank-pages-hint", count = blank.len(), pages = pages.join(", ")));
+                        }
+                        let attachments = entry.attachments();
+                        if !attachments.is_empty() {
+                            ui.label(t!("attachments-hint", count = attachments.len(), names = attachments.join(", ")));
+                        }
+                        if let (Some(source), Some(output)) = (&entry.facts, &entry.output_facts) {
+                            ui.separator();
+                            draw_comparison(ui, source, output);
+                        }
+                    })
+                    .context_menu(|ui| {
+                        let enabled = self.core.can_rework(index);
+                        if ui.add_enabled(enabled, egui::Button::new(t!("extract-pages-menu"))).clicked() {
+                            action = Some(RowAction::ExtractPages(index));
+                            ui.close_menu();
+                        }
+                        if ui.add_enabled(enabled, egui::Button::new(t!("letterhead-menu"))).clicked() {
+                            action = Some(RowAction::Letterhead(index));
+                            ui.close_menu();
+                        }
+                        let enabled = !entry.outline().is_empty();
+                        if ui.add_enabled(enabled, egui::Button::new(t!("outline-menu"))).clicked() {
+                            action = Some(RowAction::Outline(index));
+                            ui.close_menu();
+                        }
+                        let enabled = entry.output_path.as_ref().is_some_and(|output| *output != entry.path && output.exists());
+                        if ui.add_enabled(enabled, egui::Button::new(t!("compare-menu"))).clicked() {
+                            action = Some(RowAction::Compare(index));
+                            ui.close_menu();
+                        }
+                        let enabled = self.core.can_extract_attachments(index) && self.attachments_rx.is_none();
+                        if ui.add_enabled(enabled, egui::Button::new(t!("attachments-menu"))).clicked() {
+                            action = Some(RowAction::ExtractAttachments(index));
+                            ui.close_menu();
+                        }
+                        let limit = self.core.config.active().size_limit();
+                        if entry.allow_large || limit.is_some_and(|limit| entry.size().is_some_and(|size| size > limit)) {
+                            let mut allow = entry.allow_large;
+                            if ui.checkbox(&mut allow, t!("large-allow-menu")).clicked() {
+                                action = Some(RowAction::AllowLarge(index, allow));
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                if let Some(kind) = kind {
+                    let (label, hint) = match kind {
+                        PdfKind::Text => ("kind-text", "kind-text-hint"),
+                        PdfKind::Scanned => ("kind-scanned", "kind-scanned-hint"),
+                    };
+                    let mut badge = egui::RichText::new(t!(label)).small();
+                    if kind == PdfKind::Text {
+                        badge = badge.weak();
+                    }
+                    ui.add_sized(Vec2::new(40.0, ROW_HEIGHT), egui::Label::new(badge).truncate())
+                        .on_hover_text(t!(hint));
+                }
+                if entry.slow_volume {
+                    let badge = egui::RichText::new(t!("status-slow-volume"))
+                        .small()
+                        .color(ui.visuals().warn_fg_color);
+                    ui.add_sized(Vec2::new(badge_width, ROW_HEIGHT), egui::Label::new(badge).truncate())
+                        .on_hover_text(t!("status-slow-volume-hint"));
+                }
+                if let Some((source, output)) = page_mismatch {
+                    let badge = egui::RichText::new(t!("status-page-mismatch"))
+                        .small()
+                        .color(ui.visuals().warn_fg_color);
+                    ui.add_sized(Vec2::new(badge_width, ROW_HEIGHT), egui::Label::new(badge).truncate())
+                        .on_hover_text(t!("status-page-mismatch-hint", source = source, output = output));
+                }
+                if !covered.is_empty() {
+                    let badge = egui::RichText::new(t!("status-covered-text"))
+                        .small()
+                        .color(ui.visuals().warn_fg_color);
+                    let pages: Vec<String> = covered.iter().map(u32::to_string).collect();
+                    ui.add_sized(Vec2::new(badge_width, ROW_HEIGHT), egui::Label::new(badge).truncate())
+                        .on_hover_text(t!("status-covered-text-hint", count = covered.len(), pages = pages.join(", ")));
+                }
+                if signed {
+                    let badge = egui::RichText::new(t!("status-signed"))
+                        .small()
+                        .color(ui.visuals().warn_fg_color);
+                    ui.add_sized(Vec2::new(badge_width, ROW_HEIGHT), egui::Label::new(badge).truncate())
+                        .on_hover_text(t!("status-signed-hint", signers = signers(entry)));
+                }
+                ui.add_space(spacing);
+                if entry.output_path.is_some() {
+                    if ui
+                        .add_sized(Vec2::new(button_width, ROW_HEIGHT), egui::Button::new(t!("open-button")))
+                        .clicked()
+                    {
+                        open_entry(entry);
+                    }
+                } else {
+                    ui.allocate_space(Vec2::new(button_width, ROW_HEIGHT));
+                }
+                let output = entry.output_path.as_ref().filter(|path| path.exists());
+                if let Some(path) = output {
+                    if ui
+                        .add_sized(Vec2::new(button_width, ROW_HEIGHT), egui::Button::new(t!("print-button")))
+                        .on_hover_text(t!("print-hint"))
+                        .clicked()
+                    {
+                        action = Some(RowAction::Print(path.clone()));
+                    }
+                } else {
+                    ui.allocate_space(Vec2::new(button_width, ROW_HEIGHT));
+                }
+                if cfg!(target_os = "macos") {
+                    if let Some(path) = output {
+                        if ui
+                            .add_sized(Vec2::new(button_width, ROW_HEIGHT), egui::Button::new(t!("share-button")))
+                            .on_hover_text(t!("share-hint"))
+                            .clicked()
+                        {
+                            action = Some(RowAction::Share(path.clone()));
+                        }
+                    } else {
+                        ui.allocate_space(Vec2::new(button_width, ROW_HEIGHT));
+                    }
+                }
+            },
+        );
+        action
+    }
+
+    /// Advances the animation when its next frame is due.
+    fn tick_animation(&mut self) {
+        let key = self.animation_key();
+        if key == "logo" || self.last_frame_time.elapsed() < self.frame_interval {
+            return;
+        }
+        self.last_frame_time = Instant::now();
+
+        let frame_count = FrameCache::frame_count(key);
+        if frame_count == 0 {
+            return;
+        }
+
+        self.animation.frame_index = (self.animation.frame_index + 1) % frame_count;
+        if self.animation.frame_index == 0 {
+            self.animation.loops += 1;
+            match self.state {
+                AppState::Processing => self.maybe_show_result(),
+                AppState::ShowingResult { .. } => self.transition(self.resting_state()),
+                AppState::Idle | AppState::FilesLoaded => {}
+            }
+        }
+    }
+
+    /// Wakes the UI when the next animation frame is due. Everything else repaints
+    /// on input or through the core's waker, so an idle window does not redraw.
+    fn schedule_repaint(&self, ctx: &egui::Context) {
+        if self.animation_key() != "logo" {
+            ctx.request_repaint_after(self.frame_interval.saturating_sub(self.last_frame_time.elapsed()));
+        }
+    }
+
+    /// Buttons, pickers and messages along the bottom of the window.
+    fn draw_controls(&mut self, ui: &mut egui::Ui) {
+        ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+            ui.add_space(8.0);
+            self.draw_pipeline_editor(ui);
+            self.draw_profile_picker(ui);
+            self.draw_language_picker(ui);
+            self.draw_drop_filter(ui);
+
+            // Shared by every window, so it is only offered in the main one.
+            let mut high_contrast = self.core.config.ui.high_contrast;
+            if self.number == 0 && ui.toggle_value(&mut high_contrast, t!("high-contrast-button")).changed() {
+                apply_theme(ui.ctx(), high_contrast);
+                self.core.config.ui.high_contrast = high_contrast;
+                if let Err(err) = self.core.config.save() {
+                    warn!("Failed to save config: {err}");
+                }
+            }
+
+            if self.core.history().is_some() && ui.button(t!("history-button")).clicked() {
+                self.open_history_window();
+            }
+
+            if self.core.config.ui.remember_passwords && ui.button(t!("passwords-button")).clicked() {
+                self.saved_passwords = passwords::list();
+                self.passwords_window_open = true;
+            }
+
+            if ui.button(t!("mini-button")).on_hover_text(t!("mini-hint")).clicked() {
+                let ctx = ui.ctx().clone();
+                self.set_mini(&ctx, true);
+            }
+
+            if ui.button(t!("new-window-button")).on_hover_text(t!("new-window-hint")).clicked() {
+                self.new_window_requested = true;
+            }
+
+            if self.core.queue().is_some() && ui.button(t!("queue-button")).clicked() {
+                self.queue_window_open = true;
+            }
+
+            if ui.button(t!("compare-button")).clicked() {
+                self.compare_window_open = true;
+            }
+
+            if !self.is_busy() && ui.button(t!("protect-button")).clicked() {
+                self.protect_window_open = true;
+            }
+
+            if let Some(release) = &self.update {
+                let label = t!("update-available", version = release.version.to_string());
+                if ui.link(label).clicked() {
+                    self.update_window_open = true;
+                }
+            }
+
+            let locked = self.core.locked().len();
+            if locked > 0 && !self.is_busy() && ui.button(t!("retry-locked-button", count = locked)).clicked() {
+                let options = self.unlock_options();
+                if self.core.retry_locked(options) {
+                    self.transition(AppState::Processing);
+                }
+            }
+
+            if !self.core.entries().is_empty() && !self.is_busy() && ui.button(t!("clear-button")).clicked() {
+                self.core.clear();
+                self.start_when_analyzed = false;
+                self.transition(AppState::Idle);
+            }
+
+            if self.core.can_undo() && !self.is_busy() && ui.button(t!("undo-button")).clicked() {
+                self.core.undo_last_batch();
+            }
+
+            if self.core.has_results()
+                && !self.is_busy()
+                && self.pack_rx.is_none()
+                && ui.button(t!("pack-button")).clicked()
+            {
+                self.pack_window_open = true;
+            }
+
+            if !self.core.qpdf_ok {
+                if let Some(msg) = &self.core.qpdf_error {
+                    ui.label(msg);
+                }
+            } else if let Some(msg) = &self.core.qpdf_warning {
+                ui.label(msg);
+            }
+
+            if let Some((url, received, total)) = &self.download_progress {
+                let text = t!("download-progress", url = url.as_str(), size = format_size(*received));
+                let bar = match total {
+                    Some(total) if *total > 0 => egui::ProgressBar::new(*received as f32 / *total as f32),
+                    _ => egui::ProgressBar::new(0.0).animate(true),
+                };
+                ui.add(bar.text(text).desired_width(360.0));
+            }
+            if !self.core.result_text.is_empty() {
+                ui.label(&self.core.result_text);
+            }
+            ui.add_space(20.0);
+        });
+    }
+
+    /// The mascot, which adds files or starts the batch when clicked, and the hint
+    /// under it. Its size does not depend on the window or the batch.
+    fn draw_mascot(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let image = egui::Image::new(&self.current_texture(ctx)).fit_to_exact_size(Vec2::splat(LOGO_SIZE));
+        let response = ui.add(egui::ImageButton::new(image).frame(false));
+
+        self.set_logo_hovered(response.hovered());
+
+        if response.clicked() {
+            if self.state == AppState::Idle {
+                let picked = FileDialog::new()
+                    .add_filter("PDF, Office, ZIP", &["pdf", "docx", "docm", "xlsx", "xlsm", "zip"])
+                    .pick_files();
+                if let Some(paths) = picked {
+                    self.add_files(paths);
+                }
+            } else if self.state == AppState::FilesLoaded {
+                if self.core.qpdf_ok {
+                    self.start_unlock();
+                } else if let Some(msg) = &self.core.qpdf_error {
+                    self.core.result_text = msg.clone();
+                }
+            }
+        }
+
+        let hint = if self.core.entries().is_empty() {
+            t!("hint-empty")
+        } else if self.core.is_analyzing() {
+            t!("hint-analyzing", count = self.core.analysis_pending())
+        } else {
+            t!("hint-imported", count = self.core.entries().len())
+        };
+        ui.label(hint);
+    }
+
+    /// Mini mode: the mascot, which expands the window again when clicked, and how
+    /// many files are listed or, during a batch, finished.
+    fn draw_mini(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        let image = egui::Image::new(&self.current_texture(ctx)).fit_to_exact_size(Vec2::splat(MINI_LOGO_SIZE));
+        let response = ui.add(egui::ImageButton::new(image).frame(false)).on_hover_text(t!("mini-expand"));
+        self.set_logo_hovered(response.hovered());
+        if response.clicked() {
+            self.set_mini(ctx, false);
+        }
+        let count = match self.core.queue().filter(|_| self.is_busy()) {
+            Some(queue) => format!("{}/{}", queue.finished_count(), queue.len()),
+            None => self.core.entries().len().to_string(),
+        };
+        ui.label(count);
+    }
+
+    fn set_mini(&mut self, ctx: &egui::Context, mini: bool) {
+        apply_window_mode(ctx, mini);
+        self.core.config.ui.mini = mini;
+        // Only the main window comes back in mini mode.
+        if self.number == 0 {
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    /// The listed files in a collapsible section that scrolls within the space left
+    /// between the mascot and the controls, however long the list is. Returns the
+    /// result whose "打印" button was clicked.
+    fn draw_file_list(&self, ui: &mut egui::Ui) -> Option<RowAction> {
+        let entries = self.core.entries();
+        if entries.is_empty() {
+            return None;
+        }
+        let mut action = None;
+        let row_width = (ui.available_width() - 20.0).max(240.0);
+        egui::CollapsingHeader::new(t!("file-list-header", count = entries.len()))
+            .id_salt("file-list")
+            .default_open(true)
+            .show(ui, |ui| {
+                // Rows have a fixed height, so only the visible ones are built.
+                ui.spacing_mut().item_spacing = Vec2::new(0.0, 12.0);
+                egui::ScrollArea::vertical()
+                    .auto_shrink([false, true])
+                    .show_rows(ui, ROW_HEIGHT, entries.len(), |ui, rows| {
+                        for index in rows {
+                            let job = self.core.queue().and_then(|queue| queue.job(index));
+                            if let Some(clicked) = self.draw_file_row(ui, index, job, row_width) {
+                                action = Some(clicked);
+                            }
+                        }
+                    });
+            });
+        action
+    }
+
+    /// Downloads `urls` one after another in the background; each file is listed as
+    /// soon as it arrives.
+    fn download(&mut self, ctx: &egui::Context, urls: Vec<String>) {
+        if urls.is_empty() {
+            return;
+        }
+        info!(links = urls.len(), "downloading links");
+        self.downloads_running += urls.len();
+        let tx = self.download_tx.clone();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            for url in urls {
+                let mut last_repaint = Instant::now();
+                let result = download::download(&url, |received, total| {
+                    let _ = tx.send(DownloadEvent::Progress(url.clone(), received, total));
+                    if last_repaint.elapsed() >= Duration::from_millis(100) {
+                        last_repaint = Instant::now();
+                        ctx.request_repaint();
+                    }
+                });
+                let _ = tx.send(DownloadEvent::Finished(url, result));
+                ctx.request_repaint();
+            }
+        });
+    }
+
+    fn handle_toasts(&mut self) {
+        while let Ok(message) = self.toast_rx.try_recv() {
+            self.toast = Some((message, Instant::now()));
+        }
+    }
+
+    fn handle_download_events(&mut self) {
+        while let Ok(event) = self.download_rx.try_recv() {
+            match event {
+                DownloadEvent::Progress(url, received, total) => {
+                    self.download_progress = Some((url, received, total));
+                }
+                DownloadEvent::Finished(url, result) => {
+                    self.downloads_running -= 1;
+                    self.download_progress = None;
+                    match result {
+                        Ok(downloaded) => {
+                            let summary = self.core.add_download(downloaded);
+                            self.files_added(summary);
+                        }
+                        Err(err) => {
+                            warn!("Downloading {url} failed: {err}");
+                            self.toast = Some((t!("download-failed", error = err.to_string()), Instant::now()));
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    /// Sends an unlocked file to the printer and says so in a toast.
+    fn print(&mut self, path: &Path, ctx: &egui::Context) {
+        let path = path.to_path_buf();
+        let tx = self.toast_tx.clone();
+        let ctx = ctx.clone();
+        let print = move || {
+            let message = match print_file(&path) {
+                Ok(()) => t!("print-done", name = path.file_name().unwrap_or_default().to_string_lossy()),
+                Err(err) => {
+                    warn!("Printing {} failed: {err}", path.display());
+                    t!("print-failed", error = err.to_string())
+                }
+            };
+            let _ = tx.send(message);
+            ctx.request_repaint();
+        };
+        // `lp` waits for the spooler; ShellExecute returns at once and wants this thread's COM.
+        #[cfg(target_os = "windows")]
+        print();
+        #[cfg(not(target_os = "windows"))]
+        std::thread::spawn(print);
+    }
+
+    /// Opens the share sheet for an unlocked file; only failures are reported.
+    fn share(&mut self, path: &Path) {
+        if let Err(err) = share_file(path) {
+            warn!("Sharing {} failed: {err}", path.display());
+            self.toast = Some((t!("share-failed", error = err.to_string()), Instant::now()));
+        }
+    }
+
+    /// Asks for a letterhead PDF and puts it on the file at `index` alone.
+    fn apply_letterhead(&mut self, index: usize) {
+        let Some(file) = self.pick_letterhead() else {
+            return;
+        };
+        let options = self.unlock_options();
+        if self.core.apply_letterhead(index, file, options) {
+            self.transition(AppState::Processing);
+        }
+    }
+
+    /// File dialog for a letterhead PDF, opened next to the configured one.
+    fn pick_letterhead(&self) -> Option<PathBuf> {
+        let mut dialog = FileDialog::new()
+            .set_title(t!("letterhead-pick-title"))
+            .add_filter("PDF", &["pdf"]);
+        if let Some(dir) = self.core.config.letterhead.file.as_deref().and_then(Path::parent) {
+            dialog = dialog.set_directory(dir);
+        }
+        dialog.pick_file()
+    }
+
+    /// Saves the embedded files of the result at `index` next to it in the background.
+    fn extract_attachments(&mut self, index: usize, ctx: &egui::Context) {
+        let Some(output) = self.core.attachments_source(index) else {
+            return;
+        };
+        info!(index, "extracting attachments");
+        let timeout = self.core.config.unlock_options().timeout;
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let message = match block_on(extract_attachments(&output, timeout)) {
+                Ok(Some((dir, files))) => t!(
+                    "attachments-done",
+                    count = files.len(),
+                    folder = dir.file_name().unwrap_or_default().to_string_lossy()
+                ),
+                Ok(None) => t!("attachments-none"),
+                Err(err) => {
+                    warn!("Extracting attachments failed: {err}");
+                    t!("attachments-failed", error = err.to_string())
+                }
+            };
+            let _ = tx.send(message);
+            ctx.request_repaint();
+        });
+        self.attachments_rx = Some(rx);
+    }
+
+    /// Shows where the embedded files went once they are saved.
+    fn handle_attachments_result(&mut self) {
+        let Some(message) = self.attachments_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.attachments_rx = None;
+        self.toast = Some((message, Instant::now()));
+    }
+
+    /// Shows the current toast until it expires.
+    fn draw_toast(&mut self, ctx: &egui::Context) {
+        let Some((message, shown)) = &self.toast else {
+            return;
+        };
+        let remaining = TOAST_DURATION.saturating_sub(shown.elapsed());
+        if remaining.is_zero() {
+            self.toast = None;
+            return;
+        }
+        egui::Area::new(egui::Id::new("toast"))
+            .anchor(egui::Align2::CENTER_BOTTOM, Vec2::new(0.0, -56.0))
+            .order(egui::Order::Foreground)
+            .interactable(false)
+            .show(ctx, |ui| {
+                Frame::popup(ui.style()).show(ui, |ui| ui.label(message.as_str()));
+            });
+        ctx.request_repaint_after(remaining);
+    }
+
+    /// State to settle in when nothing is running.
+    fn resting_state(&self) -> AppState {
+        if self.core.entries().is_empty() {
+            AppState::Idle
+        } else {
+            AppState::FilesLoaded
+        }
+    }
+
+    /// Moves from `Processing` to `ShowingResult` once the batch is done and the
+    /// peck animation has played long enough.
+    fn maybe_show_result(&mut self) {
+        if self.state != AppState::Processing || self.animation.loops < MIN_PECK_LOOPS {
+            return;
+        }
+        let Some(summary) = self.core.summary() else {
+            return;
+        };
+        self.core.result_text = summary.message();
+        self.transition(AppState::ShowingResult {
+            failed: summary.is_failure(),
+        });
+    }
+
+    fn add_files(&mut self, paths: Vec<PathBuf>) {
+        let summary = self.core.add_files(paths, confirm_mislabeled);
+        self.files_added(summary);
+    }
+
+    fn files_added(&mut self, summary: AddSummary) {
+        if let Some(message) = summary.skipped_message() {
+            self.toast = Some((message, Instant::now()));
+        }
+        if self.state == AppState::Idle {
+            self.transition(self.resting_state());
+        }
+    }
+
+    fn start_unlock(&mut self) {
+        if self.state != AppState::FilesLoaded {
+            return;
+        }
+        if self.core.is_analyzing() {
+            self.start_when_analyzed = true;
+            return;
+        }
+        self.start_when_analyzed = false;
+        self.output_fallback_offered = false;
+        let options = self.unlock_options();
+        for (id, check) in [("ocr", &mut self.ocr_check), ("sign", &mut self.sign_check)] {
+            if !options.pipeline.iter().any(|step| step.id() == id) {
+                continue;
+            }
+            match check.outcome() {
+                Some(Ok(())) => {}
+                Some(Err(err)) => {
+                    warn!(stage = id, "Stage unavailable: {err}");
+                    self.core.result_text = err;
+                    return;
+                }
+                None => {
+                    self.start_when_analyzed = true;
+                    return;
+                }
+            }
+        }
+        if !self.confirm_preflight(&options)
+            || !self.confirm_signed(options.skips_unencrypted())
+            || !self.confirm_large()
+        {
+            return;
+        }
+        if self.core.start(options) {
+            self.transition(AppState::Processing);
+        }
+    }
+
+    /// Sums up what the batch would do, what it takes, where the results go and which
+    /// options are on, and asks whether to start.
+    fn confirm_preflight(&self, options: &UnlockOptions) -> bool {
+        let preflight = self.core.preflight();
+        let active = self.core.config.active();
+        let mut counts = vec![
+            t!("preflight-encrypted", count = preflight.encrypted),
+            t!("preflight-needs-password", count = preflight.needs_password),
+            t!("preflight-unrestricted", count = preflight.unrestricted),
+        ];
+        if preflight.unknown > 0 {
+            counts.push(t!("preflight-unknown", count = preflight.unknown));
+        }
+        let destination = if options.beside_source {
+            t!("output-beside-source")
+        } else {
+            options
+                .output_dir
+                .clone()
+                .or_else(crackleaf_core::resolve_download_dir)
+                .map(|dir| dir.display().to_string())
+                .unwrap_or_else(|| t!("output-downloads"))
+        };
+        let mut enabled: Vec<String> = options.pipeline.iter().map(|step| step_name(step.as_ref())).collect();
+        let toggles = [
+            (active.batch_folders, "output-batch-folders"),
+            (options.preserve_attributes, "pipeline-preserve-attributes"),
+            (options.copy_unchanged, "pipeline-copy-unrestricted"),
+            (options.split_by_bookmarks, "pipeline-split-by-bookmarks"),
+        ];
+        enabled.extend(toggles.into_iter().filter(|(on, _)| *on).map(|(_, key)| t!(key)));
+        let originals = match active.originals {
+            Originals::Keep => None,
+            Originals::Trash => Some("pipeline-originals-trash"),
+            Originals::Move => Some("pipeline-originals-move"),
+        };
+        if let Some(key) = originals {
+            enabled.push(format!("{} {}", t!("pipeline-originals"), t!(key)));
+        }
+        let conflict = match options.on_conflict {
+            ConflictPolicy::Rename => None,
+            ConflictPolicy::Overwrite => Some("output-conflict-overwrite"),
+            ConflictPolicy::Skip => Some("output-conflict-skip"),
+        };
+        if let Some(key) = conflict {
+            enabled.push(format!("{} {}", t!("output-conflict"), t!(key)));
+        }
+        let mut description = format!(
+            "{}\n{}",
+            counts.join(&t!("list-separator")),
+            t!("preflight-destination", path = destination)
+        );
+        if !enabled.is_empty() {
+            description.push_str(&format!("\n\n{}\n{}", t!("preflight-options"), enabled.join("\n")));
+        }
+        let answer = rfd::MessageDialog::new()
+            .set_title(t!("preflight-title"))
+            .set_description(description)
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .set_level(rfd::MessageLevel::Info)
+            .show();
+        answer == rfd::MessageDialogResult::Yes
+    }
+
+    /// Asks whether to go ahead when the batch would invalidate digital signatures;
+    /// `true` when there are none at risk.
+    fn confirm_signed(&self, skips_unencrypted: bool) -> bool {
+        let at_risk = self.core.signatures_at_risk(skips_unencrypted);
+        if at_risk.is_empty() {
+            return true;
+        }
+        let mut lines: Vec<String> = at_risk
+            .iter()
+            .take(PROMPT_LISTED)
+            .map(|&index| {
+                let entry = &self.core.entries()[index];
+                let name = entry.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                format!("{name}: {}", signers(entry))
+            })
+            .collect();
+        if at_risk.len() > PROMPT_LISTED {
+            lines.push("…".to_string());
+        }
+        let answer = rfd::MessageDialog::new()
+            .set_title(t!("signed-title"))
+            .set_description(format!("{}\n\n{}", t!("signed-prompt", count = at_risk.len()), lines.join("\n")))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .set_level(rfd::MessageLevel::Warning)
+            .show();
+        answer == rfd::MessageDialogResult::Yes
+    }
+
+    /// Asks whether to go ahead with files above the size limit, with how long they may
+    /// take; `true` when there are none or they are left out anyway.
+    fn confirm_large(&self) -> bool {
+        let oversized = self.core.oversized();
+        let active = self.core.config.active();
+        if oversized.is_empty() || active.large_files == LargeFiles::Block {
+            return true;
+        }
+        let mut lines: Vec<String> = oversized
+            .iter()
+            .take(PROMPT_LISTED)
+            .map(|&(index, size)| {
+                let entry = &self.core.entries()[index];
+                let name = entry.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+                let size = i18n::format_size(size);
+                match self.core.estimate(entry.size().unwrap_or_default()) {
+                    Some(estimate) => t!(
+                        "large-line-estimate",
+                        name = name,
+                        size = size,
+                        duration = i18n::format_duration(estimate)
+                    ),
+                    None => t!("large-line", name = name, size = size),
+                }
+            })
+            .collect();
+        if oversized.len() > PROMPT_LISTED {
+            lines.push("…".to_string());
+        }
+        let limit = i18n::format_size(active.size_limit().unwrap_or_default());
+        let answer = rfd::MessageDialog::new()
+            .set_title(t!("large-title"))
+            .set_description(format!(
+                "{}\n\n{}",
+                t!("large-prompt", count = oversized.len(), limit = limit),
+                lines.join("\n")
+            ))
+            .set_buttons(rfd::MessageButtons::YesNo)
+            .set_level(rfd::MessageLevel::Warning)
+            .show();
+        answer == rfd::MessageDialogResult::Yes
+    }
+
+    /// Asks for another output folder and unlocks the files that could not be written
+    /// there. Returns `false` if the user declines.
+    fn retry_in_other_folder(&mut self) -> bool {
+        let Some(dir) = FileDialog::new().set_title(t!("output-fallback-title")).pick_folder() else {
+            return false;
+        };
+        let mut options = self.unlock_options();
+        options.output_dir = Some(dir);
+        options.beside_source = false;
+        self.core.retry_unwritable(options)
+    }
+
+    /// Lets the user pick, order and remove pipeline stages; changes are saved right away.
+    fn draw_pipeline_editor(&mut self, ui: &mut egui::Ui) {
+        let busy = self.is_busy();
+        let mut pipeline = self.core.config.active().pipeline.clone();
+        let mut preserve_attributes = self.core.config.active().preserve_attributes;
+        let mut copy_unrestricted = self.core.config.active().copy_unrestricted;
+        let mut split_by_bookmarks = self.core.config.active().split_by_bookmarks;
+        let mut originals = self.core.config.active().originals;
+        let mut max_file_size_mb = self.core.config.active().max_file_size_mb;
+        let mut large_files = self.core.config.active().large_files;
+        let mut changed = false;
+
+        egui::CollapsingHeader::new(t!("pipeline-header")).show(ui, |ui| {
+            ui.add_enabled_ui(!busy, |ui| {
+                let mut swap = None;
+                let mut remove = None;
+                for (index, id) in pipeline.iter().enumerate() {
+                    let name = self
+                        .core
+                        .step_registry
+                        .get(id)
+                        .map(|step| step_name(step.as_ref()))
+                        .unwrap_or_else(|| t!("pipeline-unknown-step", id = id.as_str()));
+                    ui.horizontal(|ui| {
+                        ui.label(format!("{}. {name}", index + 1));
+                        if icon_button(ui, Icon::Up, index > 0).clicked() {
+                            swap = Some((index, index - 1));
+                        }
+                        if icon_button(ui, Icon::Down, index + 1 < pipeline.len()).clicked() {
+                            swap = Some((index, index + 1));
+                        }
+                        if icon_button(ui, Icon::Remove, true).clicked() {
+                            remove = Some(index);
+                        }
+                    });
+                }
+                if let Some((a, b)) = swap {
+                    pipeline.swap(a, b);
+                    changed = true;
+                }
+                if let Some(index) = remove {
+                    pipeline.remove(index);
+                    changed = true;
+                }
+
+                let mut added = None;
+                egui::ComboBox::from_id_salt("add_stage")
+                    .selected_text(t!("pipeline-add"))
+                    .show_ui(ui, |ui| {
+                        for step in self.core.step_registry.steps() {
+                            if pipeline.iter().any(|id| id == step.id()) {
+                                continue;
+                            }
+                            if ui.selectable_label(false, step_name(step.as_ref())).clicked() {
+                                added = Some(step.id().to_string());
+                            }
+                        }
+                    });
+                if let Some(id) = added {
+                    match id.as_str() {
+                        "ocr" => self.ocr_check = ToolCheck::default(),
+                        "sign" => self.sign_check = ToolCheck::default(),
+                        _ => {}
+                    }
+                    pipeline.push(id);
+                    changed = true;
+                }
+
+                if pipeline.iter().any(|id| id == "letterhead") {
+                    self.draw_letterhead_settings(ui);
+                }
+                if pipeline.iter().any(|id| id == "crop-margins") {
+                    self.draw_crop_settings(ui);
+                }
+                if pipeline.iter().any(|id| id == "sign") {
+                    self.draw_sign_settings(ui);
+                }
+
+                changed |= ui
+                    .checkbox(&mut preserve_attributes, t!("pipeline-preserve-attributes"))
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut copy_unrestricted, t!("pipeline-copy-unrestricted"))
+                    .changed();
+                changed |= ui
+                    .checkbox(&mut split_by_bookmarks, t!("pipeline-split-by-bookmarks"))
+                    .changed();
+                ui.horizontal(|ui| {
+                    ui.label(t!("pipeline-originals")).on_hover_text(t!("pipeline-originals-hint"));
+                    for (value, label) in [
+                        (Originals::Keep, "pipeline-originals-keep"),
+                        (Originals::Trash, "pipeline-originals-trash"),
+                        (Originals::Move, "pipeline-originals-move"),
+                    ] {
+                        changed |= ui.radio_value(&mut originals, value, t!(label)).changed();
+                    }
+                });
+                ui.horizontal(|ui| {
+                    ui.label(t!("pipeline-large-files")).on_hover_text(t!("pipeline-large-files-hint"));
+                    changed |= ui
+                        .add(egui::DragValue::new(&mut max_file_size_mb).range(0..=1_048_576).suffix(" MB"))
+                        .changed();
+                    ui.add_enabled_ui(max_file_size_mb > 0, |ui| {
+                        changed |= ui
+                            .radio_value(&mut large_files, LargeFiles::Warn, t!("pipeline-large-warn"))
+                            .changed();
+                        changed |= ui
+                            .radio_value(&mut large_files, LargeFiles::Block, t!("pipeline-large-block"))
+                            .changed();
+                    });
+                });
+            });
+        });
+
+        if changed {
+            let active = self.core.config.active_mut();
+            active.pipeline = pipeline;
+            active.preserve_attributes = preserve_attributes;
+            active.copy_unrestricted = copy_unrestricted;
+            active.split_by_bookmarks = split_by_bookmarks;
+            active.originals = originals;
+            active.max_file_size_mb = max_file_size_mb;
+            active.large_files = large_files;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    /// File and mode of the `letterhead` stage; changes are saved right away.
+    fn draw_letterhead_settings(&mut self, ui: &mut egui::Ui) {
+        let mut letterhead = self.core.config.letterhead.clone();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let name = match &letterhead.file {
+                Some(file) => file.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                None => t!("letterhead-none"),
+            };
+            ui.label(t!("letterhead-file", name = name));
+            if ui.button(t!("letterhead-choose")).clicked() {
+                if let Some(file) = self.pick_letterhead() {
+                    letterhead.file = Some(file);
+                    changed = true;
+                }
+            }
+        });
+        ui.horizontal(|ui| {
+            changed |= ui
+                .radio_value(&mut letterhead.mode, LetterheadMode::Underlay, t!("letterhead-underlay"))
+                .changed();
+            changed |= ui
+                .radio_value(&mut letterhead.mode, LetterheadMode::Overlay, t!("letterhead-overlay"))
+                .changed();
+        });
+        if changed {
+            self.core.config.letterhead = letterhead;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    /// Certificate and appearance of the `sign` stage; changes other than the password,
+    /// which is only kept for the session, are saved right away.
+    fn draw_sign_settings(&mut self, ui: &mut egui::Ui) {
+        let mut sign = self.core.config.sign.clone();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            let name = match &sign.certificate {
+                Some(file) => file.file_name().unwrap_or_default().to_string_lossy().into_owned(),
+                None => t!("sign-none"),
+            };
+            ui.label(t!("sign-certificate", name = name));
+            if ui.button(t!("sign-choose")).clicked() {
+                let mut dialog = FileDialog::new()
+                    .set_title(t!("sign-pick-title"))
+                    .add_filter("PKCS#12", &["p12", "pfx"]);
+                if let Some(dir) = sign.certificate.as_deref().and_then(Path::parent) {
+                    dialog = dialog.set_directory(dir);
+                }
+                if let Some(file) = dialog.pick_file() {
+                    sign.certificate = Some(file);
+                    changed = true;
+                }
+            }
+        });
+        ui.add(
+            egui::TextEdit::singleline(&mut self.core.config.sign.password)
+                .password(true)
+                .hint_text(t!("sign-password")),
+        );
+        sign.password = self.core.config.sign.password.clone();
+        let mut reason = sign.reason.clone().unwrap_or_default();
+        if ui.add(egui::TextEdit::singleline(&mut reason).hint_text(t!("sign-reason"))).changed() {
+            sign.reason = Some(reason).filter(|reason| !reason.is_empty());
+            changed = true;
+        }
+        changed |= ui.checkbox(&mut sign.visible, t!("sign-visible")).changed();
+        if changed {
+            self.core.config.sign = sign;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    /// Mode and margins of the `crop-margins` stage; changes are saved right away.
+    fn draw_crop_settings(&mut self, ui: &mut egui::Ui) {
+        let mut crop = self.core.config.crop.clone();
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            changed |= ui.radio_value(&mut crop.mode, CropMode::Auto, t!("crop-auto")).changed();
+            changed |= ui.radio_value(&mut crop.mode, CropMode::Margins, t!("crop-margins")).changed();
+        });
+        ui.horizontal(|ui| match crop.mode {
+            CropMode::Auto => {
+                ui.label(t!("crop-padding"));
+                changed |= ui.add(egui::DragValue::new(&mut crop.padding).range(0.0..=50.0).suffix(" mm")).changed();
+            }
+            CropMode::Margins => {
+                for (margin, key) in crop.margins.iter_mut().zip(["crop-top", "crop-right", "crop-bottom", "crop-left"]) {
+                    ui.label(t!(key));
+                    changed |= ui.add(egui::DragValue::new(margin).range(0.0..=100.0).suffix(" mm")).changed();
+                }
+            }
+        });
+        if changed {
+            self.core.config.crop = crop;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    fn draw_profile_picker(&mut self, ui: &mut egui::Ui) {
+        if self.core.config.profiles.is_empty() {
+            return;
+        }
+        let default_name = t!("profile-default");
+        let selected = self.core.config.active_profile.clone().unwrap_or_else(|| default_name.clone());
+
+        let mut choice = None;
+        ui.add_enabled_ui(!self.is_busy(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(t!("profile-label"));
+                egui::ComboBox::from_id_salt("profile")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        if ui
+                            .selectable_label(self.core.config.active_profile.is_none(), &default_name)
+                            .clicked()
+                        {
+                            choice = Some(None);
+                        }
+                        for profile in &self.core.config.profiles {
+                            let active = self.core.config.active_profile.as_deref() == Some(profile.name.as_str());
+                            if ui.selectable_label(active, &profile.name).clicked() {
+                                choice = Some(Some(profile.name.clone()));
+                            }
+                        }
+                    });
+            });
+        });
+
+        if let Some(name) = choice {
+            if let Err(err) = self.core.config.select_profile(name.as_deref()) {
+                warn!("{err}");
+                return;
+            }
+            info!(profile = ?name, "profile selected");
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    /// The active profile's settings, named with the language's suffix unless the
+    /// profile sets one.
+    fn unlock_options(&self) -> UnlockOptions {
+        let mut options = self.core.config.unlock_options();
+        if self.core.config.active().suffix.is_none() {
+            options.suffix = t!("output-suffix");
+        }
+        options
+    }
+
+    /// Where results go: the downloads folder or one of the pinned folders, which can
+    /// be added and removed here. The choice is saved with the active profile.
+    fn draw_output_picker(&mut self, ui: &mut egui::Ui) {
+        let current = self.core.config.active().output_dir.clone();
+        let beside = self.core.config.active().beside_source;
+        let pinned = self.core.config.ui.pinned_folders.clone();
+        let downloads = t!("output-downloads");
+        let selected = match &current {
+            _ if beside => t!("output-beside-source"),
+            Some(dir) => folder_name(dir),
+            None => downloads.clone(),
+        };
+
+        let mut choice = None;
+        let (mut pin, mut unpin, mut choose_beside) = (false, false, false);
+        ui.add_enabled_ui(!self.is_busy(), |ui| {
+            ui.horizontal(|ui| {
+                ui.label(t!("output-label"));
+                egui::ComboBox::from_id_salt("output-folder")
+                    .selected_text(selected)
+                    .show_ui(ui, |ui| {
+                        choose_beside = ui
+                            .selectable_label(beside, t!("output-beside-source"))
+                            .on_hover_text(t!("output-beside-source-hint"))
+                            .clicked();
+                        if ui.selectable_label(!beside && current.is_none(), &downloads).clicked() {
+                            choice = Some(None);
+                        }
+                        // A folder set in the config file is offered even when not pinned.
+                        let unpinned = current.iter().filter(|dir| !pinned.contains(dir));
+                        for dir in pinned.iter().chain(unpinned) {
+                            let label = ui
+                                .selectable_label(!beside && current.as_ref() == Some(dir), folder_name(dir))
+                                .on_hover_text(dir.display().to_string());
+                            if label.clicked() {
+                                choice = Some(Some(dir.clone()));
+                            }
+                        }
+                        ui.separator();
+                        pin = ui.button(t!("output-pin")).clicked();
+                        if current.as_ref().is_some_and(|dir| pinned.contains(dir)) {
+                            unpin = ui.button(t!("output-unpin")).clicked();
+                        }
+                    });
+            });
+            self.draw_suffix_field(ui);
+            self.draw_batch_folders_toggle(ui);
+            self.draw_conflict_policy(ui);
+        });
+
+        if pin {
+            let Some(dir) = FileDialog::new().set_title(t!("output-pin-title")).pick_folder() else {
+                return;
+            };
+            if !pinned.contains(&dir) {
+                self.core.config.ui.pinned_folders.push(dir.clone());
+            }
+            choice = Some(Some(dir));
+        }
+        if unpin {
+            self.core.config.ui.pinned_folders.retain(|dir| Some(dir) != current.as_ref());
+            choice = Some(None);
+        }
+        if choose_beside {
+            info!("results go next to their sources");
+            self.core.config.active_mut().beside_source = true;
+        } else if let Some(dir) = choice {
+            info!(output_dir = ?dir, "output folder selected");
+            let active = self.core.config.active_mut();
+            active.output_dir = dir;
+            active.beside_source = false;
+        } else {
+            return;
+        }
+        if let Err(err) = self.core.config.save() {
+            warn!("Failed to save config: {err}");
+        }
+    }
+
+    /// The active profile's suffix for result names; left empty it is the language's.
+    /// Characters file names cannot contain are refused as they are typed.
+    fn draw_suffix_field(&mut self, ui: &mut egui::Ui) {
+        let mut text = self.core.config.active().suffix.clone().unwrap_or_default();
+        let changed = ui
+            .horizontal(|ui| {
+                ui.label(t!("output-suffix-label"));
+                ui.add(
+                    egui::TextEdit::singleline(&mut text)
+                        .hint_text(t!("output-suffix"))
+                        .char_limit(MAX_SUFFIX_LEN)
+                        .desired_width(160.0),
+                )
+                .on_hover_text(t!("output-suffix-hint"))
+                .changed()
+            })
+            .inner;
+        if !changed {
+            return;
+        }
+        if !text.is_empty() && check_suffix(&text).is_err() {
+            self.toast = Some((t!("output-suffix-invalid"), Instant::now()));
+            return;
+        }
+        self.core.config.active_mut().suffix = Some(text).filter(|text| !text.is_empty());
+        if let Err(err) = self.core.config.save() {
+            warn!("Failed to save config: {err}");
+        }
+    }
+
+    /// Whether each batch gets its own timestamped subfolder of the output folder.
+    fn draw_batch_folders_toggle(&mut self, ui: &mut egui::Ui) {
+        let mut enabled = self.core.config.active().batch_folders;
+        let changed = ui
+            .checkbox(&mut enabled, t!("output-batch-folders"))
+            .on_hover_text(t!("output-batch-folders-hint"))
+            .changed();
+        if !changed {
+            return;
+        }
+        self.core.config.active_mut().batch_folders = enabled;
+        if let Err(err) = self.core.config.save() {
+            warn!("Failed to save config: {err}");
+        }
+    }
+
+    /// What happens when a result's name is already taken in the output folder.
+    fn draw_conflict_policy(&mut self, ui: &mut egui::Ui) {
+        let mut policy = self.core.config.active().on_conflict;
+        let mut changed = false;
+        ui.horizontal(|ui| {
+            ui.label(t!("output-conflict")).on_hover_text(t!("output-conflict-hint"));
+            for (value, label) in [
+                (ConflictPolicy::Rename, "output-conflict-rename"),
+                (ConflictPolicy::Overwrite, "output-conflict-overwrite"),
+                (ConflictPolicy::Skip, "output-conflict-skip"),
+            ] {
+                changed |= ui.radio_value(&mut policy, value, t!(label)).changed();
+            }
+        });
+        if !changed {
+            return;
+        }
+        self.core.config.active_mut().on_conflict = policy;
+        if let Err(err) = self.core.config.save() {
+            warn!("Failed to save config: {err}");
+        }
+    }
+
+    /// Which dropped files are listed; see [`DropFilter`].
+    fn draw_drop_filter(&mut self, ui: &mut egui::Ui) {
+        let filters = [
+            (DropFilter::Encrypted, "drop-filter-encrypted", "drop-filter-encrypted-hint"),
+            (DropFilter::Pdf, "drop-filter-pdf", "drop-filter-pdf-hint"),
+            (DropFilter::Sniffed, "drop-filter-sniffed", "drop-filter-sniffed-hint"),
+        ];
+        let mut filter = self.core.config.ui.drop_filter;
+        let selected = filters
+            .iter()
+            .find(|(value, ..)| *value == filter)
+            .map(|(_, label, _)| t!(label))
+            .unwrap_or_default();
+        ui.horizontal(|ui| {
+            ui.label(t!("drop-filter-label"));
+            egui::ComboBox::from_id_salt("drop-filter")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    for (value, label, hint) in filters {
+                        ui.selectable_value(&mut filter, value, t!(label)).on_hover_text(t!(hint));
+                    }
+                });
+        });
+        if filter != self.core.config.ui.drop_filter {
+            info!(?filter, "drop filter selected");
+            self.core.config.ui.drop_filter = filter;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    fn draw_language_picker(&mut self, ui: &mut egui::Ui) {
+        let auto = t!("language-auto");
+        let languages = i18n::available_languages();
+        let selected = match &self.core.config.ui.language {
+            Some(_) => languages
+                .iter()
+                .find(|(tag, _)| *tag == i18n::current_language())
+                .map(|(_, name)| name.clone())
+                .unwrap_or_else(|| auto.clone()),
+            None => auto.clone(),
+        };
+
+        let mut choice = None;
+        ui.horizontal(|ui| {
+            ui.label(t!("language-label"));
+            egui::ComboBox::from_id_salt("language")
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    if ui.selectable_label(self.core.config.ui.language.is_none(), &auto).clicked() {
+                        choice = Some(None);
+                    }
+                    for (tag, name) in &languages {
+                        let active = self.core.config.ui.language.as_deref() == Some(tag.as_str());
+                        if ui.selectable_label(active, name).clicked() {
+                            choice = Some(Some(tag.clone()));
+                        }
+                    }
+                });
+        });
+
+        if let Some(language) = choice {
+            if language == self.core.config.ui.language {
+                return;
+            }
+            i18n::set_language(language.as_deref());
+            self.core.config.ui.language = language;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+    }
+
+    fn check_for_update(&mut self, ctx: &egui::Context) {
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            match update::check() {
+                Ok(Some(release)) => {
+                    let _ = tx.send(UpdateEvent::Available(release));
+                    ctx.request_repaint();
+                }
+                Ok(None) => debug!("no update available"),
+                Err(err) => debug!("update check failed: {err}"),
+            }
+        });
+        self.update_rx = Some(rx);
+    }
+
+    fn install_update(&mut self, ctx: &egui::Context, release: update::Release) {
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let event = match update::install(&release) {
+                Ok(()) => UpdateEvent::Installed,
+                Err(err) => UpdateEvent::Failed(err.to_string()),
+            };
+            let _ = tx.send(event);
+            ctx.request_repaint();
+        });
+        self.update_rx = Some(rx);
+        self.update_status = Some(t!("update-downloading"));
+    }
+
+    fn handle_update_events(&mut self) {
+        let Some(rx) = &self.update_rx else {
+            return;
+        };
+        while let Ok(event) = rx.try_recv() {
+            match event {
+                UpdateEvent::Available(release) => {
+                    let skipped = self.core.config.update.skipped_version.as_deref();
+                    if skipped != Some(release.version.to_string().as_str()) {
+                        info!(version = %release.version, "update available");
+                        self.update = Some(release);
+                    }
+                }
+                UpdateEvent::Installed => self.update_status = Some(t!("update-installed")),
+                UpdateEvent::Failed(err) => {
+                    warn!("Update failed: {err}");
+                    self.update_status = Some(t!("update-failed", error = err));
+                }
+            }
+        }
+    }
+
+    fn draw_update_window(&mut self, ctx: &egui::Context) {
+        let Some(release) = self.update.clone() else {
+            return;
+        };
+        let mut open = self.update_window_open;
+        let mut close = false;
+        egui::Window::new(t!("update-title", version = release.version.to_string()))
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                egui::ScrollArea::vertical().max_height(200.0).show(ui, |ui| {
+                    ui.label(&release.changelog);
+                });
+                if let Some(status) = &self.update_status {
+                    ui.label(status);
+                }
+                ui.horizontal_wrapped(|ui| {
+                    if update::can_install(&release)
+                        && self.update_status.is_none()
+                        && ui.button(t!("update-install")).clicked()
+                    {
+                        self.install_update(ctx, release.clone());
+                    }
+                    if ui.button(t!("update-open-page")).clicked() {
+                        update::open_page(&release);
+                    }
+                    if ui.button(t!("update-skip")).clicked() {
+                        self.core.config.update.skipped_version = Some(release.version.to_string());
+                        if let Err(err) = self.core.config.save() {
+                            warn!("Failed to save config: {err}");
+                        }
+                        self.update = None;
+                        close = true;
+                    }
+                    if ui.button(t!("update-later")).clicked() {
+                        close = true;
+                    }
+                });
+            });
+        self.update_window_open = open && !close;
+    }
+
+    /// Asks for the password of the archive waiting in [`AppCore::pending_archive`].
+    fn draw_archive_password_window(&mut self, ctx: &egui::Context) {
+        let Some(archive) = self.core.pending_archive() else {
+            return;
+        };
+        let name = archive.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let mut submit = false;
+        let mut skip = false;
+        let mut remember = self.core.config.ui.remember_passwords;
+        egui::Window::new(t!("archive-password-title", name = name))
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("archive-password-prompt"));
+                let input = ui.add(egui::TextEdit::singleline(&mut self.archive_password).password(true));
+                input.request_focus();
+                submit = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if self.archive_password_wrong {
+                    ui.colored_label(ui.visuals().error_fg_color, t!("archive-password-wrong"));
+                }
+                ui.checkbox(&mut remember, t!("archive-password-remember"))
+                    .on_hover_text(t!("archive-password-remember-hint"));
+                ui.horizontal(|ui| {
+                    submit |= ui.button(t!("archive-password-ok")).clicked();
+                    skip = ui.button(t!("archive-password-skip")).clicked();
+                });
+            });
+        if remember != self.core.config.ui.remember_passwords {
+            // Turning it off also forgets what was kept.
+            if !remember {
+                passwords::forget();
+            }
+            self.core.config.ui.remember_passwords = remember;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+        if skip {
+            self.core.skip_archive();
+        } else if submit {
+            match self.core.unlock_archive(&self.archive_password) {
+                Ok(summary) => self.files_added(summary),
+                // Unreadable archives are counted in the summary instead.
+                Err(_) => {
+                    self.archive_password_wrong = true;
+                    return;
+                }
+            }
+        } else {
+            return;
+        }
+        self.archive_password.clear();
+        self.archive_password_wrong = false;
+    }
+
+    /// Offers to zip the last batch's results, optionally with a password.
+    fn draw_pack_window(&mut self, ctx: &egui::Context) {
+        let results = self.core.results();
+        let mut open = self.pack_window_open;
+        let mut pack = false;
+        let mut cancel = false;
+        egui::Window::new(t!("pack-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("pack-prompt", count = results.len()));
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.pack_password)
+                        .password(true)
+                        .hint_text(t!("pack-password")),
+                );
+                ui.horizontal(|ui| {
+                    pack = ui
+                        .add_enabled(!results.is_empty(), egui::Button::new(t!("pack-confirm")))
+                        .clicked();
+                    cancel = ui.button(t!("pack-cancel")).clicked();
+                });
+            });
+        self.pack_window_open = open && !pack && !cancel;
+        if !pack {
+            return;
+        }
+        let password = std::mem::take(&mut self.pack_password);
+        let password = Some(password).filter(|password| !password.is_empty());
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let packed = crackleaf_core::resolve_download_dir()
+                .ok_or_else(|| anyhow::anyhow!("no Downloads folder"))
+                .and_then(|dir| archive::pack(&results, &dir, password.as_deref()));
+            let _ = tx.send(packed);
+            ctx.request_repaint();
+        });
+        self.pack_rx = Some(rx);
+    }
+
+    /// Asks which pages of the entry in `extract_pages` to write to a new PDF.
+    fn draw_extract_pages_window(&mut self, ctx: &egui::Context) {
+        let Some(index) = self.extract_pages.filter(|&index| self.core.can_rework(index)) else {
+            self.extract_pages = None;
+            return;
+        };
+        let entry = &self.core.entries()[index];
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let pages = entry.facts.as_ref().and_then(|facts| facts.pages);
+        let mut open = true;
+        let mut submit = false;
+        let mut cancel = false;
+        egui::Window::new(t!("extract-pages-title", name = name))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("extract-pages-prompt"));
+                if let Some(pages) = pages {
+                    ui.label(t!("extract-pages-count", pages = pages));
+                }
+                let input = ui.add(egui::TextEdit::singleline(&mut self.extract_ranges).hint_text("1-3, 5, 8-"));
+                input.request_focus();
+                submit = input.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                if let Some(error) = &self.extract_error {
+                    ui.colored_label(ui.visuals().error_fg_color, error);
+                }
+                ui.horizontal(|ui| {
+                    submit |= ui.button(t!("extract-pages-ok")).clicked();
+                    cancel = ui.button(t!("extract-pages-cancel")).clicked();
+                });
+            });
+        if !open || cancel {
+            self.extract_pages = None;
+            return;
+        }
+        if !submit {
+            return;
+        }
+        let ranges = match parse_page_ranges(&self.extract_ranges, pages) {
+            Ok(ranges) => ranges,
+            Err(err) => {
+                self.extract_error = Some(err.to_string());
+                return;
+            }
+        };
+        self.extract_pages = None;
+        let typed = self.extract_ranges.trim().to_string();
+        let options = self.unlock_options();
+        if self.core.extract_pages(index, ranges, &typed, options) {
+            self.transition(AppState::Processing);
+        }
+    }
+
+    /// Read-only tree of the bookmarks of the entry in `outline_view`, to check that
+    /// the chapters survived.
+    fn draw_outline_window(&mut self, ctx: &egui::Context) {
+        let Some(entry) = self.outline_view.and_then(|index| self.core.entries().get(index)) else {
+            self.outline_view = None;
+            return;
+        };
+        let name = entry.path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+        let mut open = true;
+        egui::Window::new(t!("outline-title", name = name))
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                let outline = entry.outline();
+                ui.label(t!("outline-count", count = Bookmark::count(outline)));
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    draw_bookmarks(ui, outline, "outline");
+                });
+            });
+        if !open {
+            self.outline_view = None;
+        }
+    }
+
+    /// Two file slots, filled by dropping or picking files, and what the comparison of
+    /// them found.
+    fn draw_compare_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.compare_window_open;
+        let mut start = false;
+        egui::Window::new(t!("compare-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("compare-prompt"));
+                for (slot, label) in self.compare_files.iter_mut().zip(["compare-first", "compare-second"]) {
+                    ui.horizontal(|ui| {
+                        let name = slot
+                            .as_ref()
+                            .map(|path| path.file_name().unwrap_or_default().to_string_lossy().into_owned())
+                            .unwrap_or_else(|| t!("compare-empty"));
+                        ui.label(t!(label, name = name))
+                            .on_hover_text(slot.as_ref().map(|path| path.display().to_string()).unwrap_or_default());
+                        if ui.small_button(t!("compare-choose")).clicked() {
+                            if let Some(path) = FileDialog::new().add_filter("PDF", &["pdf"]).pick_file() {
+                                *slot = Some(path);
+                                self.compare_result = None;
+                            }
+                        }
+                    });
+                }
+                let ready = self.compare_files.iter().all(Option::is_some) && self.compare_rx.is_none();
+                start = ui.add_enabled(ready, egui::Button::new(t!("compare-start"))).clicked();
+                if self.compare_rx.is_some() {
+                    ui.spinner();
+                }
+                match &self.compare_result {
+                    Some(Ok(diff)) => {
+                        ui.separator();
+                        draw_differences(ui, diff);
+                    }
+                    Some(Err(err)) => {
+                        ui.colored_label(ui.visuals().error_fg_color, t!("compare-failed", error = err.as_str()));
+                    }
+                    None => {}
+                }
+            });
+        self.compare_window_open = open;
+        if start {
+            self.start_compare(ctx);
+        }
+    }
+
+    /// Asks for a folder, an owner password and the permissions, then encrypts the
+    /// folder's PDFs as a batch.
+    fn draw_protect_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.protect_window_open;
+        let mut start = false;
+        let mut permissions = self.core.config.encrypt;
+        let busy = self.is_busy();
+        egui::Window::new(t!("protect-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.label(t!("protect-prompt"));
+                ui.horizontal(|ui| {
+                    let name = self
+                        .protect_folder
+                        .as_ref()
+                        .map(|dir| dir.display().to_string())
+                        .unwrap_or_else(|| t!("protect-no-folder"));
+                    ui.label(t!("protect-folder", name = name));
+                    if ui.add_enabled(!busy, egui::Button::new(t!("protect-choose")).small()).clicked() {
+                        if let Some(dir) = FileDialog::new().set_title(t!("protect-title")).pick_folder() {
+                            self.core.clear();
+                            self.transition(AppState::Idle);
+                            self.add_files(vec![dir.clone()]);
+                            self.protect_folder = Some(dir);
+                        }
+                    }
+                });
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.protect_password)
+                        .password(true)
+                        .hint_text(t!("protect-password")),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut self.protect_confirm)
+                        .password(true)
+                        .hint_text(t!("protect-confirm")),
+                );
+                let mismatch = !self.protect_confirm.is_empty() && self.protect_confirm != self.protect_password;
+                if mismatch {
+                    ui.colored_label(ui.visuals().error_fg_color, t!("protect-mismatch"));
+                }
+                ui.label(t!("protect-allow"));
+                ui.checkbox(&mut permissions.print, t!("protect-allow-print"));
+                ui.checkbox(&mut permissions.copy, t!("protect-allow-copy"));
+                ui.checkbox(&mut permissions.modify, t!("protect-allow-modify"));
+                ui.checkbox(&mut permissions.annotate, t!("protect-allow-annotate"));
+                let ready = !busy
+                    && !self.core.entries().is_empty()
+                    && !self.protect_password.is_empty()
+                    && self.protect_confirm == self.protect_password;
+                ui.horizontal(|ui| {
+                    start = ui
+                        .add_enabled(ready && !self.core.is_analyzing(), egui::Button::new(t!("protect-start")))
+                        .clicked();
+                    if self.core.is_analyzing() {
+                        ui.spinner();
+                    }
+                });
+            });
+        if permissions != self.core.config.encrypt {
+            self.core.config.encrypt = permissions;
+            if let Err(err) = self.core.config.save() {
+                warn!("Failed to save config: {err}");
+            }
+        }
+        self.protect_window_open = open && !start;
+        if !open {
+            self.protect_password.clear();
+            self.protect_confirm.clear();
+        }
+        if start {
+            self.start_protect();
+        }
+    }
+
+    /// Encrypts the listed PDFs with the password typed into the protect window.
+    fn start_protect(&mut self) {
+        self.protect_confirm.clear();
+        let step = Encrypt::new(std::mem::take(&mut self.protect_password), self.core.config.encrypt);
+        self.start_when_analyzed = false;
+        self.output_fallback_offered = false;
+        if !self.confirm_signed(false) || !self.confirm_large() {
+            return;
+        }
+        let options = self.unlock_options();
+        if self.core.protect(step, options) {
+            self.transition(AppState::Processing);
+        }
+    }
+
+    /// Puts dropped PDFs into the compare slots: two replace both, one fills the first
+    /// empty slot or else the second.
+    fn drop_into_compare(&mut self, paths: Vec<PathBuf>) {
+        let mut pdfs: Vec<PathBuf> = paths.into_iter().filter(|path| crackleaf_core::is_pdf(path)).collect();
+        match pdfs.len() {
+            0 => return,
+            1 => {
+                let slot = self.compare_files.iter().position(Option::is_none).unwrap_or(1);
+                self.compare_files[slot] = pdfs.pop();
+            }
+            _ => {
+                pdfs.truncate(2);
+                let second = pdfs.pop();
+                self.compare_files = [pdfs.pop(), second];
+            }
+        }
+        self.compare_result = None;
+    }
+
+    /// Compares the two chosen files in the background.
+    fn start_compare(&mut self, ctx: &egui::Context) {
+        let [Some(first), Some(second)] = self.compare_files.clone() else {
+            return;
+        };
+        self.compare_result = None;
+        let timeout = self.core.config.unlock_options().timeout;
+        let (tx, rx) = mpsc::channel();
+        let ctx = ctx.clone();
+        std::thread::spawn(move || {
+            let _ = tx.send(block_on(compare_pdfs(&first, &second, timeout)));
+            ctx.request_repaint();
+        });
+        self.compare_rx = Some(rx);
+    }
+
+    fn handle_compare_result(&mut self) {
+        let Some(diff) = self.compare_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.compare_rx = None;
+        self.compare_result = Some(diff.map_err(|err| {
+            warn!("Comparing failed: {err}");
+            err.to_string()
+        }));
+    }
+
+    fn handle_pack_result(&mut self) {
+        let Some(packed) = self.pack_rx.as_ref().and_then(|rx| rx.try_recv().ok()) else {
+            return;
+        };
+        self.pack_rx = None;
+        let message = match packed {
+            Ok(path) => t!("pack-done", path = path.display().to_string()),
+            Err(err) => {
+                warn!("Packing results failed: {err}");
+                t!("pack-failed", error = err.to_string())
+            }
+        };
+        self.toast = Some((message, Instant::now()));
+    }
+
+    fn open_history_window(&mut self) {
+        let Some(history) = self.core.history() else {
+            return;
+        };
+        match history.recent(history::DEFAULT_LIMIT) {
+            Ok(records) => self.history_records = records,
+            Err(err) => warn!("Failed to read history: {err}"),
+        }
+        self.history_window_open = true;
+    }
+
+    fn draw_history_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.history_window_open;
+        let mut clear = false;
+        egui::Window::new(t!("history-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                if self.history_records.is_empty() {
+                    ui.label(t!("history-empty"));
+                    return;
+                }
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for record in &self.history_records {
+                        let name = record.source.file_name().unwrap_or_default().to_string_lossy();
+                        let status = match (record.status.as_str(), &record.error_kind) {
+                            ("ok", _) => t!("history-status-ok"),
+                            ("unchanged", _) => t!("history-status-unchanged"),
+                            (_, Some(kind)) => t!("history-status-failed-kind", kind = kind.as_str()),
+                            _ => t!("history-status-failed"),
+                        };
+                        ui.horizontal(|ui| {
+                            ui.small(format_date(&record.finished));
+                            ui.label(name).on_hover_text(record.source.display().to_string());
+                            ui.small(status);
+                            if let Some(output) = record.output.as_ref().filter(|path| path.exists()) {
+                                if ui.small_button(t!("open-button")).clicked() {
+                                    open_file(output);
+                                }
+                            }
+                        });
+                    }
+                });
+                if ui.button(t!("history-clear")).clicked() {
+                    clear = true;
+                }
+            });
+        if clear {
+            if let Some(history) = self.core.history() {
+                match history.clear() {
+                    Ok(()) => self.history_records.clear(),
+                    Err(err) => warn!("Failed to clear history: {err}"),
+                }
+            }
+        }
+        self.history_window_open = open;
+    }
+
+    /// Labels of the passwords kept in the keychain, each with a button to delete it.
+    fn draw_passwords_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.passwords_window_open;
+        let mut delete = None;
+        let mut delete_all = false;
+        egui::Window::new(t!("passwords-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                if self.saved_passwords.is_empty() {
+                    ui.label(t!("passwords-empty"));
+                    return;
+                }
+                ui.small(t!("passwords-hint"));
+                egui::ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for saved in &self.saved_passwords {
+                        ui.horizontal(|ui| {
+                            ui.label(&saved.label);
+                            if ui.small_button(t!("passwords-delete")).clicked() {
+                                delete = Some(saved.account.clone());
+                            }
+                        });
+                    }
+                });
+                if ui.button(t!("passwords-delete-all")).clicked() {
+                    delete_all = true;
+                }
+            });
+        if let Some(account) = delete {
+            passwords::delete(&account);
+            self.saved_passwords = passwords::list();
+        } else if delete_all {
+            passwords::forget();
+            self.saved_passwords.clear();
+        }
+        self.passwords_window_open = open;
+    }
+
+    /// Every job of the batch with its state, start, duration and the stages that
+    /// changed it; updates live while the batch runs.
+    fn draw_queue_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.queue_window_open;
+        egui::Window::new(t!("queue-title"))
+            .open(&mut open)
+            .collapsible(false)
+            .anchor(egui::Align2::CENTER_CENTER, Vec2::ZERO)
+            .show(ctx, |ui| {
+                let Some(queue) = self.core.queue() else {
+                    return;
+                };
+                // Starts are shown relative to the first job, so parallel jobs line up.
+                let first = queue.jobs().iter().filter_map(|job| job.started).min();
+                egui::ScrollArea::vertical().max_height(360.0).show(ui, |ui| {
+                    egui::Grid::new("queue").striped(true).spacing([16.0, 4.0]).show(ui, |ui| {
+                        for heading in ["queue-file", "queue-state", "queue-started", "queue-duration", "queue-stages"] {
+                            ui.strong(t!(heading));
+                        }
+                        ui.end_row();
+                        for job in queue.jobs() {
+                            let name = job.path.file_name().unwrap_or_default().to_string_lossy();
+                            ui.label(name).on_hover_text(job.path.display().to_string());
+                            ui.label(job_state(job));
+                            match (job.started, first) {
+                                (Some(started), Some(first)) => {
+                                    ui.label(format!("+{}", format_duration(started.duration_since(first))))
+                                }
+                                _ => ui.label("—"),
+                            };
+                            let duration = job.elapsed.or_else(|| job.started.map(|started| started.elapsed()));
+                            ui.label(duration.map(format_duration).unwrap_or_else(|| "—".to_string()));
+                            if job.applied.is_empty() {
+                                ui.label("—");
+                            } else {
+                                ui.label(job.applied.join(" → "));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                });
+                if queue.jobs().iter().any(|job| job.started.is_some() && !job.state.is_terminal()) {
+                    ctx.request_repaint_after(Duration::from_secs(1));
+                }
+            });
+        self.queue_window_open = open;
+    }
+
+    /// Keeps the checks of the tools the active pipeline needs up to date.
+    fn check_tools(&mut self, ctx: &egui::Context) {
+        let pipeline = &self.core.config.active().pipeline;
+        if pipeline.iter().any(|id| id == "ocr") {
+            self.ocr_check.update(ctx, check_ocr_ready);
+        }
+        if pipeline.iter().any(|id| id == "sign") {
+            self.sign_check.update(ctx, check_sign_ready);
+        }
+    }
+
+    fn handle_unlock_messages(&mut self) {
+        let listed = self.core.entries().len();
+        let finished = self.core.poll();
+        if self.core.entries().len() < listed {
+            // Rows were filtered out, so dialogs holding a row index may point elsewhere now.
+            self.extract_pages = None;
+            self.outline_view = None;
+            if self.state == AppState::FilesLoaded && self.core.entries().is_empty() {
+                self.start_when_analyzed = false;
+                self.transition(AppState::Idle);
+            }
+        }
+        if finished {
+            if !self.output_fallback_offered && !self.core.unwritable().is_empty() {
+                self.output_fallback_offered = true;
+                if self.retry_in_other_folder() {
+                    return;
+                }
+            }
+            self.maybe_show_result();
+        }
+        if let Some(message) = self.core.take_unencrypted().and_then(|summary| summary.skipped_message()) {
+            self.toast = Some((message, Instant::now()));
+        }
+        if self.start_when_analyzed && !self.core.is_analyzing() {
+            self.start_unlock();
+        }
+    }
+}
+
+fn apply_custom_font(ctx: &egui::Context, assets_dir: &Path) {
+    let font_path = assets_dir.join("Huiwenfangsong.ttf");
+    let font_data = std::fs::read(font_path).ok();
+    if let Some(bytes) = font_data {
+        let mut fonts = egui::FontDefinitions::default();
+        fonts.font_data.insert(
+            "huiwenfangsong".to_string(),
+            egui::FontData::from_owned(bytes),
+        );
+        fonts
+            .families
+            .entry(egui::FontFamily::Proportional)
+            .or_default()
+            .insert(0, "huiwenfangsong".to_string());
+        fonts
+            .families
+            .entry(egui::FontFamily::Monospace)
+            .or_default()
+            .push("huiwenfangsong".to_string());
+        ctx.set_fonts(fonts);
+    } else {
+        warn!("Failed to load font: Huiwenfangsong.ttf");
+    }
+}
+
+fn apply_theme(ctx: &egui::Context, high_contrast: bool) {
+    let mut visuals = egui::Visuals::light();
+    visuals.panel_fill = BACKGROUND;
+    if high_contrast {
+        visuals.panel_fill = Color32::WHITE;
+        visuals.window_fill = Color32::WHITE;
+        visuals.extreme_bg_color = Color32::WHITE;
+        visuals.override_text_color = Some(Color32::BLACK);
+        visuals.warn_fg_color = Color32::from_rgb(0x8A, 0x4B, 0x00);
+        visuals.error_fg_color = Color32::from_rgb(0xA0, 0x00, 0x00);
+        visuals.window_stroke = egui::Stroke::new(2.0, Color32::BLACK);
+        for widget in [
+            &mut visuals.widgets.noninteractive,
+            &mut visuals.widgets.inactive,
+            &mut visuals.widgets.hovered,
+            &mut visuals.widgets.active,
+            &mut visuals.widgets.open,
+        ] {
+            widget.bg_stroke = egui::Stroke::new(1.5, Color32::BLACK);
+            widget.fg_stroke = egui::Stroke::new(1.5, Color32::BLACK);
+        }
+    }
+    ctx.set_visuals(visuals);
+    ctx.set_pixels_per_point(1.1);
+
+    let mut style = (*ctx.style()).clone();
+    style.text_styles = [
+        (egui::TextStyle::Heading, egui::FontId::new(24.0, egui::FontFamily::Proportional)),
+        (egui::TextStyle::Body, egui::FontId::new(22.0, egui::FontFamily::Proportional)),
+        (egui::TextStyle::Button, egui::FontId::new(22.0, egui::FontFamily::Proportional)),
+        (egui::TextStyle::Small, egui::FontId::new(20.0, egui::FontFamily::Proportional)),
+    ]
+    .into();
+    ctx.set_style(style);
+}
+
+impl eframe::App for CrackLeafApp {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        self.ui(ctx);
+        self.draw_windows(ctx);
+    }
+}
+
+impl CrackLeafApp {
+    /// One frame of this window.
+    fn ui(&mut self, ctx: &egui::Context) {
+        self.tick_animation();
+        self.check_tools(ctx);
+        self.handle_unlock_messages();
+        self.handle_update_events();
+        self.handle_pack_result();
+        self.handle_attachments_result();
+        self.handle_compare_result();
+        self.handle_download_events();
+        self.handle_toasts();
+        #[cfg(target_os = "macos")]
+        self.handle_scheme_urls(url_scheme::take_received());
+
+        let minimized = ctx.input(|i| i.viewport().minimized.unwrap_or(false));
+        self.frames.trim(minimized && !self.minimized);
+        self.minimized = minimized;
+
+        let dropped_files = ctx.input(|i| i.raw.dropped_files.clone());
+        if !dropped_files.is_empty() {
+            let (shortcuts, paths): (Vec<PathBuf>, Vec<PathBuf>) = dropped_files
+                .into_iter()
+                .filter_map(|f| f.path)
+                .partition(|path| download::shortcut_url(path).is_some());
+            self.download(ctx, shortcuts.iter().filter_map(|path| download::shortcut_url(path)).collect());
+            if self.compare_window_open {
+                self.drop_into_compare(paths);
+            } else if !paths.is_empty() {
+                self.add_files(paths);
+            }
+        }
+        // Links pasted anywhere but into a text field.
+        if !ctx.wants_keyboard_input() {
+            let pasted: Vec<String> = ctx.input(|i| {
+                i.events
+                    .iter()
+                    .filter_map(|event| match event {
+                        egui::Event::Paste(text) => Some(download::links(text)),
+                        _ => None,
+                    })
+                    .flatten()
+                    .collect()
+            });
+            self.download(ctx, pasted);
+        }
+
+        if self.core.config.ui.mini {
+            egui::CentralPanel::default()
+                .frame(Frame::none().fill(ctx.style().visuals.panel_fill))
+                .show(ctx, |ui| {
+                    ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        ui.add_space(8.0);
+                        self.draw_mini(ui, ctx);
+                    });
+                });
+        } else {
+            egui::TopBottomPanel::bottom("controls")
+                .frame(Frame::none().fill(ctx.style().visuals.panel_fill).inner_margin(egui::Margin::symmetric(8.0, 0.0)))
+                .show_separator_line(false)
+                .show(ctx, |ui| self.draw_controls(ui));
+
+            egui::CentralPanel::default()
+                .frame(Frame::none().fill(ctx.style().visuals.panel_fill))
+                .show(ctx, |ui| {
+                    ui.with_layout(egui::Layout::top_down(egui::Align::Center), |ui| {
+                        ui.add_space(16.0);
+                        self.draw_mascot(ui, ctx);
+                        self.draw_output_picker(ui);
+                        ui.add_space(10.0);
+                        match self.draw_file_list(ui) {
+                            Some(RowAction::Print(path)) => self.print(&path, ctx),
+                            Some(RowAction::Share(path)) => self.share(&path),
+                            Some(RowAction::ExtractPages(index)) => {
+                                self.extract_pages = Some(index);
+                                self.extract_ranges.clear();
+                                self.extract_error = None;
+                            }
+                            Some(RowAction::Letterhead(index)) => self.apply_letterhead(index),
+                            Some(RowAction::ExtractAttachments(index)) => self.extract_attachments(index, ctx),
+                            Some(RowAction::Outline(index)) => self.outline_view = Some(index),
+                            Some(RowAction::AllowLarge(index, allow)) => self.core.set_allow_large(index, allow),
+                            Some(RowAction::Compare(index)) => {
+                                let entry = &self.core.entries()[index];
+                                self.compare_files = [Some(entry.path.clone()), entry.output_path.clone()];
+                                self.compare_window_open = true;
+                                self.start_compare(ctx);
+                            }
+                            None => {}
+                        }
+                    });
+                });
+        }
+
+        if self.update_window_open {
+            self.draw_update_window(ctx);
+        }
+        if self.history_window_open {
+            self.draw_history_window(ctx);
+        }
+        if self.passwords_window_open {
+            self.draw_passwords_window(ctx);
+        }
+        if self.queue_window_open {
+            self.draw_queue_window(ctx);
+        }
+        if self.core.pending_archive().is_some() {
+            self.draw_archive_password_window(ctx);
+        }
+        if self.pack_window_open {
+            self.draw_pack_window(ctx);
+        }
+        if self.extract_pages.is_some() {
+            self.draw_extract_pages_window(ctx);
+        }
+        if self.outline_view.is_some() {
+            self.draw_outline_window(ctx);
+        }
+        if self.compare_window_open {
+            self.draw_compare_window(ctx);
+        }
+        if self.protect_window_open {
+            self.draw_protect_window(ctx);
+        }
+
+        if !self.core.qpdf_ok && !self.qpdf_prompted {
+            self.qpdf_prompted = true;
+            show_qpdf_setup_dialog();
+        }
+
+        self.draw_toast(ctx);
+        self.schedule_repaint(ctx);
+    }
+}
+
+fn resolve_assets_dir() -> PathBuf {
+    if let Ok(cwd) = std::env::current_dir() {
+        let assets = cwd.join("assets");
+        if assets.exists() {
+            return assets;
+        }
+    }
+    if let Ok(exe_path) = std::env::current_exe() {
+        if let Some(exe_dir) = exe_path.parent() {
+            let assets = exe_dir.join("assets");
+            if assets.exists() {
+                return assets;
+            }
+            let macos_bundle_assets = exe_dir.join("..").join("Resources").join("assets");
+            if macos_bundle_assets.exists() {
+                return macos_bundle_assets;
+            }
+        }
+    }
+    if cfg!(all(unix, not(target_os = "macos"))) {
+        for dir in xdg_data_dirs() {
+            let assets = dir.join("crackleaf").join("assets");
+            if assets.exists() {
+                return assets;
+            }
+        }
+    }
+    PathBuf::from("assets")
+}
+
+/// `$XDG_DATA_HOME`, then `$XDG_DATA_DIRS` (by default `/usr/local/share` and
+/// `/usr/share`), where distribution packages install shared data.
+fn xdg_data_dirs() -> Vec<PathBuf> {
+    let system = std::env::var_os("XDG_DATA_DIRS")
+        .filter(|dirs| !dirs.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".into());
+    dirs::data_dir().into_iter().chain(std::env::split_paths(&system)).collect()
+}
+
+fn load_window_icon(assets_dir: &Path) -> IconData {
+    let icon_path = assets_dir.join("crackleaf.png");
+    let image = match image::open(&icon_path) {
+        Ok(image) => image,
+        Err(err) => {
+            warn!("Failed to load window icon {:?}: {err}", icon_path);
+            return IconData::default();
+        }
+    };
+    let rgba = image.to_rgba8();
+    let (width, height) = image.dimensions();
+    IconData {
+        rgba: rgba.into_raw(),
+        width,
+        height,
+    }
+}
+
+
+/// Opens `path` with the default application. The path is passed on untouched, so
+/// names that are not valid Unicode work too.
+fn open_file(path: &Path) {
+    #[cfg(target_os = "macos")]
+    let mut cmd = Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", "start", ""]);
+        cmd
+    };
+    #[cfg(target_os = "linux")]
+    let mut cmd = Command::new("xdg-open");
+
+    let _ = cmd.arg(path).status();
+}
+
+/// Shrinks the window to the mini drop target, kept above other windows, or
+/// restores its normal size.
+fn apply_window_mode(ctx: &egui::Context, mini: bool) {
+    use egui::viewport::{ViewportCommand, WindowLevel};
+    if mini {
+        ctx.send_viewport_cmd(ViewportCommand::MinInnerSize(MINI_WINDOW));
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(MINI_WINDOW));
+        ctx.send_viewport_cmd(ViewportCommand::WindowLevel(WindowLevel::AlwaysOnTop));
+    } else {
+        ctx.send_viewport_cmd(ViewportCommand::WindowLevel(WindowLevel::Normal));
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT)));
+        ctx.send_viewport_cmd(ViewportCommand::MinInnerSize(Vec2::new(WINDOW_WIDTH, WINDOW_MIN_HEIGHT)));
+    }
+}
+
+/// Last component of `dir`, or all of it for a drive or share root.
+fn folder_name(dir: &Path) -> String {
+    dir.file_name().unwrap_or(dir.as_os_str()).to_string_lossy().into_owned()
+}
+
+/// State of a job as shown in the queue window.
+fn job_state(job: &Job) -> String {
+    match (job.state, job.stage.as_deref()) {
+        (JobState::Queued, _) => t!("status-queued"),
+        (JobState::Analyzing, _) => t!("status-analyzing"),
+        (JobState::Running, Some(stage)) => t!("status-stage", stage = stage),
+        (JobState::Running, None) => t!("status-running"),
+        (JobState::Done, _) if job.output_path.is_none() => t!("queue-unchanged"),
+        (JobState::Done, _) => t!("queue-done"),
+        (JobState::Failed, Some(stage)) => t!("queue-failed-at", stage = stage),
+        (JobState::Failed, None) => t!("status-failed"),
+        (JobState::Cancelled, _) => t!("status-cancelled"),
+        (JobState::Skipped, _) => t!("queue-skipped"),
+    }
+}
+
+/// Short text for the state of a file, shown next to its icon: `ENC`, `OK`, `FAIL` and so on.
+fn status_code(entry: &FileEntry, state: Option<JobState>) -> (&'static str, Tone) {
+    match state {
+        Some(JobState::Queued) => ("code-queued", Tone::Neutral),
+        Some(JobState::Analyzing | JobState::Running) => ("code-running", Tone::Neutral),
+        Some(JobState::Failed) => ("code-failed", Tone::Bad),
+        Some(JobState::Cancelled) => ("code-cancelled", Tone::Warning),
+        _ => match entry.icon {
+            Icon::Unlocked if entry.output_path.is_some() => ("code-done", Tone::Good),
+            Icon::Unlocked => ("code-open", Tone::Good),
+            Icon::Password => ("code-password", Tone::Bad),
+            Icon::Cloud => ("code-cloud", Tone::Warning),
+            Icon::Waiting | Icon::Working | Icon::Searching => ("code-queued", Tone::Neutral),
+            Icon::Cancelled => ("code-cancelled", Tone::Warning),
+            Icon::Timeout | Icon::Warning | Icon::Missing | Icon::InUse => ("code-failed", Tone::Bad),
+            Icon::Locked | Icon::Up | Icon::Down | Icon::Remove => ("code-encrypted", Tone::Warning),
+        },
+    }
+}
+
+/// A status code in a box: rounded when good, square when bad, and filled black in
+/// high contrast mode.
+fn draw_code(ui: &mut egui::Ui, code: &str, tone: Tone, high_contrast: bool) {
+    let visuals = ui.visuals();
+    let color = match tone {
+        Tone::Good => Color32::from_rgb(0x1B, 0x6E, 0x2A),
+        Tone::Warning => visuals.warn_fg_color,
+        Tone::Bad => visuals.error_fg_color,
+        Tone::Neutral => visuals.weak_text_color(),
+    };
+    let rounding = match tone {
+        Tone::Good => 8.0,
+        Tone::Bad => 0.0,
+        Tone::Warning | Tone::Neutral => 3.0,
+    };
+    let (fill, text, stroke) = if high_contrast {
+        (Color32::BLACK, Color32::WHITE, egui::Stroke::new(2.0, Color32::BLACK))
+    } else {
+        (Color32::TRANSPARENT, color, egui::Stroke::new(1.5, color))
+    };
+    Frame::none()
+        .fill(fill)
+        .stroke(stroke)
+        .rounding(rounding)
+        .inner_margin(egui::Margin::symmetric(4.0, 0.0))
+        .show(ui, |ui| {
+            ui.add(egui::Label::new(egui::RichText::new(code).small().strong().color(text)).truncate());
+        });
+}
+
+/// What a comparison found, one line per kind of difference.
+fn draw_differences(ui: &mut egui::Ui, diff: &PdfDiff) {
+    if diff.is_same() {
+        ui.label(t!("compare-same"));
+        return;
+    }
+    let (first, second) = diff.pages;
+    if first != second {
+        ui.label(t!("compare-pages-differ", first = first, second = second));
+    }
+    if !diff.text_pages.is_empty() {
+        let pages: Vec<String> = diff.text_pages.iter().map(u32::to_string).collect();
+        ui.label(t!("compare-text-differs", count = pages.len(), pages = pages.join(", ")));
+    }
+    for entry in &diff.metadata {
+        let value = |value: &Option<String>| value.clone().unwrap_or_else(|| t!("compare-none"));
+        ui.label(t!(
+            "compare-metadata-differs",
+            key = entry.key.as_str(),
+            first = value(&entry.first),
+            second = value(&entry.second)
+        ));
+    }
+}
+
+/// Bookmarks as a tree whose branches start collapsed, each with the page it leads to.
+fn draw_bookmarks(ui: &mut egui::Ui, outline: &[Bookmark], id: &str) {
+    for (index, bookmark) in outline.iter().enumerate() {
+        let label = match bookmark.page {
+            Some(page) => t!("outline-entry", title = bookmark.title.as_str(), page = page),
+            None => bookmark.title.clone(),
+        };
+        if bookmark.children.is_empty() {
+            ui.label(label);
+        } else {
+            let id = format!("{id}/{index}");
+            egui::CollapsingHeader::new(label)
+                .id_salt(&id)
+                .show(ui, |ui| draw_bookmarks(ui, &bookmark.children, &id));
+        }
+    }
+}
+
+/// Before/after table of a source and its result, shown when hovering over a row.
+fn draw_comparison(ui: &mut egui::Ui, source: &PdfFacts, output: &PdfFacts) {
+    fn pair(before: Option<String>, after: Option<String>) -> String {
+        let unknown = || t!("compare-unknown");
+        format!("{} → {}", before.unwrap_or_else(unknown), after.unwrap_or_else(unknown))
+    }
+    let encrypted = |facts: &PdfFacts| facts.encrypted.map(|encrypted| t!(if encrypted { "compare-yes" } else { "compare-no" }));
+    let removed = output.removed_metadata(source);
+    let rows = [
+        ("compare-size", pair(Some(format_size(source.size)), Some(format_size(output.size)))),
+        ("compare-pages", pair(source.pages.map(|n| n.to_string()), output.pages.map(|n| n.to_string()))),
+        ("compare-version", pair(source.version.clone(), output.version.clone())),
+        ("compare-encryption", pair(encrypted(source), encrypted(output))),
+        (
+            "compare-bookmarks",
+            pair(Some(Bookmark::count(&source.outline).to_string()), Some(Bookmark::count(&output.outline).to_string())),
+        ),
+        (
+            "compare-removed-metadata",
+            if removed.is_empty() { t!("compare-none") } else { removed.join(", ") },
+        ),
+    ];
+    egui::Grid::new("comparison").num_columns(2).show(ui, |ui| {
+        for (key, value) in rows {
+            ui.label(t!(key));
+            ui.label(value);
+            ui.end_row();
+        }
+    });
+}
+
+/// Hands `path` to the system's printing: the default application's print verb on
+/// Windows, the CUPS spooler (`lp`, default printer) elsewhere.
+fn print_file(path: &Path) -> anyhow::Result<()> {
+    #[cfg(target_os = "windows")]
+    {
+        use std::os::windows::ffi::OsStrExt;
+
+        use windows_sys::Win32::UI::Shell::ShellExecuteW;
+        use windows_sys::Win32::UI::WindowsAndMessaging::SW_SHOWNORMAL;
+
+        let verb: Vec<u16> = "print\0".encode_utf16().collect();
+        let file: Vec<u16> = path.as_os_str().encode_wide().chain(std::iter::once(0)).collect();
+        // SAFETY: both strings are NUL-terminated and outlive the call.
+        let result = unsafe {
+            ShellExecuteW(
+                std::ptr::null_mut(),
+                verb.as_ptr(),
+                file.as_ptr(),
+                std::ptr::null(),
+                std::ptr::null(),
+                SW_SHOWNORMAL,
+            )
+        };
+        // Values up to 32 are error codes, e.g. no application registered to print the file.
+        if result as isize <= 32 {
+            anyhow::bail!("ShellExecute error {}", result as isize);
+        }
+        Ok(())
+    }
+    #[cfg(not(target_os = "windows"))]
+    {
+        let output = Command::new("lp").arg(path).output()?;
+        if !output.status.success() {
+            anyhow::bail!("{}", String::from_utf8_lossy(&output.stderr).trim());
+        }
+        Ok(())
+    }
+}
+
+/// Shows the macOS share sheet (AirDrop, Mail, Messages, …) for `path`, anchored to
+/// the bottom of the window.
+#[cfg(target_os = "macos")]
+fn share_file(path: &Path) -> anyhow::Result<()> {
+    use std::cell::RefCell;
+
+    use anyhow::Context;
+    use objc2::rc::Retained;
+    use objc2::ClassType;
+    use objc2_app_kit::{NSApplication, NSSharingServicePicker};
+    use objc2_foundation::{MainThreadMarker, NSArray, NSRectEdge, NSString, NSURL};
+
+    thread_local! {
+        // The picker must outlive the call, as the sheet stays open after it returns.
+        static PICKER: RefCell<Option<Retained<NSSharingServicePicker>>> = const { RefCell::new(None) };
+    }
+
+    let mtm = MainThreadMarker::new().context("not on the main thread")?;
+    let view = NSApplication::sharedApplication(mtm)
+        .keyWindow()
+        .and_then(|window| window.contentView())
+        .context("no window to show the share sheet in")?;
+    // SAFETY: AppKit calls on the main thread with valid, retained arguments.
+    unsafe {
+        let url = NSURL::fileURLWithPath(&NSString::from_str(&path.to_string_lossy()));
+        let items = NSArray::from_vec(vec![Retained::into_super(Retained::into_super(url))]);
+        let picker = NSSharingServicePicker::initWithItems(NSSharingServicePicker::alloc(), &items);
+        picker.showRelativeToRect_ofView_preferredEdge(view.bounds(), &view, NSRectEdge::MinY);
+        PICKER.with(|cell| cell.replace(Some(picker)));
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "macos"))]
+fn share_file(_path: &Path) -> anyhow::Result<()> {
+    anyhow::bail!("sharing is only available on macOS")
+}
+
+fn open_entry(entry: &FileEntry) {
+    if let Some(path) = entry.output_path.as_ref() {
+        if path.exists() {
+            open_file(path);
+            return;
+        }
+    }
+    open_file(&entry.path);
+}
+
+fn show_qpdf_setup_dialog() {
+    let msg = if cfg!(target_os = "macos") {
+        t!("qpdf-missing-macos")
+    } else if cfg!(target_os = "windows") {
+        let msg = t!("qpdf-missing-windows", arch = qpdf_windows_variant());
+        if cfg!(target_arch = "aarch64") {
+            format!("{msg}\n\n{}", t!("qpdf-missing-windows-arm64"))
+        } else {
+            msg
+        }
+    } else {
+        t!("qpdf-missing-other")
+    };
+
+    let _ = rfd::MessageDialog::new()
+        .set_title(t!("qpdf-missing-title"))
+        .set_description(&msg)
+        .set_buttons(rfd::MessageButtons::Ok)
+        .set_level(rfd::MessageLevel::Error)
+        .show();
+}
+
+/// Files listed by name in a confirmation prompt, e.g. for PDFs without a `.pdf`
+/// extension; more are elided.
+const PROMPT_LISTED: usize = 10;
+
+/// Asks whether to process files that look like PDFs but are not named like one.
+/// Who signed the source of `entry`, e.g. "张三（2024-03-01 14:05）, Example Ltd".
+fn signers(entry: &FileEntry) -> String {
+    let names: Vec<String> = entry
+        .signatures()
+        .iter()
+        .map(|signature| {
+            let signer = signature.signer.clone().unwrap_or_else(|| t!("signed-unknown"));
+            match &signature.signed_at {
+                Some(date) => t!("signed-by-on", signer = signer, date = format_date(date)),
+                None => signer,
+            }
+        })
+        .collect();
+    names.join(&t!("list-separator"))
+}
+
+fn confirm_mislabeled(files: &[PathBuf]) -> bool {
+    let name = |path: &PathBuf| path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let description = match files {
+        [file] => {
+            let prompt = match file.extension() {
+                Some(ext) => t!("mislabeled-one", ext = ext.to_string_lossy().into_owned()),
+                None => t!("mislabeled-no-extension"),
+            };
+            format!("{}\n\n{prompt}", name(file))
+        }
+        _ => {
+            let mut names: Vec<String> = files.iter().take(PROMPT_LISTED).map(name).collect();
+            if files.len() > PROMPT_LISTED {
+                names.push("…".to_string());
+            }
+            format!("{}\n\n{}", t!("mislabeled-many", count = files.len()), names.join("\n"))
+        }
+    };
+    let answer = rfd::MessageDialog::new()
+        .set_title(t!("mislabeled-title"))
+        .set_description(description)
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .set_level(rfd::MessageLevel::Info)
+        .show();
+    answer == rfd::MessageDialogResult::Yes
+}
+
+/// Translated name of a built-in step; hooks and plugins keep their own names.
+fn step_name(step: &dyn ProcessingStep) -> String {
+    i18n::try_translate(&format!("step-{}", step.id())).unwrap_or_else(|| step.name().to_string())
+}
+
+#[derive(Default)]
+struct LaunchRequest {
+    paths: Vec<PathBuf>,
+    error: Option<String>,
+}
+
+impl CrackLeafApp {
+    /// Lists the files of `crackleaf://` links that arrived while the window is open.
+    #[cfg(target_os = "macos")]
+    fn handle_scheme_urls(&mut self, urls: Vec<String>) {
+        if urls.is_empty() {
+            return;
+        }
+        let launch = parse_launch_args(&urls);
+        self.add_files(launch.paths);
+        if let Some(err) = launch.error {
+            self.core.result_text = err;
+        }
+    }
+}
+
+fn parse_launch_args(urls: &[String]) -> LaunchRequest {
+    let mut launch = LaunchRequest::default();
+    for arg in urls {
+        if !url_scheme::is_scheme_url(arg) {
+            continue;
+        }
+        match url_scheme::parse_unlock_url(arg) {
+            Ok(paths) => launch.paths.extend(paths),
+            Err(err) => launch.error = Some(err.to_string()),
+        }
+    }
+    launch
+}
+
+/// Opens the window, with the files of any `crackleaf://` links in `urls` listed.
+pub fn run(urls: &[String]) -> eframe::Result<()> {
+    let launch = parse_launch_args(urls);
+    #[cfg(target_os = "macos")]
+    url_scheme::listen();
+    if let Err(err) = url_scheme::register() {
+        warn!("Failed to register {}:// handler: {err}", url_scheme::SCHEME);
+    }
+
+    let assets_dir = resolve_assets_dir();
+    let icon_data = load_window_icon(&assets_dir);
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_inner_size(Vec2::new(WINDOW_WIDTH, WINDOW_HEIGHT))
+            .with_min_inner_size(Vec2::new(WINDOW_WIDTH, WINDOW_MIN_HEIGHT))
+            .with_icon(icon_data),
+        ..Default::default()
+    };
+
+    eframe::run_native(
+        "CrackLeaf",
+        options,
+        Box::new(|cc| Ok(Box::new(CrackLeafApp::new(cc, launch)))),
+    )
+}
@@ -0,0 +1,261 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use anyhow::Result;
+use crackleaf_core::{Error, Job, JobState, UnlockOptions};
+use rusqlite::types::{Value, ValueRef};
+use rusqlite::{params, Connection, Row};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use tracing::warn;
+
+/// Entries shown in the history window and by `crackleaf-rs history`.
+pub const DEFAULT_LIMIT: usize = 50;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS files (
+    id          INTEGER PRIMARY KEY,
+    hash        TEXT NOT NULL,
+    source      TEXT NOT NULL,
+    output      TEXT,
+    status      TEXT NOT NULL,
+    error_kind  TEXT,
+    started_at  INTEGER NOT NULL,
+    finished_at INTEGER NOT NULL,
+    options     TEXT NOT NULL
+);
+CREATE INDEX IF NOT EXISTS files_hash ON files (hash);
+";
+
+/// Columns added since the first version, created on databases that lack them.
+const ADDED_COLUMNS: [(&str, &str); 2] = [("size", "INTEGER"), ("elapsed_ms", "INTEGER")];
+
+/// Files the processing speed is averaged over, newest first.
+const THROUGHPUT_SAMPLE: usize = 50;
+/// Smaller files go by too fast for their speed to say much about large ones.
+const THROUGHPUT_MIN_SIZE: u64 = 1024 * 1024;
+
+/// One processed file.
+#[derive(Clone, Debug, Serialize)]
+pub struct Record {
+    /// SHA-256 of the source file, lowercase hex.
+    pub hash: String,
+    #[serde(with = "crackleaf_core::path_serde")]
+    pub source: PathBuf,
+    #[serde(with = "crackleaf_core::path_serde::option")]
+    pub output: Option<PathBuf>,
+    /// `ok`, `unchanged` or `failed`.
+    pub status: String,
+    pub error_kind: Option<String>,
+    /// Unix seconds.
+    pub started_at: i64,
+    pub finished_at: i64,
+    /// Settings the file was processed with, as JSON.
+    pub options: String,
+    /// `finished_at` in local time, e.g. `2025-01-31 14:05`.
+    pub finished: String,
+}
+
+impl Record {
+    /// The file was encrypted rather than unlocked, by a protect batch.
+    fn protected(&self) -> bool {
+        serde_json::from_str::<serde_json::Value>(&self.options)
+            .ok()
+            .and_then(|options| options.get("pipeline")?.as_array().cloned())
+            .is_some_and(|steps| steps.iter().any(|step| step == "encrypt"))
+    }
+}
+
+/// Processing history kept in `<local data dir>/crackleaf/history.sqlite3`.
+pub struct History {
+    conn: Connection,
+}
+
+impl History {
+    /// Opens the history database. Failing to do so only disables history.
+    pub fn open() -> Option<Self> {
+        let path = dirs::data_local_dir()?.join("crackleaf").join("history.sqlite3");
+        match Self::open_at(&path) {
+            Ok(history) => Some(history),
+            Err(err) => {
+                warn!("Failed to open history {:?}: {err}", path);
+                None
+            }
+        }
+    }
+
+    fn open_at(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let conn = Connection::open(path)?;
+        conn.busy_timeout(Duration::from_secs(5))?;
+        conn.execute_batch(SCHEMA)?;
+        for (column, kind) in ADDED_COLUMNS {
+            if conn.prepare(&format!("SELECT {column} FROM files LIMIT 0")).is_err() {
+                conn.execute(&format!("ALTER TABLE files ADD COLUMN {column} {kind}"), [])?;
+            }
+        }
+        Ok(Self { conn })
+    }
+
+    /// Stores the outcome of a finished job; other states are ignored.
+    pub fn record(&self, hash: &str, job: &Job, options: &str) {
+        let (status, error_kind) = match job.state {
+            JobState::Done if job.output_path.is_some() => ("ok", None),
+            JobState::Done => ("unchanged", None),
+            JobState::Failed => ("failed", job.error.as_ref().map(Error::kind)),
+            _ => return,
+        };
+        let finished_at = unix_now();
+        let elapsed = job.elapsed.unwrap_or_default();
+        let started_at = finished_at - elapsed.as_secs() as i64;
+        let source = std::path::absolute(&job.path).unwrap_or_else(|_| job.path.clone());
+        let size = std::fs::metadata(&job.path).ok().map(|metadata| metadata.len() as i64);
+        let result = self.conn.execute(
+            "INSERT INTO files (hash, source, output, status, error_kind, started_at, finished_at, options, size, elapsed_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                hash,
+                path_to_sql(&source),
+                job.output_path.as_deref().map(path_to_sql),
+                status,
+                error_kind,
+                started_at,
+                finished_at,
+                options,
+                size,
+                elapsed.as_millis() as i64,
+            ],
+        );
+        if let Err(err) = result {
+            warn!("Failed to record history: {err}");
+        }
+    }
+
+    /// Newest entries first.
+    pub fn recent(&self, limit: usize) -> Result<Vec<Record>> {
+        let mut stmt = self.conn.prepare(&format!("{SELECT} ORDER BY id DESC LIMIT ?1"))?;
+        let records = stmt
+            .query_map([limit as i64], read_record)?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(records)
+    }
+
+    /// The last successful unlock of a file with this content whose output still exists.
+    pub fn find_unlocked(&self, hash: &str) -> Option<Record> {
+        let records = self
+            .conn
+            .prepare(&format!("{SELECT} WHERE hash = ?1 AND status = 'ok' ORDER BY id DESC"))
+            .and_then(|mut stmt| stmt.query_map([hash], read_record)?.collect::<rusqlite::Result<Vec<_>>>());
+        match records {
+            Ok(records) => records
+                .into_iter()
+                .find(|record| !record.protected() && record.output.as_ref().is_some_and(|path| path.exists())),
+            Err(err) => {
+                warn!("Failed to query history: {err}");
+                None
+            }
+        }
+    }
+
+    /// Bytes processed per second by the last successful unlocks of files of at least
+    /// [`THROUGHPUT_MIN_SIZE`]; `None` before there are any.
+    pub fn throughput(&self) -> Option<f64> {
+        let (bytes, millis): (Option<i64>, Option<i64>) = self
+            .conn
+            .query_row(
+                "SELECT SUM(size), SUM(elapsed_ms) FROM (SELECT size, elapsed_ms FROM files
+                 WHERE status = 'ok' AND size >= ?1 AND elapsed_ms > 0 ORDER BY id DESC LIMIT ?2)",
+                [THROUGHPUT_MIN_SIZE as i64, THROUGHPUT_SAMPLE as i64],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .map_err(|err| warn!("Failed to read the history: {err}"))
+            .ok()?;
+        Some(bytes? as f64 * 1000.0 / millis? as f64)
+    }
+
+    pub fn clear(&self) -> Result<()> {
+        self.conn.execute("DELETE FROM files", [])?;
+        Ok(())
+    }
+}
+
+const SELECT: &str = "SELECT hash, source, output, status, error_kind, started_at, finished_at, options,
+    strftime('%Y-%m-%d %H:%M', finished_at, 'unixepoch', 'localtime') FROM files";
+
+fn read_record(row: &Row<'_>) -> rusqlite::Result<Record> {
+    Ok(Record {
+        hash: row.get(0)?,
+        source: path_from_sql(row.get_ref(1)?).unwrap_or_default(),
+        output: path_from_sql(row.get_ref(2)?),
+        status: row.get(3)?,
+        error_kind: row.get(4)?,
+        started_at: row.get(5)?,
+        finished_at: row.get(6)?,
+        options: row.get(7)?,
+        finished: row.get(8)?,
+    })
+}
+
+/// Paths are stored as text, or on Unix as raw bytes when they are not valid Unicode.
+fn path_to_sql(path: &Path) -> Value {
+    if let Some(text) = path.to_str() {
+        return Value::Text(text.to_string());
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Value::Blob(path.as_os_str().as_bytes().to_vec())
+    }
+    #[cfg(not(unix))]
+    Value::Text(path.to_string_lossy().into_owned())
+}
+
+fn path_from_sql(value: ValueRef<'_>) -> Option<PathBuf> {
+    let bytes = match value {
+        ValueRef::Text(bytes) | ValueRef::Blob(bytes) => bytes,
+        _ => return None,
+    };
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+    }
+    #[cfg(not(unix))]
+    Some(PathBuf::from(String::from_utf8_lossy(bytes).into_owned()))
+}
+
+/// The current local time in SQLite's `strftime` `format`, e.g. `%Y-%m-%d`; SQLite
+/// already knows the time zone.
+pub fn local_now(format: &str) -> Option<String> {
+    let conn = Connection::open_in_memory().ok()?;
+    conn.query_row("SELECT strftime(?1, 'now', 'localtime')", [format], |row| row.get(0))
+        .map_err(|err| warn!("Failed to read the local time: {err}"))
+        .ok()
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs() as i64)
+}
+
+/// SHA-256 of the file contents, lowercase hex.
+pub fn hash_file(path: &Path) -> std::io::Result<String> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    std::io::copy(&mut file, &mut hasher)?;
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// Settings worth remembering with each entry.
+pub fn describe_options(options: &UnlockOptions, profile: Option<&str>) -> String {
+    serde_json::json!({
+        "profile": profile,
+        "pipeline": options.pipeline.iter().map(|step| step.id()).collect::<Vec<_>>(),
+        "output_dir": options.output_dir.as_ref().map(|dir| dir.to_string_lossy()),
+        "timeout_secs": options.timeout.map(|limit| limit.as_secs()),
+    })
+    .to_string()
+}
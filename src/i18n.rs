@@ -0,0 +1,305 @@
+#[cfg(feature = "gui")]
+use std::path::Path;
+#[cfg(feature = "gui")]
+use std::time::Duration;
+#[cfg(feature = "gui")]
+use std::sync::{OnceLock, RwLock};
+
+use crackleaf_core::{scale_size, Error};
+#[cfg(feature = "gui")]
+use fluent_bundle::concurrent::FluentBundle;
+#[cfg(feature = "gui")]
+use fluent_bundle::{FluentArgs, FluentResource};
+#[cfg(feature = "gui")]
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+#[cfg(feature = "gui")]
+use unic_langid::LanguageIdentifier;
+#[cfg(feature = "gui")]
+use tracing::{debug, warn};
+
+/// Used for any message missing from the selected locale.
+#[cfg(feature = "gui")]
+const FALLBACK_LOCALE: &str = "zh-CN";
+#[cfg(feature = "gui")]
+const FALLBACK_FTL: &str = include_str!("../assets/locales/zh-CN.ftl");
+
+/// Looks up a message in the current language, e.g. `t!("hint-imported", count = 3)`.
+#[cfg(feature = "gui")]
+macro_rules! t {
+    ($id:expr) => {
+        $crate::i18n::translate($id, None)
+    };
+    ($id:expr, $($name:ident = $value:expr),+ $(,)?) => {{
+        let mut args = fluent_bundle::FluentArgs::new();
+        $(args.set(stringify!($name), $value);)+
+        $crate::i18n::translate($id, Some(&args))
+    }};
+}
+
+/// Without the window the texts are never shown and no translations are built in;
+/// the message id stands in for them.
+#[cfg(not(feature = "gui"))]
+macro_rules! t {
+    ($id:expr $(, $name:ident = $value:expr)* $(,)?) => {{
+        $(let _ = $value;)*
+        String::from($id)
+    }};
+}
+pub(crate) use t;
+
+#[cfg(feature = "gui")]
+struct Locale {
+    id: LanguageIdentifier,
+    source: String,
+    name: String,
+}
+
+#[cfg(feature = "gui")]
+struct Localizer {
+    locales: Vec<Locale>,
+    current: LanguageIdentifier,
+    bundle: FluentBundle<FluentResource>,
+    fallback: FluentBundle<FluentResource>,
+}
+
+#[cfg(feature = "gui")]
+static LOCALIZER: OnceLock<RwLock<Localizer>> = OnceLock::new();
+
+#[cfg(feature = "gui")]
+fn fallback_id() -> LanguageIdentifier {
+    FALLBACK_LOCALE.parse().expect("valid fallback locale")
+}
+
+#[cfg(feature = "gui")]
+fn localizer() -> &'static RwLock<Localizer> {
+    LOCALIZER.get_or_init(|| {
+        let id = fallback_id();
+        let name = display_name(&id, FALLBACK_FTL);
+        RwLock::new(Localizer {
+            locales: vec![Locale {
+                id: id.clone(),
+                source: FALLBACK_FTL.to_string(),
+                name,
+            }],
+            current: id.clone(),
+            bundle: build_bundle(&id, FALLBACK_FTL),
+            fallback: build_bundle(&id, FALLBACK_FTL),
+        })
+    })
+}
+
+#[cfg(feature = "gui")]
+fn build_bundle(id: &LanguageIdentifier, source: &str) -> FluentBundle<FluentResource> {
+    let resource = FluentResource::try_new(source.to_string()).unwrap_or_else(|(resource, errors)| {
+        warn!("Errors in {id} translations: {errors:?}");
+        resource
+    });
+    let mut bundle = FluentBundle::new_concurrent(vec![id.clone()]);
+    // Unicode isolation marks show up as boxes in egui.
+    bundle.set_use_isolating(false);
+    if let Err(errors) = bundle.add_resource(resource) {
+        warn!("Duplicate messages in {id} translations: {errors:?}");
+    }
+    bundle
+}
+
+#[cfg(feature = "gui")]
+fn display_name(id: &LanguageIdentifier, source: &str) -> String {
+    format_message(&build_bundle(id, source), "language-name", None).unwrap_or_else(|| id.to_string())
+}
+
+#[cfg(feature = "gui")]
+fn format_message(
+    bundle: &FluentBundle<FluentResource>,
+    id: &str,
+    args: Option<&FluentArgs>,
+) -> Option<String> {
+    let pattern = bundle.get_message(id)?.value()?;
+    let mut errors = Vec::new();
+    let text = bundle.format_pattern(pattern, args, &mut errors);
+    if !errors.is_empty() {
+        debug!("Errors formatting {id}: {errors:?}");
+    }
+    Some(text.into_owned())
+}
+
+/// Loads every `<assets>/locales/<language tag>.ftl` and selects `language`,
+/// or the system language when `None`.
+#[cfg(feature = "gui")]
+pub fn init(assets_dir: &Path, language: Option<&str>) {
+    let mut locales = Vec::new();
+    if let Ok(entries) = std::fs::read_dir(assets_dir.join("locales")) {
+        for path in entries.filter_map(|entry| entry.ok().map(|entry| entry.path())) {
+            if path.extension().is_none_or(|ext| ext != "ftl") {
+                continue;
+            }
+            let Some(id) = path
+                .file_stem()
+                .and_then(|stem| stem.to_str())
+                .and_then(|stem| stem.parse::<LanguageIdentifier>().ok())
+            else {
+                warn!("Ignoring translation file with an invalid name: {:?}", path);
+                continue;
+            };
+            match std::fs::read_to_string(&path) {
+                Ok(source) => {
+                    let name = display_name(&id, &source);
+                    locales.push(Locale { id, source, name });
+                }
+                Err(err) => warn!("Failed to read {:?}: {err}", path),
+            }
+        }
+    }
+    if !locales.iter().any(|locale| locale.id == fallback_id()) {
+        let id = fallback_id();
+        let name = display_name(&id, FALLBACK_FTL);
+        locales.push(Locale {
+            id,
+            source: FALLBACK_FTL.to_string(),
+            name,
+        });
+    }
+    locales.sort_by_key(|locale| locale.id.to_string());
+
+    let fallback_source = locales
+        .iter()
+        .find(|locale| locale.id == fallback_id())
+        .map(|locale| locale.source.clone())
+        .unwrap_or_else(|| FALLBACK_FTL.to_string());
+    {
+        let mut localizer = localizer().write().unwrap_or_else(|err| err.into_inner());
+        localizer.locales = locales;
+        localizer.fallback = build_bundle(&fallback_id(), &fallback_source);
+    }
+    set_language(language);
+}
+
+/// Switches the UI language; `None` follows the system setting.
+#[cfg(feature = "gui")]
+pub fn set_language(language: Option<&str>) {
+    let requested: Vec<LanguageIdentifier> = match language {
+        Some(tag) => tag.parse().ok().into_iter().collect(),
+        None => sys_locale::get_locales()
+            .filter_map(|tag| tag.parse().ok())
+            .collect(),
+    };
+
+    let mut localizer = localizer().write().unwrap_or_else(|err| err.into_inner());
+    let available: Vec<LanguageIdentifier> =
+        localizer.locales.iter().map(|locale| locale.id.clone()).collect();
+    let default = fallback_id();
+    let chosen = negotiate_languages(
+        &requested,
+        &available,
+        Some(&default),
+        NegotiationStrategy::Lookup,
+    )
+    .first()
+    .map(|id| (*id).clone())
+    .unwrap_or(default);
+
+    if let Some(locale) = localizer.locales.iter().find(|locale| locale.id == chosen) {
+        let bundle = build_bundle(&locale.id, &locale.source);
+        localizer.bundle = bundle;
+        localizer.current = chosen;
+    }
+}
+
+/// Language tags of the loaded translations with their own display names.
+#[cfg(feature = "gui")]
+pub fn available_languages() -> Vec<(String, String)> {
+    let localizer = localizer().read().unwrap_or_else(|err| err.into_inner());
+    localizer
+        .locales
+        .iter()
+        .map(|locale| (locale.id.to_string(), locale.name.clone()))
+        .collect()
+}
+
+#[cfg(feature = "gui")]
+pub fn current_language() -> String {
+    let localizer = localizer().read().unwrap_or_else(|err| err.into_inner());
+    localizer.current.to_string()
+}
+
+/// Formats message `id`, falling back to the built-in locale and then to the id itself.
+#[cfg(feature = "gui")]
+pub fn translate(id: &str, args: Option<&FluentArgs>) -> String {
+    let localizer = localizer().read().unwrap_or_else(|err| err.into_inner());
+    format_message(&localizer.bundle, id, args)
+        .or_else(|| format_message(&localizer.fallback, id, args))
+        .unwrap_or_else(|| id.to_string())
+}
+
+/// Like [`translate`], but returns `None` when no locale has the message.
+#[cfg(feature = "gui")]
+pub fn try_translate(id: &str) -> Option<String> {
+    let localizer = localizer().read().unwrap_or_else(|err| err.into_inner());
+    format_message(&localizer.bundle, id, None).or_else(|| format_message(&localizer.fallback, id, None))
+}
+
+/// A byte count with the current language's decimal separator, e.g. `1.4 MB`.
+pub fn format_size(bytes: u64) -> String {
+    let size = match scale_size(bytes) {
+        (_, "B") => (bytes.to_string(), "B"),
+        (size, unit) => (decimal(size), unit),
+    };
+    t!("format-size", size = size.0, unit = size.1)
+}
+
+/// `4.2 s` under a minute, `3:07` above, as the current language writes them.
+#[cfg(feature = "gui")]
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.as_secs_f64();
+    if seconds < 60.0 {
+        t!("format-seconds", seconds = decimal(seconds))
+    } else {
+        let seconds = duration.as_secs();
+        t!("format-minutes", minutes = seconds / 60, seconds = format!("{:02}", seconds % 60))
+    }
+}
+
+/// A local date written `2025-01-31 14:05` or `2025-01-31` in the current language's
+/// order; anything else is returned as is.
+#[cfg(feature = "history")]
+pub fn format_date(date: &str) -> String {
+    let number = |range: std::ops::Range<usize>| date.get(range)?.parse::<u32>().ok();
+    let (Some(year), Some(month), Some(day)) = (date.get(0..4), number(5..7), number(8..10)) else {
+        return date.to_string();
+    };
+    if year.parse::<u32>().is_err() {
+        return date.to_string();
+    }
+    match (number(11..13), date.get(14..16)) {
+        (Some(hour), Some(minute)) => t!(
+            "format-date-time",
+            year = year,
+            month = month,
+            day = day,
+            hour = hour,
+            minute = minute
+        ),
+        _ => t!("format-date", year = year, month = month, day = day),
+    }
+}
+
+/// `value` to one decimal, with the current language's separator.
+fn decimal(value: f64) -> String {
+    format!("{value:.1}").replace('.', &t!("format-decimal-separator"))
+}
+
+/// Localized text for an engine error.
+pub fn error_message(err: &Error) -> String {
+    match err {
+        Error::WrongPassword => t!("error-wrong-password"),
+        Error::Timeout(limit) => t!("error-timeout", seconds = limit.as_secs()),
+        Error::Corrupt(detail) => t!("error-corrupt", detail = detail.as_str()),
+        Error::SourceMissing(detail) => t!("error-source-missing", detail = detail.as_str()),
+        Error::SourceLocked(detail) => t!("error-source-locked", detail = detail.as_str()),
+        Error::CloudUnavailable(detail) => t!("error-cloud-unavailable", detail = detail.as_str()),
+        Error::OutputNotWritable(detail) => t!("error-output-not-writable", detail = detail.as_str()),
+        Error::OutputExists(detail) => t!("error-output-exists", detail = detail.as_str()),
+        Error::QpdfMissing(detail) => t!("error-qpdf-missing", detail = detail.as_str()),
+        other => other.to_string(),
+    }
+}
@@ -0,0 +1,177 @@
+#[cfg(feature = "gui")]
+use std::f32::consts::PI;
+
+#[cfg(feature = "gui")]
+use eframe::egui::{self, Color32, Pos2, Rect, Response, Sense, Shape, Stroke, Vec2};
+
+/// Status and button icons, drawn with shapes so they look the same everywhere
+/// instead of depending on the emoji in the installed fonts.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Icon {
+    /// Waiting its turn.
+    Waiting,
+    /// Being worked on; spins.
+    #[cfg(feature = "gui")]
+    Working,
+    #[cfg(feature = "gui")]
+    Searching,
+    Cancelled,
+    Locked,
+    Unlocked,
+    /// Needs a password to open.
+    Password,
+    /// Still in the cloud, not downloaded.
+    Cloud,
+    Timeout,
+    Warning,
+    Missing,
+    /// Held open by another program.
+    InUse,
+    #[cfg(feature = "gui")]
+    Up,
+    #[cfg(feature = "gui")]
+    Down,
+    #[cfg(feature = "gui")]
+    Remove,
+}
+
+/// Draws `icon` in a `size` square and returns its response, for hover text.
+#[cfg(feature = "gui")]
+pub fn icon(ui: &mut egui::Ui, icon: Icon, size: Vec2) -> Response {
+    let (rect, response) = ui.allocate_exact_size(size, Sense::hover());
+    if ui.is_rect_visible(rect) {
+        paint(ui, icon, Rect::from_center_size(rect.center(), Vec2::splat(size.min_elem() * 0.8)));
+    }
+    response
+}
+
+/// A button showing `icon`, the size of a text button.
+#[cfg(feature = "gui")]
+pub fn icon_button(ui: &mut egui::Ui, icon: Icon, enabled: bool) -> Response {
+    let side = ui.spacing().interact_size.y;
+    let response = ui.add_enabled(enabled, egui::Button::new("").min_size(Vec2::splat(side)));
+    let rect = Rect::from_center_size(response.rect.center(), Vec2::splat(side * 0.5));
+    let color = if enabled { ui.visuals().text_color() } else { ui.visuals().weak_text_color() };
+    match icon {
+        Icon::Up | Icon::Down | Icon::Remove => paint_action(ui.painter(), icon, rect, color),
+        _ => paint(ui, icon, rect),
+    }
+    response
+}
+
+/// Draws a status icon into `rect`, in the colors of the current theme.
+#[cfg(feature = "gui")]
+fn paint(ui: &egui::Ui, icon: Icon, rect: Rect) {
+    let visuals = ui.visuals();
+    let text = visuals.text_color();
+    let good = if visuals.override_text_color.is_some() { text } else { Color32::from_rgb(0x1B, 0x6E, 0x2A) };
+    let painter = ui.painter();
+    let at = |x: f32, y: f32| rect.min + rect.size() * Vec2::new(x, y);
+    let line = Stroke::new((rect.width() / 10.0).max(1.5), text);
+    match icon {
+        Icon::Working => {
+            egui::Spinner::new().size(rect.width()).paint_at(ui, rect);
+        }
+        Icon::Waiting => {
+            // An hourglass.
+            let stroke = Stroke::new(line.width, visuals.weak_text_color());
+            painter.add(Shape::closed_line(
+                vec![at(0.2, 0.1), at(0.8, 0.1), at(0.2, 0.9), at(0.8, 0.9)],
+                stroke,
+            ));
+        }
+        Icon::Searching => {
+            let stroke = Stroke::new(line.width, visuals.weak_text_color());
+            painter.circle_stroke(at(0.42, 0.42), rect.width() * 0.28, stroke);
+            painter.line_segment([at(0.62, 0.62), at(0.9, 0.9)], stroke);
+        }
+        Icon::Cancelled => {
+            let stroke = Stroke::new(line.width, visuals.warn_fg_color);
+            painter.circle_stroke(rect.center(), rect.width() * 0.42, stroke);
+            painter.line_segment([at(0.2, 0.8), at(0.8, 0.2)], stroke);
+        }
+        Icon::Locked => lock(painter, rect, visuals.warn_fg_color, false),
+        Icon::InUse => lock(painter, rect, visuals.error_fg_color, false),
+        Icon::Unlocked => lock(painter, rect, good, true),
+        Icon::Password => {
+            let stroke = Stroke::new(line.width, visuals.error_fg_color);
+            painter.circle_stroke(at(0.27, 0.5), rect.width() * 0.17, stroke);
+            painter.line_segment([at(0.44, 0.5), at(0.92, 0.5)], stroke);
+            painter.line_segment([at(0.72, 0.5), at(0.72, 0.68)], stroke);
+            painter.line_segment([at(0.86, 0.5), at(0.86, 0.68)], stroke);
+        }
+        Icon::Cloud => {
+            let color = visuals.warn_fg_color;
+            let width = rect.width();
+            painter.circle_filled(at(0.3, 0.6), width * 0.17, color);
+            painter.circle_filled(at(0.52, 0.45), width * 0.24, color);
+            painter.circle_filled(at(0.74, 0.6), width * 0.17, color);
+            painter.rect_filled(Rect::from_min_max(at(0.3, 0.55), at(0.74, 0.77)), 0.0, color);
+        }
+        Icon::Timeout => {
+            let stroke = Stroke::new(line.width, visuals.error_fg_color);
+            painter.circle_stroke(rect.center(), rect.width() * 0.42, stroke);
+            painter.line_segment([rect.center(), at(0.5, 0.22)], stroke);
+            painter.line_segment([rect.center(), at(0.7, 0.6)], stroke);
+        }
+        Icon::Warning => {
+            let color = visuals.error_fg_color;
+            painter.add(Shape::convex_polygon(vec![at(0.5, 0.05), at(0.97, 0.92), at(0.03, 0.92)], color, Stroke::NONE));
+            let mark = Stroke::new(line.width, visuals.panel_fill);
+            painter.line_segment([at(0.5, 0.35), at(0.5, 0.62)], mark);
+            painter.circle_filled(at(0.5, 0.77), line.width * 0.7, visuals.panel_fill);
+        }
+        Icon::Missing => {
+            let color = visuals.error_fg_color;
+            painter.circle_stroke(rect.center(), rect.width() * 0.42, Stroke::new(line.width, color));
+            // The arc of a question mark, its stem and dot.
+            let center = at(0.5, 0.4);
+            let radius = rect.width() * 0.14;
+            let points = (0..=8)
+                .map(|step| {
+                    let angle = PI + PI * 1.4 * step as f32 / 8.0;
+                    center + radius * Vec2::angled(angle)
+                })
+                .chain([at(0.5, 0.62)])
+                .collect();
+            painter.add(Shape::line(points, Stroke::new(line.width * 0.8, color)));
+            painter.circle_filled(at(0.5, 0.76), line.width * 0.6, color);
+        }
+        Icon::Up | Icon::Down | Icon::Remove => paint_action(painter, icon, rect, text),
+    }
+}
+
+/// A padlock; `open` lifts the right end of the shackle out of the body.
+#[cfg(feature = "gui")]
+fn lock(painter: &egui::Painter, rect: Rect, color: Color32, open: bool) {
+    let at = |x: f32, y: f32| rect.min + rect.size() * Vec2::new(x, y);
+    painter.rect_filled(Rect::from_min_max(at(0.15, 0.45), at(0.85, 0.95)), rect.width() * 0.08, color);
+    let lift = if open { 0.14 } else { 0.0 };
+    let center = at(0.5, 0.32 - lift);
+    let radius = rect.width() * 0.22;
+    let mut points: Vec<Pos2> = vec![at(0.28, 0.45)];
+    points.extend((0..=12).map(|step| center + radius * Vec2::angled(PI + PI * step as f32 / 12.0)));
+    points.push(at(0.72, if open { 0.32 - lift } else { 0.45 }));
+    painter.add(Shape::line(points, Stroke::new((rect.width() / 9.0).max(1.5), color)));
+}
+
+/// Arrows and a cross for the buttons that move and remove pipeline stages.
+#[cfg(feature = "gui")]
+fn paint_action(painter: &egui::Painter, icon: Icon, rect: Rect, color: Color32) {
+    let at = |x: f32, y: f32| rect.min + rect.size() * Vec2::new(x, y);
+    let stroke = Stroke::new((rect.width() / 7.0).max(1.5), color);
+    match icon {
+        Icon::Up => {
+            painter.line_segment([at(0.5, 0.1), at(0.5, 0.9)], stroke);
+            painter.add(Shape::line(vec![at(0.15, 0.45), at(0.5, 0.1), at(0.85, 0.45)], stroke));
+        }
+        Icon::Down => {
+            painter.line_segment([at(0.5, 0.1), at(0.5, 0.9)], stroke);
+            painter.add(Shape::line(vec![at(0.15, 0.55), at(0.5, 0.9), at(0.85, 0.55)], stroke));
+        }
+        _ => {
+            painter.line_segment([at(0.15, 0.15), at(0.85, 0.85)], stroke);
+            painter.line_segment([at(0.85, 0.15), at(0.15, 0.85)], stroke);
+        }
+    }
+}
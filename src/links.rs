@@ -0,0 +1,102 @@
+use std::path::{Path, PathBuf};
+
+/// Follows symlinks, and Finder aliases on macOS, to the file they point at, so a file
+/// added through a link is listed (and unlocked next to) its real location.
+/// Paths that cannot be resolved are returned unchanged.
+pub fn resolve(path: &Path) -> PathBuf {
+    #[cfg(target_os = "macos")]
+    let alias_target = alias::target(path);
+    #[cfg(target_os = "macos")]
+    let path = alias_target.as_deref().unwrap_or(path);
+    match std::fs::canonicalize(path) {
+        Ok(real) => strip_verbatim(real),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// `canonicalize` returns `\\?\C:\...` on Windows; keep the familiar form for local drives.
+#[cfg(windows)]
+fn strip_verbatim(path: PathBuf) -> PathBuf {
+    use std::path::{Component, Prefix};
+    match path.components().next() {
+        Some(Component::Prefix(prefix)) if matches!(prefix.kind(), Prefix::VerbatimDisk(_)) => path
+            .to_str()
+            .and_then(|text| text.strip_prefix(r"\\?\"))
+            .map_or(path.clone(), PathBuf::from),
+        _ => path,
+    }
+}
+
+#[cfg(not(windows))]
+fn strip_verbatim(path: PathBuf) -> PathBuf {
+    path
+}
+
+#[cfg(target_os = "macos")]
+mod alias {
+    use std::ffi::OsString;
+    use std::io::Read;
+    use std::os::unix::ffi::{OsStrExt, OsStringExt};
+    use std::path::{Path, PathBuf};
+    use std::ptr;
+
+    use core_foundation_sys::base::{kCFAllocatorDefault, CFIndex, CFRelease};
+    use core_foundation_sys::url::{
+        kCFURLBookmarkResolutionWithoutMountingMask, kCFURLBookmarkResolutionWithoutUIMask,
+        CFURLCreateBookmarkDataFromFile, CFURLCreateByResolvingBookmarkData,
+        CFURLCreateFromFileSystemRepresentation, CFURLGetFileSystemRepresentation,
+    };
+
+    /// Alias files start with bookmark data.
+    const MAGIC: &[u8] = b"book\0\0\0\0mark\0\0\0\0";
+    const PATH_MAX: usize = 4096;
+
+    /// Where the Finder alias at `path` points, or `None` if it is not an alias.
+    pub fn target(path: &Path) -> Option<PathBuf> {
+        let mut header = [0u8; 16];
+        std::fs::File::open(path).ok()?.read_exact(&mut header).ok()?;
+        if header[..] != *MAGIC {
+            return None;
+        }
+        let bytes = path.as_os_str().as_bytes();
+        // SAFETY: every object created here is checked for null and released once.
+        unsafe {
+            let url = CFURLCreateFromFileSystemRepresentation(
+                kCFAllocatorDefault,
+                bytes.as_ptr(),
+                bytes.len() as CFIndex,
+                0,
+            );
+            if url.is_null() {
+                return None;
+            }
+            let data = CFURLCreateBookmarkDataFromFile(kCFAllocatorDefault, url, ptr::null_mut());
+            CFRelease(url.cast());
+            if data.is_null() {
+                return None;
+            }
+            let mut stale = 0;
+            let target = CFURLCreateByResolvingBookmarkData(
+                kCFAllocatorDefault,
+                data,
+                kCFURLBookmarkResolutionWithoutUIMask | kCFURLBookmarkResolutionWithoutMountingMask,
+                ptr::null(),
+                ptr::null(),
+                &mut stale,
+                ptr::null_mut(),
+            );
+            CFRelease(data.cast());
+            if target.is_null() {
+                return None;
+            }
+            let mut buffer = vec![0u8; PATH_MAX];
+            let ok = CFURLGetFileSystemRepresentation(target, 1, buffer.as_mut_ptr(), buffer.len() as CFIndex);
+            CFRelease(target.cast());
+            if ok == 0 {
+                return None;
+            }
+            buffer.truncate(buffer.iter().position(|&b| b == 0)?);
+            Some(PathBuf::from(OsString::from_vec(buffer)))
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use std::path::PathBuf;
+
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter, Layer};
+
+const MAX_LOG_FILES: usize = 7;
+
+/// Platform log directory: `~/Library/Logs/CrackLeaf` on macOS, the local app data
+/// folder on Windows and `$XDG_STATE_HOME/crackleaf/logs` on Linux.
+pub fn log_dir() -> Option<PathBuf> {
+    if cfg!(target_os = "macos") {
+        dirs::home_dir().map(|home| home.join("Library").join("Logs").join("CrackLeaf"))
+    } else if cfg!(target_os = "windows") {
+        dirs::data_local_dir().map(|dir| dir.join("CrackLeaf").join("logs"))
+    } else {
+        dirs::state_dir()
+            .or_else(dirs::data_local_dir)
+            .map(|dir| dir.join("crackleaf").join("logs"))
+    }
+}
+
+/// Sets up daily-rotated file logging, mirrored to stderr when `verbose` is set.
+///
+/// `RUST_LOG` overrides the default level. Keep the returned guard alive until exit
+/// so buffered lines are flushed.
+pub fn init(verbose: bool) -> Option<WorkerGuard> {
+    let default_level = if verbose { "debug" } else { "info" };
+    let filter = || {
+        EnvFilter::try_from_default_env().unwrap_or_else(|_| {
+            EnvFilter::new(format!("crackleaf_rs={default_level},crackleaf_core={default_level}"))
+        })
+    };
+
+    let (file_layer, guard) = match log_dir().map(|dir| {
+        std::fs::create_dir_all(&dir)?;
+        RollingFileAppender::builder()
+            .rotation(Rotation::DAILY)
+            .filename_prefix("crackleaf")
+            .filename_suffix("log")
+            .max_log_files(MAX_LOG_FILES)
+            .build(dir)
+            .map_err(std::io::Error::other)
+    }) {
+        Some(Ok(appender)) => {
+            let (writer, guard) = tracing_appender::non_blocking(appender);
+            let layer = fmt::layer()
+                .with_writer(writer)
+                .with_ansi(false)
+                .with_filter(filter());
+            (Some(layer), Some(guard))
+        }
+        _ => (None, None),
+    };
+
+    let console_layer = verbose.then(|| {
+        fmt::layer()
+            .with_writer(std::io::stderr)
+            .with_filter(filter())
+    });
+
+    let _ = tracing_subscriber::registry()
+        .with(file_layer)
+        .with(console_layer)
+        .try_init();
+    guard
+}
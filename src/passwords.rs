@@ -0,0 +1,172 @@
+use std::path::PathBuf;
+
+use anyhow::Result;
+use keyring::Entry;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use tracing::{info, warn};
+
+/// Passwords kept; the least recently added are dropped beyond this.
+const MAX_PASSWORDS: usize = 50;
+/// Service the passwords are filed under in the platform keychain.
+const SERVICE: &str = "CrackLeaf";
+/// Keychain account of the random key the [`Saved::digest`]s are made with.
+const DIGEST_KEY_ACCOUNT: &str = "digest-key";
+
+/// A remembered password, as listed in `<local data dir>/crackleaf/passwords.json`.
+/// The password itself is in the keychain (macOS Keychain, Windows Credential
+/// Manager, Secret Service on Linux) under [`SERVICE`] and `account`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Saved {
+    pub account: String,
+    /// What the password first opened, e.g. an archive's file name.
+    pub label: String,
+    /// SHA-256 of the password behind a key kept in the keychain, so duplicates are
+    /// spotted without reading every password back.
+    #[serde(default)]
+    pub digest: String,
+}
+
+fn data_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("crackleaf"))
+}
+
+/// Remembered passwords, most recently added first.
+pub fn list() -> Vec<Saved> {
+    let Some(dir) = data_dir() else {
+        return Vec::new();
+    };
+    let Ok(json) = std::fs::read(dir.join("passwords.json")) else {
+        return Vec::new();
+    };
+    serde_json::from_slice(&json).unwrap_or_else(|err| {
+        warn!("Failed to read the password list: {err}");
+        Vec::new()
+    })
+}
+
+/// The remembered passwords, in the order of [`list`]. Ones the keychain no longer
+/// has, or will not hand out, are left out.
+pub fn load() -> Vec<String> {
+    list()
+        .iter()
+        .filter_map(|saved| match Entry::new(SERVICE, &saved.account).and_then(|entry| entry.get_password()) {
+            Ok(password) => Some(password),
+            Err(err) => {
+                warn!(label = %saved.label, "Failed to read password from the keychain: {err}");
+                None
+            }
+        })
+        .collect()
+}
+
+/// Stores `password` under `label`, unless it is remembered already.
+pub fn remember(password: &str, label: &str) {
+    let digest = match digest_key() {
+        Ok(key) => digest(&key, password),
+        Err(err) => {
+            warn!("Failed to read the password digest key from the keychain: {err}");
+            return;
+        }
+    };
+    let mut saved = list();
+    if saved.iter().any(|known| known.digest == digest) {
+        return;
+    }
+    let number = saved
+        .iter()
+        .filter_map(|saved| saved.account.strip_prefix("password-")?.parse::<u64>().ok())
+        .max()
+        .unwrap_or(0)
+        + 1;
+    let account = format!("password-{number}");
+    if let Err(err) = Entry::new(SERVICE, &account).and_then(|entry| entry.set_password(password)) {
+        warn!("Failed to store password in the keychain: {err}");
+        return;
+    }
+    saved.insert(
+        0,
+        Saved {
+            account,
+            label: label.to_string(),
+            digest,
+        },
+    );
+    for dropped in saved.split_off(saved.len().min(MAX_PASSWORDS)) {
+        delete_secret(&dropped);
+    }
+    if let Err(err) = write_list(&saved) {
+        warn!("Failed to write the password list: {err:#}");
+        return;
+    }
+    info!(%label, "password remembered");
+}
+
+/// Removes the password stored as `account`.
+#[cfg(feature = "gui")]
+pub fn delete(account: &str) {
+    let mut saved = list();
+    let Some(index) = saved.iter().position(|saved| saved.account == account) else {
+        return;
+    };
+    let removed = saved.remove(index);
+    delete_secret(&removed);
+    match write_list(&saved) {
+        Ok(()) => info!(label = %removed.label, "remembered password deleted"),
+        Err(err) => warn!("Failed to write the password list: {err:#}"),
+    }
+}
+
+/// Removes every remembered password.
+#[cfg(feature = "gui")]
+pub fn forget() {
+    for saved in list() {
+        delete_secret(&saved);
+    }
+    if let Ok(entry) = Entry::new(SERVICE, DIGEST_KEY_ACCOUNT) {
+        let _ = entry.delete_credential();
+    }
+    if let Some(dir) = data_dir() {
+        let _ = std::fs::remove_file(dir.join("passwords.json"));
+    }
+    info!("remembered passwords forgotten");
+}
+
+fn delete_secret(saved: &Saved) {
+    match Entry::new(SERVICE, &saved.account).and_then(|entry| entry.delete_credential()) {
+        Ok(()) | Err(keyring::Error::NoEntry) => {}
+        Err(err) => warn!(label = %saved.label, "Failed to delete password from the keychain: {err}"),
+    }
+}
+
+/// The key behind [`Saved::digest`], created on first use.
+fn digest_key() -> keyring::Result<String> {
+    let entry = Entry::new(SERVICE, DIGEST_KEY_ACCOUNT)?;
+    match entry.get_password() {
+        Err(keyring::Error::NoEntry) => {
+            let mut bytes = [0; 32];
+            getrandom::getrandom(&mut bytes).map_err(|err| keyring::Error::PlatformFailure(err.to_string().into()))?;
+            let key: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+            entry.set_password(&key)?;
+            Ok(key)
+        }
+        result => result,
+    }
+}
+
+/// SHA-256 of `key` and `password`, lowercase hex.
+fn digest(key: &str, password: &str) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(key.as_bytes());
+    hasher.update(password.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+fn write_list(saved: &[Saved]) -> Result<()> {
+    let dir = data_dir().ok_or_else(|| anyhow::anyhow!("no local data directory"))?;
+    std::fs::create_dir_all(&dir)?;
+    let tmp = dir.join("passwords.json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(saved)?)?;
+    std::fs::rename(&tmp, dir.join("passwords.json"))?;
+    Ok(())
+}
@@ -0,0 +1,34 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crackleaf_core::{pending_files, BatchSession};
+use tracing::warn;
+
+/// `<local data dir>/crackleaf/session.jsonl`.
+fn session_path() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("crackleaf").join("session.jsonl"))
+}
+
+/// Records a new batch, replacing whatever was left from the previous one.
+pub fn start_batch(files: &[PathBuf]) -> Option<Arc<BatchSession>> {
+    let path = session_path()?;
+    match BatchSession::create(&path, files) {
+        Ok(session) => Some(Arc::new(session)),
+        Err(err) => {
+            warn!("Failed to record batch session: {err}");
+            None
+        }
+    }
+}
+
+/// Files an interrupted batch did not get to.
+pub fn pending() -> Vec<PathBuf> {
+    session_path().map(|path| pending_files(&path)).unwrap_or_default()
+}
+
+#[cfg(feature = "gui")]
+pub fn discard() {
+    if let Some(path) = session_path() {
+        let _ = std::fs::remove_file(path);
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use anyhow::{anyhow, Result};
+use crackleaf_core::{prune_journals, Journal, UndoReport};
+use tracing::{info, warn};
+
+#[cfg(feature = "gui")]
+use crate::i18n::t;
+
+/// Journals kept for `crackleaf-rs undo`; older batches become permanent.
+const MAX_JOURNALS: usize = 20;
+
+/// `<local data dir>/crackleaf/journal`.
+pub fn journal_dir() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("crackleaf").join("journal"))
+}
+
+/// Opens a journal for a new batch. Failing to do so only disables undo.
+pub fn start_batch() -> Option<Arc<Journal>> {
+    let dir = journal_dir()?;
+    prune_journals(&dir, MAX_JOURNALS.saturating_sub(1));
+    match Journal::create(&dir) {
+        Ok(journal) => Some(Arc::new(journal)),
+        Err(err) => {
+            warn!("Failed to create undo journal: {err}");
+            None
+        }
+    }
+}
+
+/// Drops the journal when the batch wrote nothing, so undo targets the last real change.
+pub fn finish_batch(journal: Option<Arc<Journal>>) -> Option<Arc<Journal>> {
+    let journal = journal?;
+    if journal.is_empty() {
+        journal.discard();
+        return None;
+    }
+    Some(journal)
+}
+
+pub fn undo(journal: &Journal) -> Result<UndoReport> {
+    let report = journal.undo()?;
+    info!(
+        removed = report.removed.len(),
+        restored = report.restored.len(),
+        errors = report.errors.len(),
+        "undo finished"
+    );
+    Ok(report)
+}
+
+pub fn undo_latest() -> Result<UndoReport> {
+    let journal = journal_dir()
+        .and_then(|dir| Journal::latest(&dir))
+        .ok_or_else(|| anyhow!("没有可撤销的操作"))?;
+    undo(&journal)
+}
+
+#[cfg(feature = "gui")]
+pub fn summary(report: &UndoReport) -> String {
+    let mut text = t!("undo-removed", count = report.removed.len());
+    if !report.restored.is_empty() {
+        text.push_str(&t!("undo-restored", count = report.restored.len()));
+    }
+    if !report.errors.is_empty() {
+        text.push_str(&t!("undo-errors", count = report.errors.len()));
+    }
+    text
+}
@@ -0,0 +1,218 @@
+use std::time::Duration;
+
+use anyhow::{anyhow, Result};
+use semver::{Prerelease, Version};
+use serde::Deserialize;
+use tracing::debug;
+
+const RELEASES_URL: &str = "https://api.github.com/repos/muxiaoxiii/crackleaf-rs/releases/latest";
+const TIMEOUT: Duration = Duration::from_secs(15);
+/// Release asset listing the SHA-256 of every package, as written by `sha256sum`.
+const CHECKSUMS_NAME: &str = "SHA256SUMS";
+
+/// A release newer than the running build.
+#[derive(Clone, Debug)]
+pub struct Release {
+    pub version: Version,
+    pub changelog: String,
+    pub page_url: String,
+    /// Package for this platform, if the release has one.
+    pub asset: Option<Asset>,
+    /// [`CHECKSUMS_NAME`], which [`install`] checks the package against.
+    pub checksums: Option<Asset>,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    #[serde(rename = "browser_download_url")]
+    pub url: String,
+}
+
+#[derive(Deserialize)]
+struct GithubRelease {
+    tag_name: String,
+    #[serde(default)]
+    body: Option<String>,
+    html_url: String,
+    #[serde(default)]
+    assets: Vec<Asset>,
+}
+
+/// Name of the release package built for this platform by the packaging workflow.
+fn platform_asset_name() -> Option<&'static str> {
+    if cfg!(all(target_os = "windows", target_arch = "x86_64")) {
+        Some("CrackLeaf-win-x64.zip")
+    } else if cfg!(all(target_os = "windows", target_arch = "x86")) {
+        Some("CrackLeaf-win-x86.zip")
+    } else if cfg!(all(target_os = "macos", target_arch = "aarch64")) {
+        Some("CrackLeaf-mac-arm64.dmg")
+    } else {
+        None
+    }
+}
+
+/// Release tags look like `v0.2.2-build.41`. The `build.N` part is dropped so
+/// rebuilds of the running version are not offered, while a prerelease such as
+/// `v0.3.0-rc.1` is kept and sorts before `v0.3.0`.
+fn parse_tag(tag: &str) -> Option<Version> {
+    let version = Version::parse(tag.trim_start_matches('v')).ok()?;
+    let pre = if version.pre.as_str().starts_with("build.") {
+        Prerelease::EMPTY
+    } else {
+        version.pre
+    };
+    Some(Version { pre, ..Version::new(version.major, version.minor, version.patch) })
+}
+
+/// Asks GitHub for the latest release; `None` when the running build is current.
+pub fn check() -> Result<Option<Release>> {
+    let current = Version::parse(env!("CARGO_PKG_VERSION"))?;
+    let response: GithubRelease = serde_json::from_reader(
+        ureq::get(RELEASES_URL)
+            .set("User-Agent", concat!("crackleaf/", env!("CARGO_PKG_VERSION")))
+            .set("Accept", "application/vnd.github+json")
+            .timeout(TIMEOUT)
+            .call()?
+            .into_reader(),
+    )?;
+    let version = parse_tag(&response.tag_name)
+        .ok_or_else(|| anyhow!("无法识别的版本号：{}", response.tag_name))?;
+    debug!(%version, %current, "latest release");
+    if version <= current {
+        return Ok(None);
+    }
+
+    let find = |name: &str| response.assets.iter().find(|asset| asset.name == name).cloned();
+    let asset = platform_asset_name().and_then(find);
+    let checksums = find(CHECKSUMS_NAME);
+    Ok(Some(Release {
+        version,
+        changelog: response.body.unwrap_or_default(),
+        page_url: response.html_url,
+        asset,
+        checksums,
+    }))
+}
+
+/// Whether [`install`] can replace the running binary on this platform, with a
+/// package it can verify.
+pub fn can_install(release: &Release) -> bool {
+    cfg!(target_os = "windows") && release.asset.is_some() && release.checksums.is_some()
+}
+
+/// Downloads the release package, checks it against the release's
+/// [`CHECKSUMS_NAME`] and swaps the running executable for the new one. The change
+/// takes effect on the next launch.
+#[cfg(target_os = "windows")]
+pub fn install(release: &Release) -> Result<()> {
+    use sha2::{Digest, Sha256};
+
+    let asset = release
+        .asset
+        .as_ref()
+        .ok_or_else(|| anyhow!("该版本没有适用于本平台的安装包"))?;
+    let checksums = release
+        .checksums
+        .as_ref()
+        .ok_or_else(|| anyhow!("该版本没有发布校验和，无法确认安装包完整"))?;
+    let checksums = String::from_utf8(fetch(&checksums.url, TIMEOUT)?)?;
+    let expected = expected_sha256(&checksums, &asset.name)
+        .ok_or_else(|| anyhow!("校验和中没有 {}", asset.name))?;
+    tracing::info!(url = %asset.url, "downloading update");
+    let bytes = fetch(&asset.url, Duration::from_secs(300))?;
+    let actual = format!("{:x}", Sha256::digest(&bytes));
+    if !actual.eq_ignore_ascii_case(expected) {
+        return Err(anyhow!("安装包校验失败：SHA-256 为 {actual}，应为 {expected}"));
+    }
+
+    let mut archive = zip::ZipArchive::new(std::io::Cursor::new(bytes))?;
+    let mut exe = archive.by_name("CrackLeaf.exe")?;
+    let staged = std::env::temp_dir().join(format!("CrackLeaf-{}.exe", release.version));
+    let mut file = std::fs::File::create(&staged)?;
+    std::io::copy(&mut exe, &mut file)?;
+    drop(file);
+
+    self_replace::self_replace(&staged)?;
+    let _ = std::fs::remove_file(&staged);
+    tracing::info!(version = %release.version, "update installed");
+    Ok(())
+}
+
+#[cfg(target_os = "windows")]
+fn fetch(url: &str, timeout: Duration) -> Result<Vec<u8>> {
+    use std::io::Read;
+
+    let mut bytes = Vec::new();
+    ureq::get(url)
+        .set("User-Agent", concat!("crackleaf/", env!("CARGO_PKG_VERSION")))
+        .timeout(timeout)
+        .call()?
+        .into_reader()
+        .read_to_end(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// The hash `sha256sum` output lists for `name`, whose lines read `<hash>  <name>`
+/// (or `<hash> *<name>` for binary mode).
+#[cfg(any(target_os = "windows", test))]
+fn expected_sha256<'a>(checksums: &'a str, name: &str) -> Option<&'a str> {
+    checksums.lines().find_map(|line| {
+        let (hash, file) = line.split_once(char::is_whitespace)?;
+        (file.trim_start().trim_start_matches('*') == name).then_some(hash)
+    })
+}
+
+#[cfg(not(target_os = "windows"))]
+pub fn install(_release: &Release) -> Result<()> {
+    Err(anyhow!("此平台不支持自动安装，请从发布页下载"))
+}
+
+/// Opens the release page in the default browser.
+#[cfg(feature = "gui")]
+pub fn open_page(release: &Release) {
+    #[cfg(target_os = "macos")]
+    let mut cmd = std::process::Command::new("open");
+    #[cfg(target_os = "windows")]
+    let mut cmd = {
+        use std::os::windows::process::CommandExt;
+        let mut cmd = std::process::Command::new("cmd");
+        cmd.args(["/C", "start", ""]).creation_flags(0x08000000);
+        cmd
+    };
+    #[cfg(not(any(target_os = "macos", target_os = "windows")))]
+    let mut cmd = std::process::Command::new("xdg-open");
+
+    if let Err(err) = cmd.arg(&release.page_url).spawn() {
+        tracing::warn!("Failed to open {}: {err}", release.page_url);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_tag_drops_build_numbers() {
+        assert_eq!(parse_tag("v0.2.2-build.41"), Some(Version::new(0, 2, 2)));
+        assert_eq!(parse_tag("0.2.2"), Some(Version::new(0, 2, 2)));
+        assert_eq!(parse_tag("release"), None);
+    }
+
+    #[test]
+    fn parse_tag_keeps_prereleases() {
+        let rc = parse_tag("v0.3.0-rc.1").unwrap();
+        assert_eq!(rc.pre.as_str(), "rc.1");
+        assert!(rc < Version::new(0, 3, 0));
+        assert!(rc > Version::new(0, 2, 2));
+        assert!(parse_tag("v0.3.0-rc.2").unwrap() > rc);
+    }
+
+    #[test]
+    fn expected_sha256_finds_the_package() {
+        let sums = "aa11  CrackLeaf-win-x64.zip\nbb22 *CrackLeaf-win-x86.zip\n";
+        assert_eq!(expected_sha256(sums, "CrackLeaf-win-x64.zip"), Some("aa11"));
+        assert_eq!(expected_sha256(sums, "CrackLeaf-win-x86.zip"), Some("bb22"));
+        assert_eq!(expected_sha256(sums, "CrackLeaf-mac-arm64.dmg"), None);
+    }
+}
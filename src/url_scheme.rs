@@ -0,0 +1,347 @@
+use std::ffi::OsString;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, bail, Result};
+use percent_encoding::percent_decode_str;
+
+pub const SCHEME: &str = "crackleaf";
+
+pub fn is_scheme_url(arg: &str) -> bool {
+    arg.get(..SCHEME.len())
+        .is_some_and(|scheme| scheme.eq_ignore_ascii_case(SCHEME))
+        && arg[SCHEME.len()..].starts_with("://")
+}
+
+/// Parses `crackleaf://unlock?path=<percent-encoded>[&path=...]` into validated file paths.
+pub fn parse_unlock_url(url: &str) -> Result<Vec<PathBuf>> {
+    if !is_scheme_url(url) {
+        bail!("不支持的链接：{url}");
+    }
+    let rest = &url[SCHEME.len() + 3..];
+    let (action, query) = rest.split_once('?').unwrap_or((rest, ""));
+    let action = action.trim_end_matches('/');
+    if !action.eq_ignore_ascii_case("unlock") {
+        bail!("不支持的操作：{action}");
+    }
+
+    let mut paths = Vec::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        if key != "path" {
+            continue;
+        }
+        let path = decode_path(value)?;
+        paths.push(validate_path(path)?);
+    }
+
+    if paths.is_empty() {
+        bail!("链接中没有文件路径");
+    }
+    Ok(paths)
+}
+
+fn decode_path(value: &str) -> Result<PathBuf> {
+    let bytes: Vec<u8> = percent_decode_str(value).collect();
+    if bytes.is_empty() {
+        bail!("文件路径为空");
+    }
+    if bytes.contains(&0) {
+        bail!("文件路径包含非法字符");
+    }
+
+    #[cfg(unix)]
+    let os_string = {
+        use std::os::unix::ffi::OsStringExt;
+        OsString::from_vec(bytes)
+    };
+    #[cfg(not(unix))]
+    let os_string = OsString::from(
+        String::from_utf8(bytes).map_err(|_| anyhow!("文件路径不是有效的 UTF-8"))?,
+    );
+
+    Ok(PathBuf::from(os_string))
+}
+
+fn validate_path(path: PathBuf) -> Result<PathBuf> {
+    if !path.is_absolute() {
+        bail!("文件路径必须为绝对路径：{}", path.display());
+    }
+    let resolved = path
+        .canonicalize()
+        .map_err(|err| anyhow!("无法访问文件 {}：{err}", path.display()))?;
+    if !resolved.is_file() {
+        bail!("不是文件：{}", path.display());
+    }
+    if !crackleaf_core::is_pdf(&resolved) {
+        bail!("不是 PDF 文件：{}", path.display());
+    }
+    Ok(resolved)
+}
+
+/// Registers the current executable as the handler for `crackleaf://` links, unless
+/// it is already.
+///
+/// macOS registers the scheme through `CFBundleURLTypes` in the app bundle instead,
+/// and hands the links over as Apple Events, see [`listen`].
+pub fn register() -> Result<()> {
+    let exe = std::env::current_exe()?;
+    register_for(&exe)
+}
+
+#[cfg(target_os = "windows")]
+fn register_for(exe: &std::path::Path) -> Result<()> {
+    let root = format!(r"Software\Classes\{SCHEME}");
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    let entries: [(String, Option<&str>, String); 3] = [
+        (root.clone(), None, "URL:CrackLeaf Protocol".to_string()),
+        (root.clone(), Some("URL Protocol"), String::new()),
+        (format!(r"{root}\shell\open\command"), None, command),
+    ];
+
+    for (key, name, data) in &entries {
+        if registry::read(key, *name).as_deref() == Some(data.as_str()) {
+            continue;
+        }
+        registry::write(key, *name, data).map_err(|err| anyhow!("写入注册表失败：HKCU\\{key}：{err}"))?;
+    }
+    Ok(())
+}
+
+/// String values under `HKEY_CURRENT_USER`; `name` `None` is the key's default value.
+#[cfg(target_os = "windows")]
+mod registry {
+    use std::ptr;
+
+    use windows_sys::Win32::System::Registry::{
+        RegGetValueW, RegSetKeyValueW, HKEY_CURRENT_USER, REG_SZ, RRF_RT_REG_SZ,
+    };
+
+    fn wide(text: &str) -> Vec<u16> {
+        text.encode_utf16().chain(std::iter::once(0)).collect()
+    }
+
+    pub fn read(key: &str, name: Option<&str>) -> Option<String> {
+        let (key, name) = (wide(key), name.map(wide));
+        let name = name.as_ref().map_or(ptr::null(), |name| name.as_ptr());
+        let mut size = 0u32;
+        // SAFETY: null-terminated strings, and a buffer of the size the first call reports.
+        let mut buffer = unsafe {
+            if RegGetValueW(HKEY_CURRENT_USER, key.as_ptr(), name, RRF_RT_REG_SZ, ptr::null_mut(), ptr::null_mut(), &mut size) != 0 {
+                return None;
+            }
+            let mut buffer = vec![0u16; size as usize / 2];
+            if RegGetValueW(HKEY_CURRENT_USER, key.as_ptr(), name, RRF_RT_REG_SZ, ptr::null_mut(), buffer.as_mut_ptr().cast(), &mut size) != 0 {
+                return None;
+            }
+            buffer
+        };
+        buffer.truncate(buffer.iter().position(|&unit| unit == 0).unwrap_or(buffer.len()));
+        String::from_utf16(&buffer).ok()
+    }
+
+    pub fn write(key: &str, name: Option<&str>, data: &str) -> std::io::Result<()> {
+        let (key, name, data) = (wide(key), name.map(wide), wide(data));
+        let name = name.as_ref().map_or(ptr::null(), |name| name.as_ptr());
+        // SAFETY: null-terminated strings; the size includes the terminator, as REG_SZ expects.
+        let status = unsafe {
+            RegSetKeyValueW(HKEY_CURRENT_USER, key.as_ptr(), name, REG_SZ, data.as_ptr().cast(), (data.len() * 2) as u32)
+        };
+        match status {
+            0 => Ok(()),
+            code => Err(std::io::Error::from_raw_os_error(code as i32)),
+        }
+    }
+}
+
+#[cfg(target_os = "linux")]
+fn register_for(exe: &std::path::Path) -> Result<()> {
+    let apps_dir = dirs::data_dir()
+        .ok_or_else(|| anyhow!("无法定位应用数据目录"))?
+        .join("applications");
+    std::fs::create_dir_all(&apps_dir)?;
+
+    let desktop_name = format!("{SCHEME}-url-handler.desktop");
+    let desktop = format!(
+        "[Desktop Entry]\nType=Application\nName=CrackLeaf\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{SCHEME};\n",
+        exe.display()
+    );
+    let desktop_path = apps_dir.join(&desktop_name);
+    if std::fs::read_to_string(&desktop_path).ok().as_deref() == Some(desktop.as_str()) {
+        return Ok(());
+    }
+    std::fs::write(&desktop_path, desktop)?;
+
+    let status = std::process::Command::new("xdg-mime")
+        .arg("default")
+        .arg(&desktop_name)
+        .arg(format!("x-scheme-handler/{SCHEME}"))
+        .status()?;
+    if !status.success() {
+        bail!("xdg-mime 注册失败");
+    }
+    Ok(())
+}
+
+#[cfg(not(any(target_os = "windows", target_os = "linux")))]
+fn register_for(_exe: &std::path::Path) -> Result<()> {
+    Ok(())
+}
+
+/// Starts taking `crackleaf://` links from macOS, which sends them as `kAEGetURL`
+/// Apple Events rather than as arguments: the one that launched the app and any
+/// opened while it runs. Call on the main thread before the event loop starts; the
+/// links are collected for [`take_received`].
+#[cfg(all(target_os = "macos", feature = "gui"))]
+pub fn listen() {
+    apple_events::install();
+}
+
+/// Calls `wake` whenever a link arrives through [`listen`].
+#[cfg(all(target_os = "macos", feature = "gui"))]
+pub fn set_waker(wake: impl Fn() + Send + Sync + 'static) {
+    let _ = apple_events::WAKE.set(Box::new(wake));
+}
+
+/// Links that arrived through [`listen`] since the last call.
+#[cfg(all(target_os = "macos", feature = "gui"))]
+pub fn take_received() -> Vec<String> {
+    std::mem::take(&mut *apple_events::RECEIVED.lock().unwrap_or_else(|err| err.into_inner()))
+}
+
+#[cfg(all(target_os = "macos", feature = "gui"))]
+mod apple_events {
+    use std::sync::{Mutex, OnceLock};
+
+    use objc2::rc::Retained;
+    use objc2::runtime::NSObject;
+    use objc2::{declare_class, msg_send, msg_send_id, mutability, sel, ClassType, DeclaredClass};
+    use objc2_foundation::{NSAppleEventDescriptor, NSAppleEventManager};
+    use tracing::info;
+
+    /// `'GURL'`: both the event class and the event id of "open this URL".
+    const GET_URL: u32 = u32::from_be_bytes(*b"GURL");
+    /// `'----'` (`keyDirectObject`): the parameter holding the URL.
+    const DIRECT_OBJECT: u32 = u32::from_be_bytes(*b"----");
+
+    pub(super) static RECEIVED: Mutex<Vec<String>> = Mutex::new(Vec::new());
+    pub(super) static WAKE: OnceLock<Box<dyn Fn() + Send + Sync>> = OnceLock::new();
+
+    declare_class!(
+        struct UrlHandler;
+
+        // SAFETY: NSObject has no subclassing requirements, the class keeps no state
+        // and does not implement `Drop`.
+        unsafe impl ClassType for UrlHandler {
+            type Super = NSObject;
+            type Mutability = mutability::InteriorMutable;
+            const NAME: &'static str = "CrackLeafUrlHandler";
+        }
+
+        impl DeclaredClass for UrlHandler {}
+
+        unsafe impl UrlHandler {
+            #[method(handleGetURLEvent:withReplyEvent:)]
+            fn handle_get_url(&self, event: &NSAppleEventDescriptor, _reply: &NSAppleEventDescriptor) {
+                // SAFETY: `paramDescriptorForKeyword:` takes an `AEKeyword` and returns a
+                // descriptor or nil.
+                let url: Option<Retained<NSAppleEventDescriptor>> =
+                    unsafe { msg_send_id![event, paramDescriptorForKeyword: DIRECT_OBJECT] };
+                let Some(url) = url.and_then(|url| unsafe { url.stringValue() }) else {
+                    return;
+                };
+                info!("link received from the system");
+                RECEIVED.lock().unwrap_or_else(|err| err.into_inner()).push(url.to_string());
+                if let Some(wake) = WAKE.get() {
+                    wake();
+                }
+            }
+        }
+    );
+
+    pub(super) fn install() {
+        // SAFETY: called on the main thread. The event manager does not retain its
+        // handlers, so this one is leaked to live as long as the app.
+        unsafe {
+            let handler: Retained<UrlHandler> = msg_send_id![UrlHandler::alloc(), init];
+            let _: () = msg_send![
+                &NSAppleEventManager::sharedAppleEventManager(),
+                setEventHandler: &*handler,
+                andSelector: sel!(handleGetURLEvent:withReplyEvent:),
+                forEventClass: GET_URL,
+                andEventID: GET_URL
+            ];
+            std::mem::forget(handler);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use percent_encoding::{percent_encode, NON_ALPHANUMERIC};
+
+    use super::*;
+
+    fn pdf(dir: &Path, name: &str) -> PathBuf {
+        let path = dir.join(name);
+        std::fs::write(&path, b"%PDF-1.7\n%%EOF\n").unwrap();
+        path.canonicalize().unwrap()
+    }
+
+    fn encode(path: &Path) -> String {
+        percent_encode(path.as_os_str().as_encoded_bytes(), NON_ALPHANUMERIC).to_string()
+    }
+
+    #[test]
+    fn parses_encoded_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first, second) = (pdf(dir.path(), "报告 (1).pdf"), pdf(dir.path(), "a&b=c.pdf"));
+        let url = format!("CrackLeaf://unlock/?path={}&from=mail&path={}", encode(&first), encode(&second));
+        assert_eq!(parse_unlock_url(&url).unwrap(), [first, second]);
+    }
+
+    #[test]
+    fn rejects_other_links() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = encode(&pdf(dir.path(), "report.pdf"));
+        for url in [
+            format!("https://unlock?path={path}"),
+            format!("crackleaf:unlock?path={path}"),
+            format!("crackleaf://delete?path={path}"),
+            "crackleaf://unlock".to_string(),
+            "crackleaf://unlock?path=".to_string(),
+        ] {
+            assert!(parse_unlock_url(&url).is_err(), "{url}");
+        }
+    }
+
+    #[test]
+    fn rejects_unsafe_paths() {
+        let dir = tempfile::tempdir().unwrap();
+        let text = dir.path().join("notes.pdf");
+        std::fs::write(&text, b"not a pdf").unwrap();
+        let report = encode(&pdf(dir.path(), "report.pdf"));
+        for path in [
+            "report.pdf".to_string(),
+            format!("{report}%00"),
+            encode(&dir.path().join("missing.pdf")),
+            encode(dir.path()),
+            encode(&text),
+        ] {
+            assert!(parse_unlock_url(&format!("crackleaf://unlock?path={path}")).is_err(), "{path}");
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn keeps_non_utf8_paths() {
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(std::ffi::OsStr::from_bytes(b"caf\xe9.pdf"));
+        std::fs::write(&path, b"%PDF-1.4\n").unwrap();
+        let url = format!("crackleaf://unlock?path={}", encode(&path));
+        assert_eq!(parse_unlock_url(&url).unwrap(), [path.canonicalize().unwrap()]);
+    }
+}